@@ -0,0 +1,28 @@
+//! A single task triggered once, two seconds from now.
+use chrono::{Duration, Local};
+use scheduler::prelude::*;
+use std::collections::HashMap;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| Local::now().into());
+
+fn main() -> Result<(), String> {
+    let task = ScheduledTask::new(
+        Local::now() + Duration::seconds(2),
+        "Hello from the scheduler",
+        RepetitionType::Once,
+        SleepType::Native,
+    );
+    let mut scheduler = BlockingScheduler::new(
+        HashMap::from([("greetings".to_string(), vec![task])]),
+        HashMap::new(),
+    );
+    println!(
+        "Next task at {:?}",
+        scheduler.next_run_time("greetings").unwrap()
+    );
+    // Returns once the mode has no task left
+    scheduler.start("greetings", |message| println!("{}", message))?;
+    println!("Removed tasks : {:?}", scheduler.removed_tasks["greetings"]);
+    Ok(())
+}
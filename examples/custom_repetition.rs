@@ -0,0 +1,41 @@
+//! A repetition the crate doesn't provide: every working day at the same time.
+use chrono::{DateTime, Datelike, Duration, FixedOffset, Local, Weekday};
+use scheduler::prelude::*;
+use scheduler::testing::ScheduleHarness;
+use std::collections::HashMap;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| Local::now().into());
+
+#[derive(Clone)]
+struct WorkingDays;
+
+impl CustomRepetition for WorkingDays {
+    fn update_date(
+        &self,
+        origin: &DateTime<FixedOffset>,
+        current_date: &DateTime<FixedOffset>,
+    ) -> Option<DateTime<FixedOffset>> {
+        let mut date = *current_date;
+        while date <= *origin || matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+            date += Duration::days(1);
+        }
+        Some(date)
+    }
+}
+
+fn main() -> Result<(), String> {
+    let start: DateTime<FixedOffset> = Local::now().into();
+    let task = ScheduledTask::new(start, "coffee", RepetitionType::Custom, SleepType::Native);
+    let scheduler = BlockingScheduler::new_with_custom_repetition(
+        HashMap::from([("work".to_string(), vec![task])]),
+        HashMap::new(),
+        WorkingDays,
+    );
+    let mut harness = ScheduleHarness::new(scheduler, "work", start)?;
+    harness.advance(Duration::weeks(2))?;
+    for (date, task) in harness.fired() {
+        println!("{} {} : {}", date.weekday(), date, task);
+    }
+    Ok(())
+}
@@ -0,0 +1,44 @@
+//! A mode fed by a generator: a reading every 300 ms, the scheduler only holding the ones due within the second.
+use chrono::{DateTime, Duration, FixedOffset, Local};
+use scheduler::prelude::*;
+use std::collections::HashMap;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| Local::now().into());
+
+struct Readings {
+    start: DateTime<FixedOffset>,
+    count: u32,
+}
+
+impl TaskSource<u32> for Readings {
+    fn next_after(&mut self, _after: DateTime<FixedOffset>) -> Option<ScheduledTask<u32>> {
+        if self.count == 10 {
+            return None;
+        }
+        self.count += 1;
+        let date = self.start + Duration::milliseconds(300 * i64::from(self.count));
+        Some(ScheduledTask::new(
+            date,
+            self.count,
+            RepetitionType::Once,
+            SleepType::Native,
+        ))
+    }
+}
+
+fn main() -> Result<(), String> {
+    let mut scheduler = BlockingScheduler::<u32>::new(HashMap::new(), HashMap::new());
+    let readings = Readings {
+        start: Local::now().into(),
+        count: 0,
+    };
+    scheduler.set_task_source("sensors", readings, Duration::seconds(1));
+    // Returns once the source is exhausted and the mode has no task left
+    scheduler.start("sensors", |reading| println!("Reading {}", reading))?;
+    println!(
+        "Removed tasks : {}",
+        scheduler.removed_tasks["sensors"].len()
+    );
+    Ok(())
+}
@@ -0,0 +1,36 @@
+//! Two modes running on their own threads, stopped together after a few seconds.
+use chrono::{Duration, Local};
+use scheduler::prelude::*;
+use std::collections::HashMap;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| Local::now().into());
+
+fn every(milliseconds: i64, task: &'static str) -> ScheduledTask<&'static str> {
+    ScheduledTask::new(
+        Local::now() + Duration::milliseconds(milliseconds),
+        task,
+        RepetitionType::ConstGap {
+            gap: Duration::milliseconds(milliseconds),
+            termination: RepetitionCount::Infinite.into(),
+        },
+        SleepType::Native,
+    )
+}
+
+fn main() -> std::io::Result<()> {
+    let mut scheduler = ParallelScheduler::new(
+        HashMap::from([
+            ("fast".to_string(), vec![every(300, "tick")]),
+            ("slow".to_string(), vec![every(1000, "TOCK")]),
+        ]),
+        HashMap::new(),
+    );
+    scheduler.start("fast".to_string(), |task| println!("{}", task))?;
+    scheduler.start("slow".to_string(), |task| println!("{}", task))?;
+    std::thread::sleep(std::time::Duration::from_secs(3));
+    scheduler.stop();
+    let summary = scheduler.join_all();
+    println!("Workers : {:?}", summary.results);
+    Ok(())
+}
@@ -0,0 +1,37 @@
+//! A scheduler saved as JSON and loaded back, its tasks resuming where they were.
+use chrono::{Duration, Local};
+use scheduler::prelude::*;
+use std::collections::HashMap;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| Local::now().into());
+
+fn main() -> Result<(), String> {
+    let task = ScheduledTask::new(
+        Local::now() - Duration::minutes(1),
+        "sync".to_string(),
+        RepetitionType::ConstGap {
+            gap: Duration::hours(1),
+            termination: RepetitionCount::Finished(10).into(),
+        },
+        SleepType::Native,
+    );
+    let mut scheduler = BlockingScheduler::new(
+        HashMap::from([("hourly".to_string(), vec![task])]),
+        HashMap::new(),
+    );
+    scheduler.set_name(Some("backups".to_string()));
+    scheduler.tick("hourly", Local::now(), |task| println!("{}", task))?;
+
+    let saved = serde_json::to_string_pretty(&scheduler).map_err(|error| error.to_string())?;
+    println!("{}", saved);
+    let loaded: BlockingScheduler<String> =
+        serde_json::from_str(&saved).map_err(|error| error.to_string())?;
+    assert_eq!(loaded.scheduled_tasks, scheduler.scheduled_tasks);
+    println!(
+        "{:?} resumes at {:?}",
+        loaded.name(),
+        loaded.next_run_time("hourly").unwrap()
+    );
+    Ok(())
+}
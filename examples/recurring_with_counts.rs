@@ -0,0 +1,46 @@
+//! Repeating tasks stopping after a number of occurrences or at a date, run against a ManualClock so that
+//! weeks of schedule take no time.
+use chrono::{DateTime, Duration};
+use scheduler::prelude::*;
+use scheduler::testing::ScheduleHarness;
+use std::collections::HashMap;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| DateTime::parse_from_rfc3339("2024-01-01T08:00:00+01:00").unwrap());
+
+fn main() -> Result<(), String> {
+    let start = DateTime::parse_from_rfc3339("2024-01-01T08:00:00+01:00").unwrap();
+    let tasks = vec![
+        ScheduledTask::new(
+            start,
+            "standup",
+            RepetitionType::ConstGap {
+                gap: Duration::days(1),
+                termination: RepetitionCount::Finished(5).into(),
+            },
+            SleepType::Native,
+        ),
+        ScheduledTask::new(
+            start + Duration::hours(9),
+            "weekly report",
+            RepetitionType::Weekly(Termination {
+                count: RepetitionCount::Infinite,
+                until: Some(start + Duration::weeks(3)),
+            }),
+            SleepType::Native,
+        ),
+    ];
+    let scheduler = BlockingScheduler::new(
+        HashMap::from([("office".to_string(), tasks)]),
+        HashMap::new(),
+    );
+    let mut harness = ScheduleHarness::new(scheduler, "office", start - Duration::minutes(1))?;
+    harness.advance(Duration::weeks(6))?;
+    for (date, task) in harness.fired() {
+        println!("{} : {}", date, task);
+    }
+    for task in &harness.scheduler().removed_tasks["office"] {
+        println!("{} removed : {:?}", task.task, task.removal_reason);
+    }
+    Ok(())
+}
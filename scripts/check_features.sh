@@ -0,0 +1,20 @@
+#!/bin/sh
+# Builds and lints the feature combinations that matter: the minimal build (chrono without its clock, no serde),
+# serde without spin_sleep, the default one and all the features (tested too), the build without ambient time, then the
+# Windows timer for its own target (`rustup target add x86_64-pc-windows-msvc` first, skipped when the target isn't
+# installed).
+set -e
+cd "$(dirname "$0")/.."
+cargo clippy --all-targets --no-default-features -- -D warnings
+cargo clippy --all-targets --no-default-features --features serde -- -D warnings
+cargo clippy --all-targets --features serde,json -- -D warnings
+cargo clippy --all-targets -- -D warnings
+cargo clippy --all-targets --all-features -- -D warnings
+cargo test --all-features
+cargo clippy --all-targets --features no-ambient-time -- -D warnings
+if rustup target list --installed 2>/dev/null | grep -q x86_64-pc-windows-msvc; then
+    cargo check --target x86_64-pc-windows-msvc --features windows-timer
+    cargo clippy --target x86_64-pc-windows-msvc --all-targets --all-features -- -D warnings
+else
+    echo "x86_64-pc-windows-msvc not installed, windows-timer not checked" >&2
+fi
@@ -0,0 +1,170 @@
+//! Backfills: the past occurrences of a task over a range fired on demand ("process every day of last month again"),
+//! without changing the schedule. BlockingScheduler::backfill computes the occurrences the repetition of the task gives
+//! in the range, then gives each one to the callback with TaskContext::is_backfill set, waiting
+//! SchedulerConfig::backfill_interval between two of them. The task keeps its date and its count.
+//! The gates of the task and of its mode are asked as for a live occurrence, the holidays and the blackout windows
+//! aren't applied.
+//!
+//! ```
+//! use chrono::{DateTime, Duration};
+//! use scheduler::backfill::BackfillOutcome;
+//! use scheduler::prelude::*;
+//! use std::collections::HashMap;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let at = |date| DateTime::parse_from_rfc3339(date).unwrap();
+//! let daily = RepetitionType::ConstGap { gap: Duration::days(1), termination: RepetitionCount::Finished(3).into() };
+//! let scheduler = BlockingScheduler::new(
+//!     HashMap::from([(
+//!         "pipeline".to_string(),
+//!         vec![ScheduledTask::new(at("2024-02-01T02:00:00+01:00"), "aggregate", daily, SleepType::Native)],
+//!     )]),
+//!     HashMap::new(),
+//! );
+//! let live = scheduler.scheduled_tasks["pipeline"][0].clone();
+//!
+//! let mut processed = Vec::new();
+//! let (from, to) = (at("2024-01-10T00:00:00+01:00"), at("2024-01-19T23:59:59+01:00"));
+//! let report = scheduler
+//!     .backfill("pipeline", live.id().unwrap(), from, to, |context| {
+//!         assert!(context.is_backfill);
+//!         processed.push(context.date);
+//!     })
+//!     .unwrap();
+//! assert_eq!(processed.len(), 10);
+//! assert_eq!(processed[0], at("2024-01-10T02:00:00+01:00"));
+//! assert_eq!(processed[9], at("2024-01-19T02:00:00+01:00"));
+//! assert_eq!(report.fired(), 10);
+//! assert!(report.occurrences.iter().all(|(_, outcome)| *outcome == BackfillOutcome::Fired));
+//! // The schedule is left as is
+//! assert_eq!(scheduler.scheduled_tasks["pipeline"][0], live);
+//! assert_eq!(scheduler.scheduled_tasks["pipeline"][0].repetition, live.repetition);
+//! ```
+use super::id::TaskId;
+use super::occurrence::TaskContext;
+use super::repetitions::{CustomRepetition, RepetitionType};
+use super::schedulers::BlockingScheduler;
+use chrono::{DateTime, Duration, FixedOffset};
+use std::thread;
+
+// Above that, the range is considered a mistake and nothing is fired
+const MAX_BACKFILLED: usize = 100_000;
+
+/// Represents what became of a backfilled occurrence
+/// - Fired : the callback has been called for it
+/// - GateClosed : the gate of the task or of its mode was closed
+/// - Stopped : the scheduler has been stopped before the occurrence, see BlockingScheduler::stop
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum BackfillOutcome {
+    Fired,
+    GateClosed,
+    Stopped,
+}
+
+/// Occurrences of a backfill in their order, see BlockingScheduler::backfill
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct BackfillReport {
+    pub occurrences: Vec<(DateTime<FixedOffset>, BackfillOutcome)>,
+}
+
+impl BackfillReport {
+    // Number of occurrences the callback has been called for
+    pub fn fired(&self) -> usize {
+        self.occurrences
+            .iter()
+            .filter(|(_, outcome)| *outcome == BackfillOutcome::Fired)
+            .count()
+    }
+}
+
+impl<TaskType, CustomRepetitionType> BlockingScheduler<TaskType, CustomRepetitionType>
+where
+    TaskType: Eq + Default,
+    CustomRepetitionType: CustomRepetition + Clone,
+{
+    // Fires the occurrences of the task from `from` to `to` (both included) that its repetition gives, without changing
+    // the schedule, see the backfill module. Fails when the mode or the task can't be found, for a SelfScheduled task
+    // and when the range holds more than 100 000 occurrences, nothing being fired.
+    pub fn backfill(
+        &self,
+        mode: &str,
+        id: TaskId,
+        from: impl Into<DateTime<FixedOffset>>,
+        to: impl Into<DateTime<FixedOffset>>,
+        mut f: impl FnMut(&TaskContext<'_, TaskType>),
+    ) -> Result<BackfillReport, String> {
+        let (from, to) = (from.into(), to.into());
+        let task = self
+            .scheduled_tasks
+            .get(mode)
+            .ok_or_else(|| self.unknown_mode(mode).to_string())?
+            .iter()
+            .find(|task| task.id() == Some(id))
+            .ok_or(format!("Couldn't find the requested task : {}", id))?;
+        if matches!(task.repetition, RepetitionType::SelfScheduled(_)) {
+            return Err(format!(
+                "The occurrences of the task {} are only known by its callback, it can't be backfilled",
+                id
+            ));
+        }
+        let custom_repetition = self.custom_repetition();
+        let mut dates = Vec::new();
+        let mut next = match task.repetition {
+            RepetitionType::Once => Some(task.date),
+            _ => task.repetition.next_occurrence(
+                &(from - Duration::seconds(1)),
+                &task.date,
+                &custom_repetition,
+            ),
+        };
+        while let Some(date) =
+            next.filter(|date| *date <= to && !task.repetition.is_expired_at(date))
+        {
+            if from <= date {
+                if dates.len() == MAX_BACKFILLED {
+                    return Err(format!(
+                        "The backfill of the task {} holds more than {} occurrences",
+                        id, MAX_BACKFILLED
+                    ));
+                }
+                dates.push(date);
+            }
+            next = task
+                .repetition
+                .next_occurrence(&date, &date, &custom_repetition)
+                .filter(|next_date| date < *next_date);
+        }
+        let interval = self
+            .config
+            .for_mode(mode)
+            .backfill_interval
+            .and_then(|interval| interval.to_std().ok());
+        let cancellation_token = self.cancellation_token();
+        let mode_gate = self.gates.get(mode);
+        let mut report = BackfillReport::default();
+        let mut has_fired = false;
+        for date in dates {
+            let outcome = if cancellation_token.is_cancelled() {
+                BackfillOutcome::Stopped
+            } else if !task.is_gate_open(mode_gate) {
+                BackfillOutcome::GateClosed
+            } else {
+                if let Some(interval) = interval.filter(|_| has_fired) {
+                    thread::sleep(interval);
+                }
+                has_fired = true;
+                f(&TaskContext {
+                    mode,
+                    task: &task.task,
+                    date,
+                    is_backfill: true,
+                    cancel_token: None,
+                    execution: None,
+                });
+                BackfillOutcome::Fired
+            };
+            report.occurrences.push((date, outcome));
+        }
+        Ok(report)
+    }
+}
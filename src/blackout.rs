@@ -0,0 +1,126 @@
+//! Blackout windows: times of the day during which a mode never triggers anything, set by SchedulerConfig::blackout_windows
+//! for every mode and by ModeConfig::blackout_windows for a single one. A window goes from its start (included) to its
+//! end (excluded), read in the offset of each task, and spans midnight when its start comes after its end.
+//! The occurrences falling inside a window are moved according to the BlackoutPolicy before the scheduler sleeps, so that
+//! it sleeps until the end of the window, and so that next_run_time, global_next and tick (hence ScheduleHarness) show
+//! the moved dates. The missed occurrences fired by the catch-up pass when a mode starts aren't moved.
+//!
+//! ```
+//! use chrono::{DateTime, Duration, NaiveTime};
+//! use scheduler::prelude::*;
+//! use std::collections::HashMap;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let at = |time| DateTime::parse_from_rfc3339(&format!("2024-01-01T{}+01:00", time)).unwrap();
+//! let hms = |hour, minute| NaiveTime::from_hms_opt(hour, minute, 0).unwrap();
+//! let every_30_minutes = RepetitionType::ConstGap { gap: Duration::minutes(30), termination: Termination::default() };
+//! let mut scheduler = BlockingScheduler::new(
+//!     HashMap::from([(
+//!         "billing".to_string(),
+//!         vec![ScheduledTask::new(at("22:30:00"), "invoice", every_30_minutes, SleepType::Native)],
+//!     )]),
+//!     HashMap::new(),
+//! );
+//! // Nothing runs from 23:00 to 01:00
+//! scheduler.set_mode_config(
+//!     "billing",
+//!     Some(ModeConfig { blackout_windows: vec![(hms(23, 0), hms(1, 0))], ..ModeConfig::default() }),
+//! );
+//! scheduler.tick("billing", at("22:30:00"), |_| {}).unwrap();
+//! // 23:00 is inside the window, the occurrence is deferred to its end, the next day
+//! let next = scheduler.next_run_time("billing").unwrap();
+//! assert_eq!(next, at("01:00:00") + Duration::days(1));
+//! assert_eq!(scheduler.tick("billing", at("23:59:00"), |_| {}).unwrap(), 0);
+//! assert_eq!(scheduler.tick("billing", next, |_| {}).unwrap(), 1);
+//! // 01:00 is outside: the end of a window is excluded
+//! assert_eq!(scheduler.next_run_time("billing").unwrap(), next + Duration::minutes(30));
+//!
+//! // With Skip, the occurrences inside the window are dropped instead
+//! scheduler.config.blackout_policy = BlackoutPolicy::Skip;
+//! scheduler.scheduled_tasks.get_mut("billing").unwrap()[0].date = at("22:30:00");
+//! scheduler.tick("billing", at("22:30:00"), |_| {}).unwrap();
+//! assert_eq!(scheduler.next_run_time("billing").unwrap(), at("01:00:00") + Duration::days(1));
+//! scheduler.config.blackout_windows = vec![(hms(1, 0), hms(1, 0)), (hms(1, 0), hms(2, 0))];
+//! scheduler.tick("billing", at("22:30:00"), |_| {}).unwrap();
+//! assert_eq!(scheduler.next_run_time("billing").unwrap(), at("02:00:00") + Duration::days(1));
+//! ```
+use chrono::{DateTime, FixedOffset, NaiveTime, TimeZone};
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// Above that, the windows are considered to cover the whole day and the occurrence is kept as is
+const MAX_MOVES: usize = 10_000;
+
+/// Represents what happens to an occurrence falling inside a blackout window
+/// - Defer : the occurrence is moved to the end of the window
+/// - Skip : the occurrence is replaced by the next occurrence of the task outside the windows, a task without one being
+///   removed
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum BlackoutPolicy {
+    #[default]
+    Defer,
+    Skip,
+}
+
+// Windows of a mode and the policy applied to them
+#[derive(Clone, Debug)]
+pub(crate) struct Blackouts {
+    windows: Vec<(NaiveTime, NaiveTime)>,
+    policy: BlackoutPolicy,
+}
+
+impl Blackouts {
+    // None when there's no window, the empty ones (same start and end) being left out
+    pub(crate) fn new(windows: &[(NaiveTime, NaiveTime)], policy: BlackoutPolicy) -> Option<Self> {
+        let windows: Vec<_> = windows
+            .iter()
+            .copied()
+            .filter(|(start, end)| start != end)
+            .collect();
+        (!windows.is_empty()).then_some(Self { windows, policy })
+    }
+
+    // End of a window containing the date, in the offset of the date
+    fn window_end(&self, date: &DateTime<FixedOffset>) -> Option<DateTime<FixedOffset>> {
+        let time = date.time();
+        let day = date.date_naive();
+        self.windows.iter().find_map(|(start, end)| {
+            let end_day = match (start < end, *start <= time, time < *end) {
+                (true, true, true) => day,
+                // Spanning midnight: the end is the next day when the date is before midnight
+                (false, true, _) => day.succ_opt()?,
+                (false, false, true) => day,
+                _ => return None,
+            };
+            date.offset()
+                .from_local_datetime(&end_day.and_time(*end))
+                .single()
+        })
+    }
+
+    // First date from the occurrence outside the windows, next_occurrence giving the occurrence following a date.
+    // None when the occurrence is skipped and the task has no occurrence left.
+    pub(crate) fn avoid(
+        &self,
+        occurrence: DateTime<FixedOffset>,
+        next_occurrence: impl Fn(&DateTime<FixedOffset>) -> Option<DateTime<FixedOffset>>,
+    ) -> Option<DateTime<FixedOffset>> {
+        let mut date = occurrence;
+        for _ in 0..MAX_MOVES {
+            let Some(window_end) = self.window_end(&date) else {
+                return Some(date);
+            };
+            date = match self.policy {
+                BlackoutPolicy::Defer => window_end,
+                BlackoutPolicy::Skip => {
+                    next_occurrence(&date).filter(|next_date| date < *next_date)?
+                }
+            };
+        }
+        Some(occurrence)
+    }
+}
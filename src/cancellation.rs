@@ -0,0 +1,203 @@
+//! Cooperative cancellation. A CancellationToken stops a running scheduler, a CancelToken tells a running callback to
+//! give up (see BlockingScheduler::start_with_context): the callback checks it from time to time, registers a function
+//! called on cancellation, or awaits it.
+//!
+//! ```
+//! use chrono::{Duration, Local};
+//! use scheduler::prelude::*;
+//! use std::collections::HashMap;
+//! use std::sync::atomic::{AtomicBool, Ordering};
+//! use std::sync::Arc;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let in_10_ms = Local::now() + Duration::milliseconds(10);
+//! let task = ScheduledTask::new(in_10_ms, "export", RepetitionType::Once, SleepType::Native);
+//! let mut scheduler = BlockingScheduler::new(HashMap::from([("jobs".to_string(), vec![task])]), HashMap::new());
+//! scheduler.config.expected_max_runtime = Some(Duration::milliseconds(20));
+//! let notified = Arc::new(AtomicBool::new(false));
+//! scheduler
+//!     .start_with_context("jobs", |context| {
+//!         let cancel_token = context.cancel_token().unwrap();
+//!         let notified = notified.clone();
+//!         cancel_token.on_cancel(move || notified.store(true, Ordering::SeqCst));
+//!         // Exports until the callback has overrun its expected runtime
+//!         while !cancel_token.is_cancelled() {
+//!             std::thread::sleep(std::time::Duration::from_millis(1));
+//!         }
+//!     })
+//!     .unwrap();
+//! assert!(notified.load(Ordering::SeqCst));
+//! assert_eq!(scheduler.removed_tasks["jobs"].len(), 1);
+//! ```
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+/// Flag used to ask a running scheduler to stop.
+/// Clones share the same flag, so cancelling any of them cancels every scheduler holding one.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<CancellationState>);
+
+#[derive(Debug, Default)]
+struct CancellationState {
+    cancelled: AtomicBool,
+    // Tokens of the callbacks running, cancelled along with the scheduler
+    callbacks: Mutex<Vec<CancelToken>>,
+    // Tokens of the runs stopped on their own, cancelled along with the scheduler, see CancellationToken::child
+    children: Mutex<Vec<CancellationToken>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::SeqCst);
+        for cancel_token in self.0.callbacks.lock().unwrap().iter() {
+            cancel_token.cancel();
+        }
+        for child in self.0.children.lock().unwrap().iter() {
+            child.cancel();
+        }
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+    }
+
+    // Cancels the token of a callback along with the scheduler, until Self::unlink
+    pub(crate) fn link(&self, cancel_token: &CancelToken) {
+        let mut callbacks = self.0.callbacks.lock().unwrap();
+        callbacks.push(cancel_token.clone());
+        // Checked under the lock, so that a cancellation happening meanwhile isn't missed
+        if self.is_cancelled() {
+            cancel_token.cancel();
+        }
+    }
+    pub(crate) fn unlink(&self, cancel_token: &CancelToken) {
+        self.0
+            .callbacks
+            .lock()
+            .unwrap()
+            .retain(|linked| linked != cancel_token);
+    }
+    // Token cancelled along with this one until Self::release_child, whose own cancellation leaves this one as is
+    pub(crate) fn child(&self) -> CancellationToken {
+        let child = CancellationToken::new();
+        let mut children = self.0.children.lock().unwrap();
+        children.push(child.clone());
+        // Same as Self::link
+        if self.is_cancelled() {
+            child.cancel();
+        }
+        child
+    }
+    pub(crate) fn release_child(&self, child: &CancellationToken) {
+        self.0
+            .children
+            .lock()
+            .unwrap()
+            .retain(|linked| !Arc::ptr_eq(&linked.0, &child.0));
+    }
+}
+
+/// Tells a running callback to give up, see BlockingScheduler::start_with_context.
+/// Clones share the same state. A callback checks is_cancelled from time to time, registers a function with on_cancel,
+/// awaits cancelled or blocks with wait_timeout.
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<CancelState>);
+
+#[derive(Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    on_cancel: Mutex<Vec<Box<dyn FnOnce() + Send>>>,
+    wakers: Mutex<Vec<Waker>>,
+    // Notified under the lock of on_cancel, see CancelToken::wait_timeout
+    waiting: Condvar,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    // Cancels the token, the functions registered are called once, from the thread cancelling it
+    pub fn cancel(&self) {
+        if self.0.cancelled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let on_cancel = std::mem::take(&mut *self.0.on_cancel.lock().unwrap());
+        self.0.waiting.notify_all();
+        for f in on_cancel {
+            f();
+        }
+        for waker in self.0.wakers.lock().unwrap().drain(..) {
+            waker.wake();
+        }
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+    }
+    // Calls f when the token is cancelled, right away if it already is
+    pub fn on_cancel(&self, f: impl FnOnce() + Send + 'static) {
+        let mut on_cancel = self.0.on_cancel.lock().unwrap();
+        if self.is_cancelled() {
+            drop(on_cancel);
+            f();
+        } else {
+            on_cancel.push(Box::new(f));
+        }
+    }
+    // Completes once the token is cancelled
+    pub fn cancelled(&self) -> Cancelled<'_> {
+        Cancelled(self)
+    }
+    // Blocks until the token is cancelled or the timeout has elapsed, true when it has been cancelled
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let on_cancel = self.0.on_cancel.lock().unwrap();
+        // Checked under the lock: CancelToken::cancel takes it after setting the flag, before notifying
+        let _ = self
+            .0
+            .waiting
+            .wait_timeout_while(on_cancel, timeout, |_| !self.is_cancelled())
+            .unwrap();
+        self.is_cancelled()
+    }
+}
+
+impl PartialEq for CancelToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CancelToken {}
+
+impl std::fmt::Debug for CancelToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("CancelToken")
+            .field(&self.is_cancelled())
+            .finish()
+    }
+}
+
+/// Future returned by CancelToken::cancelled
+#[derive(Debug)]
+pub struct Cancelled<'c>(&'c CancelToken);
+
+impl Future for Cancelled<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<()> {
+        let mut wakers = self.0 .0.wakers.lock().unwrap();
+        // Checked under the lock: CancelToken::cancel takes the wakers after setting the flag
+        if self.0.is_cancelled() {
+            return Poll::Ready(());
+        }
+        if !wakers.iter().any(|waker| waker.will_wake(context.waker())) {
+            wakers.push(context.waker().clone());
+        }
+        Poll::Pending
+    }
+}
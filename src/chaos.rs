@@ -0,0 +1,176 @@
+//! Failures injected into a started mode, to check how a schedule copes with them without patching the crate.
+//! BlockingScheduler::start_with_chaos runs the mode like start, the ChaosConfig deciding for each firing whether it's
+//! dropped, whether its callback fails (it isn't called) and how late it wakes up. The decisions are drawn from the
+//! seed of the start (see SchedulerConfig::rng_seed), so that a run with the same seed and the same tasks injects the
+//! same failures. Each injected failure is reported to the occurrence hook as OccurrenceOutcome::Injected, and returned
+//! in the ChaosReport.
+//!
+//! ```
+//! use chrono::{Duration, Local};
+//! use scheduler::chaos::{ChaosConfig, InjectedFault};
+//! use scheduler::prelude::*;
+//! use std::collections::HashMap;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let chaos = ChaosConfig {
+//!     drop_every_nth_firing: Some(4),
+//!     fail_callback_with_probability: 0.3,
+//!     ..ChaosConfig::default()
+//! };
+//! let run = || {
+//!     let now = Local::now();
+//!     let task = |index: i64| {
+//!         ScheduledTask::new(now + Duration::milliseconds(10 + index), index, RepetitionType::Once, SleepType::Native)
+//!     };
+//!     let tasks = (0..12).map(task).collect();
+//!     let mut scheduler = BlockingScheduler::new(HashMap::from([("jobs".to_string(), tasks)]), HashMap::new());
+//!     scheduler.config.rng_seed = Some(42);
+//!     let mut called = Vec::new();
+//!     let report = scheduler.start_with_chaos("jobs", |task| called.push(*task), &chaos).unwrap();
+//!     (called, report)
+//! };
+//! let (called, report) = run();
+//! assert_eq!(report.seed, 42);
+//! let dropped: Vec<_> = report.injected.iter().filter(|event| event.fault == InjectedFault::Dropped).collect();
+//! assert_eq!(dropped.iter().map(|event| event.firing).collect::<Vec<_>>(), [4, 8, 12]);
+//! assert_eq!(called.len() + report.injected.len(), 12);
+//!
+//! // Same seed, same failures
+//! let (called_again, report_again) = run();
+//! assert_eq!(called_again, called);
+//! assert_eq!(
+//!     report_again.injected.iter().map(|event| (event.firing, event.fault)).collect::<Vec<_>>(),
+//!     report.injected.iter().map(|event| (event.firing, event.fault)).collect::<Vec<_>>()
+//! );
+//! ```
+use super::config::SchedulerConfig;
+use super::occurrence::{OccurrenceOutcome, TaskContext};
+use super::repetitions::CustomRepetition;
+use super::schedulers::BlockingScheduler;
+use chrono::{DateTime, FixedOffset};
+use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::thread;
+use std::time::Duration;
+
+// Mixed into the seed, so that the failures aren't drawn like the other random decisions of the start
+const CHAOS_SALT: u64 = 0xc4a0_5eed_c4a0_5eed;
+
+/// Failures injected by BlockingScheduler::start_with_chaos, none by default
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct ChaosConfig {
+    // Drops the nth firing of the mode, then the 2nth...
+    pub drop_every_nth_firing: Option<u64>,
+    // Waited after each wake-up, before the callback
+    pub extra_latency: Option<Duration>,
+    // Upper bound of a random time waited after each wake-up, on top of extra_latency
+    pub randomize_clock_jitter: Option<Duration>,
+    // Between 0 and 1, the firings failing this way don't call the callback
+    pub fail_callback_with_probability: f32,
+}
+
+/// Represents a failure injected into a firing
+/// - Dropped : the firing has been dropped, see ChaosConfig::drop_every_nth_firing
+/// - CallbackFailed : the callback has failed without being called, see ChaosConfig::fail_callback_with_probability
+/// - Latency : the firing has been delayed by the duration, see ChaosConfig::extra_latency and randomize_clock_jitter
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum InjectedFault {
+    Dropped,
+    CallbackFailed,
+    Latency(Duration),
+}
+
+/// Failure injected into a firing of the mode
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct InjectedEvent {
+    // Number of the firing in the run, starting at 1
+    pub firing: u64,
+    pub scheduled: DateTime<FixedOffset>,
+    pub fault: InjectedFault,
+}
+
+/// Failures injected by a BlockingScheduler::start_with_chaos, in their order
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct ChaosReport {
+    // Seed the failures have been drawn from
+    pub seed: u64,
+    pub injected: Vec<InjectedEvent>,
+}
+
+impl<TaskType, CustomRepetitionType> BlockingScheduler<TaskType, CustomRepetitionType>
+where
+    TaskType: Eq + Default + Clone,
+    CustomRepetitionType: CustomRepetition + Clone,
+{
+    // Same as start, the failures of the ChaosConfig being injected into the firings, see the chaos module
+    pub fn start_with_chaos(
+        &mut self,
+        mode: &str,
+        mut f: impl FnMut(&TaskType),
+        chaos: &ChaosConfig,
+    ) -> Result<ChaosReport, String> {
+        let seed = self.config.effective_rng_seed();
+        let mut rng = SchedulerConfig::rng(seed ^ CHAOS_SALT, mode);
+        let occurrence_hook = self.occurrence_hook;
+        let mut report = ChaosReport {
+            seed,
+            injected: Vec::new(),
+        };
+        let mut firing = 0;
+        self.run(
+            mode,
+            |task| {
+                firing += 1;
+                let mut inject = |fault| {
+                    report.injected.push(InjectedEvent {
+                        firing,
+                        scheduled: task.date,
+                        fault,
+                    });
+                    if let Some(occurrence_hook) = occurrence_hook {
+                        occurrence_hook(
+                            OccurrenceOutcome::Injected { fault },
+                            &TaskContext {
+                                mode,
+                                task: &task.task,
+                                date: task.date,
+                                is_backfill: false,
+                                cancel_token: None,
+                                execution: None,
+                            },
+                        );
+                    }
+                };
+                // Drawn for every firing, so that the draws of a firing don't depend on the ones before
+                let fails = rng.gen::<f32>() < chaos.fail_callback_with_probability;
+                let jitter = chaos
+                    .randomize_clock_jitter
+                    .map_or(Duration::ZERO, |jitter| {
+                        rng.gen_range(Duration::ZERO..=jitter)
+                    });
+                if chaos
+                    .drop_every_nth_firing
+                    .is_some_and(|nth| nth > 0 && firing % nth == 0)
+                {
+                    inject(InjectedFault::Dropped);
+                    return None;
+                }
+                let latency = chaos.extra_latency.unwrap_or_default() + jitter;
+                if !latency.is_zero() {
+                    thread::sleep(latency);
+                    inject(InjectedFault::Latency(latency));
+                }
+                if fails {
+                    inject(InjectedFault::CallbackFailed);
+                } else {
+                    f(&task.task);
+                }
+                None
+            },
+            &mut (),
+        )?;
+        Ok(report)
+    }
+}
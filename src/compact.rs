@@ -0,0 +1,185 @@
+//! Columnar serialized form of a scheduler, for the big schedules that are serialized often.
+//! Every mode is stored as parallel arrays instead of one object per task, and the repetition, sleep type and
+//! removal reason (or holiday flag) shared by all the tasks of a mode are written once.
+//!
+//! ```
+//! use chrono::DateTime;
+//! use scheduler::compact::{Column, CompactScheduler};
+//! use scheduler::prelude::*;
+//! use std::collections::HashMap;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let date = DateTime::parse_from_rfc3339("2024-01-01T09:00:00+01:00").unwrap();
+//! let tasks = (0..3)
+//!     .map(|task| ScheduledTask::new(date, task, RepetitionType::Once, SleepType::Native))
+//!     .collect();
+//! let scheduler = BlockingScheduler::new(HashMap::from([("mode".to_string(), tasks)]), HashMap::new());
+//!
+//! let compact = CompactScheduler::from(scheduler.clone());
+//! assert_eq!(compact.scheduled_tasks["mode"].repetitions, Column::Shared(RepetitionType::Once));
+//! let restored = BlockingScheduler::try_from(compact).unwrap();
+//! assert_eq!(restored.scheduled_tasks, scheduler.scheduled_tasks);
+//! ```
+use super::config::SchedulerConfig;
+use super::id::{TaskId, TaskIds};
+use super::ledger::OccurrenceLedger;
+use super::repetitions::RepetitionType;
+use super::schedulers::{PendingDeletion, RemovalReason, ScheduledTask};
+use super::sleeptype::SleepType;
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Values of a column, written once when every task shares the same value
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+#[serde(untagged)]
+pub enum Column<Value> {
+    Each(Vec<Value>),
+    Shared(Value),
+}
+
+impl Column<bool> {
+    fn none_skipping_holidays() -> Self {
+        Self::Shared(false)
+    }
+}
+
+impl Column<Option<TaskId>> {
+    fn no_ids() -> Self {
+        Self::Shared(None)
+    }
+}
+
+impl<Value> Column<Value>
+where
+    Value: PartialEq + Clone,
+{
+    fn new(values: Vec<Value>) -> Self {
+        match values.first() {
+            Some(first) if values.iter().all(|value| value == first) => Self::Shared(first.clone()),
+            _ => Self::Each(values),
+        }
+    }
+    fn into_values(self, len: usize) -> Result<Vec<Value>, String> {
+        match self {
+            Self::Shared(value) => Ok(vec![value; len]),
+            Self::Each(values) if values.len() == len => Ok(values),
+            Self::Each(values) => Err(format!(
+                "A column holds {} values instead of {}",
+                values.len(),
+                len
+            )),
+        }
+    }
+}
+
+/// Tasks of a mode stored as parallel arrays, the i-th task being made of the i-th value of each column
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct CompactTasks<TaskType> {
+    pub dates: Vec<DateTime<FixedOffset>>,
+    pub tasks: Vec<TaskType>,
+    pub repetitions: Column<RepetitionType>,
+    pub sleep_types: Column<SleepType>,
+    pub removal_reasons: Column<Option<RemovalReason>>,
+    #[serde(default = "Column::none_skipping_holidays")]
+    pub skip_holidays: Column<bool>,
+    #[serde(default = "Column::no_ids")]
+    pub ids: Column<Option<TaskId>>,
+}
+
+impl<TaskType> From<Vec<ScheduledTask<TaskType>>> for CompactTasks<TaskType> {
+    fn from(scheduled_tasks: Vec<ScheduledTask<TaskType>>) -> Self {
+        let len = scheduled_tasks.len();
+        let mut dates = Vec::with_capacity(len);
+        let mut tasks = Vec::with_capacity(len);
+        let mut repetitions = Vec::with_capacity(len);
+        let mut sleep_types = Vec::with_capacity(len);
+        let mut removal_reasons = Vec::with_capacity(len);
+        let mut skip_holidays = Vec::with_capacity(len);
+        let mut ids = Vec::with_capacity(len);
+        for scheduled_task in scheduled_tasks {
+            dates.push(scheduled_task.date);
+            tasks.push(scheduled_task.task);
+            repetitions.push(scheduled_task.repetition);
+            sleep_types.push(scheduled_task.sleep_type);
+            removal_reasons.push(scheduled_task.removal_reason);
+            skip_holidays.push(scheduled_task.skip_holidays);
+            ids.push(scheduled_task.id);
+        }
+        Self {
+            dates,
+            tasks,
+            repetitions: Column::new(repetitions),
+            sleep_types: Column::new(sleep_types),
+            removal_reasons: Column::new(removal_reasons),
+            skip_holidays: Column::new(skip_holidays),
+            ids: Column::new(ids),
+        }
+    }
+}
+
+impl<TaskType> TryFrom<CompactTasks<TaskType>> for Vec<ScheduledTask<TaskType>> {
+    type Error = String;
+
+    fn try_from(compact_tasks: CompactTasks<TaskType>) -> Result<Self, Self::Error> {
+        let len = compact_tasks.dates.len();
+        if compact_tasks.tasks.len() != len {
+            return Err(format!(
+                "{} tasks are given for {} dates",
+                compact_tasks.tasks.len(),
+                len
+            ));
+        }
+        let repetitions = compact_tasks.repetitions.into_values(len)?;
+        let sleep_types = compact_tasks.sleep_types.into_values(len)?;
+        let removal_reasons = compact_tasks.removal_reasons.into_values(len)?;
+        let skip_holidays = compact_tasks.skip_holidays.into_values(len)?;
+        let ids = compact_tasks.ids.into_values(len)?;
+        Ok(compact_tasks
+            .dates
+            .into_iter()
+            .zip(compact_tasks.tasks)
+            .zip(repetitions)
+            .zip(sleep_types)
+            .zip(removal_reasons)
+            .zip(skip_holidays)
+            .zip(ids)
+            .map(
+                |(
+                    (((((date, task), repetition), sleep_type), removal_reason), skip_holidays),
+                    id,
+                )| {
+                    ScheduledTask {
+                        removal_reason,
+                        skip_holidays,
+                        id,
+                        ..ScheduledTask::new(date, task, repetition, sleep_type)
+                    }
+                },
+            )
+            .collect())
+    }
+}
+
+/// Same content as the serialized form of a BlockingScheduler, with CompactTasks instead of task lists.
+/// Converted from and to a BlockingScheduler, which is normalized on the way back.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CompactScheduler<TaskType, CustomRepetitionType> {
+    // See BlockingScheduler::next_fire, written first as in the serialized form of a BlockingScheduler
+    #[serde(default)]
+    pub next_fire: Option<DateTime<FixedOffset>>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub created_at: DateTime<FixedOffset>,
+    pub modified_at: DateTime<FixedOffset>,
+    pub scheduled_tasks: BTreeMap<String, CompactTasks<TaskType>>,
+    pub removed_tasks: BTreeMap<String, CompactTasks<TaskType>>,
+    #[serde(default)]
+    pub pending_deletions: BTreeMap<String, Vec<PendingDeletion<TaskType>>>,
+    pub config: SchedulerConfig,
+    pub custom_repetition: CustomRepetitionType,
+    #[serde(default)]
+    pub(crate) task_ids: TaskIds,
+    #[serde(default, skip_serializing_if = "OccurrenceLedger::is_unused")]
+    pub(crate) occurrence_ledger: OccurrenceLedger,
+}
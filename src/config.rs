@@ -0,0 +1,424 @@
+use super::blackout::{BlackoutPolicy, Blackouts};
+#[cfg(feature = "spin_sleep")]
+use super::sleeptype::SpinConfig;
+use super::wall::WallZone;
+use chrono::{Duration, NaiveTime};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Represents what happens to the occurrences missed while the scheduler wasn't running
+/// - Skip : the missed occurrences are dropped, tasks are moved to their next occurrence (Once tasks are removed)
+/// - FireOnce : a single late firing stands for all the missed occurrences of a task
+/// - FireAll : every missed occurrence is fired, up to SchedulerConfig::max_catch_up (see CatchUpOrder)
+///
+/// A late Once task has a single missed occurrence, fired late by FireOnce and FireAll
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum MissedTickBehavior {
+    #[default]
+    Skip,
+    FireOnce,
+    FireAll,
+}
+
+/// Represents which missed occurrences of a task MissedTickBehavior::FireAll replays when they're more than
+/// SchedulerConfig::max_catch_up, the others being skipped
+/// - Oldest : the first ones after the date of the task
+/// - Newest : the last ones before now
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum CatchUpOrder {
+    #[default]
+    Oldest,
+    Newest,
+}
+
+/// Represents what the normalization of a scheduler does with the removed tasks of modes that aren't scheduled
+/// - Keep : the removed tasks are kept, in case the mode is added back
+/// - Prune : the removed tasks are dropped
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum OrphanRemovedTasks {
+    #[default]
+    Keep,
+    Prune,
+}
+
+/// Represents what a start does when its mode is already started, by the same scheduler or by one of its clones (the
+/// workers of a ParallelScheduler for instance)
+/// - Fail : the start returns SchedulerError::AlreadyRunning right away
+/// - Wait : the start waits for the other one to return, then starts the mode with its own tasks
+///
+/// ```
+/// use chrono::{Duration, Local};
+/// use scheduler::prelude::*;
+/// use std::collections::HashMap;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::thread;
+/// # scheduler::system_clock!(|| chrono::Local::now().into());
+///
+/// static FIRED: AtomicUsize = AtomicUsize::new(0);
+/// let soon = Local::now() + Duration::milliseconds(50);
+/// let tasks = (0..10).map(|task| ScheduledTask::new(soon, task, RepetitionType::Once, SleepType::Native));
+/// let mut parallel = ParallelScheduler::new([("jobs".to_string(), tasks.collect())], []);
+/// let fire = |_: &i32| {
+///     FIRED.fetch_add(1, Ordering::SeqCst);
+/// };
+/// parallel.start("jobs".to_string(), fire).unwrap();
+/// let error = parallel.start("jobs".to_string(), fire).unwrap_err();
+/// assert_eq!(error.to_string(), "The requested mode is already started : jobs");
+/// assert!(parallel.join_all().results.iter().all(Result::is_ok));
+/// assert_eq!(FIRED.load(Ordering::SeqCst), 10);
+///
+/// // The clones of a scheduler share the started modes
+/// let task = ScheduledTask::new(Local::now() + Duration::milliseconds(100), 0, RepetitionType::Once, SleepType::Native);
+/// let mut scheduler = BlockingScheduler::new(HashMap::from([("jobs".to_string(), vec![task])]), HashMap::new());
+/// let mut clone = scheduler.clone();
+/// let first = thread::spawn(move || clone.start("jobs", |_| {}));
+/// thread::sleep(std::time::Duration::from_millis(30));
+/// assert_eq!(scheduler.start("jobs", |_| {}).unwrap_err(), "The requested mode is already started : jobs");
+/// scheduler.config.concurrent_start = ConcurrentStart::Wait;
+/// scheduler.start("jobs", |_| {}).unwrap();
+/// assert!(first.is_finished());
+/// first.join().unwrap().unwrap();
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum ConcurrentStart {
+    #[default]
+    Fail,
+    Wait,
+}
+
+/// Represents what an eager stream does with a firing when its channel is full, see SchedulerConfig::stream_capacity
+/// - Block : the schedule waits for the consumer, the later firings drifting but none being lost
+/// - DropOldest : the oldest firing not consumed yet is dropped to make room for the new one
+/// - DropNewest : the new firing is dropped
+///
+/// The dropped firings are counted by EagerFiringStream::dropped and reported to the occurrence hook as
+/// OccurrenceOutcome::StreamDropped.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum BackpressurePolicy {
+    #[default]
+    Block,
+    DropOldest,
+    DropNewest,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct SchedulerConfig {
+    pub missed_tick_behavior: MissedTickBehavior,
+    // Maximum number of missed occurrences fired per task with MissedTickBehavior::FireAll, the others are skipped
+    pub max_catch_up: Option<u32>,
+    // Missed occurrences replayed when they're more than max_catch_up
+    pub catch_up_order: CatchUpOrder,
+    // Number of outdated tasks caught up by start between two checks of the tasks already caught up, so that a long
+    // catch-up pass doesn't delay the tasks due meanwhile. The whole pass is done at once when None.
+    pub catch_up_batch_size: Option<usize>,
+    // Waited once at the beginning of each start, before the catch-up pass
+    #[cfg_attr(feature = "serde", serde(with = "crate::duration_seconds::option"))]
+    #[cfg_attr(feature = "json-schema", schemars(with = "Option<i64>"))]
+    pub initial_delay: Option<Duration>,
+    // Upper bound of a random delay added to initial_delay, so that schedulers started together don't catch up together
+    #[cfg_attr(feature = "serde", serde(with = "crate::duration_seconds::option"))]
+    #[cfg_attr(feature = "json-schema", schemars(with = "Option<i64>"))]
+    pub splay: Option<Duration>,
+    // Seeds the random decisions of a start (the splay) so that a run can be reproduced, a random seed is drawn when None.
+    // The seed used is given by CatchUpReport::rng_seed.
+    pub rng_seed: Option<u64>,
+    // Used by the SleepType::Spin tasks, the sleeper is built once per start
+    #[cfg(feature = "spin_sleep")]
+    pub spin: SpinConfig,
+    // Lets BlockingScheduler::validate move the repeating tasks dated in the past to their first future occurrence
+    pub auto_advance: bool,
+    // Callbacks lasting longer are reported by BlockingScheduler::start_watched
+    #[cfg_attr(feature = "serde", serde(with = "crate::duration_seconds::option"))]
+    #[cfg_attr(feature = "json-schema", schemars(with = "Option<i64>"))]
+    pub expected_max_runtime: Option<Duration>,
+    // Time between two reports of the same overrunning callback, expected_max_runtime when None
+    #[cfg_attr(feature = "serde", serde(with = "crate::duration_seconds::option"))]
+    #[cfg_attr(feature = "json-schema", schemars(with = "Option<i64>"))]
+    pub overrun_interval: Option<Duration>,
+    // Applied by BlockingScheduler::normalize
+    pub orphan_removed_tasks: OrphanRemovedTasks,
+    // Times of the day during which nothing is triggered, see the blackout module
+    pub blackout_windows: Vec<(NaiveTime, NaiveTime)>,
+    pub blackout_policy: BlackoutPolicy,
+    // Records how long after the date of its task each callback of start has begun, see BlockingScheduler::latency_report.
+    // Meant for the modes needing a bound on that latency, along with SleepType::Spin tasks.
+    pub realtime: bool,
+    // Time after which an occurrence held by a closed gate is checked again, the occurrence being skipped when None. See
+    // the gate module.
+    #[cfg_attr(feature = "serde", serde(with = "crate::duration_seconds::option"))]
+    #[cfg_attr(feature = "json-schema", schemars(with = "Option<i64>"))]
+    pub gate_recheck_interval: Option<Duration>,
+    // Time waited between two occurrences fired by BlockingScheduler::backfill, so that a long backfill doesn't flood
+    // what its callbacks call
+    #[cfg_attr(feature = "serde", serde(with = "crate::duration_seconds::option"))]
+    #[cfg_attr(feature = "json-schema", schemars(with = "Option<i64>"))]
+    pub backfill_interval: Option<Duration>,
+    // Applied when a start finds its mode already started, see ConcurrentStart
+    pub concurrent_start: ConcurrentStart,
+    // Number of completed occurrences remembered so that none is fired twice across restarts, see the ledger module.
+    // Nothing is remembered when None.
+    pub occurrence_ledger: Option<usize>,
+    // Offset the dates of the tasks are meant to be in, see BlockingScheduler::assume_local_time and
+    // BlockingScheduler::offset_warnings
+    #[cfg_attr(feature = "json-schema", schemars(with = "String"))]
+    pub zone: WallZone,
+    // Number of firings an eager stream buffers for its consumer (see BlockingScheduler::into_eager_stream), applying
+    // backpressure_policy once they're reached. The buffer isn't bounded when None.
+    pub stream_capacity: Option<usize>,
+    pub backpressure_policy: BackpressurePolicy,
+    // Lets start raise the resolution of the Windows timer to 1 ms while it runs a mode with gaps shorter than 100 ms
+    #[cfg(feature = "windows-timer")]
+    pub high_resolution_timer: bool,
+    // Overrides of the settings above for some modes, see Self::for_mode
+    pub modes: BTreeMap<String, ModeConfig>,
+}
+
+/// Settings of a SchedulerConfig overridden for a single mode, None keeping the scheduler's setting
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct ModeConfig {
+    pub missed_tick_behavior: Option<MissedTickBehavior>,
+    pub max_catch_up: Option<u32>,
+    pub catch_up_order: Option<CatchUpOrder>,
+    pub catch_up_batch_size: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::duration_seconds::option"))]
+    #[cfg_attr(feature = "json-schema", schemars(with = "Option<i64>"))]
+    pub initial_delay: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::duration_seconds::option"))]
+    #[cfg_attr(feature = "json-schema", schemars(with = "Option<i64>"))]
+    pub splay: Option<Duration>,
+    // Added to the windows of the scheduler
+    pub blackout_windows: Vec<(NaiveTime, NaiveTime)>,
+    pub blackout_policy: Option<BlackoutPolicy>,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            missed_tick_behavior: MissedTickBehavior::default(),
+            max_catch_up: None,
+            catch_up_order: CatchUpOrder::default(),
+            catch_up_batch_size: None,
+            initial_delay: None,
+            splay: None,
+            rng_seed: None,
+            #[cfg(feature = "spin_sleep")]
+            spin: SpinConfig::default(),
+            auto_advance: true,
+            expected_max_runtime: None,
+            overrun_interval: None,
+            orphan_removed_tasks: OrphanRemovedTasks::default(),
+            blackout_windows: Vec::new(),
+            blackout_policy: BlackoutPolicy::default(),
+            realtime: false,
+            gate_recheck_interval: None,
+            backfill_interval: None,
+            concurrent_start: ConcurrentStart::default(),
+            occurrence_ledger: None,
+            zone: WallZone::default(),
+            stream_capacity: None,
+            backpressure_policy: BackpressurePolicy::default(),
+            #[cfg(feature = "windows-timer")]
+            high_resolution_timer: true,
+            modes: BTreeMap::new(),
+        }
+    }
+}
+
+impl SchedulerConfig {
+    // Settings used by the mode: the ones of the scheduler, overridden by the ModeConfig of the mode if any
+    pub fn for_mode(&self, mode: &str) -> Self {
+        let mut config = Self {
+            modes: BTreeMap::new(),
+            ..self.clone()
+        };
+        if let Some(mode_config) = self.modes.get(mode) {
+            if let Some(missed_tick_behavior) = mode_config.missed_tick_behavior {
+                config.missed_tick_behavior = missed_tick_behavior;
+            }
+            config.max_catch_up = mode_config.max_catch_up.or(config.max_catch_up);
+            if let Some(catch_up_order) = mode_config.catch_up_order {
+                config.catch_up_order = catch_up_order;
+            }
+            config.catch_up_batch_size = mode_config
+                .catch_up_batch_size
+                .or(config.catch_up_batch_size);
+            config.initial_delay = mode_config.initial_delay.or(config.initial_delay);
+            config.splay = mode_config.splay.or(config.splay);
+            config
+                .blackout_windows
+                .extend(&mode_config.blackout_windows);
+            if let Some(blackout_policy) = mode_config.blackout_policy {
+                config.blackout_policy = blackout_policy;
+            }
+        }
+        config
+    }
+
+    // Windows of the config, which is the one of a mode (see Self::for_mode)
+    pub(crate) fn blackouts(&self) -> Option<Blackouts> {
+        Blackouts::new(&self.blackout_windows, self.blackout_policy)
+    }
+
+    // Seed of the random decisions of a start, see Self::rng_seed
+    pub(crate) fn effective_rng_seed(&self) -> u64 {
+        self.rng_seed.unwrap_or_else(|| rand::thread_rng().gen())
+    }
+    // Generator of the random decisions of a start of the mode.
+    // The mode is mixed in so that the modes started together with the same seed don't make the same decisions.
+    pub(crate) fn rng(seed: u64, mode: &str) -> StdRng {
+        let mode_hash = mode.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        });
+        StdRng::seed_from_u64(seed ^ mode_hash)
+    }
+
+    // Delay to wait before the catch-up pass: initial_delay plus a random part of splay
+    pub(crate) fn startup_delay(&self, rng: &mut impl Rng) -> Duration {
+        let splay_ms = self
+            .splay
+            .map_or(0, |splay| splay.num_milliseconds().max(0));
+        let random_splay = Duration::milliseconds(rng.gen_range(0..=splay_ms));
+        self.initial_delay
+            .unwrap_or_else(Duration::zero)
+            .checked_add(&random_splay)
+            .unwrap_or(Duration::MAX)
+    }
+}
+
+/// Lag of a mode whose callbacks last longer than the gap of their task, see BlockingScheduler::lag_report.
+/// The occurrences passed while a callback was running are dropped, whatever the MissedTickBehavior.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct LagReport {
+    // Time between the first occurrence missed by the last lagging task and the end of its callback
+    pub lag: Duration,
+    // Number of lagging firings in a row, reset by a callback ending before the next occurrence of its task
+    pub consecutive: u64,
+    // Occurrences dropped because of the lag since the mode has been started
+    pub missed: u64,
+}
+
+/// Occurrences of a mode held by a closed gate since its last start, see BlockingScheduler::gate_report
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct GateReport {
+    // Occurrences dropped, the task being moved to its next occurrence
+    pub skipped: u64,
+    // Occurrences checked again after SchedulerConfig::gate_recheck_interval
+    pub deferred: u64,
+}
+
+impl GateReport {
+    pub(crate) fn record(&mut self, deferred: bool) {
+        if deferred {
+            self.deferred += 1;
+        } else {
+            self.skipped += 1;
+        }
+    }
+}
+
+/// Wake-to-fire latency of a mode since its last start, recorded with SchedulerConfig::realtime: how long after the date
+/// of its task each callback has begun. The latencies are counted in buckets whose width is an eighth of their lower
+/// bound (1 µs below 16 µs), so that a percentile is at most an eighth above the latency it stands for.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct LatencyReport {
+    firings: u64,
+    max: std::time::Duration,
+    // Number of firings per bucket, see Self::bucket
+    buckets: Vec<u64>,
+}
+
+impl LatencyReport {
+    // Buckets of 1 µs below this latency, 8 buckets per power of two above it
+    const LINEAR_MICROS: u64 = 16;
+    const SUB_BUCKETS: u64 = 8;
+
+    pub(crate) fn record(&mut self, latency: std::time::Duration) {
+        let bucket = Self::bucket(u64::try_from(latency.as_micros()).unwrap_or(u64::MAX));
+        if self.buckets.len() <= bucket {
+            self.buckets.resize(bucket + 1, 0);
+        }
+        self.buckets[bucket] += 1;
+        self.firings += 1;
+        self.max = self.max.max(latency);
+    }
+
+    pub fn firings(&self) -> u64 {
+        self.firings
+    }
+    pub fn max(&self) -> std::time::Duration {
+        self.max
+    }
+    // Latency under which the given percentage of the firings have begun, rounded up to its bucket. None before a firing.
+    pub fn percentile(&self, percentage: f64) -> Option<std::time::Duration> {
+        if self.firings == 0 {
+            return None;
+        }
+        let rank =
+            ((percentage / 100.0 * self.firings as f64).ceil() as u64).clamp(1, self.firings);
+        let mut counted = 0;
+        let bucket = self.buckets.iter().position(|count| {
+            counted += count;
+            counted >= rank
+        })?;
+        Some(std::time::Duration::from_micros(Self::bucket_max(bucket)).min(self.max))
+    }
+
+    fn bucket(micros: u64) -> usize {
+        if micros < Self::LINEAR_MICROS {
+            return micros as usize;
+        }
+        let exponent = u64::from(micros.ilog2());
+        let sub_bucket = (micros >> (exponent - 3)) - Self::SUB_BUCKETS;
+        (Self::LINEAR_MICROS + (exponent - 4) * Self::SUB_BUCKETS + sub_bucket) as usize
+    }
+    // Highest latency counted in the bucket, in µs
+    fn bucket_max(bucket: usize) -> u64 {
+        let bucket = bucket as u64;
+        if bucket < Self::LINEAR_MICROS {
+            return bucket;
+        }
+        let exponent = (bucket - Self::LINEAR_MICROS) / Self::SUB_BUCKETS + 4;
+        let sub_bucket = (bucket - Self::LINEAR_MICROS) % Self::SUB_BUCKETS;
+        let width = 1_u64 << (exponent - 3);
+        (Self::SUB_BUCKETS + sub_bucket)
+            .saturating_mul(width)
+            .saturating_add(width - 1)
+    }
+}
+
+/// Summary of the catch-up pass done at the beginning of a start
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct CatchUpReport {
+    // Missed occurrences that have been fired late
+    pub replayed: u64,
+    // Missed occurrences dropped because of SchedulerConfig::max_catch_up, the ones after the count of their task aren't
+    // missed ones
+    pub skipped: u64,
+    // Late Once tasks removed without being fired, with MissedTickBehavior::Skip
+    pub removed_unfired: u64,
+    // Occurrences left after the pass to the tasks of the mode whose count is finished, see RepetitionType::remaining_count
+    pub remaining: u64,
+    // Time waited before the catch-up pass, see SchedulerConfig::initial_delay and SchedulerConfig::splay
+    pub initial_delay: Duration,
+    // Seed of the random decisions of the start, see SchedulerConfig::rng_seed
+    pub rng_seed: u64,
+}
@@ -0,0 +1,291 @@
+use super::id::TaskId;
+use super::repetitions::{CustomRepetitionSwap, RepetitionType};
+use super::schedulers::ScheduledTask;
+use super::sleeper::{ClockAdjustment, Wake};
+use std::collections::HashMap;
+use std::sync::mpsc::{SendError, Sender};
+use std::sync::{Arc, Mutex};
+
+/// Represents an order sent to a running mode
+/// - AddTask : the task is added after the tasks planned at the same date
+/// - AddUrgent : the task is added before the tasks planned at the same date, the pending sleep is interrupted
+///   so that a task due right now is triggered before anything else
+/// - Cancel : the task of the id is removed (RemovalReason::Cancelled)
+/// - Batch : the commands are applied in order, all of them before the next task is evaluated
+/// - SetRepetition : the task of the id gets the repetition, rebased according to `rebase`.
+///   With `carry_count`, the remaining count of its previous repetition is kept instead of the new one's.
+/// - Handoff : the tasks of the mode are replaced by `tasks`, see ParallelScheduler::handoff. The outcome is sent to `reply`
+/// - SetClockAdjustment : the scheduler (and its clones) reads the current date through the adjustment from then on,
+///   the pending sleep being aimed again as after a clock jump
+/// - SetCustomRepetition : the custom repetition of the scheduler (and its clones) is replaced, the next Custom task
+///   moved to its next occurrence using the new one, see BlockingScheduler::custom_repetition_swap
+#[derive(Debug)]
+pub enum SchedulerCommand<TaskType> {
+    AddTask(ScheduledTask<TaskType>),
+    AddUrgent(ScheduledTask<TaskType>),
+    Cancel(TaskId),
+    Batch(Vec<SchedulerCommand<TaskType>>),
+    SetRepetition {
+        id: TaskId,
+        repetition: RepetitionType,
+        rebase: RebasePolicy,
+        carry_count: bool,
+    },
+    Handoff {
+        tasks: Vec<ScheduledTask<TaskType>>,
+        reply: Sender<HandoffOutcome<TaskType>>,
+    },
+    SetClockAdjustment(ClockAdjustment),
+    SetCustomRepetition(CustomRepetitionSwap),
+}
+
+/// What a SchedulerCommand::Handoff did to the tasks of a mode
+#[derive(Debug)]
+pub struct HandoffOutcome<TaskType> {
+    // For each task handed over, whether it took over the progress of a task of the mode
+    pub carried: Vec<bool>,
+    // Tasks of the mode that no task handed over matched, they aren't kept in the removed tasks
+    pub dropped: Vec<ScheduledTask<TaskType>>,
+}
+
+/// Represents the date a task keeps when its repetition is changed by SchedulerCommand::SetRepetition
+/// - FromNow : the task is moved to the first occurrence of the new repetition after the current time
+///   (kept as is when the new repetition has none, Once for instance)
+/// - FromOriginal : the task keeps its date, the new repetition applies from there
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum RebasePolicy {
+    FromNow,
+    FromOriginal,
+}
+
+/// Commands gathered by SchedulerController::batch
+#[derive(Debug)]
+pub struct CommandBatch<TaskType> {
+    commands: Vec<SchedulerCommand<TaskType>>,
+}
+
+impl<TaskType> CommandBatch<TaskType> {
+    pub fn command(&mut self, command: SchedulerCommand<TaskType>) -> &mut Self {
+        self.commands.push(command);
+        self
+    }
+    pub fn add_task(&mut self, task: ScheduledTask<TaskType>) -> &mut Self {
+        self.command(SchedulerCommand::AddTask(task))
+    }
+    pub fn add_urgent(&mut self, task: ScheduledTask<TaskType>) -> &mut Self {
+        self.command(SchedulerCommand::AddUrgent(task))
+    }
+    pub fn cancel(&mut self, id: TaskId) -> &mut Self {
+        self.command(SchedulerCommand::Cancel(id))
+    }
+    pub fn set_repetition(
+        &mut self,
+        id: TaskId,
+        repetition: RepetitionType,
+        rebase: RebasePolicy,
+        carry_count: bool,
+    ) -> &mut Self {
+        self.command(SchedulerCommand::SetRepetition {
+            id,
+            repetition,
+            rebase,
+            carry_count,
+        })
+    }
+}
+
+// Senders of the modes being started, shared by a scheduler and its clones
+pub(crate) type CommandSenders<TaskType> = Arc<Mutex<HashMap<String, CommandSender<TaskType>>>>;
+
+// Sending side of a started mode, waking it up from its sleep so that the command is applied right away
+#[derive(Debug)]
+pub(crate) struct CommandSender<TaskType> {
+    sender: Sender<SchedulerCommand<TaskType>>,
+    wake: Wake,
+}
+
+impl<TaskType> Clone for CommandSender<TaskType> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            wake: self.wake.clone(),
+        }
+    }
+}
+
+impl<TaskType> CommandSender<TaskType> {
+    pub(crate) fn new(sender: Sender<SchedulerCommand<TaskType>>, wake: Wake) -> Self {
+        Self { sender, wake }
+    }
+    pub(crate) fn send(
+        &self,
+        command: SchedulerCommand<TaskType>,
+    ) -> Result<(), SendError<SchedulerCommand<TaskType>>> {
+        self.sender.send(command)?;
+        self.wake.wake();
+        Ok(())
+    }
+}
+
+/// Handle sending commands to the modes of a scheduler while they are started, from any thread
+#[derive(Debug)]
+pub struct SchedulerController<TaskType> {
+    senders: CommandSenders<TaskType>,
+}
+
+impl<TaskType> Clone for SchedulerController<TaskType> {
+    fn clone(&self) -> Self {
+        Self {
+            senders: self.senders.clone(),
+        }
+    }
+}
+
+impl<TaskType> SchedulerController<TaskType> {
+    pub(crate) fn new(senders: CommandSenders<TaskType>) -> Self {
+        Self { senders }
+    }
+
+    pub fn send(&self, mode: &str, command: SchedulerCommand<TaskType>) -> Result<(), String> {
+        self.senders
+            .lock()
+            .unwrap()
+            .get(mode)
+            .ok_or(format!("The requested mode isn't started : {}", mode))?
+            .send(command)
+            .or(Err(format!("The requested mode has stopped : {}", mode)))
+    }
+    pub fn add_task(&self, mode: &str, task: ScheduledTask<TaskType>) -> Result<(), String> {
+        self.send(mode, SchedulerCommand::AddTask(task))
+    }
+    pub fn add_urgent(&self, mode: &str, task: ScheduledTask<TaskType>) -> Result<(), String> {
+        self.send(mode, SchedulerCommand::AddUrgent(task))
+    }
+    pub fn cancel(&self, mode: &str, id: TaskId) -> Result<(), String> {
+        self.send(mode, SchedulerCommand::Cancel(id))
+    }
+    // Changes the repetition of the task of the id, see SchedulerCommand::SetRepetition.
+    // The running mode wakes up, so that a task moved earlier than the one it was waiting for isn't delayed.
+    pub fn set_repetition(
+        &self,
+        mode: &str,
+        id: TaskId,
+        repetition: RepetitionType,
+        rebase: RebasePolicy,
+        carry_count: bool,
+    ) -> Result<(), String> {
+        self.send(
+            mode,
+            SchedulerCommand::SetRepetition {
+                id,
+                repetition,
+                rebase,
+                carry_count,
+            },
+        )
+    }
+    // See SchedulerCommand::SetClockAdjustment
+    pub fn set_clock_adjustment(
+        &self,
+        mode: &str,
+        clock_adjustment: ClockAdjustment,
+    ) -> Result<(), String> {
+        self.send(mode, SchedulerCommand::SetClockAdjustment(clock_adjustment))
+    }
+    // See SchedulerCommand::SetCustomRepetition
+    pub fn set_custom_repetition(
+        &self,
+        mode: &str,
+        swap: CustomRepetitionSwap,
+    ) -> Result<(), String> {
+        self.send(mode, SchedulerCommand::SetCustomRepetition(swap))
+    }
+    // Sends the commands gathered by build as a single SchedulerCommand::Batch, so that the running mode wakes up once
+    pub fn batch(
+        &self,
+        mode: &str,
+        build: impl FnOnce(&mut CommandBatch<TaskType>),
+    ) -> Result<(), String> {
+        let mut batch = CommandBatch {
+            commands: Vec::new(),
+        };
+        build(&mut batch);
+        self.send(mode, SchedulerCommand::Batch(batch.commands))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use crate::schedulers::RunObserver;
+    use chrono::{Duration, Local};
+    use std::collections::HashMap;
+    use std::thread;
+
+    crate::system_clock!(|| Local::now().into());
+
+    // Tasks of each replacement of the mode, and the number of waits for its next task
+    #[derive(Default)]
+    struct Reevaluations {
+        replaced: Vec<Vec<u32>>,
+        waits: usize,
+    }
+
+    impl RunObserver<u32> for Reevaluations {
+        fn replaced(&mut self, tasks: &[ScheduledTask<u32>], _: &[ScheduledTask<u32>]) {
+            self.replaced
+                .push(tasks.iter().map(|task| task.task).collect());
+        }
+        fn removed(
+            &mut self,
+            _: &mut Vec<ScheduledTask<u32>>,
+            waiting: Option<std::time::Duration>,
+        ) {
+            self.waits += usize::from(waiting.is_some());
+        }
+    }
+
+    #[test]
+    fn batch_is_applied_at_once() {
+        let once = |in_ms, task| {
+            let date = Local::now() + Duration::milliseconds(in_ms);
+            ScheduledTask::new(date, task, RepetitionType::Once, SleepType::Native)
+        };
+        let tasks = vec![once(300, 1), once(600, 9)];
+        let mut scheduler =
+            BlockingScheduler::new(HashMap::from([("m".to_string(), tasks)]), HashMap::new());
+        let head = scheduler.scheduled_tasks["m"][0].id().unwrap();
+        let controller = scheduler.controller();
+        let worker = thread::spawn(move || {
+            let mut fired = Vec::new();
+            let mut reevaluations = Reevaluations::default();
+            scheduler
+                .run(
+                    "m",
+                    |task| {
+                        fired.push(task.task);
+                        None
+                    },
+                    &mut reevaluations,
+                )
+                .unwrap();
+            (fired, reevaluations, scheduler)
+        });
+        thread::sleep(std::time::Duration::from_millis(100));
+        // The head is cancelled and an earlier task added, while the mode sleeps toward the head
+        controller
+            .batch("m", |batch| {
+                batch.cancel(head).add_task(once(50, 2));
+            })
+            .unwrap();
+        let (fired, reevaluations, scheduler) = worker.join().unwrap();
+        assert_eq!(fired, [2, 9]);
+        assert_eq!(reevaluations.replaced, [vec![2, 9]]);
+        // Toward the head, then toward the task added and the last one
+        assert_eq!(reevaluations.waits, 3);
+        let cancelled = &scheduler.removed_tasks["m"];
+        assert!(cancelled
+            .iter()
+            .any(|task| task.task == 1 && task.removal_reason == Some(RemovalReason::Cancelled)));
+    }
+}
@@ -0,0 +1,307 @@
+//! Schedules read from CSV, one task per row, the first row naming the columns:
+//! - the date column : the date of the task, RFC 3339 or a strftime pattern read in a given offset (see CsvDate)
+//! - the repetition column : human form of the repetition, see the FromStr impl of RepetitionType ("once" when missing
+//!   or empty)
+//! - the schedule column : when not empty, a WallSchedule replacing the date and the repetition, the row giving a task
+//!   per weekday and time (see WallSchedule::slots). The date column can be left out when there's a schedule column.
+//! - the other columns : given in their order to the payload constructor of the CsvOptions
+//!
+//! The errors cite the line of the row. With CsvOptions::skip_bad_rows, the invalid rows are skipped and returned
+//! along with the scheduler instead.
+//!
+//! ```
+//! use chrono::FixedOffset;
+//! use scheduler::csv_import::{CsvDate, CsvImportError, CsvOptions};
+//! use scheduler::prelude::*;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let sends = "\
+//! date,repetition,recipient,subject
+//! 2024-03-01T09:00:00+01:00,,ana@example.com,Welcome
+//! 2024-03-02T09:00:00+01:00,every 1d 3 times,bob@example.com,Reminder
+//! 2024-02-30T09:00:00+01:00,,carl@example.com,Invoice
+//! 2024-03-04T09:00:00+01:00,fortnightly,dana@example.com,Digest
+//! ";
+//! let options = CsvOptions::new(|columns| Ok(columns.iter().collect::<Vec<_>>().join(" : ")));
+//! let error = BlockingScheduler::<String>::from_csv_reader(sends.as_bytes(), "sends", options.clone()).unwrap_err();
+//! assert!(matches!(error, CsvImportError::InvalidRow { line: 4, .. }));
+//!
+//! let (scheduler, skipped) = BlockingScheduler::<String>::from_csv_reader(
+//!     sends.as_bytes(),
+//!     "sends",
+//!     CsvOptions { skip_bad_rows: true, ..options.clone() },
+//! )
+//! .unwrap();
+//! assert_eq!(scheduler.scheduled_tasks["sends"].len(), 2);
+//! assert_eq!(scheduler.scheduled_tasks["sends"][1].task, "bob@example.com : Reminder");
+//! assert_eq!(skipped.iter().map(CsvImportError::line).collect::<Vec<_>>(), [Some(4), Some(5)]);
+//! assert_eq!(
+//!     skipped[1].to_string(),
+//!     "Invalid row at line 5 : Unknown repetition : fortnightly"
+//! );
+//!
+//! // Dates written by a spreadsheet, in the offset of the analysts
+//! let sends = "when,recipient\n01/03/2024 09:00,ana@example.com\n";
+//! let paris = FixedOffset::east_opt(3600).unwrap();
+//! let options = CsvOptions {
+//!     date_column: "when".to_string(),
+//!     date_format: CsvDate::Pattern { format: "%d/%m/%Y %H:%M".to_string(), offset: paris },
+//!     ..options
+//! };
+//! let (scheduler, _) = BlockingScheduler::<String>::from_csv_reader(sends.as_bytes(), "sends", options).unwrap();
+//! let task = &scheduler.scheduled_tasks["sends"][0];
+//! assert_eq!(task.date.to_rfc3339(), "2024-03-01T09:00:00+01:00");
+//! assert_eq!(task.repetition, RepetitionType::Once);
+//!
+//! // Rows following a weekly schedule
+//! let sends = "schedule,recipient\n\"Mon,Fri 08:00 +01:00\",ana@example.com\n";
+//! let options = CsvOptions::new(|columns| Ok(columns.iter().collect::<Vec<_>>().join(" : ")));
+//! let (scheduler, _) = BlockingScheduler::<String>::from_csv_reader(sends.as_bytes(), "sends", options).unwrap();
+//! assert_eq!(scheduler.scheduled_tasks["sends"].len(), 2);
+//! assert!(scheduler.scheduled_tasks["sends"].iter().all(|task| task.task == "ana@example.com"));
+//! ```
+use super::repetitions::{NoCustomRepetition, RepetitionType};
+use super::schedulers::{BlockingScheduler, ScheduledTask};
+use super::sleeper::{Clock, SystemClock};
+use super::sleeptype::SleepType;
+use super::wall::WallSchedule;
+use ::csv::{ReaderBuilder, StringRecord};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::io::Read;
+
+/// Represents how the dates are written
+/// - Rfc3339 : "2024-03-01T09:00:00+01:00"
+/// - Pattern : a strftime pattern without offset ("%d/%m/%Y %H:%M"), the dates being read in the given offset
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum CsvDate {
+    Rfc3339,
+    Pattern { format: String, offset: FixedOffset },
+}
+
+/// How the rows are read by BlockingScheduler::from_csv_reader
+#[derive(Clone, Debug)]
+pub struct CsvOptions<TaskType> {
+    // Name of the date column, "date" by default
+    pub date_column: String,
+    pub date_format: CsvDate,
+    // Name of the repetition column, "repetition" by default. The tasks are triggered once when there is no such column.
+    pub repetition_column: Option<String>,
+    // Name of the schedule column, "schedule" by default
+    pub schedule_column: Option<String>,
+    // Builds the payload from the other columns of the row, in their order
+    pub payload: fn(StringRecord) -> Result<TaskType, String>,
+    // Skips the invalid rows instead of failing, they're returned along with the scheduler
+    pub skip_bad_rows: bool,
+}
+
+impl<TaskType> CsvOptions<TaskType> {
+    pub fn new(payload: fn(StringRecord) -> Result<TaskType, String>) -> Self {
+        Self {
+            date_column: "date".to_string(),
+            date_format: CsvDate::Rfc3339,
+            repetition_column: Some("repetition".to_string()),
+            schedule_column: Some("schedule".to_string()),
+            payload,
+            skip_bad_rows: false,
+        }
+    }
+}
+
+impl<TaskType> CsvOptions<TaskType>
+where
+    TaskType: TryFrom<StringRecord>,
+    TaskType::Error: Display,
+{
+    // Options building the payloads with their TryFrom<StringRecord> impl
+    pub fn try_from_record() -> Self {
+        Self::new(|columns| TaskType::try_from(columns).map_err(|error| error.to_string()))
+    }
+}
+
+/// Represents why a CSV schedule couldn't be read
+/// - Read : the CSV itself is invalid or couldn't be read
+/// - MissingColumn : the first row doesn't name the date column (nor the schedule one)
+/// - InvalidRow : the date, repetition, schedule or payload of the row at the line is invalid
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[non_exhaustive]
+pub enum CsvImportError {
+    Read { line: Option<u64>, reason: String },
+    MissingColumn(String),
+    InvalidRow { line: u64, reason: String },
+}
+
+impl CsvImportError {
+    // Line of the row the error is about, when it's about one
+    pub fn line(&self) -> Option<u64> {
+        match self {
+            Self::Read { line, .. } => *line,
+            Self::MissingColumn(_) => None,
+            Self::InvalidRow { line, .. } => Some(*line),
+        }
+    }
+}
+
+impl Display for CsvImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Read {
+                line: Some(line),
+                reason,
+            } => write!(f, "Couldn't read the row at line {} : {}", line, reason),
+            Self::Read { line: None, reason } => write!(f, "Couldn't read the CSV : {}", reason),
+            Self::MissingColumn(column) => write!(f, "Missing column : {}", column),
+            Self::InvalidRow { line, reason } => {
+                write!(f, "Invalid row at line {} : {}", line, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CsvImportError {}
+
+impl From<CsvImportError> for String {
+    fn from(error: CsvImportError) -> Self {
+        error.to_string()
+    }
+}
+
+impl From<::csv::Error> for CsvImportError {
+    fn from(error: ::csv::Error) -> Self {
+        Self::Read {
+            line: error.position().map(|position| position.line()),
+            reason: error.to_string(),
+        }
+    }
+}
+
+// Positions of the columns read by the scheduler
+struct Columns {
+    date: Option<usize>,
+    repetition: Option<usize>,
+    schedule: Option<usize>,
+}
+
+impl Columns {
+    fn find(headers: &StringRecord, name: &str) -> Result<usize, CsvImportError> {
+        headers
+            .iter()
+            .position(|header| header.trim() == name)
+            .ok_or(CsvImportError::MissingColumn(name.to_owned()))
+    }
+
+    fn tasks<TaskType>(
+        &self,
+        row: &StringRecord,
+        options: &CsvOptions<TaskType>,
+    ) -> Result<Vec<ScheduledTask<TaskType>>, String> {
+        let cell = |column: Option<usize>| {
+            column
+                .and_then(|column| row.get(column))
+                .map(str::trim)
+                .filter(|cell| !cell.is_empty())
+        };
+        let slots = match (cell(self.date), cell(self.repetition), cell(self.schedule)) {
+            (None, None, Some(schedule)) => {
+                schedule.parse::<WallSchedule>()?.slots(SystemClock.now())?
+            }
+            (_, _, Some(_)) => {
+                return Err(
+                    "A row with a schedule can't be given a date or a repetition".to_string(),
+                )
+            }
+            (date, repetition, None) => {
+                let date = date.unwrap_or_default();
+                let date = match &options.date_format {
+                    CsvDate::Rfc3339 => DateTime::parse_from_rfc3339(date),
+                    CsvDate::Pattern { format, offset } => {
+                        NaiveDateTime::parse_from_str(date, format).map(|naive| {
+                            // A fixed offset maps every local date to a single date
+                            offset.from_local_datetime(&naive).unwrap()
+                        })
+                    }
+                }
+                .map_err(|error| format!("Invalid date {} : {}", date, error))?;
+                let repetition = match repetition {
+                    Some(repetition) => repetition.parse()?,
+                    None => RepetitionType::Once,
+                };
+                if repetition == RepetitionType::Custom {
+                    return Err("Custom repetitions can't be loaded from a file".to_string());
+                }
+                vec![(date, repetition)]
+            }
+        };
+        let read = [self.date, self.repetition, self.schedule];
+        let payload: StringRecord = row
+            .iter()
+            .enumerate()
+            .filter(|(column, _)| !read.contains(&Some(*column)))
+            .map(|(_, value)| value)
+            .collect();
+        slots
+            .into_iter()
+            .map(|(date, repetition)| {
+                Ok(ScheduledTask::new(
+                    date,
+                    (options.payload)(payload.clone())?,
+                    repetition,
+                    SleepType::Native,
+                ))
+            })
+            .collect()
+    }
+}
+
+impl<TaskType> BlockingScheduler<TaskType, NoCustomRepetition>
+where
+    TaskType: Eq + Default,
+{
+    // Builds a scheduler whose mode holds a task per row of the CSV, see the csv_import module.
+    // The rows skipped by CsvOptions::skip_bad_rows are returned along with it.
+    pub fn from_csv_reader<R: Read>(
+        reader: R,
+        mode: &str,
+        options: CsvOptions<TaskType>,
+    ) -> Result<(Self, Vec<CsvImportError>), CsvImportError> {
+        let mut reader = ReaderBuilder::new().flexible(true).from_reader(reader);
+        let headers = reader.headers()?;
+        let optional = |column: &Option<String>| {
+            column
+                .as_ref()
+                .and_then(|column| Columns::find(headers, column).ok())
+        };
+        let schedule = optional(&options.schedule_column);
+        let columns = Columns {
+            date: match Columns::find(headers, &options.date_column) {
+                Ok(date) => Some(date),
+                Err(_) if schedule.is_some() => None,
+                Err(error) => return Err(error),
+            },
+            repetition: optional(&options.repetition_column),
+            schedule,
+        };
+        let mut tasks = Vec::new();
+        let mut skipped = Vec::new();
+        for row in reader.records() {
+            let row_tasks = row.map_err(CsvImportError::from).and_then(|row| {
+                columns
+                    .tasks(&row, &options)
+                    .map_err(|reason| CsvImportError::InvalidRow {
+                        line: row.position().map_or(0, |position| position.line()),
+                        reason,
+                    })
+            });
+            match row_tasks {
+                Ok(row_tasks) => tasks.extend(row_tasks),
+                Err(error) if options.skip_bad_rows => skipped.push(error),
+                Err(error) => return Err(error),
+            }
+        }
+        Ok((
+            Self::new([(mode.to_owned(), tasks)], HashMap::new()),
+            skipped,
+        ))
+    }
+}
@@ -0,0 +1,170 @@
+//! Schedules written in TOML. Each top-level key is a mode, holding an array of tasks:
+//! - date : RFC 3339 date, quoted or not
+//! - repetition : human form of the repetition, see the FromStr impl of RepetitionType ("once" when missing)
+//! - schedule : instead of the date and the repetition, a WallSchedule ("Mon,Wed 09:30 +01:00"), the entry giving a task
+//!   per weekday and time, see WallSchedule::slots
+//! - sleep : the SleepType, "Native" when missing
+//! - skip_holidays : see ScheduledTask::skipping_holidays, false when missing
+//! - payload : the task itself, deserialized into the task type
+//!
+//! ```
+//! use scheduler::prelude::*;
+//! use serde::Deserialize;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! #[derive(Deserialize, PartialEq, Eq, Default, Debug)]
+//! struct Backup {
+//!     path: String,
+//! }
+//!
+//! let schedule = r#"
+//!     [[nightly]]
+//!     date = 2024-01-01T02:00:00+01:00
+//!     repetition = "every 1d 30 times"
+//!     payload = { path = "/home" }
+//!
+//!     [[nightly]]
+//!     date = "2024-01-01T03:00:00+01:00"
+//!     repetition = "weekly"
+//!     sleep = "Native"
+//!     payload = { path = "/var" }
+//!
+//!     [[weekly]]
+//!     schedule = "Mon,Thu 22:00 +01:00"
+//!     payload = { path = "/srv" }
+//! "#;
+//! let scheduler = BlockingScheduler::<Backup>::from_toml_str(schedule).unwrap();
+//! assert_eq!(scheduler.scheduled_tasks["nightly"].len(), 2);
+//! assert_eq!(scheduler.scheduled_tasks["weekly"].len(), 2);
+//! assert_eq!(scheduler.scheduled_tasks["weekly"][0].repetition, RepetitionType::Weekly(Termination::default()));
+//! assert_eq!(scheduler.scheduled_tasks["nightly"][1].task.path, "/var");
+//!
+//! let error = BlockingScheduler::<Backup>::from_toml_str(&schedule.replace("weekly", "fortnightly")).unwrap_err();
+//! assert_eq!(error, "Invalid task 1 of the mode nightly : Unknown repetition : fortnightly");
+//! let scheduler = BlockingScheduler::<Backup>::from_toml_str(&schedule.replace("Thu", "Thursday,Sat")).unwrap();
+//! assert_eq!(scheduler.scheduled_tasks["weekly"].len(), 3);
+//! let error = BlockingScheduler::<Backup>::from_toml_str(&schedule.replace("22:00 +01:00", "22:00 Europe/Paris")).unwrap_err();
+//! assert!(error.starts_with("Invalid task 0 of the mode weekly : Named time zones aren't supported"));
+//! ```
+use super::repetitions::{NoCustomRepetition, RepetitionType};
+use super::schedulers::{BlockingScheduler, ScheduledTask};
+use super::sleeper::{Clock, SystemClock};
+use super::sleeptype::SleepType;
+use super::wall::WallSchedule;
+use chrono::{DateTime, FixedOffset};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use toml::{Table, Value};
+
+// Task as written in the file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TaskEntry {
+    #[serde(default)]
+    date: Option<Value>,
+    #[serde(default)]
+    repetition: Option<String>,
+    #[serde(default)]
+    schedule: Option<String>,
+    #[serde(default)]
+    sleep: SleepType,
+    #[serde(default)]
+    skip_holidays: bool,
+    // Deserialized into the task type once per task of the entry
+    payload: Value,
+}
+
+impl TaskEntry {
+    fn into_tasks<TaskType: DeserializeOwned>(
+        self,
+        now: DateTime<FixedOffset>,
+    ) -> Result<Vec<ScheduledTask<TaskType>>, String> {
+        let slots = match (self.date, self.repetition, self.schedule) {
+            (None, None, Some(schedule)) => schedule.parse::<WallSchedule>()?.slots(now)?,
+            (Some(date), repetition, None) => {
+                let date = match date {
+                    Value::String(date) => date,
+                    Value::Datetime(date) => date.to_string(),
+                    date => return Err(format!("Invalid date : {}", date)),
+                };
+                let date = DateTime::parse_from_rfc3339(&date)
+                    .map_err(|error| format!("Invalid date {} : {}", date, error))?;
+                let repetition = match repetition {
+                    Some(repetition) => repetition.parse()?,
+                    None => RepetitionType::Once,
+                };
+                if repetition == RepetitionType::Custom {
+                    return Err("Custom repetitions can't be loaded from a file".to_string());
+                }
+                vec![(date, repetition)]
+            }
+            (_, _, Some(_)) => {
+                return Err(
+                    "A task with a schedule can't be given a date or a repetition".to_string(),
+                )
+            }
+            (None, _, None) => return Err("A task needs a date or a schedule".to_string()),
+        };
+        slots
+            .into_iter()
+            .map(|(date, repetition)| {
+                let payload = self
+                    .payload
+                    .clone()
+                    .try_into()
+                    .map_err(|error: toml::de::Error| error.message().to_owned())?;
+                let task = ScheduledTask::new(date, payload, repetition, self.sleep);
+                Ok(if self.skip_holidays {
+                    task.skipping_holidays()
+                } else {
+                    task
+                })
+            })
+            .collect()
+    }
+}
+
+impl<TaskType> BlockingScheduler<TaskType, NoCustomRepetition>
+where
+    TaskType: DeserializeOwned + Eq + Default,
+{
+    // Builds a scheduler from a TOML schedule, see the module documentation.
+    // The errors cite the mode and the position of the task in it.
+    pub fn from_toml_str(schedule: &str) -> Result<Self, String> {
+        let modes: Table =
+            toml::from_str(schedule).map_err(|error| format!("Invalid schedule : {}", error))?;
+        let now = SystemClock.now();
+        let mut scheduled_tasks = HashMap::new();
+        for (mode, tasks) in modes {
+            let Value::Array(tasks) = tasks else {
+                return Err(format!("The mode {} must be an array of tasks", mode));
+            };
+            let tasks = tasks
+                .into_iter()
+                .enumerate()
+                .map(|(index, task)| {
+                    task.try_into()
+                        .map_err(|error: toml::de::Error| error.message().to_owned())
+                        .and_then(|entry: TaskEntry| entry.into_tasks(now))
+                        .map_err(|error| {
+                            format!("Invalid task {} of the mode {} : {}", index, mode, error)
+                        })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let tasks: Vec<_> = tasks.into_iter().flatten().collect();
+            scheduled_tasks.insert(mode, tasks);
+        }
+        Ok(Self::new(scheduled_tasks, HashMap::new()))
+    }
+
+    pub fn from_toml_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let schedule = fs::read_to_string(path).map_err(|error| {
+            format!("Couldn't read the schedule {} : {}", path.display(), error)
+        })?;
+        Self::from_toml_str(&schedule)
+    }
+}
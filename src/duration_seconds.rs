@@ -0,0 +1,65 @@
+// Serde form of the durations: a whole number of seconds, rounded half away from zero. Written the same as with the
+// DurationSeconds<i64> of serde_with, so that the schedules saved before are read and written the same.
+use chrono::Duration;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub(crate) fn serialize<S: Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let seconds = duration.num_seconds();
+    let rounded = match duration.subsec_nanos() {
+        nanos if nanos >= 500_000_000 => seconds + 1,
+        nanos if nanos <= -500_000_000 => seconds - 1,
+        _ => seconds,
+    };
+    rounded.serialize(serializer)
+}
+
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Duration, D::Error> {
+    from_seconds(i64::deserialize(deserializer)?).map_err(D::Error::custom)
+}
+
+fn from_seconds(seconds: i64) -> Result<Duration, String> {
+    Duration::try_seconds(seconds).ok_or(format!(
+        "Duration is outside of the representable range: {} seconds",
+        seconds
+    ))
+}
+
+// Same for the optional durations
+pub(crate) mod option {
+    use chrono::Duration;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    // Written as a duration, inside the Some of the format
+    struct Seconds<'d>(&'d Duration);
+
+    impl Serialize for Seconds<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            super::serialize(self.0, serializer)
+        }
+    }
+
+    pub(crate) fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_some(&Seconds(duration)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Option::<i64>::deserialize(deserializer)?
+            .map(|seconds| super::from_seconds(seconds).map_err(D::Error::custom))
+            .transpose()
+    }
+}
@@ -0,0 +1,130 @@
+//! Errors of the scheduler. The methods returning a String error give the Display of these.
+//!
+//! ```
+//! use chrono::Local;
+//! use scheduler::prelude::*;
+//! use std::collections::HashMap;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let task = ScheduledTask::new(Local::now(), "report", RepetitionType::Once, SleepType::Native);
+//! let modes = ["weekly", "weeks", "monthly", "daily"];
+//! let mut scheduler = BlockingScheduler::new(
+//!     modes.map(|mode| (mode.to_string(), vec![task.clone()])),
+//!     HashMap::new(),
+//! );
+//! assert!(scheduler.has_mode("weekly"));
+//! assert_eq!(
+//!     scheduler.start("weekl", |_| {}).unwrap_err(),
+//!     "Couldn't find the requested mode : weekl (did you mean weekly, weeks ?)"
+//! );
+//! assert_eq!(
+//!     scheduler.unknown_mode("dayly"),
+//!     SchedulerError::UnknownMode {
+//!         mode: "dayly".to_string(),
+//!         suggestions: vec!["daily".to_string()],
+//!     }
+//! );
+//! assert_eq!(
+//!     scheduler.tick("hourly", Local::now(), |_| {}).unwrap_err(),
+//!     "Couldn't find the requested mode : hourly"
+//! );
+//! ```
+use std::collections::BTreeMap;
+
+// Modes suggested at most by SchedulerError::UnknownMode
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Represents why an operation of the scheduler failed
+/// - UnknownMode : no mode has the requested name. The suggestions are the modes with a close name, the closest first.
+/// - AlreadyRunning : the mode is already started, by this scheduler or one of its clones (see ConcurrentStart)
+/// - Failed : the run of the mode has stopped with the error, see ModeRunOutcome
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[non_exhaustive]
+pub enum SchedulerError {
+    UnknownMode {
+        mode: String,
+        suggestions: Vec<String>,
+    },
+    AlreadyRunning {
+        mode: String,
+    },
+    Failed {
+        mode: String,
+        reason: String,
+    },
+}
+
+impl SchedulerError {
+    // UnknownMode for the mode, suggesting the modes whose name is within a few edits of it
+    pub(crate) fn unknown_mode<'m>(mode: &str, modes: impl Iterator<Item = &'m String>) -> Self {
+        let max_distance = (mode.chars().count() / 3).max(2);
+        let mut close_modes: Vec<(usize, &String)> = modes
+            .map(|existing| (edit_distance(mode, existing), existing))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .collect();
+        close_modes.sort();
+        Self::UnknownMode {
+            mode: mode.to_owned(),
+            suggestions: close_modes
+                .into_iter()
+                .take(MAX_SUGGESTIONS)
+                .map(|(_, existing)| existing.clone())
+                .collect(),
+        }
+    }
+
+    // Tasks of the mode, or the UnknownMode error (given as a String by the methods returning one)
+    pub(crate) fn mode_mut<'m, T>(
+        modes: &'m mut BTreeMap<String, T>,
+        mode: &str,
+    ) -> Result<&'m mut T, Self> {
+        if !modes.contains_key(mode) {
+            return Err(Self::unknown_mode(mode, modes.keys()));
+        }
+        // The mode has just been found
+        Ok(modes.get_mut(mode).unwrap())
+    }
+}
+
+impl std::fmt::Display for SchedulerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownMode { mode, suggestions } => {
+                write!(f, "Couldn't find the requested mode : {}", mode)?;
+                if !suggestions.is_empty() {
+                    write!(f, " (did you mean {} ?)", suggestions.join(", "))?;
+                }
+                Ok(())
+            }
+            Self::AlreadyRunning { mode } => {
+                write!(f, "The requested mode is already started : {}", mode)
+            }
+            Self::Failed { mode, reason } => write!(f, "The mode {} has failed : {}", mode, reason),
+        }
+    }
+}
+
+impl std::error::Error for SchedulerError {}
+
+impl From<SchedulerError> for String {
+    fn from(error: SchedulerError) -> Self {
+        error.to_string()
+    }
+}
+
+// Levenshtein distance between the two strings, counted in chars
+fn edit_distance(from: &str, to: &str) -> usize {
+    let to: Vec<char> = to.chars().collect();
+    // Distances from the part of from read so far to each prefix of to
+    let mut distances: Vec<usize> = (0..=to.len()).collect();
+    for (i, from_char) in from.chars().enumerate() {
+        let mut previous_diagonal = distances[0];
+        distances[0] = i + 1;
+        for (j, to_char) in to.iter().enumerate() {
+            let substitution = previous_diagonal + usize::from(from_char != *to_char);
+            previous_diagonal = distances[j + 1];
+            distances[j + 1] = substitution.min(distances[j] + 1).min(distances[j + 1] + 1);
+        }
+    }
+    distances[to.len()]
+}
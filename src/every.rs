@@ -0,0 +1,195 @@
+//! Shortcuts for the simplest recurring jobs: a single task repeated forever on its own thread.
+//!
+//! ```
+//! use scheduler::every::Every;
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! static FIRED: AtomicUsize = AtomicUsize::new(0);
+//!
+//! let job = Every::milliseconds(100)
+//!     .starting_now()
+//!     .with_payload("ping")
+//!     .spawn(|_| {
+//!         FIRED.fetch_add(1, Ordering::SeqCst);
+//!     })
+//!     .unwrap();
+//! assert!(job.next_fire().is_some());
+//! std::thread::sleep(std::time::Duration::from_millis(350));
+//! job.stop();
+//! job.join().unwrap();
+//! assert!(FIRED.load(Ordering::SeqCst) >= 3);
+//! ```
+use super::config::MissedTickBehavior;
+use super::repetitions::{NoCustomRepetition, RepetitionCount, RepetitionType};
+use super::schedulers::{BlockingScheduler, ParallelScheduler, ScheduledTask};
+use super::sleeper::{Clock, SystemClock};
+use super::sleeptype::SleepType;
+use super::wall::{DayRule, MonthOverflow, WallSchedule, WallZone};
+use chrono::{DateTime, Duration, FixedOffset, NaiveTime, Timelike, Weekday};
+use std::collections::HashMap;
+
+// Mode of the scheduler built by EveryJob::spawn
+const EVERY_MODE: &str = "every";
+
+/// When a job repeats, see the module documentation
+#[derive(Clone, Debug)]
+pub struct Every {
+    date: DateTime<FixedOffset>,
+    repetition: RepetitionType,
+}
+
+impl Every {
+    // The first occurrence is one gap after now, see Self::starting_now
+    pub fn gap(gap: Duration) -> Self {
+        let now = SystemClock.now();
+        Self {
+            date: now + gap,
+            repetition: RepetitionType::ConstGap {
+                gap,
+                termination: RepetitionCount::Infinite.into(),
+            },
+        }
+    }
+    pub fn milliseconds(milliseconds: i64) -> Self {
+        Self::gap(Duration::milliseconds(milliseconds))
+    }
+    pub fn seconds(seconds: i64) -> Self {
+        Self::gap(Duration::seconds(seconds))
+    }
+    pub fn minutes(minutes: i64) -> Self {
+        Self::gap(Duration::minutes(minutes))
+    }
+    pub fn hours(hours: i64) -> Self {
+        Self::gap(Duration::hours(hours))
+    }
+
+    // Every day at the given local time, starting with the next one. Fails when the time never happens in the local zone
+    // in the coming days, see WallSchedule::slots.
+    pub fn day_at(time: NaiveTime) -> Result<Self, String> {
+        Self::local_slot(DayRule::Daily, time)
+    }
+
+    // Every week on the given day at the given local time, starting with the next one. Fails as Self::day_at.
+    pub fn weekday_at(weekday: Weekday, time: NaiveTime) -> Result<Self, String> {
+        Self::local_slot(DayRule::Weekdays(vec![weekday]), time)
+    }
+
+    // Every month on the given day at the given local time, starting with the next one. The months without the day get
+    // their last day (MonthOverflow::Clamp), see RepetitionType::Monthly.
+    pub fn month_day_at(day: u32, time: NaiveTime) -> Result<Self, String> {
+        Self::schedule(
+            &WallSchedule::new(DayRule::DayOfMonth(day), vec![time], WallZone::Local)?
+                .with_overflow(MonthOverflow::Clamp)?,
+        )
+    }
+
+    // Following the schedule, starting with its next occurrence. Fails when the schedule is made of several tasks (several
+    // weekdays or times), see WallSchedule::slots.
+    pub fn schedule(schedule: &WallSchedule) -> Result<Self, String> {
+        match schedule.slots(SystemClock.now())?.as_slice() {
+            [(date, repetition)] => Ok(Self {
+                date: *date,
+                repetition: repetition.clone(),
+            }),
+            _ => Err(format!(
+                "The schedule {} is made of several tasks, a job only has one",
+                schedule
+            )),
+        }
+    }
+
+    // The first occurrence happens as soon as the job is spawned
+    pub fn starting_now(self) -> Self {
+        self.starting_at(SystemClock.now())
+    }
+    pub fn starting_at(self, date: impl Into<DateTime<FixedOffset>>) -> Self {
+        Self {
+            date: date.into(),
+            ..self
+        }
+    }
+
+    pub fn with_payload<TaskType>(self, task: TaskType) -> EveryJob<TaskType> {
+        EveryJob { every: self, task }
+    }
+
+    // Schedule of a single local time, the time being whole seconds
+    fn local_slot(days: DayRule, time: NaiveTime) -> Result<Self, String> {
+        let time = time.with_nanosecond(0).unwrap_or(time);
+        Self::schedule(&WallSchedule::new(days, vec![time], WallZone::Local)?)
+    }
+}
+
+/// A payload and when it repeats, ready to be spawned
+#[derive(Clone, Debug)]
+pub struct EveryJob<TaskType> {
+    every: Every,
+    task: TaskType,
+}
+
+impl<TaskType> EveryJob<TaskType>
+where
+    TaskType: Eq + Default + Send + Sync + Clone + 'static,
+{
+    // Starts the job on its own thread
+    pub fn spawn(self, f: fn(&TaskType)) -> std::io::Result<RunningJob<TaskType>> {
+        let task = ScheduledTask::new(
+            self.every.date,
+            self.task,
+            self.every.repetition.clone(),
+            SleepType::Native,
+        );
+        let mut scheduler = BlockingScheduler::new(
+            HashMap::from([(EVERY_MODE.to_owned(), vec![task])]),
+            HashMap::new(),
+        );
+        // A job starting now is already late once its thread runs
+        scheduler.config.missed_tick_behavior = MissedTickBehavior::FireOnce;
+        let mut parallel_scheduler = ParallelScheduler::from(scheduler);
+        parallel_scheduler.start(EVERY_MODE.to_owned(), f)?;
+        Ok(RunningJob {
+            scheduler: parallel_scheduler,
+            date: self.every.date,
+            repetition: self.every.repetition,
+        })
+    }
+}
+
+/// Handle of a spawned job
+pub struct RunningJob<TaskType> {
+    scheduler: ParallelScheduler<'static, TaskType>,
+    date: DateTime<FixedOffset>,
+    repetition: RepetitionType,
+}
+
+impl<TaskType> RunningJob<TaskType> {
+    // Stops the job once its current callback is over
+    pub fn stop(&self) {
+        self.scheduler.stop();
+    }
+
+    // Waits for the job to stop
+    pub fn join(mut self) -> Result<(), String> {
+        self.scheduler
+            .join_all()
+            .results
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(())
+    }
+
+    // Date of the next occurrence, None once the job is stopped
+    pub fn next_fire(&self) -> Option<DateTime<FixedOffset>> {
+        if self.scheduler.is_stopped() {
+            return None;
+        }
+        let now = SystemClock.now();
+        if now < self.date {
+            Some(self.date)
+        } else {
+            self.repetition
+                .next_occurrence(&now, &self.date, &NoCustomRepetition)
+        }
+    }
+}
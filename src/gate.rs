@@ -0,0 +1,71 @@
+//! Gates: external conditions a task only fires under ("only when the feature flag is on", "only when the disk is less
+//! than 80% full"), attached to a task (ScheduledTask::with_gate) or to every task of a mode (BlockingScheduler::set_gate).
+//! A gate is asked when an occurrence is due, by start and tick. When the gate of the task or the one of its mode is
+//! closed, the occurrence isn't fired: it's checked again after SchedulerConfig::gate_recheck_interval (the task
+//! keeping its cadence once it fires), or skipped (the task moving to its next occurrence, its count left as is) when
+//! there's no interval. Either way it's reported as
+//! OccurrenceOutcome::GateClosed and counted by BlockingScheduler::gate_report.
+//! The missed occurrences fired by the catch-up pass aren't gated. Like the override handlers, the gates aren't saved
+//! and are attached again after a load.
+//!
+//! ```
+//! use chrono::{DateTime, Duration};
+//! use scheduler::prelude::*;
+//! use std::collections::HashMap;
+//! use std::sync::atomic::{AtomicBool, Ordering};
+//! use std::sync::Arc;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let start = DateTime::parse_from_rfc3339("2024-01-01T09:00:00+01:00").unwrap();
+//! let hourly = RepetitionType::ConstGap { gap: Duration::hours(1), termination: Termination::default() };
+//! let mut scheduler = BlockingScheduler::new(
+//!     HashMap::from([("cleanup".to_string(), vec![ScheduledTask::new(start, "purge", hourly, SleepType::Native)])]),
+//!     HashMap::new(),
+//! );
+//! let feature_flag = Arc::new(AtomicBool::new(true));
+//! let flag = feature_flag.clone();
+//! scheduler.set_gate("cleanup", Some(Arc::new(move |_: &&str| flag.load(Ordering::SeqCst))));
+//! assert_eq!(scheduler.tick("cleanup", start, |_| {}).unwrap(), 1);
+//!
+//! // Closed: the occurrence is skipped
+//! feature_flag.store(false, Ordering::SeqCst);
+//! assert_eq!(scheduler.tick("cleanup", start + Duration::hours(1), |_| {}).unwrap(), 0);
+//! assert_eq!(scheduler.next_run_time("cleanup"), Some(start + Duration::hours(2)));
+//!
+//! // Or checked again every 10 minutes, until the gate opens
+//! scheduler.config.gate_recheck_interval = Some(Duration::minutes(10));
+//! assert_eq!(scheduler.tick("cleanup", start + Duration::hours(2), |_| {}).unwrap(), 0);
+//! let recheck = start + Duration::minutes(130);
+//! assert_eq!(scheduler.next_run_time("cleanup"), Some(recheck));
+//! feature_flag.store(true, Ordering::SeqCst);
+//! assert_eq!(scheduler.tick("cleanup", recheck, |_| {}).unwrap(), 1);
+//! // The task keeps its cadence
+//! assert_eq!(scheduler.next_run_time("cleanup"), Some(start + Duration::hours(3)));
+//!
+//! let report = scheduler.gate_report("cleanup").unwrap();
+//! assert_eq!((report.skipped, report.deferred), (1, 1));
+//! ```
+use std::sync::Arc;
+
+/// Condition a task only fires under, see the gate module. Implemented by the closures taking the payload.
+pub trait Gate<TaskType>: Send + Sync {
+    fn is_open(&self, task: &TaskType) -> bool;
+}
+
+impl<TaskType, F> Gate<TaskType> for F
+where
+    F: Fn(&TaskType) -> bool + Send + Sync,
+{
+    fn is_open(&self, task: &TaskType) -> bool {
+        self(task)
+    }
+}
+
+impl<TaskType> std::fmt::Debug for dyn Gate<TaskType> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Gate")
+    }
+}
+
+/// Gate attached to a task or to a mode
+pub type SharedGate<TaskType> = Arc<dyn Gate<TaskType>>;
@@ -0,0 +1,92 @@
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, Weekday};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+// Above that, a provider is considered to mark every day as a holiday and the occurrence is kept as is
+const MAX_HOLIDAYS_IN_A_ROW: usize = 366;
+
+/// Tells which days are holidays for the tasks created with `skip_holidays`, see BlockingScheduler::set_holiday_provider.
+/// The date given is the day of the occurrence in the offset of the task.
+pub trait HolidayProvider {
+    fn is_holiday(&self, date: NaiveDate) -> bool;
+}
+
+/// Saturdays and Sundays
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WeekendProvider;
+
+impl HolidayProvider for WeekendProvider {
+    fn is_holiday(&self, date: NaiveDate) -> bool {
+        matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+    }
+}
+
+/// A fixed set of days
+#[derive(Clone, Debug, Default)]
+pub struct StaticSetProvider(pub HashSet<NaiveDate>);
+
+impl FromIterator<NaiveDate> for StaticSetProvider {
+    fn from_iter<Dates: IntoIterator<Item = NaiveDate>>(dates: Dates) -> Self {
+        Self(dates.into_iter().collect())
+    }
+}
+
+impl HolidayProvider for StaticSetProvider {
+    fn is_holiday(&self, date: NaiveDate) -> bool {
+        self.0.contains(&date)
+    }
+}
+
+/// Represents what happens to an occurrence landing on a holiday
+/// - RollForward : the occurrence is moved to the next day which isn't a holiday, at the same time
+/// - Skip : the occurrence is replaced by the next occurrence of the task which isn't on a holiday
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum HolidayPolicy {
+    #[default]
+    RollForward,
+    Skip,
+}
+
+// Provider of a scheduler and the policy applied to it, shared with the clones of the scheduler
+#[derive(Clone)]
+pub(crate) struct Holidays {
+    pub(crate) provider: Arc<dyn HolidayProvider + Send + Sync>,
+    pub(crate) policy: HolidayPolicy,
+}
+
+impl Debug for Holidays {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Holidays")
+            .field("policy", &self.policy)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Holidays {
+    // First date from the occurrence which isn't a holiday, next_occurrence giving the occurrence following a date
+    pub(crate) fn avoid(
+        &self,
+        occurrence: DateTime<FixedOffset>,
+        next_occurrence: impl Fn(&DateTime<FixedOffset>) -> Option<DateTime<FixedOffset>>,
+    ) -> DateTime<FixedOffset> {
+        let mut date = occurrence;
+        for _ in 0..MAX_HOLIDAYS_IN_A_ROW {
+            if !self.provider.is_holiday(date.date_naive()) {
+                return date;
+            }
+            let next_date = match self.policy {
+                HolidayPolicy::RollForward => date.checked_add_signed(Duration::days(1)),
+                HolidayPolicy::Skip => next_occurrence(&date).filter(|next_date| date < *next_date),
+            };
+            match next_date {
+                Some(next_date) => date = next_date,
+                None => break,
+            }
+        }
+        occurrence
+    }
+}
@@ -0,0 +1,237 @@
+//! Ids of the tasks, given by the scheduler to every task it holds. An id is made of the epoch of the scheduler that gave
+//! it, drawn at random when the scheduler is built, and of a counter saved along with the scheduler: the tasks added
+//! after a scheduler has been loaded never get the id of a task saved before, and the ids of two schedulers only collide
+//! when they've been loaded from the same snapshot (see BlockingScheduler::merge).
+//!
+//! ```
+//! # #[cfg(feature = "json")]
+//! # {
+//! use chrono::Local;
+//! use scheduler::prelude::*;
+//! use std::collections::HashMap;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let task = |name| ScheduledTask::new(Local::now(), name, RepetitionType::Once, SleepType::Native);
+//! let scheduler = BlockingScheduler::new(
+//!     HashMap::from([("jobs".to_string(), vec![task("backup".to_string())])]),
+//!     HashMap::new(),
+//! );
+//! let backup = scheduler.scheduled_tasks["jobs"][0].id().unwrap();
+//! let saved = serde_json::to_string(&scheduler).unwrap();
+//!
+//! // Two processes load the same snapshot: each gives a new id to its new task, the same one
+//! let load = || serde_json::from_str::<BlockingScheduler<String>>(&saved).unwrap();
+//! let (mut first, mut second) = (load(), load());
+//! first.add_task("jobs", task("report".to_string()));
+//! second.add_task("reports", task("report".to_string()));
+//! let report = first.scheduled_tasks["jobs"].iter().find(|task| task.task == "report").unwrap();
+//! assert_ne!(report.id(), Some(backup));
+//!
+//! // Merged back, the tasks of the second one are given new ids where they collide
+//! let remapped = first.merge(second);
+//! assert_eq!(remapped.len(), 2);
+//! assert!(remapped.iter().any(|(from, _)| *from == backup));
+//! let mut ids: Vec<TaskId> = first.scheduled_tasks.values().flatten().filter_map(|task| task.id()).collect();
+//! assert_eq!(ids.len(), 4);
+//! ids.sort();
+//! ids.dedup();
+//! assert_eq!(ids.len(), 4);
+//! assert_eq!(backup.to_string().parse::<TaskId>(), Ok(backup));
+//! # }
+//! ```
+//!
+//! The id is the identity of a task (see ScheduledTask::id): it stays the same while the task is rescheduled,
+//! whereas Eq and Hash compare the definitions as they stand.
+//! ```
+//! use chrono::{Duration, Local};
+//! use scheduler::prelude::*;
+//! use std::collections::{HashMap, HashSet};
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let now = Local::now();
+//! let every_hour = RepetitionType::ConstGap { gap: Duration::hours(1), termination: Termination::default() };
+//! let task = ScheduledTask::new(now, "backup", every_hour, SleepType::Native);
+//! let mut scheduler = BlockingScheduler::new(HashMap::from([("jobs".to_string(), vec![task])]), HashMap::new());
+//! let before = scheduler.scheduled_tasks["jobs"][0].clone();
+//! scheduler.tick("jobs", now, |_| {}).unwrap();
+//! let after = scheduler.scheduled_tasks["jobs"][0].clone();
+//! assert_eq!(after.date, before.date + Duration::hours(1));
+//! assert_ne!(after, before);
+//! assert_eq!(after.id(), before.id());
+//! assert_eq!(HashSet::from([before.clone(), after.clone()]).len(), 2);
+//! assert_eq!(HashSet::from([before.id(), after.id()]).len(), 1);
+//! ```
+use super::schedulers::ScheduledTask;
+#[cfg(feature = "json-schema")]
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Schema, SchemaObject, StringValidation},
+    JsonSchema,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Id of a task, written as the epoch of its scheduler in hexadecimal and its counter ("8f3a0c21d94b7e65-12")
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+pub struct TaskId {
+    epoch: u64,
+    counter: u64,
+}
+
+impl Display for TaskId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}-{}", self.epoch, self.counter)
+    }
+}
+
+impl FromStr for TaskId {
+    type Err = String;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        let (epoch, counter) = id
+            .split_once('-')
+            .ok_or(format!("Invalid task id : {}", id))?;
+        Ok(Self {
+            epoch: u64::from_str_radix(epoch, 16)
+                .map_err(|error| format!("Invalid task id {} : {}", id, error))?,
+            counter: counter
+                .parse()
+                .map_err(|error| format!("Invalid task id {} : {}", id, error))?,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for TaskId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for TaskId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+// Described as the string written by Display
+#[cfg(feature = "json-schema")]
+impl JsonSchema for TaskId {
+    fn schema_name() -> String {
+        "TaskId".to_string()
+    }
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            string: Some(Box::new(StringValidation {
+                pattern: Some("^[0-9a-fA-F]+-[0-9]+$".to_string()),
+                ..StringValidation::default()
+            })),
+            ..SchemaObject::default()
+        }
+        .into()
+    }
+}
+
+// Gives the ids of a scheduler, shared with its clones so that the workers of a ParallelScheduler don't give the same id
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(from = "TaskIdsForm", into = "TaskIdsForm")
+)]
+#[derive(Clone, Debug)]
+pub(crate) struct TaskIds {
+    epoch: u64,
+    // Counter of the next id, the high-water mark of the ids given so far
+    next: Arc<AtomicU64>,
+}
+
+impl Default for TaskIds {
+    fn default() -> Self {
+        Self {
+            epoch: rand::random(),
+            next: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl TaskIds {
+    pub(crate) fn next(&self) -> TaskId {
+        TaskId {
+            epoch: self.epoch,
+            counter: self.next.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+    // Gives an id to the tasks without one
+    pub(crate) fn assign<'t, TaskType: 't>(
+        &self,
+        tasks: impl IntoIterator<Item = &'t mut ScheduledTask<TaskType>>,
+    ) {
+        for task in tasks {
+            if task.id.is_none() {
+                task.id = Some(self.next());
+            }
+        }
+    }
+    // Moves the counter past the ids already given with the epoch, which may have been saved after the counter
+    pub(crate) fn skip(&self, id: TaskId) {
+        if id.epoch == self.epoch {
+            self.next
+                .fetch_max(id.counter.saturating_add(1), Ordering::Relaxed);
+        }
+    }
+}
+
+// Serialized form of TaskIds, the epoch being written like in a TaskId
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "json-schema",
+    derive(JsonSchema),
+    schemars(rename = "TaskIds")
+)]
+struct TaskIdsForm {
+    epoch: String,
+    next: u64,
+}
+
+#[cfg(feature = "serde")]
+impl From<TaskIds> for TaskIdsForm {
+    fn from(task_ids: TaskIds) -> Self {
+        Self {
+            epoch: format!("{:016x}", task_ids.epoch),
+            next: task_ids.next.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<TaskIdsForm> for TaskIds {
+    // An invalid epoch is replaced by a new one: its ids can't be taken by the ones given afterwards
+    fn from(form: TaskIdsForm) -> Self {
+        match u64::from_str_radix(&form.epoch, 16) {
+            Ok(epoch) => Self {
+                epoch,
+                next: Arc::new(AtomicU64::new(form.next)),
+            },
+            Err(_) => Self::default(),
+        }
+    }
+}
+
+#[cfg(feature = "json-schema")]
+impl JsonSchema for TaskIds {
+    fn schema_name() -> String {
+        TaskIdsForm::schema_name()
+    }
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        TaskIdsForm::json_schema(gen)
+    }
+}
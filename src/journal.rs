@@ -0,0 +1,228 @@
+//! Journal of what a started mode did, written by BlockingScheduler::start_with_journal as one JSON line per event.
+//! replay_journal triggers the recorded tasks again and checks that the current repetition code computes the same
+//! next dates, which makes a journal a regression test of the date computations.
+//! The holidays and the blackout windows aren't part of the journal: the occurrences moved because of them are replayed as
+//! divergences.
+//! The journal grows with the run, rotation::RotatingFileSink bounds its size for the long-running daemons.
+//!
+//! ```
+//! use scheduler::journal::{replay_journal, JournalEvent};
+//! use scheduler::prelude::*;
+//!
+//! let journal = r#"{"event":"Fired","mode":"daily","task":"backup","repetition":{"ConstGap":{"gap":86400,"termination":"Infinite"}},"scheduled":"2024-01-01T09:00:00+01:00","fired_at":"2024-01-01T09:00:00.001+01:00","next":"2024-01-02T09:00:00+01:00","removal_reason":null}"#;
+//! let mut fired = Vec::new();
+//! let divergences = replay_journal(journal.as_bytes(), &NoCustomRepetition, |task: &String| {
+//!     fired.push(task.clone())
+//! })
+//! .unwrap();
+//! assert_eq!(fired, ["backup"]);
+//! assert!(divergences.is_empty());
+//! ```
+use super::blackout::Blackouts;
+use super::config::CatchUpReport;
+use super::holidays::Holidays;
+use super::id::TaskId;
+use super::records::{ExecutionRecord, FiredTask};
+use super::repetitions::{CustomRepetition, NextRun, RepetitionType};
+use super::schedulers::{next_run, RemovalReason, RunObserver, ScheduledTask};
+use super::sleeper::ClockAdjustment;
+use super::sleeptype::SleepType;
+use chrono::{DateTime, Duration, FixedOffset};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+
+/// Line of a journal
+/// - CaughtUp : the catch-up pass done when the mode started
+/// - Fired : a task has been triggered, `next` or `removal_reason` telling what happened to it afterwards. The journals
+///   written before the ExecutionRecord had its task id, latency, outcome and occurrence index are read with their defaults
+/// - ClockAdjusted : the ClockAdjustment of the mode has been set, or removed when `offset` is None
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+#[serde(tag = "event")]
+pub enum JournalEvent<TaskType> {
+    CaughtUp {
+        mode: String,
+        replayed: u64,
+        skipped: u64,
+        #[serde(default)]
+        removed_unfired: u64,
+        // Seed to set as SchedulerConfig::rng_seed to reproduce the run
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rng_seed: Option<u64>,
+    },
+    // The mode, task and dates are the ones of the FiredTask, written at the top level of the line
+    Fired {
+        #[serde(flatten)]
+        fired: FiredTask<TaskType>,
+        // Repetition of the task when it was triggered, count included
+        repetition: RepetitionType,
+        // Returned by the callback, for the SelfScheduled tasks
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        next_run: Option<NextRun>,
+        next: Option<DateTime<FixedOffset>>,
+        removal_reason: Option<RemovalReason>,
+    },
+    ClockAdjusted {
+        mode: String,
+        #[serde(with = "crate::duration_seconds::option")]
+        offset: Option<Duration>,
+        updated_at: Option<DateTime<FixedOffset>>,
+    },
+}
+
+/// Fired event whose outcome isn't the one computed by the current code
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Divergence {
+    // Line of the event in the journal, starting at 1
+    pub line: usize,
+    pub recorded: Result<DateTime<FixedOffset>, Option<RemovalReason>>,
+    pub computed: Result<DateTime<FixedOffset>, RemovalReason>,
+}
+
+// Observer of BlockingScheduler::run writing the journal, the first write error stops the writing
+pub(crate) struct JournalWriter<'jw, Writer> {
+    mode: &'jw str,
+    journal: Writer,
+    error: Option<String>,
+    // Firings written so far by task, giving the occurrence index of the next one
+    fired: BTreeMap<Option<TaskId>, u64>,
+}
+
+impl<'jw, Writer: Write> JournalWriter<'jw, Writer> {
+    pub(crate) fn new(mode: &'jw str, journal: Writer) -> Self {
+        Self {
+            mode,
+            journal,
+            error: None,
+            fired: BTreeMap::new(),
+        }
+    }
+
+    fn write<TaskType: Serialize>(&mut self, event: &JournalEvent<TaskType>) {
+        if self.error.is_some() {
+            return;
+        }
+        let written = serde_json::to_writer(&mut self.journal, event)
+            .map_err(|error| error.to_string())
+            .and_then(|_| writeln!(self.journal).map_err(|error| error.to_string()));
+        if let Err(error) = written {
+            self.error = Some(format!("Couldn't write the journal : {}", error));
+        }
+    }
+
+    pub(crate) fn finish(mut self) -> Result<(), String> {
+        if self.error.is_none() {
+            if let Err(error) = self.journal.flush() {
+                self.error = Some(format!("Couldn't write the journal : {}", error));
+            }
+        }
+        self.error.map_or(Ok(()), Err)
+    }
+}
+
+impl<'jw, TaskType, Writer> RunObserver<TaskType> for JournalWriter<'jw, Writer>
+where
+    TaskType: Serialize + Eq,
+    Writer: Write,
+{
+    fn caught_up(&mut self, report: &CatchUpReport) {
+        self.write(&JournalEvent::<()>::CaughtUp {
+            mode: self.mode.to_owned(),
+            replayed: report.replayed,
+            skipped: report.skipped,
+            removed_unfired: report.removed_unfired,
+            rng_seed: Some(report.rng_seed),
+        });
+    }
+
+    fn fired(
+        &mut self,
+        task: &ScheduledTask<TaskType>,
+        now: &DateTime<FixedOffset>,
+        next_run_returned: Option<&NextRun>,
+        custom_repetition: &impl CustomRepetition,
+        holidays: Option<&Holidays>,
+        blackouts: Option<&Blackouts>,
+    ) {
+        let next = next_run(
+            task,
+            now,
+            next_run_returned,
+            custom_repetition,
+            holidays,
+            blackouts,
+        );
+        let occurrence_index = self.fired.entry(task.id()).or_default();
+        let record = ExecutionRecord::builder(self.mode, task.date, *now)
+            .task_id(task.id())
+            .occurrence_index(*occurrence_index)
+            .build();
+        *occurrence_index += 1;
+        self.write(&JournalEvent::Fired {
+            fired: FiredTask::new(&task.task, record),
+            repetition: task.repetition.clone(),
+            next_run: next_run_returned.cloned(),
+            next: next.ok(),
+            removal_reason: next.err(),
+        });
+    }
+
+    fn clock_adjusted(&mut self, clock_adjustment: Option<&ClockAdjustment>) {
+        self.write(&JournalEvent::<()>::ClockAdjusted {
+            mode: self.mode.to_owned(),
+            offset: clock_adjustment.map(|clock_adjustment| clock_adjustment.offset),
+            updated_at: clock_adjustment.map(|clock_adjustment| clock_adjustment.updated_at),
+        });
+    }
+}
+
+// Triggers the tasks of the Fired events of the journal in order, then compares the recorded outcome of each firing
+// with the one computed by the current code, at the recorded dates. The CaughtUp events are skipped.
+pub fn replay_journal<TaskType>(
+    journal: impl BufRead,
+    custom_repetition: &impl CustomRepetition,
+    mut f: impl FnMut(&TaskType),
+) -> Result<Vec<Divergence>, String>
+where
+    TaskType: DeserializeOwned + Eq,
+{
+    let mut divergences = Vec::new();
+    for (index, line) in journal.lines().enumerate() {
+        let line = line.map_err(|error| format!("Couldn't read the journal : {}", error))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event = serde_json::from_str(&line)
+            .map_err(|error| format!("Invalid journal line {} : {}", index + 1, error))?;
+        let JournalEvent::Fired {
+            fired: FiredTask { task, record },
+            repetition,
+            next_run: next_run_returned,
+            next,
+            removal_reason,
+            ..
+        } = event
+        else {
+            continue;
+        };
+        f(&task);
+        let recorded = next.ok_or(removal_reason);
+        let computed = next_run(
+            &ScheduledTask::new(record.scheduled_for, task, repetition, SleepType::Native),
+            &record.fired_at,
+            next_run_returned.as_ref(),
+            custom_repetition,
+            None,
+            None,
+        );
+        if recorded != computed.map_err(Some) {
+            divergences.push(Divergence {
+                line: index + 1,
+                recorded,
+                computed,
+            });
+        }
+    }
+    Ok(divergences)
+}
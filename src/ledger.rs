@@ -0,0 +1,207 @@
+//! Occurrences completed lately, remembered by the scheduler so that an occurrence isn't fired twice when the scheduler
+//! is restored from a snapshot saved after the occurrence was fired but before its task was moved past it.
+//! Enabled by SchedulerConfig::occurrence_ledger, which bounds the number of occurrences remembered, the least recently
+//! completed ones being forgotten first. The ledger is saved with the scheduler and shared with its clones (the copy
+//! written by BlockingScheduler::start_with_snapshots, the workers of a ParallelScheduler).
+//!
+//! Before an occurrence is fired by start, tick or catch_up, the ledger is looked up by the id of its task and its date:
+//! an occurrence already completed is reported as OccurrenceOutcome::AlreadyCompleted and its task is moved to its next
+//! occurrence without being fired. SelfScheduled tasks, whose next date is given by their callback, aren't guarded.
+//!
+//! Each occurrence fired is given an execution token, increasing across the scheduler and its restores, recorded in the
+//! ledger as started before its callback runs and as completed once it has returned (see TaskContext::execution_token).
+//! BlockingScheduler::start_with_snapshots writes both markers right away, the start one before the callback is called:
+//! an occurrence restored as started but not completed is fired again with the same token and is told to be a replay
+//! (TaskContext::is_replay), so that what the callback did the first time can be found by its token.
+//!
+//! ```
+//! use chrono::DateTime;
+//! use scheduler::prelude::*;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let at = |date| DateTime::parse_from_rfc3339(date).unwrap();
+//! let daily = RepetitionType::ConstGap { gap: chrono::Duration::days(1), termination: Termination::default() };
+//! let task = ScheduledTask::new(at("2024-01-01T09:00:00+01:00"), "report", daily, SleepType::Native);
+//! let mut scheduler = BlockingScheduler::new([("daily".to_string(), vec![task])], []);
+//! scheduler.config.occurrence_ledger = Some(100);
+//! let mut fired = 0;
+//! scheduler.tick("daily", at("2024-01-01T09:00:00+01:00"), |_| fired += 1).unwrap();
+//! let id = scheduler.scheduled_tasks["daily"][0].id().unwrap();
+//! assert!(scheduler.occurrence_ledger().contains(id, at("2024-01-01T09:00:00+01:00")));
+//!
+//! // Back to the occurrence already fired, as a stale snapshot would be
+//! scheduler.scheduled_tasks.get_mut("daily").unwrap()[0].date = at("2024-01-01T09:00:00+01:00");
+//! assert_eq!(scheduler.tick("daily", at("2024-01-01T09:00:00+01:00"), |_| fired += 1).unwrap(), 0);
+//! assert_eq!(fired, 1);
+//! assert_eq!(scheduler.scheduled_tasks["daily"][0].date, at("2024-01-02T09:00:00+01:00"));
+//! ```
+use super::id::TaskId;
+use chrono::{DateTime, FixedOffset};
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+
+// Occurrence of a task, the date being the one it was planned at
+type Occurrence = (TaskId, DateTime<FixedOffset>);
+
+/// Occurrences completed by the tasks of a scheduler, see the ledger module. Saved as a list of occurrences, the least
+/// recently completed first, or once an execution token has been given as an object holding that list, the occurrences
+/// started and not completed with their token, and the next token.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(from = "LedgerForm", into = "LedgerForm")
+)]
+#[derive(Clone, Debug, Default)]
+pub struct OccurrenceLedger(Arc<Mutex<Occurrences>>);
+
+#[derive(Debug, Default)]
+struct Occurrences {
+    // Least recently completed first
+    order: VecDeque<Occurrence>,
+    known: HashSet<Occurrence>,
+    // Occurrences whose callback has been called and hasn't returned, or was running when the scheduler was saved
+    started: HashMap<Occurrence, Execution>,
+    next_token: u64,
+}
+
+// Token of a started occurrence, see TaskContext::execution_token
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub(crate) struct Execution {
+    pub(crate) token: u64,
+    // Started again after a restore, the first start not having been completed
+    pub(crate) is_replay: bool,
+}
+
+// Serialized form of a ledger, the bare list being the one written before the execution tokens
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[serde(untagged)]
+pub(crate) enum LedgerForm {
+    Completed(Vec<Occurrence>),
+    Markers {
+        completed: Vec<Occurrence>,
+        started: Vec<(TaskId, DateTime<FixedOffset>, u64)>,
+        next_token: u64,
+    },
+}
+
+impl OccurrenceLedger {
+    // True when the occurrence of the task planned at the date has been completed
+    pub fn contains(&self, id: TaskId, date: DateTime<FixedOffset>) -> bool {
+        self.0.lock().unwrap().known.contains(&(id, date))
+    }
+    pub fn len(&self) -> usize {
+        self.0.lock().unwrap().order.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    // True when the ledger has nothing to save, no token having been given
+    #[cfg(feature = "serde")]
+    pub(crate) fn is_unused(&self) -> bool {
+        let occurrences = self.0.lock().unwrap();
+        occurrences.order.is_empty() && occurrences.next_token == 0
+    }
+    // Records the occurrence as started, giving it its token: a new one, or the one of the start that hasn't been
+    // completed (before the scheduler was saved, or because its callback has panicked)
+    pub(crate) fn start(&self, id: TaskId, date: DateTime<FixedOffset>) -> Execution {
+        let mut occurrences = self.0.lock().unwrap();
+        let occurrences = &mut *occurrences;
+        match occurrences.started.get_mut(&(id, date)) {
+            Some(execution) => {
+                execution.is_replay = true;
+                *execution
+            }
+            None => {
+                let execution = Execution {
+                    token: occurrences.next_token,
+                    is_replay: false,
+                };
+                occurrences.next_token += 1;
+                occurrences.started.insert((id, date), execution);
+                execution
+            }
+        }
+    }
+    // Token of the occurrence while it's started
+    pub(crate) fn execution(&self, id: TaskId, date: DateTime<FixedOffset>) -> Option<Execution> {
+        self.0.lock().unwrap().started.get(&(id, date)).copied()
+    }
+    // Ledger given to the reading of a mode with its capacity, None when it's disabled so that nothing is looked up
+    pub(crate) fn enabled(&self, capacity: Option<usize>) -> Option<(Self, usize)> {
+        capacity.map(|capacity| (self.clone(), capacity))
+    }
+    // Records the completed occurrence as the most recent one, forgetting the oldest ones beyond the capacity
+    pub(crate) fn record(&self, id: TaskId, date: DateTime<FixedOffset>, capacity: usize) {
+        let mut occurrences = self.0.lock().unwrap();
+        let occurrence = (id, date);
+        occurrences.started.remove(&occurrence);
+        if !occurrences.known.insert(occurrence) {
+            occurrences.order.retain(|known| *known != occurrence);
+        }
+        occurrences.order.push_back(occurrence);
+        while occurrences.order.len() > capacity {
+            if let Some(forgotten) = occurrences.order.pop_front() {
+                occurrences.known.remove(&forgotten);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<LedgerForm> for OccurrenceLedger {
+    fn from(form: LedgerForm) -> Self {
+        let (order, started, next_token) = match form {
+            LedgerForm::Completed(order) => (order, Vec::new(), 0),
+            LedgerForm::Markers {
+                completed,
+                started,
+                next_token,
+            } => (completed, started, next_token),
+        };
+        let mut occurrences = Occurrences {
+            next_token,
+            ..Occurrences::default()
+        };
+        for occurrence in order {
+            if occurrences.known.insert(occurrence) {
+                occurrences.order.push_back(occurrence);
+            }
+        }
+        for (id, date, token) in started {
+            let execution = Execution {
+                token,
+                is_replay: false,
+            };
+            occurrences.started.insert((id, date), execution);
+        }
+        Self(Arc::new(Mutex::new(occurrences)))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<OccurrenceLedger> for LedgerForm {
+    fn from(ledger: OccurrenceLedger) -> Self {
+        let occurrences = ledger.0.lock().unwrap();
+        let completed = occurrences.order.iter().copied().collect();
+        if occurrences.next_token == 0 {
+            return Self::Completed(completed);
+        }
+        let mut started: Vec<_> = occurrences
+            .started
+            .iter()
+            .map(|((id, date), execution)| (*id, *date, execution.token))
+            .collect();
+        started.sort_by_key(|(_, _, token)| *token);
+        Self::Markers {
+            completed,
+            started,
+            next_token: occurrences.next_token,
+        }
+    }
+}
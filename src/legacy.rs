@@ -0,0 +1,197 @@
+//! Migration of the JSON files written when the crate exposed BlockingPlanner: the modes were held by
+//! `planned_actions`/`removed_actions`, the payload of an ActionPlanned was its `action`, the repetition with a gap was
+//! `Custom { gap, count }` (now ConstGap) and a finite count was `RepetitionCount::Custom` (now Finished).
+//!
+//! ```
+//! use chrono::{DateTime, Duration};
+//! use scheduler::prelude::*;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let legacy = r#"{
+//!     "planned_actions": {"reports": [
+//!         {"action": "digest", "date": "2024-01-01T09:00:00+01:00",
+//!          "repetition": {"Custom": {"gap": 3600, "count": {"Custom": 5}}}, "sleep_type": "Native"},
+//!         {"action": "summary", "date": "2024-01-01T18:00:00+01:00",
+//!          "repetition": {"Weekly": "Infinite"}, "sleep_type": "Native"}
+//!     ]},
+//!     "removed_actions": {"reports": [
+//!         {"action": "welcome", "date": "2023-12-31T09:00:00+01:00", "repetition": "Once", "sleep_type": "Native"}
+//!     ]}
+//! }"#;
+//! let directory = std::env::temp_dir();
+//! let (input, output) = (directory.join("scheduler_legacy_planner.json"), directory.join("scheduler_migrated.json"));
+//! std::fs::write(&input, legacy).unwrap();
+//! BlockingScheduler::<String>::migrate_file(&input, &output).unwrap();
+//!
+//! let migrated: BlockingScheduler<String> =
+//!     serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+//! let at = |date| DateTime::parse_from_rfc3339(date).unwrap();
+//! let tasks = &migrated.scheduled_tasks["reports"];
+//! assert_eq!((tasks[0].task.as_str(), tasks[0].date), ("digest", at("2024-01-01T09:00:00+01:00")));
+//! assert_eq!(
+//!     tasks[0].repetition,
+//!     RepetitionType::ConstGap { gap: Duration::hours(1), termination: RepetitionCount::Finished(5).into() }
+//! );
+//! assert_eq!(tasks[1].repetition, RepetitionType::Weekly(RepetitionCount::Infinite.into()));
+//! assert_eq!(migrated.removed_tasks["reports"][0].task, "welcome");
+//! assert!(migrated.scheduled_tasks["reports"].iter().all(|task| task.id().is_some()));
+//!
+//! // A current file isn't a legacy one
+//! let error = BlockingScheduler::<String>::migrate_file(&output, &input).unwrap_err();
+//! assert!(matches!(error, scheduler::legacy::MigrateError::Invalid(_)));
+//! # std::fs::remove_file(&input).unwrap();
+//! # std::fs::remove_file(&output).unwrap();
+//! ```
+use super::repetitions::{NoCustomRepetition, RepetitionCount, RepetitionType};
+use super::schedulers::{BlockingScheduler, ScheduledTask};
+use super::sleeptype::SleepType;
+use chrono::{DateTime, Duration, FixedOffset};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+
+/// Represents why a legacy file couldn't be migrated
+/// - Read : the file couldn't be read
+/// - Invalid : the file isn't a BlockingPlanner file, or one of its actions is invalid
+/// - Write : the migrated scheduler couldn't be written
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[non_exhaustive]
+pub enum MigrateError {
+    Read(String),
+    Invalid(String),
+    Write(String),
+}
+
+impl Display for MigrateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Read(reason) => write!(f, "Couldn't read the legacy file : {}", reason),
+            Self::Invalid(reason) => write!(f, "Invalid legacy file : {}", reason),
+            Self::Write(reason) => write!(f, "Couldn't write the migrated file : {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for MigrateError {}
+
+impl From<MigrateError> for String {
+    fn from(error: MigrateError) -> Self {
+        error.to_string()
+    }
+}
+
+// Shape of a BlockingPlanner file
+#[derive(Deserialize)]
+struct LegacyPlanner<ActionType> {
+    planned_actions: BTreeMap<String, Vec<ActionPlanned<ActionType>>>,
+    #[serde(default)]
+    removed_actions: BTreeMap<String, Vec<ActionPlanned<ActionType>>>,
+}
+
+#[derive(Deserialize)]
+struct ActionPlanned<ActionType> {
+    action: ActionType,
+    date: DateTime<FixedOffset>,
+    repetition: LegacyRepetition,
+    sleep_type: SleepType,
+}
+
+#[derive(Deserialize)]
+enum LegacyRepetition {
+    Once,
+    Weekly(LegacyCount),
+    Monthly(LegacyCount),
+    Yearly(LegacyCount),
+    Custom {
+        #[serde(with = "crate::duration_seconds")]
+        gap: Duration,
+        count: LegacyCount,
+    },
+}
+
+#[derive(Deserialize)]
+enum LegacyCount {
+    Infinite,
+    Custom(u64),
+}
+
+impl From<LegacyCount> for RepetitionCount {
+    fn from(count: LegacyCount) -> Self {
+        match count {
+            LegacyCount::Infinite => Self::Infinite,
+            LegacyCount::Custom(count) => Self::Finished(count),
+        }
+    }
+}
+
+impl From<LegacyRepetition> for RepetitionType {
+    fn from(repetition: LegacyRepetition) -> Self {
+        let termination = |count: LegacyCount| RepetitionCount::from(count).into();
+        match repetition {
+            LegacyRepetition::Once => Self::Once,
+            LegacyRepetition::Weekly(count) => Self::Weekly(termination(count)),
+            LegacyRepetition::Monthly(count) => Self::Monthly {
+                day: None,
+                termination: termination(count),
+            },
+            LegacyRepetition::Yearly(count) => Self::Yearly(termination(count)),
+            LegacyRepetition::Custom { gap, count } => Self::ConstGap {
+                gap,
+                termination: termination(count),
+            },
+        }
+    }
+}
+
+impl<ActionType> From<ActionPlanned<ActionType>> for ScheduledTask<ActionType> {
+    fn from(action: ActionPlanned<ActionType>) -> Self {
+        Self::new(
+            action.date,
+            action.action,
+            action.repetition.into(),
+            action.sleep_type,
+        )
+    }
+}
+
+impl<TaskType> BlockingScheduler<TaskType, NoCustomRepetition>
+where
+    TaskType: DeserializeOwned + Eq + Default,
+{
+    // Reads the BlockingPlanner file at the path as a scheduler, see the legacy module. The tasks are given ids.
+    pub fn from_legacy_planner_json(path: impl AsRef<Path>) -> Result<Self, MigrateError> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path)
+            .map_err(|error| MigrateError::Read(format!("{} : {}", path.display(), error)))?;
+        let planner: LegacyPlanner<TaskType> = serde_json::from_str(&json)
+            .map_err(|error| MigrateError::Invalid(format!("{} : {}", path.display(), error)))?;
+        let tasks = |modes: BTreeMap<String, Vec<ActionPlanned<TaskType>>>| {
+            modes.into_iter().map(|(mode, actions)| {
+                (mode, actions.into_iter().map(ScheduledTask::from).collect())
+            })
+        };
+        Ok(Self::new(
+            tasks(planner.planned_actions),
+            tasks(planner.removed_actions),
+        ))
+    }
+
+    // Rewrites the BlockingPlanner file at input as a current scheduler file at output
+    pub fn migrate_file(
+        input: impl AsRef<Path>,
+        output: impl AsRef<Path>,
+    ) -> Result<(), MigrateError>
+    where
+        TaskType: Serialize,
+    {
+        let scheduler = Self::from_legacy_planner_json(input)?;
+        let output = output.as_ref();
+        let json = serde_json::to_string_pretty(&scheduler)
+            .map_err(|error| MigrateError::Write(error.to_string()))?;
+        fs::write(output, json)
+            .map_err(|error| MigrateError::Write(format!("{} : {}", output.display(), error)))
+    }
+}
@@ -3,13 +3,117 @@
 //!
 //!  
 //! #Example :
+//! Every date of the API accepts a `DateTime<Utc>` (or `Local`), it's stored as a `DateTime<FixedOffset>`.
 //! ```
-//!```
+//! use chrono::{Duration, TimeZone, Utc};
+//! use scheduler::prelude::*;
+//! use std::collections::HashMap;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let start = Utc.with_ymd_and_hms(2024, 1, 1, 8, 0, 0).unwrap();
+//! let task = ScheduledTask::new(
+//!     start,
+//!     "report",
+//!     RepetitionType::Weekly(RepetitionCount::Infinite.into()),
+//!     SleepType::Native,
+//! );
+//! let mut scheduler = BlockingScheduler::new(
+//!     HashMap::from([("weekly".to_string(), vec![task])]),
+//!     HashMap::new(),
+//! );
+//! assert_eq!(scheduler.next_run_time_utc("weekly"), Some(start));
+//!
+//! let triggered = scheduler.tick("weekly", start, |task| println!("{}", task)).unwrap();
+//! assert_eq!(triggered, 1);
+//! assert_eq!(scheduler.next_run_time_utc("weekly"), Some(start + Duration::weeks(1)));
+//! ```
+//!
+//! Runnable programs are in the examples directory: basic_once, recurring_with_counts, parallel_modes,
+//! persistence_roundtrip, custom_repetition and lazy_source (`cargo run --example parallel_modes`, some need `--all-features`).
+pub mod backfill;
+pub mod blackout;
+pub mod cancellation;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+#[cfg(feature = "serde")]
+pub mod compact;
+pub mod config;
+pub mod controller;
+#[cfg(feature = "csv")]
+pub mod csv_import;
+#[cfg(feature = "config")]
+pub mod declarative;
+#[cfg(feature = "serde")]
+pub(crate) mod duration_seconds;
+pub mod error;
+pub mod every;
+pub mod gate;
+pub mod holidays;
+pub mod id;
+#[cfg(feature = "journal")]
+pub mod journal;
+pub mod ledger;
+#[cfg(feature = "json")]
+pub mod legacy;
+pub mod multi;
+pub mod occurrence;
+#[cfg(feature = "json")]
+pub mod persistence;
+pub mod plan;
+pub mod records;
 pub mod repetitions;
+pub mod resolver;
+#[cfg(feature = "journal")]
+pub mod rotation;
+pub mod runtime;
 pub mod schedulers;
+#[cfg(feature = "json-schema")]
+pub mod schema;
+pub mod scoped;
+pub mod simulation;
+pub mod sleeper;
 pub mod sleeptype;
+#[cfg(feature = "json")]
+pub mod snapshot;
+pub mod source;
+#[cfg(feature = "tokio")]
+pub mod stream;
+#[cfg(feature = "test-util")]
+pub mod testing;
+pub mod timer;
+pub mod wall;
+/// Types used by a typical schedule, the helpers of each module (RepetitionHelpers, the compact form, the journal...)
+/// are imported from their module
 pub mod prelude {
-    pub use super::repetitions::*;
-    pub use super::schedulers::{BlockingScheduler, ParallelScheduler, ScheduledTask};
+    pub use super::blackout::BlackoutPolicy;
+    pub use super::cancellation::{CancelToken, CancellationToken};
+    pub use super::config::{
+        BackpressurePolicy, CatchUpOrder, CatchUpReport, ConcurrentStart, GateReport, LagReport,
+        LatencyReport, MissedTickBehavior, ModeConfig, OrphanRemovedTasks, SchedulerConfig,
+    };
+    pub use super::controller::{
+        CommandBatch, RebasePolicy, SchedulerCommand, SchedulerController,
+    };
+    pub use super::error::SchedulerError;
+    pub use super::every::{Every, RunningJob};
+    pub use super::gate::{Gate, SharedGate};
+    pub use super::holidays::{HolidayPolicy, HolidayProvider, StaticSetProvider, WeekendProvider};
+    pub use super::id::TaskId;
+    pub use super::occurrence::{OccurrenceOutcome, TaskContext};
+    pub use super::records::{ExecutionRecord, FiredTask};
+    pub use super::repetitions::{
+        CustomRepetition, NextRun, NoCustomRepetition, RepetitionCount, RepetitionType,
+        Termination, WeekParity,
+    };
+    pub use super::resolver::TaskResolver;
+    pub use super::schedulers::{
+        BlockingScheduler, FailurePolicy, HandoffReport, JoinSummary, ModeHandoff, ModeRunOutcome,
+        OffsetSummary, OverrideHandler, ParallelScheduler, PendingDeletion, RemovalFlush,
+        RemovalReason, RunSummary, ScheduledTask, ThreadSetup, ValidationError,
+    };
     pub use super::sleeptype::SleepType;
+    #[cfg(feature = "spin_sleep")]
+    pub use super::sleeptype::SpinConfig;
+    pub use super::source::{TaskSource, VecSource};
+    pub use super::wall::WallSchedule;
 }
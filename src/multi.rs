@@ -0,0 +1,354 @@
+//! Several schedulers serviced by a single thread, whatever their task types: the earliest task among them is triggered first.
+//!
+//! ```
+//! use chrono::{Duration, Local};
+//! use scheduler::multi::{MultiScheduler, SchedulerRunner};
+//! use scheduler::prelude::*;
+//! use std::collections::HashMap;
+//! use std::sync::Mutex;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! static FIRED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+//!
+//! let in_ms = |offset| Local::now() + Duration::milliseconds(offset);
+//! let reports = BlockingScheduler::new(
+//!     HashMap::from([(
+//!         "reports".to_string(),
+//!         vec![
+//!             ScheduledTask::new(in_ms(100), "first", RepetitionType::Once, SleepType::Native),
+//!             ScheduledTask::new(in_ms(300), "third", RepetitionType::Once, SleepType::Native),
+//!         ],
+//!     )]),
+//!     HashMap::new(),
+//! );
+//! let backups = BlockingScheduler::new(
+//!     HashMap::from([(
+//!         "backups".to_string(),
+//!         vec![ScheduledTask::new(in_ms(200), 2_u32, RepetitionType::Once, SleepType::Native)],
+//!     )]),
+//!     HashMap::new(),
+//! );
+//! let mut multi = MultiScheduler::new();
+//! multi.add("reports", Box::new(SchedulerRunner::new(reports, "reports", |task: &&str| {
+//!     FIRED.lock().unwrap().push(task.to_string())
+//! })));
+//! multi.add("backups", Box::new(SchedulerRunner::new(backups, "backups", |task: &u32| {
+//!     FIRED.lock().unwrap().push(task.to_string())
+//! })));
+//! multi.run().unwrap();
+//! assert_eq!(*FIRED.lock().unwrap(), ["first", "2", "third"]);
+//! ```
+//!
+//! The member given the worker is chosen again after each tick, the most overdue first. A member with a backlog (a
+//! catch-up, callbacks slower than its gaps) would keep the worker to itself: MultiScheduler::set_max_consecutive_ticks
+//! lets the other due members through once it has been ticked that many times in a row. The delay of each member is
+//! recorded in its StarvationReport.
+//!
+//! ```
+//! use chrono::{Duration, Local};
+//! use scheduler::multi::{MultiScheduler, SchedulerRunner};
+//! use scheduler::prelude::*;
+//! use std::collections::HashMap;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let now = Local::now();
+//! let scheduler = |mode: &str, tasks: Vec<ScheduledTask<i64>>| {
+//!     BlockingScheduler::new(HashMap::from([(mode.to_string(), tasks)]), HashMap::new())
+//! };
+//! // Twenty late tasks, each callback lasting 20 ms
+//! let backlog = (0..20)
+//!     .map(|index| ScheduledTask::new(now - Duration::seconds(1) + Duration::milliseconds(index), index, RepetitionType::Once, SleepType::Native))
+//!     .collect();
+//! let fast = (0..5)
+//!     .map(|index| ScheduledTask::new(now + Duration::milliseconds(30 * index), index, RepetitionType::Once, SleepType::Native))
+//!     .collect();
+//! let mut multi = MultiScheduler::new();
+//! multi.add("slow", Box::new(SchedulerRunner::new(scheduler("slow", backlog), "slow", |_: &i64| {
+//!     std::thread::sleep(std::time::Duration::from_millis(20))
+//! })));
+//! multi.add("fast", Box::new(SchedulerRunner::new(scheduler("fast", fast), "fast", |_: &i64| {})));
+//! multi.set_max_consecutive_ticks(Some(2));
+//! multi.run().unwrap();
+//!
+//! let (slow, fast) = (multi.starvation_report("slow").unwrap(), multi.starvation_report("fast").unwrap());
+//! assert_eq!((slow.ticks, fast.ticks), (20, 5));
+//! // Without the cap, the last fast task would wait for the whole backlog (400 ms)
+//! assert!(fast.max_delay < Duration::milliseconds(150), "{:?}", fast.max_delay);
+//! assert!(slow.yielded > 0);
+//! ```
+use super::cancellation::CancellationToken;
+use super::repetitions::CustomRepetition;
+use super::schedulers::BlockingScheduler;
+use super::sleeper::{Clock, SleepOutcome, Sleeper, SystemClock, Wake};
+use super::sleeptype::SleepType;
+use chrono::{DateTime, Duration, FixedOffset};
+use std::collections::BTreeMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// Member of a MultiScheduler
+pub trait ModeRunner {
+    // Date of the next task, None when there's nothing left to trigger
+    fn next_fire(&self) -> Option<DateTime<FixedOffset>>;
+    // Triggers the tasks due at now, see BlockingScheduler::tick
+    fn tick(&mut self, now: DateTime<FixedOffset>) -> Result<usize, String>;
+}
+
+/// A mode of a scheduler along with the callback its tasks are given to
+pub struct SchedulerRunner<TaskType, CustomRepetitionType, F> {
+    scheduler: BlockingScheduler<TaskType, CustomRepetitionType>,
+    mode: String,
+    f: F,
+}
+
+impl<TaskType, CustomRepetitionType, F> SchedulerRunner<TaskType, CustomRepetitionType, F> {
+    pub fn new(
+        scheduler: BlockingScheduler<TaskType, CustomRepetitionType>,
+        mode: &str,
+        f: F,
+    ) -> Self {
+        Self {
+            scheduler,
+            mode: mode.to_owned(),
+            f,
+        }
+    }
+    pub fn scheduler(&self) -> &BlockingScheduler<TaskType, CustomRepetitionType> {
+        &self.scheduler
+    }
+    pub fn into_scheduler(self) -> BlockingScheduler<TaskType, CustomRepetitionType> {
+        self.scheduler
+    }
+}
+
+impl<TaskType, CustomRepetitionType, F> ModeRunner
+    for SchedulerRunner<TaskType, CustomRepetitionType, F>
+where
+    TaskType: Eq + Default,
+    CustomRepetitionType: CustomRepetition + Clone,
+    F: FnMut(&TaskType),
+{
+    fn next_fire(&self) -> Option<DateTime<FixedOffset>> {
+        self.scheduler.next_run_time(&self.mode)
+    }
+    fn tick(&mut self, now: DateTime<FixedOffset>) -> Result<usize, String> {
+        self.scheduler.tick(&self.mode, now, &mut self.f)
+    }
+}
+
+pub type BoxedModeRunner = Box<dyn ModeRunner + Send>;
+
+/// Represents a change of the members of a running MultiScheduler
+/// - Add : the member is added, replacing the member with the same key
+/// - Remove : the member is dropped
+pub enum MultiCommand {
+    Add(String, BoxedModeRunner),
+    Remove(String),
+}
+
+/// Handle changing the members of a MultiScheduler while it runs, from any thread
+#[derive(Clone)]
+pub struct MultiController {
+    sender: Sender<MultiCommand>,
+    wake: Wake,
+}
+
+impl MultiController {
+    pub fn add(&self, key: &str, runner: BoxedModeRunner) -> Result<(), String> {
+        self.send(MultiCommand::Add(key.to_owned(), runner))
+    }
+    pub fn remove(&self, key: &str) -> Result<(), String> {
+        self.send(MultiCommand::Remove(key.to_owned()))
+    }
+    fn send(&self, command: MultiCommand) -> Result<(), String> {
+        self.sender
+            .send(command)
+            .or(Err("The multi scheduler has been dropped".to_string()))?;
+        self.wake.wake();
+        Ok(())
+    }
+}
+
+/// Delays of a member of a MultiScheduler since it has been added, see MultiScheduler::starvation_report
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct StarvationReport {
+    // Longest time between the date of a task of the member and the tick triggering it
+    pub max_delay: Duration,
+    pub ticks: u64,
+    // Times the member has been due but left to the others, having reached the cap of consecutive ticks
+    pub yielded: u64,
+}
+
+/// Services its members on the calling thread, see the module documentation
+pub struct MultiScheduler {
+    // Kept in insertion order, which breaks the ties between members due at the same date
+    members: Vec<(String, BoxedModeRunner)>,
+    sender: Sender<MultiCommand>,
+    commands: Receiver<MultiCommand>,
+    cancellation_token: CancellationToken,
+    // Wakes run up when a command is sent
+    wake: Wake,
+    // See Self::set_max_consecutive_ticks
+    max_consecutive_ticks: Option<usize>,
+    starvation_reports: BTreeMap<String, StarvationReport>,
+}
+
+impl Default for MultiScheduler {
+    fn default() -> Self {
+        let (sender, commands) = mpsc::channel();
+        let cancellation_token = CancellationToken::new();
+        Self {
+            members: Vec::new(),
+            sender,
+            commands,
+            wake: Wake::new(&cancellation_token),
+            cancellation_token,
+            max_consecutive_ticks: None,
+            starvation_reports: BTreeMap::new(),
+        }
+    }
+}
+
+impl MultiScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Adds the member, replacing the member with the same key
+    pub fn add(&mut self, key: &str, runner: BoxedModeRunner) {
+        self.apply_command(MultiCommand::Add(key.to_owned(), runner));
+    }
+    pub fn remove(&mut self, key: &str) -> Option<BoxedModeRunner> {
+        let index = self.members.iter().position(|(member, _)| member == key)?;
+        self.starvation_reports.remove(key);
+        Some(self.members.remove(index).1)
+    }
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.members.iter().map(|(key, _)| key.as_str())
+    }
+
+    pub fn controller(&self) -> MultiController {
+        MultiController {
+            sender: self.sender.clone(),
+            wake: self.wake.clone(),
+        }
+    }
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation_token.clone()
+    }
+
+    // Number of ticks in a row after which a member lets the other due members through, even when its own task is the
+    // most overdue. No cap when None.
+    pub fn set_max_consecutive_ticks(&mut self, max_consecutive_ticks: Option<usize>) {
+        self.max_consecutive_ticks = max_consecutive_ticks;
+    }
+    // Delays of the member since it has been added, None before its first tick
+    pub fn starvation_report(&self, key: &str) -> Option<&StarvationReport> {
+        self.starvation_reports.get(key)
+    }
+
+    // Earliest next task among the members
+    pub fn next_fire(&self) -> Option<DateTime<FixedOffset>> {
+        self.members
+            .iter()
+            .filter_map(|(_, runner)| runner.next_fire())
+            .min()
+    }
+
+    // Sleeps toward the earliest task of the members and triggers it, until none of them has a task left or the
+    // cancellation token is cancelled. The due tasks are triggered in date order across the members, each member being
+    // ticked at the date of its task. The members due are looked at again after each tick, so that a long callback
+    // doesn't hide the tasks that became due meanwhile, see Self::set_max_consecutive_ticks. A member failing to tick
+    // stops the run, its key is part of the error.
+    pub fn run(&mut self) -> Result<(), String> {
+        // Member ticked last, with the number of times in a row
+        let mut streak: Option<(String, usize)> = None;
+        loop {
+            let wake = self.wake.token();
+            while let Ok(command) = self.commands.try_recv() {
+                self.apply_command(command);
+            }
+            let Some(next_fire) = self.next_fire() else {
+                return Ok(());
+            };
+            match SleepType::Native.sleep_until(next_fire, &SystemClock, &wake) {
+                SleepOutcome::Cancelled if self.cancellation_token.is_cancelled() => return Ok(()),
+                // A command has been sent
+                SleepOutcome::Cancelled | SleepOutcome::ClockJumped => continue,
+                SleepOutcome::ReachedTarget => {}
+            }
+            loop {
+                let now = SystemClock.now();
+                let capped = streak
+                    .as_ref()
+                    .filter(|(_, ticks)| {
+                        self.max_consecutive_ticks.is_some_and(|max| *ticks >= max)
+                    })
+                    .map(|(key, _)| key.as_str());
+                let Some((index, date, passed_over)) = self.most_overdue(&now, capped) else {
+                    break;
+                };
+                if let Some(capped) = capped.filter(|_| passed_over) {
+                    self.starvation_reports
+                        .entry(capped.to_owned())
+                        .or_default()
+                        .yielded += 1;
+                }
+                let (key, runner) = &mut self.members[index];
+                let report = self.starvation_reports.entry(key.clone()).or_default();
+                report.max_delay = report.max_delay.max(now - date);
+                report.ticks += 1;
+                streak = match streak.take() {
+                    Some((previous, ticks)) if previous == *key => Some((previous, ticks + 1)),
+                    _ => Some((key.clone(), 1)),
+                };
+                runner
+                    .tick(date)
+                    .map_err(|error| format!("The member {} failed : {}", key, error))?;
+            }
+        }
+    }
+
+    // Member whose next task is the earliest of those due at now, with the date of that task. The capped member is only
+    // chosen when no other member is due, the returned bool telling whether it was due but passed over.
+    fn most_overdue(
+        &self,
+        now: &DateTime<FixedOffset>,
+        capped: Option<&str>,
+    ) -> Option<(usize, DateTime<FixedOffset>, bool)> {
+        let mut earliest: Option<(usize, DateTime<FixedOffset>)> = None;
+        let mut earliest_capped = None;
+        for (index, (key, runner)) in self.members.iter().enumerate() {
+            let earliest = if capped == Some(key.as_str()) {
+                &mut earliest_capped
+            } else {
+                &mut earliest
+            };
+            match runner.next_fire() {
+                Some(date)
+                    if date <= *now && earliest.is_none_or(|(_, earliest)| date < earliest) =>
+                {
+                    *earliest = Some((index, date))
+                }
+                _ => {}
+            }
+        }
+        match earliest {
+            Some((index, date)) => Some((index, date, earliest_capped.is_some())),
+            None => earliest_capped.map(|(index, date)| (index, date, false)),
+        }
+    }
+
+    fn apply_command(&mut self, command: MultiCommand) {
+        match command {
+            MultiCommand::Add(key, runner) => {
+                match self.members.iter_mut().find(|(member, _)| *member == key) {
+                    Some(member) => member.1 = runner,
+                    None => self.members.push((key, runner)),
+                }
+            }
+            MultiCommand::Remove(key) => {
+                self.remove(&key);
+            }
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! What becomes of each occurrence of the tasks, reported through a single hook (see BlockingScheduler::set_occurrence_hook)
+//! so that an integration can tell the occurrences fired from the ones that didn't happen, and why.
+//!
+//! ```
+//! use chrono::{Duration, Local};
+//! use scheduler::prelude::*;
+//! use std::collections::HashMap;
+//! use std::sync::Mutex;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! static OUTCOMES: Mutex<Vec<(String, OccurrenceOutcome)>> = Mutex::new(Vec::new());
+//!
+//! let now = Local::now();
+//! let every_hour = |date, task| {
+//!     ScheduledTask::new(
+//!         date,
+//!         task,
+//!         RepetitionType::ConstGap {
+//!             gap: Duration::hours(1),
+//!             termination: Termination::default(),
+//!         },
+//!         SleepType::Native,
+//!     )
+//! };
+//! let mut scheduler = BlockingScheduler::new(
+//!     HashMap::from([(
+//!         "reports".to_string(),
+//!         vec![every_hour(now - Duration::minutes(90), "late"), every_hour(now, "due")],
+//!     )]),
+//!     HashMap::new(),
+//! );
+//! scheduler.set_occurrence_hook(Some(|outcome, context| {
+//!     OUTCOMES.lock().unwrap().push((context.task.to_string(), outcome))
+//! }));
+//! scheduler.catch_up("reports", now, |_| {}).unwrap();
+//! scheduler.tick("reports", now, |_| {}).unwrap();
+//! scheduler.cancel_prefix("reports");
+//! assert_eq!(
+//!     *OUTCOMES.lock().unwrap(),
+//!     [
+//!         ("late".to_string(), OccurrenceOutcome::Skipped { missed: 2 }),
+//!         ("due".to_string(), OccurrenceOutcome::Fired),
+//!         ("late".to_string(), OccurrenceOutcome::Cancelled),
+//!         ("due".to_string(), OccurrenceOutcome::Cancelled),
+//!     ]
+//! );
+//! ```
+use super::cancellation::CancelToken;
+#[cfg(feature = "chaos")]
+use super::chaos::InjectedFault;
+use super::ledger::Execution;
+use chrono::{DateTime, FixedOffset};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// What became of an occurrence, the list growing as the scheduler learns new ways to delay or drop one:
+/// - Fired : the occurrence has been triggered at its date
+/// - CaughtUp : the occurrence had been missed while the mode wasn't running and has been triggered late by the catch-up pass
+/// - Skipped : missed occurrences of the task have been dropped, by the catch-up pass (see MissedTickBehavior and
+///   SchedulerConfig::max_catch_up) or because they passed while the callback of the task was running (see LagReport)
+/// - HolidayMoved : the occurrence landed on a holiday and has been moved to another date, see BlockingScheduler::set_holiday_provider
+/// - BlackoutMoved : the occurrence fell inside a blackout window of its mode and has been moved to another date, see the
+///   blackout module
+/// - GateClosed : a gate of the task or of its mode was closed when the occurrence was due, the occurrence has been
+///   skipped or is checked again at recheck_at (see the gate module)
+/// - Cancelled : the task has been cancelled, its next occurrence won't happen
+/// - AlreadyCompleted : the occurrence had already been fired before the scheduler was restored, the task has been moved
+///   past it without firing it again (see the ledger module)
+/// - StreamDropped : the occurrence has been fired into an eager stream whose consumer was behind, and dropped by the
+///   BackpressurePolicy of the scheduler (see SchedulerConfig::stream_capacity)
+/// - Injected : a failure has been injected into the occurrence by BlockingScheduler::start_with_chaos, see the chaos
+///   module
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum OccurrenceOutcome {
+    Fired,
+    CaughtUp,
+    Skipped {
+        missed: u64,
+    },
+    HolidayMoved {
+        to: DateTime<FixedOffset>,
+    },
+    BlackoutMoved {
+        to: DateTime<FixedOffset>,
+    },
+    GateClosed {
+        recheck_at: Option<DateTime<FixedOffset>>,
+    },
+    Cancelled,
+    AlreadyCompleted,
+    StreamDropped,
+    #[cfg(feature = "chaos")]
+    Injected {
+        fault: InjectedFault,
+    },
+}
+
+/// The occurrence an OccurrenceOutcome is about, or the one given to the callback of BlockingScheduler::start_with_context
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct TaskContext<'tc, TaskType> {
+    pub mode: &'tc str,
+    pub task: &'tc TaskType,
+    // Date the occurrence was planned at (the first missed one for Skipped)
+    pub date: DateTime<FixedOffset>,
+    // Fired for a past occurrence by BlockingScheduler::backfill, the schedule being left as is
+    pub is_backfill: bool,
+    pub(crate) cancel_token: Option<&'tc CancelToken>,
+    pub(crate) execution: Option<Execution>,
+}
+
+impl<'tc, TaskType> TaskContext<'tc, TaskType> {
+    // Token of the running callback, None for the contexts given to the occurrence hook
+    pub fn cancel_token(&self) -> Option<&'tc CancelToken> {
+        self.cancel_token
+    }
+    // Token given to the occurrence when it was started, the same when it's started again after a restore (see the
+    // ledger module). None when the ledger is disabled (see SchedulerConfig::occurrence_ledger), and for the contexts
+    // given to the occurrence hook.
+    pub fn execution_token(&self) -> Option<u64> {
+        self.execution.map(|execution| execution.token)
+    }
+    // True when the occurrence had been started and not completed before the scheduler was saved: the callback may
+    // have run for it already, with the same execution token
+    pub fn is_replay(&self) -> bool {
+        self.execution.is_some_and(|execution| execution.is_replay)
+    }
+}
+
+pub type OccurrenceHook<TaskType> = fn(OccurrenceOutcome, &TaskContext<'_, TaskType>);
@@ -0,0 +1,585 @@
+//! Tolerant loading of a scheduler saved as JSON, for snapshots written before the task type changed.
+//! The tasks that can't be deserialized anymore are dropped one by one instead of failing the whole snapshot.
+//! Giving `#[serde(default)]` to the fields added to the task type lets the old tasks load without being dropped.
+//!
+//! ```
+//! use scheduler::persistence::parse_json_lenient;
+//! use scheduler::prelude::*;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let snapshot = r#"{
+//!     "scheduled_tasks": {"daily": [
+//!         {"date": "2024-01-01T09:00:00+01:00", "task": 1, "repetition": "Once", "sleep_type": "Native"},
+//!         {"date": "2024-01-01T10:00:00+01:00", "task": "two", "repetition": "Once", "sleep_type": "Native"}
+//!     ]},
+//!     "removed_tasks": {},
+//!     "custom_repetition": null
+//! }"#;
+//! let (scheduler, warnings) = parse_json_lenient::<u32, NoCustomRepetition>(snapshot).unwrap();
+//! assert_eq!(scheduler.scheduled_tasks["daily"].len(), 1);
+//! assert_eq!(warnings.len(), 1);
+//! assert_eq!((warnings[0].mode.as_str(), warnings[0].index), ("daily", 1));
+//! ```
+//!
+//! The payloads that can't derive Serialize and Deserialize (a handle on a foreign type) are saved through
+//! PersistPayload by to_json_persisted instead. A payload that can't be saved leaves a stub holding the rest of its
+//! task (id, date, repetition...), given back by parse_json_persisted so that the payload is registered again, by
+//! rehydrate for instance.
+//!
+//! ```
+//! use chrono::DateTime;
+//! use scheduler::persistence::{parse_json_persisted, rehydrate, to_json_persisted, PersistPayload};
+//! use scheduler::prelude::*;
+//! use serde_json::Value;
+//! use std::collections::HashMap;
+//! use std::sync::{mpsc, Arc, Mutex};
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! // Without serde impls, compared by address
+//! #[derive(Clone, Debug)]
+//! struct Channel(Arc<Mutex<mpsc::Sender<String>>>);
+//! impl PartialEq for Channel {
+//!     fn eq(&self, other: &Self) -> bool {
+//!         Arc::ptr_eq(&self.0, &other.0)
+//!     }
+//! }
+//! impl Eq for Channel {}
+//!
+//! #[derive(PartialEq, Eq, Clone, Default, Debug)]
+//! enum Job {
+//!     #[default]
+//!     Idle,
+//!     Report(String),
+//!     Notify(Channel),
+//! }
+//! impl PersistPayload for Job {
+//!     fn to_persist(&self) -> Option<Value> {
+//!         match self {
+//!             Job::Report(name) => Some(Value::from(name.as_str())),
+//!             Job::Idle | Job::Notify(_) => None,
+//!         }
+//!     }
+//!     fn from_persist(value: Value) -> Option<Self> {
+//!         value.as_str().map(|name| Job::Report(name.to_string()))
+//!     }
+//! }
+//!
+//! let at = |date| DateTime::parse_from_rfc3339(date).unwrap();
+//! let (sender, _receiver) = mpsc::channel();
+//! let notify = Job::Notify(Channel(Arc::new(Mutex::new(sender))));
+//! let weekly = RepetitionType::Weekly(Termination::default());
+//! let scheduler = BlockingScheduler::new(
+//!     [(
+//!         "jobs".to_string(),
+//!         vec![
+//!             ScheduledTask::new(at("2024-01-01T09:00:00+01:00"), Job::Report("sales".to_string()), weekly.clone(), SleepType::Native),
+//!             ScheduledTask::new(at("2024-01-01T10:00:00+01:00"), notify.clone(), weekly, SleepType::Native),
+//!         ],
+//!     )],
+//!     [],
+//! );
+//! let saved = to_json_persisted(&scheduler).unwrap();
+//!
+//! let (mut loaded, stubs) = parse_json_persisted::<Job, NoCustomRepetition>(&saved).unwrap();
+//! assert_eq!(loaded.scheduled_tasks["jobs"], scheduler.scheduled_tasks["jobs"][..1]);
+//! // The notification needs its channel again
+//! assert_eq!(stubs.len(), 1);
+//! let notify_id = scheduler.scheduled_tasks["jobs"][1].id().unwrap();
+//! assert_eq!((stubs[0].mode.as_str(), stubs[0].task.id()), ("jobs", Some(notify_id)));
+//! let registry = HashMap::from([(notify_id, notify)]);
+//! assert!(rehydrate(&mut loaded, stubs, &registry).is_empty());
+//! assert_eq!(loaded.scheduled_tasks, scheduler.scheduled_tasks);
+//! ```
+//!
+//! A daemon loads its snapshot through a PersistedScheduler, which locks the snapshot for as long as it's kept: a second
+//! instance started on the same snapshot fails with PersistError::AlreadyLocked instead of firing every task twice.
+use super::id::TaskId;
+use super::repetitions::{CustomRepetition, NoCustomRepetition};
+use super::resolver::TaskResolver;
+use super::schedulers::{BlockingScheduler, ScheduledTask};
+use chrono::{DateTime, FixedOffset};
+use fs2::FileExt;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+// Bytes of a snapshot read by read_next_fire, enough for the next_fire and the spaces of a pretty printed snapshot
+const NEXT_FIRE_HEAD: u64 = 256;
+
+/// Saved form of a payload, for the payloads that can't derive Serialize and Deserialize, see the persistence module
+pub trait PersistPayload: Sized {
+    // None when the payload can't be saved, its task being saved as a stub
+    fn to_persist(&self) -> Option<Value>;
+    fn from_persist(value: Value) -> Option<Self>;
+}
+
+/// Task saved without its payload by to_json_persisted, or whose payload couldn't be read back
+#[derive(PartialEq, Clone, Debug)]
+pub struct PayloadStub {
+    pub mode: String,
+    // True when the task was one of the removed tasks of the mode. A pending deletion comes back as a removed task.
+    pub removed: bool,
+    pub task: ScheduledTask<()>,
+}
+
+impl PayloadStub {
+    // The task of the stub with its payload, keeping its id
+    pub fn rehydrate<TaskType>(&self, payload: TaskType) -> ScheduledTask<TaskType> {
+        self.task.with_payload(payload)
+    }
+}
+
+/// Task dropped by a lenient load, `raw` being the task as it was found in the snapshot
+#[derive(Serialize, PartialEq, Clone, Debug)]
+pub struct LoadWarning {
+    pub mode: String,
+    // True when the task was one of the removed tasks of the mode
+    pub removed: bool,
+    // Position of the task in its mode, in the snapshot
+    pub index: usize,
+    pub error: String,
+    pub raw: Value,
+}
+
+// Reads the snapshot at the path, see parse_json_lenient.
+// When some tasks are dropped and a quarantine path is given, they're written there as a JSON array of LoadWarning.
+pub fn load_json_lenient<TaskType, CustomRepetitionType>(
+    path: impl AsRef<Path>,
+    quarantine: Option<&Path>,
+) -> Result<
+    (
+        BlockingScheduler<TaskType, CustomRepetitionType>,
+        Vec<LoadWarning>,
+    ),
+    String,
+>
+where
+    TaskType: DeserializeOwned + Eq + Default,
+    CustomRepetitionType: DeserializeOwned,
+{
+    let path = path.as_ref();
+    let json = fs::read_to_string(path)
+        .map_err(|error| format!("Couldn't read the snapshot {} : {}", path.display(), error))?;
+    let (scheduler, warnings) = parse_json_lenient(&json)?;
+    if let (Some(quarantine), false) = (quarantine, warnings.is_empty()) {
+        let dropped = serde_json::to_string_pretty(&warnings).map_err(|error| error.to_string())?;
+        fs::write(quarantine, dropped).map_err(|error| {
+            format!(
+                "Couldn't write the quarantine file {} : {}",
+                quarantine.display(),
+                error
+            )
+        })?;
+    }
+    Ok((scheduler, warnings))
+}
+
+// Deserializes the scheduler, dropping the tasks that can't be deserialized.
+// An error is only returned when the rest of the snapshot is invalid.
+pub fn parse_json_lenient<TaskType, CustomRepetitionType>(
+    json: &str,
+) -> Result<
+    (
+        BlockingScheduler<TaskType, CustomRepetitionType>,
+        Vec<LoadWarning>,
+    ),
+    String,
+>
+where
+    TaskType: DeserializeOwned + Eq + Default,
+    CustomRepetitionType: DeserializeOwned,
+{
+    let mut snapshot: Value =
+        serde_json::from_str(json).map_err(|error| format!("Invalid snapshot : {}", error))?;
+    let mut warnings = Vec::new();
+    for (field, removed) in [("scheduled_tasks", false), ("removed_tasks", true)] {
+        let Some(modes) = snapshot.get_mut(field).and_then(Value::as_object_mut) else {
+            continue;
+        };
+        for (mode, tasks) in modes.iter_mut() {
+            let Some(tasks) = tasks.as_array_mut() else {
+                continue;
+            };
+            let mut index = 0;
+            tasks.retain(|raw| {
+                let kept = match ScheduledTask::<TaskType>::deserialize(raw) {
+                    Ok(_) => true,
+                    Err(error) => {
+                        warnings.push(LoadWarning {
+                            mode: mode.clone(),
+                            removed,
+                            index,
+                            error: error.to_string(),
+                            raw: raw.clone(),
+                        });
+                        false
+                    }
+                };
+                index += 1;
+                kept
+            });
+        }
+    }
+    let scheduler = serde_json::from_value(snapshot)
+        .map_err(|error| format!("Invalid snapshot : {}", error))?;
+    Ok((scheduler, warnings))
+}
+
+// Reads the next_fire written first by the serialization of a scheduler (see BlockingScheduler::next_fire) from the
+// beginning of the snapshot at the path, without reading the rest of it
+pub fn read_next_fire(path: impl AsRef<Path>) -> Result<Option<DateTime<FixedOffset>>, String> {
+    let path = path.as_ref();
+    let mut head = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(NEXT_FIRE_HEAD).read_to_end(&mut head))
+        .map_err(|error| format!("Couldn't read the snapshot {} : {}", path.display(), error))?;
+    peek_next_fire(&String::from_utf8_lossy(&head))
+}
+
+/// Same as read_next_fire, from the beginning of a snapshot, the rest of it being ignored
+/// ```
+/// use chrono::DateTime;
+/// use scheduler::persistence::peek_next_fire;
+/// use scheduler::prelude::*;
+/// # scheduler::system_clock!(|| chrono::Local::now().into());
+///
+/// let at = |date| DateTime::parse_from_rfc3339(date).unwrap();
+/// let task = |date, task| ScheduledTask::new(at(date), task, RepetitionType::Once, SleepType::Native);
+/// let mut scheduler = BlockingScheduler::new(
+///     [
+///         ("reports".to_string(), vec![task("2024-01-01T09:00:00+01:00", 1), task("2024-01-02T09:00:00+01:00", 2)]),
+///         ("backups".to_string(), vec![task("2024-01-01T03:00:00+01:00", 3)]),
+///     ],
+///     [],
+/// );
+/// let json = serde_json::to_string_pretty(&scheduler).unwrap();
+/// // A supervisor only needs the beginning of the file
+/// assert_eq!(peek_next_fire(&json[..64]).unwrap(), Some(at("2024-01-01T03:00:00+01:00")));
+/// // Written again by each serialization
+/// scheduler.tick("backups", at("2024-01-01T03:00:00+01:00"), |_| {}).unwrap();
+/// let json = serde_json::to_string(&scheduler).unwrap();
+/// assert_eq!(peek_next_fire(&json).unwrap(), Some(at("2024-01-01T09:00:00+01:00")));
+/// let loaded: BlockingScheduler<u32> = serde_json::from_str(&json).unwrap();
+/// assert_eq!(loaded.next_fire(), Some(at("2024-01-01T09:00:00+01:00")));
+/// scheduler.tick("reports", at("2024-01-02T09:00:00+01:00"), |_| {}).unwrap();
+/// assert_eq!(peek_next_fire(&serde_json::to_string(&scheduler).unwrap()).unwrap(), None);
+/// ```
+pub fn peek_next_fire(json: &str) -> Result<Option<DateTime<FixedOffset>>, String> {
+    let value = json
+        .trim_start()
+        .strip_prefix('{')
+        .map(str::trim_start)
+        .and_then(|json| json.strip_prefix(r#""next_fire""#))
+        .map(str::trim_start)
+        .and_then(|json| json.strip_prefix(':'))
+        // Neither null nor a date holds a comma or a brace
+        .and_then(|json| json.find([',', '}']).map(|end| &json[..end]))
+        .ok_or("The snapshot doesn't start with its next_fire".to_string())?;
+    serde_json::from_str(value.trim()).map_err(|error| format!("Invalid next_fire : {}", error))
+}
+
+// Serializes the scheduler, the payloads being saved through PersistPayload. A task whose payload gives None is saved
+// with a null payload, as a stub.
+pub fn to_json_persisted<TaskType, CustomRepetitionType>(
+    scheduler: &BlockingScheduler<TaskType, CustomRepetitionType>,
+) -> Result<String, String>
+where
+    TaskType: PersistPayload,
+    CustomRepetitionType: Serialize,
+{
+    serde_json::to_string(&scheduler.map_payloads(PersistPayload::to_persist))
+        .map_err(|error| format!("Couldn't serialize the scheduler : {}", error))
+}
+
+// Deserializes a scheduler saved by to_json_persisted. The tasks saved as stubs, or whose payload can't be read
+// back, are left out of the scheduler and returned, to be registered again (see rehydrate).
+pub fn parse_json_persisted<TaskType, CustomRepetitionType>(
+    json: &str,
+) -> Result<
+    (
+        BlockingScheduler<TaskType, CustomRepetitionType>,
+        Vec<PayloadStub>,
+    ),
+    String,
+>
+where
+    TaskType: PersistPayload,
+    CustomRepetitionType: DeserializeOwned,
+{
+    let saved: BlockingScheduler<Option<Value>, CustomRepetitionType> =
+        serde_json::from_str(json).map_err(|error| format!("Invalid snapshot : {}", error))?;
+    let mut stubs = Vec::new();
+    let scheduler = saved.filter_map_payloads(|mode, removed, task| {
+        match task.task.clone().and_then(TaskType::from_persist) {
+            Some(payload) => Some(task.with_payload(payload)),
+            None => {
+                stubs.push(PayloadStub {
+                    mode: mode.to_owned(),
+                    removed,
+                    task: task.with_payload(()),
+                });
+                None
+            }
+        }
+    });
+    Ok((scheduler, stubs))
+}
+
+// Puts the tasks of the stubs back into the scheduler with the payloads the registry gives for their ids, the stubs
+// it knows nothing about being returned
+pub fn rehydrate<TaskType, CustomRepetitionType>(
+    scheduler: &mut BlockingScheduler<TaskType, CustomRepetitionType>,
+    stubs: Vec<PayloadStub>,
+    registry: &impl TaskResolver<TaskId, TaskType>,
+) -> Vec<PayloadStub>
+where
+    TaskType: Eq + Default,
+    CustomRepetitionType: CustomRepetition + Clone,
+{
+    let mut unknown = Vec::new();
+    for stub in stubs {
+        let Some(payload) = stub.task.id().and_then(|id| registry.resolve(&id)) else {
+            unknown.push(stub);
+            continue;
+        };
+        let task = stub.rehydrate(payload);
+        if stub.removed {
+            scheduler
+                .removed_tasks
+                .entry(stub.mode)
+                .or_default()
+                .push(task);
+        } else {
+            scheduler.add_task(&stub.mode, task);
+        }
+    }
+    unknown
+}
+
+/// Represents why a snapshot couldn't be used through a PersistedScheduler
+/// - AlreadyLocked : another PersistedScheduler holds the lock of the snapshot, in this process or another one. The pid
+///   is the one written in the lock file by its holder, when it could be read.
+/// - ReadOnly : the PersistedScheduler has been loaded without the lock (see PersistedScheduler::load_read_only) and
+///   can't save the snapshot
+/// - Failed : the snapshot or its lock file couldn't be read, parsed or written
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[non_exhaustive]
+pub enum PersistError {
+    AlreadyLocked { pid_hint: Option<u32> },
+    ReadOnly,
+    Failed(String),
+}
+
+impl std::fmt::Display for PersistError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AlreadyLocked {
+                pid_hint: Some(pid),
+            } => write!(f, "The snapshot is locked by the process {}", pid),
+            Self::AlreadyLocked { pid_hint: None } => write!(f, "The snapshot is locked"),
+            Self::ReadOnly => write!(f, "The snapshot has been loaded read-only"),
+            Self::Failed(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for PersistError {}
+
+impl From<String> for PersistError {
+    fn from(reason: String) -> Self {
+        Self::Failed(reason)
+    }
+}
+
+impl From<PersistError> for String {
+    fn from(error: PersistError) -> Self {
+        error.to_string()
+    }
+}
+
+// Advisory lock of a snapshot, taken on a lock file next to it (the snapshot itself being replaced by each save) and
+// released when the file is closed, the process dying included
+#[derive(Debug)]
+struct SnapshotLock {
+    // Only held, closing it releases the lock
+    _file: File,
+}
+
+impl SnapshotLock {
+    fn acquire(snapshot: &Path) -> Result<Self, PersistError> {
+        let mut path = snapshot.as_os_str().to_owned();
+        path.push(".lock");
+        let path = PathBuf::from(path);
+        let failed = |error: std::io::Error| {
+            PersistError::Failed(format!(
+                "Couldn't lock the snapshot {} : {}",
+                snapshot.display(),
+                error
+            ))
+        };
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(failed)?;
+        if let Err(error) = file.try_lock_exclusive() {
+            if error.raw_os_error() != fs2::lock_contended_error().raw_os_error() {
+                return Err(failed(error));
+            }
+            let mut pid = String::new();
+            let _ = file.read_to_string(&mut pid);
+            return Err(PersistError::AlreadyLocked {
+                pid_hint: pid.trim().parse().ok(),
+            });
+        }
+        file.set_len(0)
+            .and_then(|_| write!(file, "{}", std::process::id()))
+            .and_then(|_| file.flush())
+            .map_err(failed)?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// Scheduler loaded from a snapshot, holding the lock of the snapshot until it's dropped so that a second process
+/// loading it gets PersistError::AlreadyLocked instead of firing the same tasks. The lock is advisory: load_json_lenient,
+/// parse_json_persisted and read_next_fire don't look at it, for the tools inspecting a snapshot in use, and neither does
+/// PersistedScheduler::load_read_only.
+///
+/// ```
+/// use chrono::DateTime;
+/// use scheduler::persistence::{PersistError, PersistedScheduler};
+/// use scheduler::prelude::*;
+/// # scheduler::system_clock!(|| chrono::Local::now().into());
+///
+/// let path = std::env::temp_dir().join("scheduler_persisted_doctest.json");
+/// let at = DateTime::parse_from_rfc3339("2024-01-01T09:00:00+01:00").unwrap();
+/// let task = ScheduledTask::new(at, 1, RepetitionType::Once, SleepType::Native);
+/// let scheduler = BlockingScheduler::<u32>::new([("jobs".to_string(), vec![task])], []);
+/// std::fs::write(&path, serde_json::to_string(&scheduler).unwrap()).unwrap();
+///
+/// let (persisted, _) = PersistedScheduler::<u32>::load(&path, None).unwrap();
+/// assert_eq!(
+///     PersistedScheduler::<u32>::load(&path, None).unwrap_err(),
+///     PersistError::AlreadyLocked { pid_hint: Some(std::process::id()) }
+/// );
+/// let (inspected, _) = PersistedScheduler::<u32>::load_read_only(&path, None).unwrap();
+/// assert_eq!(inspected.scheduler.scheduled_tasks, persisted.scheduler.scheduled_tasks);
+/// assert_eq!(inspected.save(), Err(PersistError::ReadOnly));
+/// persisted.save().unwrap();
+/// drop(persisted);
+/// assert!(PersistedScheduler::<u32>::load(&path, None).is_ok());
+/// # std::fs::remove_file(&path).unwrap();
+/// # std::fs::remove_file(path.with_extension("json.lock")).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct PersistedScheduler<TaskType, CustomRepetitionType = NoCustomRepetition> {
+    pub scheduler: BlockingScheduler<TaskType, CustomRepetitionType>,
+    path: PathBuf,
+    // None when loaded read-only
+    lock: Option<SnapshotLock>,
+}
+
+impl<TaskType, CustomRepetitionType> PersistedScheduler<TaskType, CustomRepetitionType> {
+    // Locks the snapshot at the path, then reads it with load_json_lenient
+    pub fn load(
+        path: impl AsRef<Path>,
+        quarantine: Option<&Path>,
+    ) -> Result<(Self, Vec<LoadWarning>), PersistError>
+    where
+        TaskType: DeserializeOwned + Eq + Default,
+        CustomRepetitionType: DeserializeOwned,
+    {
+        let path = path.as_ref();
+        let lock = SnapshotLock::acquire(path)?;
+        let (scheduler, warnings) = load_json_lenient(path, quarantine)?;
+        Ok((Self::new(scheduler, path, Some(lock)), warnings))
+    }
+    // Same as Self::load without taking the lock, whoever holds it: the scheduler can be looked at but not saved
+    pub fn load_read_only(
+        path: impl AsRef<Path>,
+        quarantine: Option<&Path>,
+    ) -> Result<(Self, Vec<LoadWarning>), PersistError>
+    where
+        TaskType: DeserializeOwned + Eq + Default,
+        CustomRepetitionType: DeserializeOwned,
+    {
+        let path = path.as_ref();
+        let (scheduler, warnings) = load_json_lenient(path, quarantine)?;
+        Ok((Self::new(scheduler, path, None), warnings))
+    }
+    // Locks the snapshot at the path, then reads it with parse_json_persisted
+    pub fn load_persisted(path: impl AsRef<Path>) -> Result<(Self, Vec<PayloadStub>), PersistError>
+    where
+        TaskType: PersistPayload,
+        CustomRepetitionType: DeserializeOwned,
+    {
+        let path = path.as_ref();
+        let lock = SnapshotLock::acquire(path)?;
+        let json = fs::read_to_string(path).map_err(|error| {
+            format!("Couldn't read the snapshot {} : {}", path.display(), error)
+        })?;
+        let (scheduler, stubs) = parse_json_persisted(&json)?;
+        Ok((Self::new(scheduler, path, Some(lock)), stubs))
+    }
+
+    fn new(
+        scheduler: BlockingScheduler<TaskType, CustomRepetitionType>,
+        path: &Path,
+        lock: Option<SnapshotLock>,
+    ) -> Self {
+        Self {
+            scheduler,
+            path: path.to_owned(),
+            lock,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    pub fn is_read_only(&self) -> bool {
+        self.lock.is_none()
+    }
+
+    // Writes the scheduler to the snapshot as JSON
+    pub fn save(&self) -> Result<(), PersistError>
+    where
+        TaskType: Serialize,
+        CustomRepetitionType: Serialize,
+    {
+        let json = serde_json::to_string(&self.scheduler)
+            .map_err(|error| format!("Couldn't serialize the scheduler : {}", error))?;
+        self.write(&json)
+    }
+    // Writes the scheduler to the snapshot with to_json_persisted
+    pub fn save_persisted(&self) -> Result<(), PersistError>
+    where
+        TaskType: PersistPayload,
+        CustomRepetitionType: Serialize,
+    {
+        self.write(&to_json_persisted(&self.scheduler)?)
+    }
+
+    // Writes next to the snapshot then moves the file there, so that a crash never leaves a partial snapshot
+    fn write(&self, json: &str) -> Result<(), PersistError> {
+        if self.is_read_only() {
+            return Err(PersistError::ReadOnly);
+        }
+        let mut temporary_path = self.path.clone().into_os_string();
+        temporary_path.push(".tmp");
+        fs::write(&temporary_path, json)
+            .and_then(|_| fs::rename(&temporary_path, &self.path))
+            .map_err(|error| {
+                PersistError::Failed(format!(
+                    "Couldn't write the snapshot {} : {}",
+                    self.path.display(),
+                    error
+                ))
+            })
+    }
+}
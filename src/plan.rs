@@ -0,0 +1,231 @@
+//! Catch-up pass planned before being done, so that an operator tool can show what a mode would do on restart (what it
+//! fires, where its tasks are moved, which ones are removed) and only then do it. BlockingScheduler::plan_catch_up runs
+//! the pass of BlockingScheduler::catch_up on a copy of the mode, without calling anything, and apply_plan runs it for
+//! real as long as the mode and its config haven't changed since: the pass doesn't read the current time, so it does
+//! exactly what was planned (unless a custom repetition answers differently the second time).
+//!
+//! ```
+//! use chrono::{DateTime, Duration};
+//! use scheduler::plan::PlannedMutation;
+//! use scheduler::prelude::*;
+//! use std::collections::HashMap;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let start = DateTime::parse_from_rfc3339("2024-01-01T09:00:00+01:00").unwrap();
+//! let hourly = RepetitionType::ConstGap { gap: Duration::hours(1), termination: Termination::default() };
+//! let define = || {
+//!     let mut scheduler = BlockingScheduler::new(
+//!         HashMap::from([(
+//!             "jobs".to_string(),
+//!             vec![
+//!                 ScheduledTask::new(start, "backup", hourly.clone(), SleepType::Native),
+//!                 ScheduledTask::new(start, "welcome", RepetitionType::Once, SleepType::Native),
+//!             ],
+//!         )]),
+//!         HashMap::new(),
+//!     );
+//!     scheduler.config.missed_tick_behavior = MissedTickBehavior::FireAll;
+//!     scheduler
+//! };
+//! let restart = start + Duration::minutes(150);
+//!
+//! let mut planned = define();
+//! let plan = planned.plan_catch_up("jobs", restart).unwrap();
+//! let backup = planned.scheduled_tasks["jobs"][0].id().unwrap();
+//! let welcome = planned.scheduled_tasks["jobs"][1].id().unwrap();
+//! assert_eq!(
+//!     plan.mutations,
+//!     [
+//!         PlannedMutation::Fire { id: backup, task: "backup", scheduled_for: start },
+//!         PlannedMutation::Fire { id: backup, task: "backup", scheduled_for: start + Duration::hours(1) },
+//!         PlannedMutation::Fire { id: backup, task: "backup", scheduled_for: start + Duration::hours(2) },
+//!         PlannedMutation::Reschedule { id: backup, from: start, to: start + Duration::hours(3) },
+//!         PlannedMutation::Fire { id: welcome, task: "welcome", scheduled_for: start },
+//!         PlannedMutation::Remove { id: welcome, reason: RemovalReason::Finished },
+//!     ]
+//! );
+//! // Nothing has been done yet
+//! assert_eq!(planned.scheduled_tasks["jobs"].len(), 2);
+//!
+//! let mut fired = Vec::new();
+//! planned.apply_plan(plan.clone(), |task| fired.push(*task)).unwrap();
+//! assert_eq!(fired, ["backup", "backup", "backup", "welcome"]);
+//!
+//! // Same outcome as a direct catch-up
+//! let mut direct = define();
+//! let mut fired_directly = Vec::new();
+//! direct.catch_up("jobs", restart, |task| fired_directly.push(*task)).unwrap();
+//! assert_eq!(fired, fired_directly);
+//! let dates = |scheduler: &BlockingScheduler<&str>| {
+//!     scheduler.scheduled_tasks["jobs"].iter().map(|task| (task.task.to_string(), task.date)).collect::<Vec<_>>()
+//! };
+//! assert_eq!(dates(&planned), dates(&direct));
+//! assert_eq!(planned.removed_tasks["jobs"].len(), direct.removed_tasks["jobs"].len());
+//!
+//! // A plan is only applied to the state it has been made from
+//! assert!(planned.apply_plan(plan, |_| {}).is_err());
+//! ```
+use super::config::{CatchUpReport, SchedulerConfig};
+use super::id::TaskId;
+use super::repetitions::CustomRepetition;
+use super::schedulers::{BlockingScheduler, RemovalReason, ScheduledTask};
+use chrono::{DateTime, FixedOffset};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Represents what the catch-up pass does to a task
+/// - Reschedule : the task is moved from its date to its next occurrence after now
+/// - Remove : the task is removed, for the reason
+/// - Fire : the task is fired for a missed occurrence
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum PlannedMutation<TaskType> {
+    Reschedule {
+        id: TaskId,
+        from: DateTime<FixedOffset>,
+        to: DateTime<FixedOffset>,
+    },
+    Remove {
+        id: TaskId,
+        reason: RemovalReason,
+    },
+    Fire {
+        id: TaskId,
+        task: TaskType,
+        scheduled_for: DateTime<FixedOffset>,
+    },
+}
+
+/// Catch-up pass of a mode planned by BlockingScheduler::plan_catch_up, to be done by BlockingScheduler::apply_plan
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct CatchUpPlan<TaskType> {
+    // In the order they happen, the firings of a task coming before its rescheduling or removal
+    pub mutations: Vec<PlannedMutation<TaskType>>,
+    mode: String,
+    now: DateTime<FixedOffset>,
+    // Of the tasks of the mode when the plan has been made
+    fingerprint: u64,
+    config: SchedulerConfig,
+}
+
+impl<TaskType> CatchUpPlan<TaskType> {
+    pub fn mode(&self) -> &str {
+        &self.mode
+    }
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        self.now
+    }
+}
+
+// What the catch-up pass of a mode does, run on a copy of its tasks
+pub(crate) struct CatchUpSimulation<TaskType> {
+    // The tasks as they are fired, once per firing
+    pub(crate) fired: Vec<ScheduledTask<TaskType>>,
+    pub(crate) tasks: Vec<ScheduledTask<TaskType>>,
+    pub(crate) removed_tasks: Vec<ScheduledTask<TaskType>>,
+}
+
+impl<TaskType, CustomRepetitionType> BlockingScheduler<TaskType, CustomRepetitionType>
+where
+    TaskType: Eq + Default + Clone + Hash,
+    CustomRepetitionType: CustomRepetition + Clone,
+{
+    // What catch_up would do to the mode at now, nothing being fired nor changed. Fails when catch_up would, and when a
+    // task of the mode has no id.
+    pub fn plan_catch_up(
+        &self,
+        mode: &str,
+        now: impl Into<DateTime<FixedOffset>>,
+    ) -> Result<CatchUpPlan<TaskType>, String> {
+        let now = now.into();
+        let simulation = self.simulate_catch_up(mode, &now)?;
+        let mut tasks: Vec<_> = self.scheduled_tasks[mode].iter().collect();
+        if tasks.iter().any(|task| task.id().is_none()) {
+            return Err(format!(
+                "Couldn't plan the catch-up of the mode {} : a task has no id, see BlockingScheduler::normalize",
+                mode
+            ));
+        }
+        tasks.sort_by_key(|task| task.date);
+        let custom_repetition = self.custom_repetition();
+        let mut mutations = Vec::new();
+        for (task, task_id) in tasks
+            .into_iter()
+            .filter_map(|task| Some((task, task.id()?)))
+        {
+            // The replays of a task are fired for its successive missed occurrences
+            let mut scheduled_for = None;
+            for fired in simulation
+                .fired
+                .iter()
+                .filter(|fired| fired.id() == Some(task_id))
+            {
+                let date = scheduled_for.map_or(fired.date, |previous| {
+                    fired
+                        .repetition
+                        .next_occurrence(&previous, &previous, &custom_repetition)
+                        .unwrap_or(previous)
+                });
+                scheduled_for = Some(date);
+                mutations.push(PlannedMutation::Fire {
+                    id: task_id,
+                    task: fired.task.clone(),
+                    scheduled_for: date,
+                });
+            }
+            if let Some(removed) = simulation
+                .removed_tasks
+                .iter()
+                .find(|removed| removed.id() == Some(task_id))
+            {
+                mutations.push(PlannedMutation::Remove {
+                    id: task_id,
+                    reason: removed.removal_reason.unwrap_or(RemovalReason::Finished),
+                });
+            } else if let Some(moved) = simulation
+                .tasks
+                .iter()
+                .find(|moved| moved.id() == Some(task_id) && moved.date != task.date)
+            {
+                mutations.push(PlannedMutation::Reschedule {
+                    id: task_id,
+                    from: task.date,
+                    to: moved.date,
+                });
+            }
+        }
+        Ok(CatchUpPlan {
+            mutations,
+            mode: mode.to_owned(),
+            now,
+            fingerprint: self.fingerprint(mode),
+            config: self.config.for_mode(mode),
+        })
+    }
+
+    // Runs the catch-up pass the plan has been made from, f being called for its Fire mutations. Fails without doing
+    // anything when the tasks or the config of the mode have changed since the plan has been made.
+    pub fn apply_plan(
+        &mut self,
+        plan: CatchUpPlan<TaskType>,
+        f: impl FnMut(&TaskType),
+    ) -> Result<CatchUpReport, String> {
+        if !self.has_mode(&plan.mode) {
+            return Err(self.unknown_mode(&plan.mode).to_string());
+        }
+        if self.fingerprint(&plan.mode) != plan.fingerprint
+            || self.config.for_mode(&plan.mode) != plan.config
+        {
+            return Err(format!(
+                "The mode {} has changed since its catch-up has been planned",
+                plan.mode
+            ));
+        }
+        self.catch_up(&plan.mode, plan.now, f)
+    }
+
+    fn fingerprint(&self, mode: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.scheduled_tasks.get(mode).hash(&mut hasher);
+        hasher.finish()
+    }
+}
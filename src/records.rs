@@ -0,0 +1,129 @@
+//! What a firing is, the same for every feature describing one: the items of the firing streams (see the stream module)
+//! and the Fired lines of the journal (see the journal module) are FiredTasks. The types are non-exhaustive so that they
+//! can grow a field without breaking their users, the records being built through ExecutionRecord::builder.
+//!
+//! ```
+//! use chrono::{DateTime, Duration};
+//! use scheduler::prelude::*;
+//!
+//! let scheduled = DateTime::parse_from_rfc3339("2024-01-01T09:00:00+01:00").unwrap();
+//! let record = ExecutionRecord::builder("daily", scheduled, scheduled + Duration::milliseconds(3))
+//!     .occurrence_index(2)
+//!     .build();
+//! let fired = FiredTask::new("backup", record);
+//! assert_eq!(fired.record.latency, std::time::Duration::from_millis(3));
+//! assert_eq!(fired.record.outcome, OccurrenceOutcome::Fired);
+//! assert_eq!(fired.record.task_id, None);
+//! ```
+use super::id::TaskId;
+use super::occurrence::OccurrenceOutcome;
+use chrono::{DateTime, FixedOffset};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Task fired, with its ExecutionRecord. The record is flattened in the serialized form.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[non_exhaustive]
+pub struct FiredTask<TaskType> {
+    pub task: TaskType,
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub record: ExecutionRecord,
+}
+
+impl<TaskType> FiredTask<TaskType> {
+    pub fn new(task: TaskType, record: ExecutionRecord) -> Self {
+        Self { task, record }
+    }
+    // Same firing with another payload (a reference to it, an Arc of it...)
+    pub fn map<Other>(self, f: impl FnOnce(TaskType) -> Other) -> FiredTask<Other> {
+        FiredTask {
+            task: f(self.task),
+            record: self.record,
+        }
+    }
+}
+
+/// Firing of a task of a mode. The planned date is written as "scheduled" (the key of the journals written before),
+/// the fields added since then are optional when read.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[non_exhaustive]
+pub struct ExecutionRecord {
+    // None for a task that isn't held by a scheduler
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub task_id: Option<TaskId>,
+    pub mode: String,
+    // Date the occurrence was planned at
+    #[cfg_attr(feature = "serde", serde(rename = "scheduled"))]
+    pub scheduled_for: DateTime<FixedOffset>,
+    pub fired_at: DateTime<FixedOffset>,
+    // How long after scheduled_for the task has been fired, 0 when it was fired early
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub latency: std::time::Duration,
+    #[cfg_attr(feature = "serde", serde(default = "fired"))]
+    pub outcome: OccurrenceOutcome,
+    // Rank of the firing among the ones of its task in the run (or in the stream), from 0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub occurrence_index: u64,
+    // What the callback has told of the firing (its result or its error), None when it told nothing
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub result: Option<String>,
+}
+
+#[cfg(feature = "serde")]
+fn fired() -> OccurrenceOutcome {
+    OccurrenceOutcome::Fired
+}
+
+impl ExecutionRecord {
+    // Record of a firing, OccurrenceOutcome::Fired and the first of its task until told otherwise
+    pub fn builder(
+        mode: &str,
+        scheduled_for: DateTime<FixedOffset>,
+        fired_at: DateTime<FixedOffset>,
+    ) -> ExecutionRecordBuilder {
+        ExecutionRecordBuilder(Self {
+            task_id: None,
+            mode: mode.to_owned(),
+            scheduled_for,
+            fired_at,
+            latency: (fired_at - scheduled_for).to_std().unwrap_or_default(),
+            outcome: OccurrenceOutcome::Fired,
+            occurrence_index: 0,
+            result: None,
+        })
+    }
+}
+
+/// Builds an ExecutionRecord, see ExecutionRecord::builder
+#[derive(Clone, Debug)]
+pub struct ExecutionRecordBuilder(ExecutionRecord);
+
+impl ExecutionRecordBuilder {
+    pub fn task_id(mut self, task_id: Option<TaskId>) -> Self {
+        self.0.task_id = task_id;
+        self
+    }
+    pub fn outcome(mut self, outcome: OccurrenceOutcome) -> Self {
+        self.0.outcome = outcome;
+        self
+    }
+    pub fn occurrence_index(mut self, occurrence_index: u64) -> Self {
+        self.0.occurrence_index = occurrence_index;
+        self
+    }
+    pub fn result(mut self, result: Option<String>) -> Self {
+        self.0.result = result;
+        self
+    }
+    pub fn build(self) -> ExecutionRecord {
+        self.0
+    }
+}
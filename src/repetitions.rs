@@ -1,13 +1,43 @@
-use chrono::{DateTime, Datelike, Duration, FixedOffset, TimeZone, Timelike};
-#[cfg(feature = "serde")]
-use {
-    serde::{Deserialize, Serialize},
-    serde_with::{As, DurationSeconds},
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc,
+    Weekday,
 };
+#[cfg(feature = "json-schema")]
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::Debug;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+// Advancements of a repetition by RepetitionType::next_occurrence before giving up reaching its origin, also the most
+// occurrences walked through to count the missed ones
+pub(crate) const MAX_ADVANCES: usize = 10_000;
 
-/// Represents the number of times the repetitions will occurs
+/// Represents the number of times the repetitions will occurs. A Finished(0) task never fires: it's removed
+/// (RemovalReason::Finished) when its mode is started or ticked.
+/// ```
+/// use chrono::{DateTime, Duration};
+/// use scheduler::prelude::*;
+/// use std::collections::HashMap;
+/// # scheduler::system_clock!(|| chrono::Local::now().into());
+///
+/// let start = DateTime::parse_from_rfc3339("2024-01-01T09:00:00+01:00").unwrap();
+/// for count in [0, 1, 2] {
+///     let repetition = RepetitionType::Weekly(RepetitionCount::Finished(count).into());
+///     let task = ScheduledTask::new(start, "report", repetition, SleepType::Native);
+///     let mut scheduler = BlockingScheduler::new(HashMap::from([("jobs".to_string(), vec![task])]), HashMap::new());
+///     let mut fired = 0;
+///     for week in 0..4 {
+///         scheduler.tick("jobs", start + Duration::weeks(week), |_| fired += 1).unwrap();
+///     }
+///     assert_eq!(fired, count);
+///     assert_eq!(scheduler.removed_tasks["jobs"][0].removal_reason, Some(RemovalReason::Finished));
+/// }
+/// ```
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Default)]
 pub enum RepetitionCount {
     #[default]
     Infinite,
@@ -16,17 +46,69 @@ pub enum RepetitionCount {
 
 impl RepetitionCount {
     /// If the repetition's count is finished, then the counter is decremented.
-    // The returned bool is the result of a test that checks if the count has reached 0
+    // The returned bool is the result of a test that checks if the count has reached 0, a count of 0 being already
+    // finished
     pub(crate) fn is_finished_on_update(&mut self) -> bool {
         match self {
             Self::Infinite => false,
+            Self::Finished(0) => true,
             Self::Finished(count) => {
                 *count -= 1;
-                *count <= 0
+                *count == 0
             }
         }
     }
 }
+/// Represents when a repeating task stops: after `count` occurrences or once its next occurrence is after `until`,
+/// whichever comes first. A bare RepetitionCount is accepted wherever a Termination is expected (see From).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "TerminationForm"))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Default)]
+pub struct Termination {
+    pub count: RepetitionCount,
+    pub until: Option<DateTime<FixedOffset>>,
+}
+
+impl Termination {
+    // See RepetitionCount::is_finished_on_update
+    pub(crate) fn is_finished_on_update(&mut self) -> bool {
+        self.count.is_finished_on_update()
+    }
+    pub fn is_expired_at(&self, date: &DateTime<FixedOffset>) -> bool {
+        self.until.is_some_and(|until| until < *date)
+    }
+}
+
+impl From<RepetitionCount> for Termination {
+    fn from(count: RepetitionCount) -> Self {
+        Self { count, until: None }
+    }
+}
+
+// Serialized forms of a Termination, the bare count being the one used before `until` existed
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TerminationForm {
+    Full {
+        count: RepetitionCount,
+        #[serde(default)]
+        until: Option<DateTime<FixedOffset>>,
+    },
+    Bare(RepetitionCount),
+}
+
+#[cfg(feature = "serde")]
+impl From<TerminationForm> for Termination {
+    fn from(form: TerminationForm) -> Self {
+        match form {
+            TerminationForm::Full { count, until } => Self { count, until },
+            TerminationForm::Bare(count) => count.into(),
+        }
+    }
+}
+
 pub trait CustomRepetition {
     fn update_date(
         &self,
@@ -34,6 +116,102 @@ pub trait CustomRepetition {
         current_date: &DateTime<FixedOffset>,
     ) -> Option<DateTime<FixedOffset>>;
 }
+// Custom repetition shared by a scheduler and its clones (the workers of a ParallelScheduler), so that it can be
+// replaced while they run. A computation already started keeps the previous one: update_date is called with the read
+// lock held, so a custom repetition must not replace itself from update_date (through set_custom_repetition), which
+// would deadlock.
+#[derive(Debug, Default)]
+pub(crate) struct SharedCustomRepetition<CustomRepetitionType>(Arc<RwLock<CustomRepetitionType>>);
+
+impl<CustomRepetitionType> SharedCustomRepetition<CustomRepetitionType> {
+    pub(crate) fn new(custom_repetition: CustomRepetitionType) -> Self {
+        Self(Arc::new(RwLock::new(custom_repetition)))
+    }
+    pub(crate) fn set(&self, custom_repetition: CustomRepetitionType) {
+        *self.0.write().unwrap() = custom_repetition;
+    }
+    pub(crate) fn get(&self) -> CustomRepetitionType
+    where
+        CustomRepetitionType: Clone,
+    {
+        self.0.read().unwrap().clone()
+    }
+    pub(crate) fn swap(&self, custom_repetition: CustomRepetitionType) -> CustomRepetitionSwap
+    where
+        CustomRepetitionType: Send + Sync + 'static,
+    {
+        let shared = self.clone();
+        CustomRepetitionSwap(Box::new(move || shared.set(custom_repetition)))
+    }
+}
+
+/// Custom repetition sent to a started mode by SchedulerCommand::SetCustomRepetition, made by
+/// BlockingScheduler::custom_repetition_swap. It replaces the custom repetition of the scheduler it's made from once the
+/// mode applies it, in order with the other commands.
+pub struct CustomRepetitionSwap(Box<dyn FnOnce() + Send>);
+
+impl CustomRepetitionSwap {
+    pub(crate) fn apply(self) {
+        (self.0)()
+    }
+}
+
+impl Debug for CustomRepetitionSwap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CustomRepetitionSwap")
+    }
+}
+
+impl<CustomRepetitionType> Clone for SharedCustomRepetition<CustomRepetitionType> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<CustomRepetitionType: CustomRepetition> CustomRepetition
+    for SharedCustomRepetition<CustomRepetitionType>
+{
+    fn update_date(
+        &self,
+        origin: &DateTime<FixedOffset>,
+        current_date: &DateTime<FixedOffset>,
+    ) -> Option<DateTime<FixedOffset>> {
+        self.0.read().unwrap().update_date(origin, current_date)
+    }
+}
+
+// Serialized as the custom repetition itself, and described as such by its schema
+#[cfg(feature = "serde")]
+impl<CustomRepetitionType: Serialize> Serialize for SharedCustomRepetition<CustomRepetitionType> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.read().unwrap().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, CustomRepetitionType: Deserialize<'de>> Deserialize<'de>
+    for SharedCustomRepetition<CustomRepetitionType>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        CustomRepetitionType::deserialize(deserializer).map(Self::new)
+    }
+}
+
+#[cfg(feature = "json-schema")]
+impl<CustomRepetitionType: JsonSchema> JsonSchema for SharedCustomRepetition<CustomRepetitionType> {
+    fn is_referenceable() -> bool {
+        CustomRepetitionType::is_referenceable()
+    }
+    fn schema_name() -> String {
+        CustomRepetitionType::schema_name()
+    }
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        CustomRepetitionType::json_schema(gen)
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
 #[derive(Clone, Debug)]
 pub struct NoCustomRepetition;
 
@@ -49,70 +227,532 @@ impl CustomRepetition for NoCustomRepetition {
 /// Represents how the date will be repeated
 /// - Once
 /// - Weekly
-/// - Monthly
+/// - Monthly : on its day of the month, the months without it getting their last day instead: the 31st of January is
+///   followed by the 28th or 29th of February, then by the 31st of March. Without a day, the one of the date of the
+///   task is taken, which ScheduledTask::new gives to the repetition.
 /// - Yearly
 /// - StaticGap
 /// - Custom : the gap represents the amount of time between two repetitions
-/// For Weekly, Monthly, Yearly and Custom, you need to give a RepetitionCount
+///
+/// - SelfScheduled : the next date is returned by the callback, see BlockingScheduler::start_self_scheduled
+/// - IsoWeekly : on the weekday of the ISO weeks, only the even or odd ones when a parity is given
+///
+/// For Weekly, Monthly, Yearly, ConstGap, SelfScheduled and IsoWeekly, you need to give a Termination
+///
+/// ```
+/// use chrono::{DateTime, Weekday};
+/// use scheduler::prelude::*;
+///
+/// let at = |date| DateTime::parse_from_rfc3339(date).unwrap();
+/// let next = |repetition: &RepetitionType, origin| {
+///     repetition.next_occurrence(&at(origin), &at("2020-01-06T08:30:00+01:00"), &NoCustomRepetition).unwrap()
+/// };
+/// let even_mondays = RepetitionType::IsoWeekly {
+///     parity: Some(WeekParity::Even),
+///     weekday: Weekday::Mon,
+///     termination: RepetitionCount::Infinite.into(),
+/// };
+/// // 2020 has 53 ISO weeks: the week 53 and the week 1 of 2021 are both odd
+/// assert_eq!(next(&even_mondays, "2020-12-15T00:00:00+01:00"), at("2020-12-21T08:30:00+01:00"));
+/// assert_eq!(next(&even_mondays, "2020-12-21T08:30:00+01:00"), at("2021-01-11T08:30:00+01:00"));
+///
+/// // The 1st of January 2021 is a Friday of the week 53 of the ISO year 2020
+/// let fridays = RepetitionType::IsoWeekly { parity: None, weekday: Weekday::Fri, termination: Termination::default() };
+/// assert_eq!(next(&fridays, "2020-12-26T00:00:00+01:00"), at("2021-01-01T08:30:00+01:00"));
+/// // The 30th of December 2024 is the Monday of the week 1 of the ISO year 2025
+/// let odd_mondays = RepetitionType::IsoWeekly {
+///     parity: Some(WeekParity::Odd),
+///     weekday: Weekday::Mon,
+///     termination: RepetitionCount::Finished(3).into(),
+/// };
+/// assert_eq!(next(&odd_mondays, "2024-12-24T00:00:00+01:00"), at("2024-12-30T08:30:00+01:00"));
+/// assert_eq!(next(&odd_mondays, "2024-12-30T09:00:00+01:00"), at("2025-01-13T08:30:00+01:00"));
+/// ```
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Default)]
 pub enum RepetitionType {
     #[default]
     Once,
-    Weekly(RepetitionCount),
-    Monthly(RepetitionCount),
-    Yearly(RepetitionCount),
+    Weekly(Termination),
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_monthly"))]
+    Monthly {
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Option::is_none")
+        )]
+        day: Option<u32>,
+        #[cfg_attr(feature = "serde", serde(flatten))]
+        termination: Termination,
+    },
+    Yearly(Termination),
     ConstGap {
-        #[cfg_attr(feature = "serde", serde(with = "As::<DurationSeconds<i64>>"))]
+        #[cfg_attr(feature = "serde", serde(with = "crate::duration_seconds"))]
+        #[cfg_attr(feature = "json-schema", schemars(with = "i64"))]
         gap: Duration,
-        count: RepetitionCount,
+        #[cfg_attr(feature = "serde", serde(alias = "count"))]
+        termination: Termination,
     },
     Custom,
+    SelfScheduled(Termination),
+    IsoWeekly {
+        #[cfg_attr(feature = "serde", serde(default))]
+        parity: Option<WeekParity>,
+        weekday: Weekday,
+        #[cfg_attr(feature = "serde", serde(alias = "count"))]
+        termination: Termination,
+    },
 }
-pub struct RepetitionHelpers;
-impl RepetitionHelpers {
-    pub fn update_weekly(origin: &DateTime<FixedOffset>, date: &mut DateTime<FixedOffset>) {
-        Self::update_const_gap(origin, date, Duration::days(7));
+
+// Serialized forms of a Monthly repetition, the bare termination being the one used before its day existed
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MonthlyForm {
+    Anchored {
+        day: u32,
+        #[serde(flatten)]
+        termination: Termination,
+    },
+    Bare(Termination),
+}
+
+#[cfg(feature = "serde")]
+fn deserialize_monthly<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<(Option<u32>, Termination), D::Error> {
+    Ok(match MonthlyForm::deserialize(deserializer)? {
+        MonthlyForm::Anchored { day, termination } => (Some(day), termination),
+        MonthlyForm::Bare(termination) => (None, termination),
+    })
+}
+
+/// Represents the ISO weeks an IsoWeekly repetition keeps, from their number in the ISO year
+/// - Even : the weeks 2, 4... 52
+/// - Odd : the weeks 1, 3... 53, so that a year of 53 weeks is followed by two odd weeks in a row
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum WeekParity {
+    Even,
+    Odd,
+}
+
+impl WeekParity {
+    pub fn matches(&self, week: u32) -> bool {
+        match self {
+            Self::Even => week.is_multiple_of(2),
+            Self::Odd => !week.is_multiple_of(2),
+        }
     }
-    pub fn update_monthly(origin: &DateTime<FixedOffset>, date: &mut DateTime<FixedOffset>) {
-        let updated_month = {
-            if origin.day() > date.day() {
-                (origin.month() + 1) % 12
-            } else {
-                origin.month()
+}
+
+/// Returned by the callback of BlockingScheduler::start_self_scheduled for the SelfScheduled tasks
+/// - At : the task is triggered again at the date
+/// - AfterGap : the task is triggered again once the gap has elapsed, from the end of the callback
+/// - Done : the task is removed
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum NextRun {
+    At(DateTime<FixedOffset>),
+    AfterGap(#[cfg_attr(feature = "serde", serde(with = "crate::duration_seconds"))] Duration),
+    Done,
+}
+
+impl RepetitionType {
+    pub fn termination(&self) -> Option<&Termination> {
+        match self {
+            Self::Weekly(termination)
+            | Self::Yearly(termination)
+            | Self::SelfScheduled(termination) => Some(termination),
+            Self::ConstGap {
+                gap: _,
+                termination,
             }
+            | Self::IsoWeekly { termination, .. }
+            | Self::Monthly { termination, .. } => Some(termination),
+            Self::Once | Self::Custom => None,
+        }
+    }
+
+    /// Occurrences left to the repetition, None when its count is infinite or it has no count (Once, Custom)
+    pub fn remaining_count(&self) -> Option<u64> {
+        match self.termination()?.count {
+            RepetitionCount::Infinite => None,
+            RepetitionCount::Finished(count) => Some(count),
+        }
+    }
+    /// True for a count of 0, the task being removed without firing
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining_count() == Some(0)
+    }
+
+    pub(crate) fn termination_mut(&mut self) -> Option<&mut Termination> {
+        match self {
+            Self::Weekly(termination)
+            | Self::Yearly(termination)
+            | Self::SelfScheduled(termination) => Some(termination),
+            Self::ConstGap {
+                gap: _,
+                termination,
+            }
+            | Self::IsoWeekly { termination, .. }
+            | Self::Monthly { termination, .. } => Some(termination),
+            Self::Once | Self::Custom => None,
+        }
+    }
+
+    // Decrements the count of the repeating variants, see RepetitionCount::is_finished_on_update
+    pub(crate) fn is_finished_on_update(&mut self) -> bool {
+        match self {
+            Self::Weekly(termination)
+            | Self::Yearly(termination)
+            | Self::SelfScheduled(termination) => termination.is_finished_on_update(),
+            Self::ConstGap {
+                gap: _,
+                termination,
+            }
+            | Self::IsoWeekly { termination, .. }
+            | Self::Monthly { termination, .. } => termination.is_finished_on_update(),
+            Self::Once | Self::Custom => false,
+        }
+    }
+
+    // True when the date is after the `until` of the termination
+    pub fn is_expired_at(&self, date: &DateTime<FixedOffset>) -> bool {
+        self.termination()
+            .is_some_and(|termination| termination.is_expired_at(date))
+    }
+
+    // Gap between two occurrences of the repetitions whose occurrences are evenly spaced
+    pub(crate) fn const_gap(&self) -> Option<Duration> {
+        match self {
+            Self::Weekly(_) => Some(Duration::days(7)),
+            Self::ConstGap { gap, .. } => Some(*gap),
+            _ => None,
+        }
+    }
+
+    // Gives a Monthly repetition without a day the one of the date, so that its occurrences clamped to the end of the
+    // shorter months come back to it
+    pub(crate) fn anchored_on(self, date: &DateTime<FixedOffset>) -> Self {
+        match self {
+            Self::Monthly {
+                day: None,
+                termination,
+            } => Self::Monthly {
+                day: Some(date.day()),
+                termination,
+            },
+            repetition => repetition,
+        }
+    }
+
+    /// Computes the first occurrence strictly after `origin` of a task currently dated `date`.
+    /// Nothing is mutated and the current time is never read, `origin` is usually "now".
+    /// Returns None when the task won't happen again (Once, or a custom repetition that stops),
+    /// and for SelfScheduled whose next date is only known by the callback.
+    /// The termination isn't taken into account, see is_expired_at.
+    ///
+    /// However stale the date, the occurrence is after `origin`: a repetition falling short is advanced again from its
+    /// result. One that makes no progress (a custom repetition giving the same date) returns a date that isn't after
+    /// `origin`, which a started mode reports as an error instead of triggering the task again and again.
+    /// ```
+    /// use chrono::{Datelike, Duration, Local, Months, NaiveTime, TimeZone, Weekday};
+    /// use scheduler::prelude::*;
+    /// use std::collections::HashMap;
+    /// # scheduler::system_clock!(|| chrono::Local::now().into());
+    ///
+    /// let now = Local::now();
+    /// let (today, nine) = (now.date_naive(), NaiveTime::from_hms_opt(9, 0, 0).unwrap());
+    /// let at_nine = |day: chrono::NaiveDate| Local.from_local_datetime(&day.and_time(nine)).unwrap();
+    /// let even_wednesdays = RepetitionType::IsoWeekly {
+    ///     parity: Some(WeekParity::Even),
+    ///     weekday: Weekday::Wed,
+    ///     termination: Termination::default(),
+    /// };
+    /// let stale = [
+    ///     // 3 years, 14 months and 10 intervals of 2 weeks
+    ///     (at_nine(today.with_day(1).unwrap().with_month(3).unwrap() - Months::new(36)), RepetitionType::Yearly(Termination::default())),
+    ///     (at_nine(today.with_day(5).unwrap() - Months::new(14)), RepetitionType::Monthly { day: None, termination: Termination::default() }),
+    ///     (at_nine(today - Duration::weeks(20)), even_wednesdays),
+    /// ];
+    /// let tasks = stale.iter().map(|(date, repetition)| ScheduledTask::new(*date, date.month(), repetition.clone(), SleepType::Native));
+    /// let mut scheduler = BlockingScheduler::new(HashMap::from([("stale".to_string(), tasks.collect())]), HashMap::new());
+    /// scheduler.config.missed_tick_behavior = MissedTickBehavior::FireOnce;
+    /// let token = scheduler.cancellation_token();
+    /// let mut fired = 0;
+    /// scheduler.start("stale", |_| {
+    ///     fired += 1;
+    ///     if fired == 3 {
+    ///         token.cancel();
+    ///     }
+    /// }).unwrap();
+    /// assert_eq!(fired, 3);
+    /// // Each one is caught up once, then waits for its first occurrence to come
+    /// let next: Vec<_> = scheduler.scheduled_tasks["stale"].iter().map(|task| task.date).collect();
+    /// assert!(next.iter().all(|date| now < *date && date.time() == nine));
+    /// assert!(next.iter().any(|date| date.month() == 3 && date.day() == 1 && *date <= now + Duration::days(366)));
+    /// assert!(next.iter().any(|date| date.day() == 5 && *date <= now + Duration::days(32)));
+    /// assert!(next.iter().any(|date| {
+    ///     date.weekday() == Weekday::Wed && date.iso_week().week() % 2 == 0 && *date <= now + Duration::days(21)
+    /// }));
+    /// ```
+    pub fn next_occurrence<CustomRepetitionType: CustomRepetition>(
+        &self,
+        origin: &DateTime<FixedOffset>,
+        date: &DateTime<FixedOffset>,
+        custom_repetition: &CustomRepetitionType,
+    ) -> Option<DateTime<FixedOffset>> {
+        let mut next_date = self.advance(origin, date, custom_repetition)?;
+        for _ in 1..MAX_ADVANCES {
+            if *origin < next_date {
+                break;
+            }
+            match self.advance(&next_date, &next_date, custom_repetition)? {
+                advanced if next_date < advanced => next_date = advanced,
+                // No progress, left to the caller
+                _ => break,
+            }
+        }
+        Some(next_date)
+    }
+
+    // One advancement of the variant from date, meant to be after origin
+    fn advance<CustomRepetitionType: CustomRepetition>(
+        &self,
+        origin: &DateTime<FixedOffset>,
+        date: &DateTime<FixedOffset>,
+        custom_repetition: &CustomRepetitionType,
+    ) -> Option<DateTime<FixedOffset>> {
+        let mut next_date = *date;
+        match self {
+            Self::Once | Self::SelfScheduled(_) => return None,
+            // Important to keep: weekday, time
+            Self::Weekly(_) => RepetitionHelpers::update_weekly(origin, &mut next_date),
+            // Important to keep: month's day, time
+            Self::Monthly { day, .. } => {
+                let day = day.unwrap_or(next_date.day());
+                RepetitionHelpers::update_monthly_on_day(origin, &mut next_date, day)
+            }
+            Self::Yearly(_) => RepetitionHelpers::update_yearly(origin, &mut next_date),
+            Self::ConstGap {
+                gap,
+                termination: _,
+            } => RepetitionHelpers::update_const_gap(origin, &mut next_date, *gap),
+            Self::Custom => return custom_repetition.update_date(origin, date),
+            // Important to keep: time
+            Self::IsoWeekly {
+                parity, weekday, ..
+            } => RepetitionHelpers::update_iso_weekly(origin, &mut next_date, *weekday, *parity),
+        }
+        Some(next_date)
+    }
+}
+// Human form of a repetition: "once", "weekly", "monthly", "yearly", "custom", "self-scheduled" or "every <gap>",
+// the gap being a number followed by ms, s, m, h, d or w ("every 90m"). The repeating ones can be followed by
+// "<count> times" and/or "until <RFC 3339 date>": "every 1d 5 times until 2025-01-01T00:00:00+00:00".
+impl FromStr for RepetitionType {
+    type Err = String;
+
+    fn from_str(repetition: &str) -> Result<Self, Self::Err> {
+        let mut words = repetition.split_whitespace();
+        let kind = words.next().ok_or("Empty repetition".to_string())?;
+        let gap = match kind {
+            "every" => Some(RepetitionHelpers::parse_gap(
+                words.next().ok_or("Missing gap after every".to_string())?,
+            )?),
+            _ => None,
         };
-        let updated_year = {
-            if updated_month == 1 {
-                origin.year() + 1
-            } else {
-                origin.year()
+        let mut termination = Termination::default();
+        while let Some(word) = words.next() {
+            match (word, words.next()) {
+                ("until", Some(until)) => {
+                    let until = DateTime::parse_from_rfc3339(until)
+                        .map_err(|error| format!("Invalid until date {} : {}", until, error))?;
+                    termination.until = Some(until);
+                }
+                (count, Some("times")) => {
+                    let count = count
+                        .parse()
+                        .map_err(|error| format!("Invalid count {} : {}", count, error))?;
+                    termination.count = RepetitionCount::Finished(count);
+                }
+                _ => {
+                    return Err(format!(
+                        "Unexpected words in the repetition : {}",
+                        repetition
+                    ))
+                }
             }
+        }
+        let repeating = termination != Termination::default();
+        match (kind, gap) {
+            ("once", _) | ("custom", _) if repeating => Err(format!(
+                "The repetition can't be given a termination : {}",
+                repetition
+            )),
+            ("once", _) => Ok(Self::Once),
+            ("custom", _) => Ok(Self::Custom),
+            ("weekly", _) => Ok(Self::Weekly(termination)),
+            ("monthly", _) => Ok(Self::Monthly {
+                day: None,
+                termination,
+            }),
+            ("yearly", _) => Ok(Self::Yearly(termination)),
+            ("self-scheduled", _) => Ok(Self::SelfScheduled(termination)),
+            (_, Some(gap)) => Ok(Self::ConstGap { gap, termination }),
+            _ => Err(format!("Unknown repetition : {}", repetition)),
+        }
+    }
+}
+
+/// Calendar computations of the repetitions. Each update_* helper on offset-aware dates has a next_*_naive
+/// counterpart on dates without offset, the offset-aware one computing in the local time of the date.
+///
+/// ```
+/// use chrono::{DateTime, Duration};
+/// use scheduler::repetitions::RepetitionHelpers;
+///
+/// let origin = DateTime::parse_from_rfc3339("2024-03-20T08:00:00+02:00").unwrap();
+/// for date in ["2024-03-11T09:30:00+02:00", "2024-02-29T23:00:00+02:00", "2023-05-25T07:00:00+02:00"] {
+///     let date = DateTime::parse_from_rfc3339(date).unwrap();
+///     let (naive_origin, naive_date) = (origin.naive_local(), date.naive_local());
+///     let mut weekly = date;
+///     RepetitionHelpers::update_weekly(&origin, &mut weekly);
+///     assert_eq!(weekly.naive_local(), RepetitionHelpers::next_weekly_naive(&naive_origin, &naive_date));
+///     let mut monthly = date;
+///     RepetitionHelpers::update_monthly(&origin, &mut monthly);
+///     assert_eq!(monthly.naive_local(), RepetitionHelpers::next_monthly_naive(&naive_origin, &naive_date));
+///     let mut yearly = date;
+///     RepetitionHelpers::update_yearly(&origin, &mut yearly);
+///     assert_eq!(yearly.naive_local(), RepetitionHelpers::next_yearly_naive(&naive_origin, &naive_date));
+///     let mut every_hour = date;
+///     RepetitionHelpers::update_const_gap(&origin, &mut every_hour, Duration::hours(1));
+///     assert_eq!(
+///         every_hour.naive_local(),
+///         RepetitionHelpers::next_const_gap_naive(&naive_origin, &naive_date, Duration::hours(1))
+///     );
+/// }
+/// let date = DateTime::parse_from_rfc3339("2024-02-29T23:00:00+02:00").unwrap();
+/// assert_eq!(
+///     RepetitionHelpers::next_yearly_naive(&origin.naive_local(), &date.naive_local()).to_string(),
+///     "2028-02-29 23:00:00"
+/// );
+/// ```
+pub struct RepetitionHelpers;
+impl RepetitionHelpers {
+    // Gap of the human form of a repetition, see the FromStr impl of RepetitionType
+    fn parse_gap(gap: &str) -> Result<Duration, String> {
+        let unit_start = gap
+            .find(|character: char| !character.is_ascii_digit())
+            .ok_or(format!("Missing unit in the gap : {}", gap))?;
+        let amount: i64 = gap[..unit_start]
+            .parse()
+            .map_err(|error| format!("Invalid gap {} : {}", gap, error))?;
+        let gap = match &gap[unit_start..] {
+            "ms" => Duration::try_milliseconds(amount),
+            "s" => Duration::try_seconds(amount),
+            "m" => Duration::try_minutes(amount),
+            "h" => Duration::try_hours(amount),
+            "d" => Duration::try_days(amount),
+            "w" => Duration::try_weeks(amount),
+            unit => return Err(format!("Unknown unit in the gap {} : {}", gap, unit)),
         };
-        *date = FixedOffset::east(2 * 3600)
-            .ymd(updated_year, updated_month, date.day())
-            .and_hms(date.hour(), date.minute(), date.second());
+        match gap {
+            Some(gap) if gap > Duration::zero() => Ok(gap),
+            _ => Err("The gap must be positive and not too large".to_string()),
+        }
     }
-    pub fn update_yearly(origin: &DateTime<FixedOffset>, date: &mut DateTime<FixedOffset>) {
-        // Important to keep: month, month's day, time
-        // + take care of leap year
+    // The offset-aware helpers below compute in the offset of the date, through their naive counterpart
+    pub fn update_weekly(origin: &DateTime<FixedOffset>, date: &mut DateTime<FixedOffset>) {
+        let offset = date.timezone();
+        let next_date =
+            Self::next_weekly_naive(&Self::local(origin, &offset), &Self::local(date, &offset));
+        *date = Self::from_local(&next_date, &offset);
+    }
+    /// Moves the date to its day of the month in the first month after origin, December rolling over to January of the
+    /// next year. The months without the day get their last day, see update_monthly_on_day.
+    /// ```
+    /// use chrono::DateTime;
+    /// use scheduler::repetitions::RepetitionHelpers;
+    ///
+    /// let at = |date: &str| DateTime::parse_from_rfc3339(date).unwrap();
+    /// for month in 1..=12 {
+    ///     // Fired after the day of the date: the next month
+    ///     let (origin, mut date) = (at(&format!("2023-{:02}-20T10:00:00+01:00", month)), at("2023-01-15T09:00:00+01:00"));
+    ///     RepetitionHelpers::update_monthly(&origin, &mut date);
+    ///     let (year, next_month) = if month == 12 { (2024, 1) } else { (2023, month + 1) };
+    ///     assert_eq!(date, at(&format!("{}-{:02}-15T09:00:00+01:00", year, next_month)));
+    ///     // Before the day of the date: the same month
+    ///     let (origin, mut date) = (at(&format!("2023-{:02}-10T10:00:00+01:00", month)), at("2023-01-15T09:00:00+01:00"));
+    ///     RepetitionHelpers::update_monthly(&origin, &mut date);
+    ///     assert_eq!(date, at(&format!("2023-{:02}-15T09:00:00+01:00", month)));
+    /// }
+    /// ```
+    pub fn update_monthly(origin: &DateTime<FixedOffset>, date: &mut DateTime<FixedOffset>) {
         let day = date.day();
-        let month = date.month();
-        if month != 2 && day != 29 {
-            // Not 29 February
-            *date = FixedOffset::east(2 * 3600)
-                .ymd(origin.year() + 1, month, day)
-                .and_hms(date.hour(), date.minute(), date.second());
-        } else {
-            // 29 February => Leap year
-            *date = FixedOffset::east(2 * 3600)
-                .ymd(
-                    (origin.year() - date.year()) % 4 + origin.year(),
-                    month,
-                    day,
-                )
-                .and_hms(date.hour(), date.minute(), date.second()); // Leap Year
-        }
+        Self::update_monthly_on_day(origin, date, day);
+    }
+    /// Moves the date to the day of the month in the first month after origin, clamped to the last day of the months
+    /// without it. The day is kept whatever the date: a 31st moved to the end of February comes back in March.
+    /// ```
+    /// use chrono::DateTime;
+    /// use scheduler::repetitions::RepetitionHelpers;
+    ///
+    /// let at = |date: &str| DateTime::parse_from_rfc3339(date).unwrap();
+    /// for (year, end_of_february) in [(2023, 28), (2024, 29)] {
+    ///     let mut date = at(&format!("{}-01-31T09:00:00+01:00", year));
+    ///     let mut months = Vec::new();
+    ///     for _ in 0..4 {
+    ///         RepetitionHelpers::update_monthly_on_day(&date.clone(), &mut date, 31);
+    ///         months.push(date);
+    ///     }
+    ///     let expected = [(2, end_of_february), (3, 31), (4, 30), (5, 31)]
+    ///         .map(|(month, day)| at(&format!("{}-{:02}-{}T09:00:00+01:00", year, month, day)));
+    ///     assert_eq!(months, expected);
+    /// }
+    ///
+    /// // update_monthly keeps the day of the date: a 31st sticks to the 29th once clamped, unlike RepetitionType::Monthly
+    /// let mut date = at("2024-01-31T09:00:00+01:00");
+    /// RepetitionHelpers::update_monthly(&date.clone(), &mut date);
+    /// assert_eq!(date, at("2024-02-29T09:00:00+01:00"));
+    /// RepetitionHelpers::update_monthly(&date.clone(), &mut date);
+    /// assert_eq!(date, at("2024-03-29T09:00:00+01:00"));
+    /// ```
+    pub fn update_monthly_on_day(
+        origin: &DateTime<FixedOffset>,
+        date: &mut DateTime<FixedOffset>,
+        day: u32,
+    ) {
+        let offset = date.timezone();
+        let next_date = Self::next_monthly_on_day_naive(
+            &Self::local(origin, &offset),
+            &Self::local(date, &offset),
+            day,
+        );
+        *date = Self::from_local(&next_date, &offset);
+    }
+    pub fn update_yearly(origin: &DateTime<FixedOffset>, date: &mut DateTime<FixedOffset>) {
+        let offset = date.timezone();
+        let next_date =
+            Self::next_yearly_naive(&Self::local(origin, &offset), &Self::local(date, &offset));
+        *date = Self::from_local(&next_date, &offset);
+    }
+    pub fn update_iso_weekly(
+        origin: &DateTime<FixedOffset>,
+        date: &mut DateTime<FixedOffset>,
+        weekday: Weekday,
+        parity: Option<WeekParity>,
+    ) {
+        let offset = date.timezone();
+        let next_date = Self::next_iso_weekly_naive(
+            &Self::local(origin, &offset),
+            &Self::local(date, &offset),
+            weekday,
+            parity,
+        );
+        *date = Self::from_local(&next_date, &offset);
     }
     //TODO: Rethink about the name of this method and its associated variant
     pub fn update_const_gap(
@@ -120,13 +760,117 @@ impl RepetitionHelpers {
         date: &mut DateTime<FixedOffset>,
         gap: Duration,
     ) {
-        // Check new count
-        let diff = *origin - *date;
-        *date = *origin
-            + (gap
-                - Duration::milliseconds(
-                    // Milliseconds precision, we don't know the need of the user
-                    diff.num_milliseconds() % gap.num_milliseconds(),
-                ));
+        let offset = date.timezone();
+        let next_date = Self::next_const_gap_naive(
+            &Self::local(origin, &offset),
+            &Self::local(date, &offset),
+            gap,
+        );
+        *date = Self::from_local(&next_date, &offset);
+    }
+
+    // Same as update_weekly, on dates without offset
+    pub fn next_weekly_naive(origin: &NaiveDateTime, date: &NaiveDateTime) -> NaiveDateTime {
+        Self::next_const_gap_naive(origin, date, Duration::days(7))
+    }
+    // Same as update_monthly, on dates without offset
+    pub fn next_monthly_naive(origin: &NaiveDateTime, date: &NaiveDateTime) -> NaiveDateTime {
+        Self::next_monthly_on_day_naive(origin, date, date.day())
+    }
+    // Same as update_monthly_on_day, on dates without offset
+    pub fn next_monthly_on_day_naive(
+        origin: &NaiveDateTime,
+        date: &NaiveDateTime,
+        day: u32,
+    ) -> NaiveDateTime {
+        // Strictly after origin: the day at the time of the date, this month when still to come
+        let time = date.time().with_nanosecond(0).unwrap();
+        let this_month = Self::clamped_day(origin.year(), origin.month(), day).and_time(time);
+        if *origin < this_month {
+            return this_month;
+        }
+        let (next_year, next_month) = if origin.month() == 12 {
+            (origin.year() + 1, 1)
+        } else {
+            (origin.year(), origin.month() + 1)
+        };
+        Self::clamped_day(next_year, next_month, day).and_time(time)
+    }
+    // The day of the month, or the last day of the month when it has fewer days
+    fn clamped_day(year: i32, month: u32, day: u32) -> NaiveDate {
+        (1..=day.clamp(1, 31))
+            .rev()
+            .find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+            .unwrap_or(NaiveDate::MAX)
+    }
+    // Same as update_yearly, on dates without offset
+    pub fn next_yearly_naive(origin: &NaiveDateTime, date: &NaiveDateTime) -> NaiveDateTime {
+        // Important to keep: month, month's day, time
+        // + take care of leap year: a 29th of February only exists once every 4 (up to 8) years
+        (origin.year()..=origin.year().saturating_add(8))
+            .filter_map(|year| {
+                NaiveDate::from_ymd_opt(year, date.month(), date.day())?.and_hms_opt(
+                    date.hour(),
+                    date.minute(),
+                    date.second(),
+                )
+            })
+            .find(|next_date| next_date > origin)
+            .unwrap_or(NaiveDateTime::MAX)
+    }
+    // Same as update_iso_weekly, on dates without offset
+    pub fn next_iso_weekly_naive(
+        origin: &NaiveDateTime,
+        date: &NaiveDateTime,
+        weekday: Weekday,
+        parity: Option<WeekParity>,
+    ) -> NaiveDateTime {
+        // The weekday of the ISO week of origin, then the next weeks until one is after origin and of the parity.
+        // Walking the days rather than the week numbers keeps the ISO years of 53 weeks and the weeks straddling two
+        // calendar years right. Three weeks at most: a week 53 and the week 1 after it can both be odd.
+        let week = origin.date().iso_week();
+        let mut next_date = NaiveDate::from_isoywd_opt(week.year(), week.week(), weekday)
+            .map(|day| day.and_time(date.time()));
+        while let Some(candidate) = next_date.filter(|candidate| {
+            candidate <= origin
+                || parity.is_some_and(|parity| !parity.matches(candidate.iso_week().week()))
+        }) {
+            next_date = candidate.checked_add_signed(Duration::days(7));
+        }
+        next_date.unwrap_or(NaiveDateTime::MAX)
+    }
+    // Same as update_const_gap, on dates without offset
+    pub fn next_const_gap_naive(
+        origin: &NaiveDateTime,
+        date: &NaiveDateTime,
+        gap: Duration,
+    ) -> NaiveDateTime {
+        // Milliseconds precision, we don't know the need of the user.
+        // Every computation saturates so that absurd dates or gaps degrade gracefully instead of panicking.
+        // The euclidean remainder is the time elapsed since the last occurrence at or before origin, whether the date
+        // is before or after origin: the next occurrence is always strictly after origin, at most a gap later.
+        let gap_ms = gap.num_milliseconds().max(1);
+        let diff_ms = (*origin - *date).num_milliseconds();
+        let offset_ms = gap_ms - diff_ms.rem_euclid(gap_ms);
+        origin
+            .checked_add_signed(Duration::milliseconds(offset_ms))
+            .unwrap_or(NaiveDateTime::MAX)
+    }
+
+    // Local time of the date in the offset, saturating at the bounds of NaiveDateTime
+    fn local(date: &DateTime<FixedOffset>, offset: &FixedOffset) -> NaiveDateTime {
+        let local_minus_utc = Duration::seconds(offset.local_minus_utc().into());
+        match date.naive_utc().checked_add_signed(local_minus_utc) {
+            Some(local) => local,
+            None if local_minus_utc > Duration::zero() => NaiveDateTime::MAX,
+            None => NaiveDateTime::MIN,
+        }
+    }
+    // Date at the local time in the offset, saturating at the latest date
+    fn from_local(local: &NaiveDateTime, offset: &FixedOffset) -> DateTime<FixedOffset> {
+        offset
+            .from_local_datetime(local)
+            .single()
+            .unwrap_or_else(|| DateTime::<Utc>::MAX_UTC.with_timezone(offset))
     }
 }
@@ -0,0 +1,18 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Turns the keys stored in a scheduler into the payloads given to the callback, see BlockingScheduler::start_resolved.
+/// It lets a schedule be persisted with small keys while its payloads live in code.
+pub trait TaskResolver<Key, TaskType> {
+    fn resolve(&self, key: &Key) -> Option<TaskType>;
+}
+
+impl<Key, TaskType> TaskResolver<Key, TaskType> for HashMap<Key, TaskType>
+where
+    Key: Eq + Hash,
+    TaskType: Clone,
+{
+    fn resolve(&self, key: &Key) -> Option<TaskType> {
+        self.get(key).cloned()
+    }
+}
@@ -0,0 +1,211 @@
+//! Journal sink of bounded size, for the long-running daemons. A RotatingFileSink writes the lines it's given to
+//! "<prefix>.jsonl" in its directory until the next line would take the file past max_bytes: the file is then renamed
+//! "<prefix>.<number>.jsonl" (a rename being atomic, a rotated file is always complete) and a new one is begun. The
+//! oldest rotated files are deleted so that there are never more than max_files files, the current one included.
+//! A line is never split between two files, a line longer than max_bytes getting a file of its own.
+//!
+//! ```
+//! use scheduler::rotation::{FlushPolicy, RotatingFileSink};
+//! use std::io::{BufRead, BufReader, Write};
+//!
+//! let dir = std::env::temp_dir().join("scheduler_rotation_doctest");
+//! # let _ = std::fs::remove_dir_all(&dir);
+//! let mut sink = RotatingFileSink::new(&dir, "journal", 1024, 4)
+//!     .unwrap()
+//!     .flush_on(FlushPolicy::EveryEvents(10));
+//! for event in 0..3000 {
+//!     // Written in two parts, as the journal does
+//!     serde_json::to_writer(&mut sink, &serde_json::json!({ "event": event })).unwrap();
+//!     writeln!(sink).unwrap();
+//! }
+//! sink.flush().unwrap();
+//!
+//! let mut files: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+//! files.sort();
+//! assert_eq!(files.len(), 4);
+//! assert!(files.iter().all(|file| std::fs::metadata(file).unwrap().len() <= 1024));
+//! // The rotated files in their order, then the current one, hold the last events line by line
+//! let events: Vec<u64> = files
+//!     .iter()
+//!     .flat_map(|file| BufReader::new(std::fs::File::open(file).unwrap()).lines())
+//!     .map(|line| serde_json::from_str::<serde_json::Value>(&line.unwrap()).unwrap()["event"].as_u64().unwrap())
+//!     .collect();
+//! assert_eq!(events.last(), Some(&2999));
+//! assert!(events.windows(2).all(|pair| pair[1] == pair[0] + 1));
+//! # std::fs::remove_dir_all(&dir).unwrap();
+//! ```
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Represents when a RotatingFileSink flushes the lines written to its current file
+/// - EveryEvent : after each line
+/// - EveryEvents : after the given number of lines
+/// - Every : after the first line written once the duration has elapsed since the last flush
+///
+/// The lines are flushed whatever the policy before a rotation and when the sink is flushed or dropped
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum FlushPolicy {
+    #[default]
+    EveryEvent,
+    EveryEvents(usize),
+    Every(Duration),
+}
+
+/// File sink rotated past a size, see the rotation module
+#[derive(Debug)]
+pub struct RotatingFileSink {
+    dir: PathBuf,
+    prefix: String,
+    max_bytes: u64,
+    max_files: usize,
+    flush_on: FlushPolicy,
+    file: BufWriter<File>,
+    // Size of the current file, written lines included
+    written: u64,
+    // Beginning of the line being written, kept until its end
+    line: Vec<u8>,
+    unflushed: usize,
+    last_flush: Instant,
+    // Number given to the next rotated file
+    next_number: u64,
+}
+
+impl RotatingFileSink {
+    // Appends to the current file of the directory if any, the directory being created when missing. Fails when
+    // max_bytes or max_files is 0.
+    pub fn new(
+        dir: impl AsRef<Path>,
+        prefix: &str,
+        max_bytes: u64,
+        max_files: usize,
+    ) -> Result<Self, String> {
+        if max_bytes == 0 || max_files == 0 {
+            return Err("A rotating sink needs a size and a number of files".to_string());
+        }
+        let dir = dir.as_ref().to_owned();
+        let error = |error: io::Error| {
+            format!(
+                "Couldn't open the journal {} in {} : {}",
+                prefix,
+                dir.display(),
+                error
+            )
+        };
+        fs::create_dir_all(&dir).map_err(error)?;
+        let mut sink = Self {
+            file: Self::open(&dir.join(format!("{}.jsonl", prefix))).map_err(error)?,
+            dir: dir.clone(),
+            prefix: prefix.to_owned(),
+            max_bytes,
+            max_files,
+            flush_on: FlushPolicy::default(),
+            written: 0,
+            line: Vec::new(),
+            unflushed: 0,
+            last_flush: Instant::now(),
+            next_number: 0,
+        };
+        sink.written = sink.file.get_ref().metadata().map_err(error)?.len();
+        sink.next_number = sink
+            .rotated_files()
+            .map_err(error)?
+            .last()
+            .map_or(0, |(number, _)| number + 1);
+        Ok(sink)
+    }
+    pub fn flush_on(self, flush_on: FlushPolicy) -> Self {
+        Self { flush_on, ..self }
+    }
+
+    fn open(path: &Path) -> io::Result<BufWriter<File>> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map(BufWriter::new)
+    }
+    fn current_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.jsonl", self.prefix))
+    }
+    // Rotated files of the directory with their numbers, oldest first
+    fn rotated_files(&self) -> io::Result<Vec<(u64, PathBuf)>> {
+        let mut rotated = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let number = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix(&self.prefix)?.strip_prefix('.'))
+                .and_then(|name| name.strip_suffix(".jsonl")?.parse().ok());
+            if let Some(number) = number {
+                rotated.push((number, path));
+            }
+        }
+        rotated.sort();
+        Ok(rotated)
+    }
+
+    // Renames the current file after the rotated ones, deletes the oldest ones beyond max_files and begins a new file
+    fn rotate(&mut self) -> io::Result<()> {
+        self.flush_file()?;
+        let rotated_path = self
+            .dir
+            .join(format!("{}.{:010}.jsonl", self.prefix, self.next_number));
+        fs::rename(self.current_path(), rotated_path)?;
+        self.next_number += 1;
+        let rotated = self.rotated_files()?;
+        let excess = (rotated.len() + 1).saturating_sub(self.max_files);
+        for (_, path) in &rotated[..excess] {
+            fs::remove_file(path)?;
+        }
+        self.file = Self::open(&self.current_path())?;
+        self.written = 0;
+        Ok(())
+    }
+    fn flush_file(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        self.unflushed = 0;
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+
+    // Writes the line completed in self.line to the current file, rotated first when the line doesn't fit
+    fn write_line(&mut self) -> io::Result<()> {
+        let length = self.line.len() as u64;
+        if self.written > 0 && self.written + length > self.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(&self.line)?;
+        self.written += length;
+        self.line.clear();
+        self.unflushed += 1;
+        let flush = match self.flush_on {
+            FlushPolicy::EveryEvent => true,
+            FlushPolicy::EveryEvents(events) => self.unflushed >= events,
+            FlushPolicy::Every(interval) => self.last_flush.elapsed() >= interval,
+        };
+        if flush {
+            self.flush_file()?;
+        }
+        Ok(())
+    }
+}
+
+// Only the complete lines reach the files, the end of a line being held until its newline is written
+impl Write for RotatingFileSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while let Some(end) = rest.iter().position(|byte| *byte == b'\n') {
+            self.line.extend_from_slice(&rest[..=end]);
+            self.write_line()?;
+            rest = &rest[end + 1..];
+        }
+        self.line.extend_from_slice(rest);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_file()
+    }
+}
@@ -0,0 +1,197 @@
+//! Runtime state of a scheduler kept apart from the definition of its tasks, so that a new process can resume where the
+//! previous one left off while its tasks are defined by the new build: the dates the tasks have been moved to, their
+//! remaining counts and the removed tasks are exported by the old process then imported onto the new definition.
+//!
+//! ```
+//! use chrono::{DateTime, Duration};
+//! use scheduler::prelude::*;
+//! use scheduler::runtime::{MatchBy, Unrestored};
+//! use std::collections::HashMap;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let start = DateTime::parse_from_rfc3339("2024-01-01T09:00:00+01:00").unwrap();
+//! let define = |report_gap| {
+//!     let every = |gap, task| {
+//!         ScheduledTask::new(
+//!             start,
+//!             task,
+//!             RepetitionType::ConstGap {
+//!                 gap,
+//!                 termination: RepetitionCount::Finished(10).into(),
+//!             },
+//!             SleepType::Native,
+//!         )
+//!     };
+//!     BlockingScheduler::new(
+//!         HashMap::from([(
+//!             "jobs".to_string(),
+//!             vec![every(Duration::hours(1), "backup"), every(report_gap, "report")],
+//!         )]),
+//!         HashMap::new(),
+//!     )
+//! };
+//! let mut old = define(Duration::hours(2));
+//! old.tick("jobs", start, |_| {}).unwrap();
+//! let state = old.export_runtime_state();
+//!
+//! // The new build triggers the report every 3 hours
+//! let mut new = define(Duration::hours(3));
+//! let report = new.import_runtime_state(state, MatchBy::PayloadEquality);
+//! assert_eq!(report.restored, 1);
+//! assert_eq!(report.unrestored.len(), 1);
+//! assert_eq!((report.unrestored[0].1.task, report.unrestored[0].2), ("report", Unrestored::Redefined));
+//! let backup = new.scheduled_tasks["jobs"].iter().find(|task| task.task == "backup").unwrap();
+//! assert_eq!(backup.date, start + Duration::hours(1));
+//! assert_eq!(backup.repetition.termination().unwrap().count, RepetitionCount::Finished(9));
+//! let report = new.scheduled_tasks["jobs"].iter().find(|task| task.task == "report").unwrap();
+//! assert_eq!(report.date, start);
+//! ```
+use super::id::TaskId;
+use super::repetitions::RepetitionType;
+use super::schedulers::{BlockingScheduler, ScheduledTask};
+use chrono::{DateTime, FixedOffset};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Progress of a scheduled task, see BlockingScheduler::export_runtime_state
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct TaskState<TaskType> {
+    pub id: Option<TaskId>,
+    pub task: TaskType,
+    pub date: DateTime<FixedOffset>,
+    // The repetition the state has been reached with, its count being the remaining one
+    pub repetition: RepetitionType,
+}
+
+/// What a scheduler has changed since its tasks have been defined
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct RuntimeState<TaskType> {
+    pub scheduled_tasks: BTreeMap<String, Vec<TaskState<TaskType>>>,
+    pub removed_tasks: BTreeMap<String, Vec<ScheduledTask<TaskType>>>,
+}
+
+/// Represents how the states are bound to the tasks of the new definition
+/// - TaskId : a state goes to the task of its mode with the same id, when the new definition has been loaded with its ids
+/// - PayloadEquality : a state goes to the first task of its mode with an equal payload that has no state yet
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum MatchBy {
+    TaskId,
+    PayloadEquality,
+}
+
+/// Represents why a state hasn't been restored
+/// - NoTask : no task of the new definition matches the state
+/// - Redefined : the matching task repeats differently now, it keeps the date and count of its definition
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Unrestored {
+    NoTask,
+    Redefined,
+}
+
+/// Outcome of BlockingScheduler::import_runtime_state
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ImportReport<TaskType> {
+    pub restored: usize,
+    // The states not restored, with their mode
+    pub unrestored: Vec<(String, TaskState<TaskType>, Unrestored)>,
+}
+
+impl<TaskType, CustomRepetitionType> BlockingScheduler<TaskType, CustomRepetitionType>
+where
+    TaskType: Eq + Clone,
+{
+    // Dates, remaining counts and removed tasks of every mode, to be imported by Self::import_runtime_state
+    pub fn export_runtime_state(&self) -> RuntimeState<TaskType> {
+        let scheduled_tasks = self
+            .scheduled_tasks
+            .iter()
+            .map(|(mode, tasks)| {
+                let states = tasks
+                    .iter()
+                    .map(|task| TaskState {
+                        id: task.id(),
+                        task: task.task.clone(),
+                        date: task.date,
+                        repetition: task.repetition.clone(),
+                    })
+                    .collect();
+                (mode.clone(), states)
+            })
+            .collect();
+        RuntimeState {
+            scheduled_tasks,
+            removed_tasks: self.removed_tasks.clone(),
+        }
+    }
+
+    // Moves the tasks matching a state to its date, with its remaining count, when they still repeat the same way.
+    // The removed tasks of the state replace the ones of their modes. The tasks without a state are left as defined.
+    pub fn import_runtime_state(
+        &mut self,
+        state: RuntimeState<TaskType>,
+        matching: MatchBy,
+    ) -> ImportReport<TaskType> {
+        let mut report = ImportReport {
+            restored: 0,
+            unrestored: Vec::new(),
+        };
+        for (mode, states) in state.scheduled_tasks {
+            let Some(tasks) = self.scheduled_tasks.get_mut(&mode) else {
+                report.unrestored.extend(
+                    states
+                        .into_iter()
+                        .map(|task_state| (mode.clone(), task_state, Unrestored::NoTask)),
+                );
+                continue;
+            };
+            let mut matched = vec![false; tasks.len()];
+            for task_state in states {
+                let index = match matching {
+                    MatchBy::TaskId => tasks.iter().zip(&matched).position(|(task, matched)| {
+                        !matched && task.id().is_some() && task.id() == task_state.id
+                    }),
+                    MatchBy::PayloadEquality => tasks
+                        .iter()
+                        .zip(&matched)
+                        .position(|(task, matched)| !matched && task.task == task_state.task),
+                };
+                let Some(index) = index else {
+                    report
+                        .unrestored
+                        .push((mode.clone(), task_state, Unrestored::NoTask));
+                    continue;
+                };
+                matched[index] = true;
+                let task = &mut tasks[index];
+                if !Self::repeats_alike(&task.repetition, &task_state.repetition) {
+                    report
+                        .unrestored
+                        .push((mode.clone(), task_state, Unrestored::Redefined));
+                    continue;
+                }
+                task.date = task_state.date;
+                task.repetition = task_state.repetition;
+                report.restored += 1;
+            }
+        }
+        self.removed_tasks.extend(state.removed_tasks);
+        self.normalize();
+        report
+    }
+
+    // True when the repetitions only differ by their remaining count
+    fn repeats_alike(defined: &RepetitionType, reached: &RepetitionType) -> bool {
+        let mut defined = defined.clone();
+        if let (Some(termination), Some(reached)) =
+            (defined.termination_mut(), reached.termination())
+        {
+            termination.count = reached.count.clone();
+        }
+        defined == *reached
+    }
+}
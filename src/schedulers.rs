@@ -1,75 +1,575 @@
-use super::repetitions::{CustomRepetition, NoCustomRepetition, RepetitionHelpers, RepetitionType};
-use super::sleeptype::SleepType;
-use chrono::{DateTime, FixedOffset, Local};
-use std::cmp::Ordering;
-#[cfg(feature = "spin_sleep")]
-use std::cmp::Ordering;
-use std::collections::HashMap;
-use std::fmt::Debug;
-use std::thread::{self, JoinHandle, ScopedJoinHandle};
-#[cfg(all(feature = "spin_sleep", feature = "serde"))]
-use {
-    serde::{de::VariantAccess, ser::SerializeStructVariant},
-    spin_sleep::SpinStrategy,
-};
+use super::blackout::Blackouts;
+use super::cancellation::{CancelToken, CancellationToken};
 #[cfg(feature = "serde")]
-use {
-    serde::{
-        de::{EnumAccess, Visitor},
-        Deserialize, Serialize,
-    },
-    serde_with::{As, DurationSeconds},
+use super::compact::{CompactScheduler, CompactTasks};
+use super::config::{
+    CatchUpOrder, CatchUpReport, ConcurrentStart, GateReport, LagReport, LatencyReport,
+    MissedTickBehavior, ModeConfig, OrphanRemovedTasks, SchedulerConfig,
 };
+use super::controller::{
+    CommandSender, CommandSenders, HandoffOutcome, RebasePolicy, SchedulerCommand,
+    SchedulerController,
+};
+use super::error::SchedulerError;
+use super::gate::{Gate, SharedGate};
+use super::holidays::{HolidayPolicy, HolidayProvider, Holidays};
+use super::id::{TaskId, TaskIds};
+#[cfg(feature = "journal")]
+use super::journal::JournalWriter;
+#[cfg(feature = "json-schema")]
+use super::ledger::LedgerForm;
+use super::ledger::OccurrenceLedger;
+use super::occurrence::{OccurrenceHook, OccurrenceOutcome, TaskContext};
+use super::plan::CatchUpSimulation;
+use super::repetitions::{
+    CustomRepetition, CustomRepetitionSwap, NextRun, NoCustomRepetition, RepetitionCount,
+    RepetitionType, SharedCustomRepetition, MAX_ADVANCES,
+};
+use super::resolver::TaskResolver;
+#[cfg(feature = "json-schema")]
+use super::schema;
+use super::sleeper::{
+    Clock, ClockAdjustment, SharedClockAdjustment, SharedSleeper, SleepOutcome, Sleeper,
+    SystemClock, Wake,
+};
+#[cfg(feature = "windows-timer")]
+use super::sleeptype::TimerResolution;
+use super::sleeptype::{SleepType, WakeUpError};
+use super::source::{ModeSource, TaskSource};
+use super::wall::WallZone;
+use chrono::{DateTime, Duration, FixedOffset, NaiveDateTime, Utc};
+#[cfg(feature = "json-schema")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::panic::{self, AssertUnwindSafe};
+#[cfg(feature = "signals")]
+use std::sync::atomic::AtomicBool;
+#[cfg(feature = "test-util")]
+use std::sync::atomic::AtomicU64;
+#[cfg(any(feature = "signals", feature = "test-util"))]
+use std::sync::atomic::Ordering as AtomicOrdering;
+use std::sync::mpsc;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle, ScopedJoinHandle};
+use std::time::Instant;
+
+// Keeps a heartbeat (or an overrun report) from turning the wait into a busy loop
+const MIN_HEARTBEAT_INTERVAL: std::time::Duration = std::time::Duration::from_millis(10);
+
+// Closure set on a scheduler (see BlockingScheduler::set_heartbeat and BlockingScheduler::set_catch_up_progress),
+// shared with its clones
+pub(crate) struct SharedHook<Hook: ?Sized>(Arc<Mutex<Box<Hook>>>);
+
+impl<Hook: ?Sized> Clone for SharedHook<Hook> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<Hook: ?Sized> Debug for SharedHook<Hook> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SharedHook")
+    }
+}
+
+/// Represents why a task has been moved to the removed tasks
+/// - Finished : the task won't happen again (Once task, count reached, custom repetition over)
+/// - MissedSkip : a Once task was already late when the mode started, with MissedTickBehavior::Skip
+/// - Expired : the next occurrence of the task is after the `until` of its Termination
+/// - Unresolvable : the TaskResolver couldn't resolve the task's key
+/// - Cancelled : the task has been removed on purpose, see BlockingScheduler::cancel_prefix and SchedulerCommand::Cancel
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum RemovalReason {
+    Finished,
+    MissedSkip,
+    Expired,
+    Unresolvable,
+    Cancelled,
+}
+
+/// Represents a schedule that can't be started as is, see BlockingScheduler::validate
+/// - DateInPast : a Once task is dated before now, it would be dropped or triggered late by the catch-up pass
+/// - OffsetMismatch : a task is dated in another offset than the one of SchedulerConfig::zone at its date, likely
+///   built for the wrong local time. A warning, see BlockingScheduler::offset_warnings.
 #[derive(PartialEq, Eq, Clone, Debug)]
+pub enum ValidationError {
+    DateInPast {
+        mode: String,
+        date: DateTime<FixedOffset>,
+    },
+    OffsetMismatch {
+        mode: String,
+        date: DateTime<FixedOffset>,
+        expected: FixedOffset,
+    },
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DateInPast { mode, date } => {
+                write!(
+                    f,
+                    "A task of the mode {} is dated in the past : {}",
+                    mode, date
+                )
+            }
+            Self::OffsetMismatch {
+                mode,
+                date,
+                expected,
+            } => {
+                write!(
+                    f,
+                    "A task of the mode {} is dated in the offset {} instead of {} : {}",
+                    mode,
+                    date.offset(),
+                    expected,
+                    date
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Date of a task in its offset, in UTC and in the offset of the system, see ScheduledTask::offset_summary
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct OffsetSummary {
+    pub offset: FixedOffset,
+    pub stored: NaiveDateTime,
+    pub utc: NaiveDateTime,
+    pub local: NaiveDateTime,
+}
+
+/// Called instead of the callback of the mode for a single task, see ScheduledTask::with_override_handler
+pub type OverrideHandler<TaskType> = Arc<dyn Fn(&TaskType) + Send + Sync>;
+
+/// Task of a mode, with its next occurrence. Eq (and Hash) compare the definitions as they stand, date and remaining
+/// count included: a task compares unequal to itself once it has been triggered. The identity of a task, which stays
+/// the same while it's rescheduled, is its TaskId (see Self::id). Tasks have no Ord: a mode is sorted by date, the tasks
+/// of a same date keeping the order they were added in.
+/// The fields most tasks leave unset (override handler, gate, held occurrence) are boxed, so that a task with a small
+/// payload stays within 128 bytes.
+/// ```
+/// use chrono::{DateTime, Duration};
+/// use scheduler::prelude::*;
+/// use std::collections::HashMap;
+/// use std::sync::Arc;
+/// # scheduler::system_clock!(|| chrono::Local::now().into());
+///
+/// assert!(std::mem::size_of::<ScheduledTask<u32>>() <= 128);
+/// # #[cfg(feature = "json")]
+/// # {
+/// let date = DateTime::parse_from_rfc3339("2024-01-01T09:00:00+01:00").unwrap();
+/// let weekly = RepetitionType::Weekly(RepetitionCount::Finished(2).into());
+/// let task = ScheduledTask::new(date, 7, weekly, SleepType::Native).with_override_handler(|_| {});
+/// assert_eq!(
+///     serde_json::to_string(&task).unwrap(),
+///     r#"{"task":7,"date":"2024-01-01T09:00:00+01:00","repetition":{"Weekly":{"count":{"Finished":2},"until":null}},"sleep_type":"Native"}"#
+/// );
+/// // The gaps are written as whole seconds
+/// let every = RepetitionType::ConstGap {
+///     gap: Duration::minutes(90) + Duration::milliseconds(500),
+///     termination: Termination::default(),
+/// };
+/// let saved = r#"{"ConstGap":{"gap":5401,"termination":{"count":"Infinite","until":null}}}"#;
+/// assert_eq!(serde_json::to_string(&every).unwrap(), saved);
+/// assert_eq!(
+///     serde_json::from_str::<RepetitionType>(saved).unwrap(),
+///     RepetitionType::ConstGap { gap: Duration::seconds(5401), termination: Termination::default() }
+/// );
+///
+/// // The occurrence held by a closed gate is saved
+/// let mut scheduler = BlockingScheduler::new(HashMap::from([("jobs".to_string(), vec![task])]), HashMap::new());
+/// scheduler.set_gate("jobs", Some(Arc::new(|_: &i32| false)));
+/// scheduler.config.gate_recheck_interval = Some(Duration::minutes(5));
+/// scheduler.tick("jobs", date, |_| {}).unwrap();
+/// let held = &scheduler.scheduled_tasks["jobs"][0];
+/// assert_eq!(held.held_since(), Some(date));
+/// let saved = serde_json::to_string(held).unwrap();
+/// assert!(saved.ends_with(r#""held_since":"2024-01-01T09:00:00+01:00"}"#));
+/// assert_eq!(serde_json::from_str::<ScheduledTask<i32>>(&saved).unwrap(), *held);
+/// # }
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone)]
 pub struct ScheduledTask<TaskType> {
     pub task: TaskType,
     pub date: DateTime<FixedOffset>,
     pub repetition: RepetitionType,
     pub sleep_type: SleepType,
+    // Set when the task is moved to the removed tasks
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub removal_reason: Option<RemovalReason>,
+    // Occurrences landing on a holiday are moved, see BlockingScheduler::set_holiday_provider
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub skip_holidays: bool,
+    // Given by the scheduler holding the task, see the id module
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub(crate) id: Option<TaskId>,
+    // Rarely set, boxed so that the common task stays small. Only held_since is saved, under its own name.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "held_since",
+            default = "Option::default",
+            skip_serializing_if = "TaskExtras::has_no_held_since",
+            with = "held_since"
+        )
+    )]
+    pub(crate) extras: Option<Box<TaskExtras<TaskType>>>,
 }
-impl<TaskType> PartialOrd for ScheduledTask<TaskType>
-where
-    TaskType: Eq,
-{
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
+
+// Fields of a ScheduledTask left unset by most tasks
+#[derive(Clone)]
+pub(crate) struct TaskExtras<TaskType> {
+    // Date of the occurrence held by a closed gate while it's checked again, the task is rescheduled from it
+    held_since: Option<DateTime<FixedOffset>>,
+    // Not saved, attached again after a load with BlockingScheduler::set_override_handler. Ignored by the comparisons.
+    override_handler: Option<OverrideHandler<TaskType>>,
+    // Not saved either, see the gate module
+    gate: Option<SharedGate<TaskType>>,
 }
-impl<TaskType> Ord for ScheduledTask<TaskType>
-where
-    TaskType: Eq,
-{
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        if self.date < other.date {
-            Ordering::Less
-        } else if self.date > other.date {
-            Ordering::Greater
-        } else {
-            Ordering::Equal
+
+impl<TaskType> Default for TaskExtras<TaskType> {
+    fn default() -> Self {
+        Self {
+            held_since: None,
+            override_handler: None,
+            gate: None,
         }
     }
 }
+
+impl<TaskType> TaskExtras<TaskType> {
+    #[cfg(feature = "serde")]
+    fn has_no_held_since(extras: &Option<Box<Self>>) -> bool {
+        extras
+            .as_ref()
+            .is_none_or(|extras| extras.held_since.is_none())
+    }
+}
+
+// Saves the extras of a task as their held_since
+#[cfg(feature = "serde")]
+mod held_since {
+    use super::TaskExtras;
+    use chrono::{DateTime, FixedOffset};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(super) fn serialize<TaskType, S: Serializer>(
+        extras: &Option<Box<TaskExtras<TaskType>>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        extras
+            .as_ref()
+            .and_then(|extras| extras.held_since)
+            .serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, TaskType, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Box<TaskExtras<TaskType>>>, D::Error> {
+        let held_since = Option::<DateTime<FixedOffset>>::deserialize(deserializer)?;
+        Ok(held_since.map(|held_since| {
+            Box::new(TaskExtras {
+                held_since: Some(held_since),
+                ..TaskExtras::default()
+            })
+        }))
+    }
+}
+
+// Serialized form of a ScheduledTask, described by its schema: the extras are written as their held_since
+#[cfg(feature = "json-schema")]
+#[derive(JsonSchema)]
+#[schemars(rename = "ScheduledTask_for_{TaskType}")]
+#[allow(dead_code)]
+struct ScheduledTaskForm<TaskType> {
+    task: TaskType,
+    date: DateTime<FixedOffset>,
+    repetition: RepetitionType,
+    sleep_type: SleepType,
+    #[serde(default)]
+    removal_reason: Option<RemovalReason>,
+    #[serde(default)]
+    skip_holidays: bool,
+    #[serde(default)]
+    id: Option<TaskId>,
+    #[serde(default)]
+    held_since: Option<DateTime<FixedOffset>>,
+}
+
+#[cfg(feature = "json-schema")]
+impl<TaskType: JsonSchema> JsonSchema for ScheduledTask<TaskType> {
+    fn schema_name() -> String {
+        ScheduledTaskForm::<TaskType>::schema_name()
+    }
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        ScheduledTaskForm::<TaskType>::json_schema(gen)
+    }
+}
+
+// The override handler and the gate are left out of the comparisons and of the Debug output
+impl<TaskType: PartialEq> PartialEq for ScheduledTask<TaskType> {
+    fn eq(&self, other: &Self) -> bool {
+        self.task == other.task
+            && self.date == other.date
+            && self.repetition == other.repetition
+            && self.sleep_type == other.sleep_type
+            && self.removal_reason == other.removal_reason
+            && self.skip_holidays == other.skip_holidays
+            && self.id == other.id
+            && self.held_since() == other.held_since()
+    }
+}
+impl<TaskType: Eq> Eq for ScheduledTask<TaskType> {}
+impl<TaskType: Hash> Hash for ScheduledTask<TaskType> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.task.hash(state);
+        self.date.hash(state);
+        self.repetition.hash(state);
+        self.sleep_type.hash(state);
+        self.removal_reason.hash(state);
+        self.skip_holidays.hash(state);
+        self.id.hash(state);
+        self.held_since().hash(state);
+    }
+}
+impl<TaskType: Debug> Debug for ScheduledTask<TaskType> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScheduledTask")
+            .field("task", &self.task)
+            .field("date", &self.date)
+            .field("repetition", &self.repetition)
+            .field("sleep_type", &self.sleep_type)
+            .field("removal_reason", &self.removal_reason)
+            .field("skip_holidays", &self.skip_holidays)
+            .field("id", &self.id)
+            .field("held_since", &self.held_since())
+            .finish_non_exhaustive()
+    }
+}
 impl<TaskType> ScheduledTask<TaskType> {
-    fn new(
-        date: DateTime<FixedOffset>,
+    // The date can be given in any offset convertible to a FixedOffset (Utc, Local), it's stored as a FixedOffset
+    pub fn new(
+        date: impl Into<DateTime<FixedOffset>>,
         task: TaskType,
         repetition: RepetitionType,
         sleep_type: SleepType,
     ) -> Self {
+        let date = date.into();
         Self {
             date,
             task,
-            repetition,
+            repetition: repetition.anchored_on(&date),
             sleep_type,
+            removal_reason: None,
+            skip_holidays: false,
+            id: None,
+            extras: None,
+        }
+    }
+    pub fn skipping_holidays(self) -> Self {
+        Self {
+            skip_holidays: true,
+            ..self
+        }
+    }
+    pub fn date_utc(&self) -> DateTime<Utc> {
+        self.date.with_timezone(&Utc)
+    }
+    // The date as stored and as read in UTC and in the offset of the system, to tell what time the task fires at
+    pub fn offset_summary(&self) -> OffsetSummary {
+        let local_offset = WallZone::Local.offset_at(&self.date);
+        OffsetSummary {
+            offset: *self.date.offset(),
+            stored: self.date.naive_local(),
+            utc: self.date.naive_utc(),
+            local: self.date.with_timezone(&local_offset).naive_local(),
+        }
+    }
+    // What tells the task apart from the others of its scheduler whatever its date and count, unlike Eq. None until the
+    // task is held by a scheduler, the collections of tasks of the scheduler are keyed by it.
+    pub fn id(&self) -> Option<TaskId> {
+        self.id
+    }
+    // The handler is called instead of the callback of the mode when the task is triggered, the hooks and reports
+    // being the same
+    pub fn with_override_handler(
+        self,
+        handler: impl Fn(&TaskType) + Send + Sync + 'static,
+    ) -> Self {
+        self.with_extras(|extras| extras.override_handler = Some(Arc::new(handler)))
+    }
+    // The task only fires while the gate is open, see the gate module
+    pub fn with_gate(self, gate: impl Gate<TaskType> + 'static) -> Self {
+        self.with_extras(|extras| extras.gate = Some(Arc::new(gate)))
+    }
+    pub fn override_handler(&self) -> Option<&OverrideHandler<TaskType>> {
+        self.extras.as_ref()?.override_handler.as_ref()
+    }
+    pub fn gate(&self) -> Option<&SharedGate<TaskType>> {
+        self.extras.as_ref()?.gate.as_ref()
+    }
+    // Date of the occurrence held by a closed gate, see the gate module
+    pub fn held_since(&self) -> Option<DateTime<FixedOffset>> {
+        self.extras.as_ref()?.held_since
+    }
+    // Same task with another payload, the extras that aren't saved (override handler, gate) being left out
+    #[cfg(feature = "json")]
+    pub(crate) fn with_payload<Other>(&self, task: Other) -> ScheduledTask<Other> {
+        ScheduledTask {
+            task,
+            date: self.date,
+            repetition: self.repetition.clone(),
+            sleep_type: self.sleep_type,
+            removal_reason: self.removal_reason,
+            skip_holidays: self.skip_holidays,
+            id: self.id,
+            extras: self.held_since().map(|held_since| {
+                Box::new(TaskExtras {
+                    held_since: Some(held_since),
+                    ..TaskExtras::default()
+                })
+            }),
+        }
+    }
+    // Same task with its payload behind an Arc, for the schedulers handing the payloads over by value (the FiredTasks of
+    // the stream module): the payload isn't cloned on each firing, only its Arc is. The override handler and the gate are
+    // kept, called with the shared payload.
+    pub fn into_shared(self) -> ScheduledTask<Arc<TaskType>>
+    where
+        TaskType: 'static,
+    {
+        let extras = self.extras.map(|extras| {
+            let TaskExtras {
+                held_since,
+                override_handler,
+                gate,
+            } = *extras;
+            Box::new(TaskExtras {
+                held_since,
+                override_handler: override_handler.map(|handler| {
+                    Arc::new(move |task: &Arc<TaskType>| handler(task)) as OverrideHandler<_>
+                }),
+                gate: gate.map(|gate| {
+                    Arc::new(move |task: &Arc<TaskType>| gate.is_open(task)) as SharedGate<_>
+                }),
+            })
+        });
+        ScheduledTask {
+            task: Arc::new(self.task),
+            date: self.date,
+            repetition: self.repetition,
+            sleep_type: self.sleep_type,
+            removal_reason: self.removal_reason,
+            skip_holidays: self.skip_holidays,
+            id: self.id,
+            extras,
+        }
+    }
+    // Occurrences of the task from its date on, as start triggers them when nothing is late: the count and the until
+    // of the termination are followed, the holidays, blackout windows and gates aren't. Endless for an infinite
+    // repetition, only the date of the task for a SelfScheduled one.
+    pub fn occurrences<'t, CustomRepetitionType: CustomRepetition>(
+        &'t self,
+        custom_repetition: &'t CustomRepetitionType,
+    ) -> impl Iterator<Item = DateTime<FixedOffset>> + 't {
+        let mut remaining = match self.repetition.termination() {
+            Some(termination) => match termination.count {
+                RepetitionCount::Finished(count) => Some(count),
+                RepetitionCount::Infinite => None,
+            },
+            None => None,
+        };
+        let mut next = Some(self.date);
+        std::iter::from_fn(move || {
+            let date =
+                next.filter(|date| remaining != Some(0) && !self.repetition.is_expired_at(date))?;
+            remaining = remaining.map(|remaining| remaining - 1);
+            next = self
+                .repetition
+                .next_occurrence(&date, &date, custom_repetition)
+                .filter(|next_date| date < *next_date);
+            Some(date)
+        })
+    }
+    fn with_extras(mut self, set: impl FnOnce(&mut TaskExtras<TaskType>)) -> Self {
+        set(self.extras.get_or_insert_with(Box::default));
+        self
+    }
+    fn set_held_since(&mut self, held_since: Option<DateTime<FixedOffset>>) {
+        if held_since.is_some() || self.extras.is_some() {
+            self.extras.get_or_insert_with(Box::default).held_since = held_since;
+        }
+    }
+    // True unless the gate of the task or the one of its mode is closed
+    pub(crate) fn is_gate_open(&self, mode_gate: Option<&SharedGate<TaskType>>) -> bool {
+        self.gate()
+            .into_iter()
+            .chain(mode_gate)
+            .all(|gate| gate.is_open(&self.task))
+    }
+    // Triggers the task through its override handler if it has one, through f otherwise
+    fn trigger<Output: Default>(&self, f: impl FnOnce(&Self) -> Output) -> Output {
+        match self.override_handler() {
+            Some(override_handler) => {
+                override_handler(&self.task);
+                Output::default()
+            }
+            None => f(self),
         }
     }
 }
+// Catch-up pass of start, done batch by batch (see SchedulerConfig::catch_up_batch_size)
+struct PendingCatchUp<TaskType> {
+    now: DateTime<FixedOffset>,
+    outdated: VecDeque<ScheduledTask<TaskType>>,
+    total: usize,
+    report: CatchUpReport,
+}
+
 // This struct handles the reading of the Scheduler, meaning that it handles the process of updating the tasks when triggered (ie their dates).
-pub struct SchedulerReadingHandler<'srh, TaskType, RepetitionHandlerType = NoCustomRepetition> {
+pub(crate) struct SchedulerReadingHandler<
+    'srh,
+    TaskType,
+    RepetitionHandlerType = NoCustomRepetition,
+> {
     current_tasks: &'srh mut Vec<ScheduledTask<TaskType>>,
     removed_tasks: Vec<ScheduledTask<TaskType>>,
     repetition_handler: RepetitionHandlerType,
+    holidays: Option<Holidays>,
+    // Blackout windows of the mode, see the blackout module
+    blackouts: Option<Blackouts>,
+    // Mode read and the hook told what becomes of its occurrences, see BlockingScheduler::set_occurrence_hook
+    occurrence_hook: Option<(&'srh str, OccurrenceHook<TaskType>)>,
+    // Set when the tasks have changed otherwise than by the rescheduling of a triggered task, see RunObserver::replaced
+    changed: bool,
+    // Gives an id to the tasks added while the mode is read
+    task_ids: TaskIds,
+    // Read by Self::now, set by SchedulerCommand::SetClockAdjustment
+    clock_adjustment: SharedClockAdjustment,
+    // Ledger of the scheduler and its capacity when it's enabled, see the ledger module
+    occurrence_ledger: Option<(OccurrenceLedger, usize)>,
 }
 
 impl<'srh, TaskType, RepetitionHandlerType>
@@ -81,243 +581,3667 @@ where
     fn new(
         current_tasks: &'srh mut Vec<ScheduledTask<TaskType>>,
         repetition_handler: RepetitionHandlerType,
+        holidays: Option<Holidays>,
+        blackouts: Option<Blackouts>,
+        occurrence_hook: Option<(&'srh str, OccurrenceHook<TaskType>)>,
+        task_ids: TaskIds,
     ) -> Self {
+        // The tasks may have been edited through BlockingScheduler::scheduled_tasks since the last run.
+        // Sorting tasks which are already sorted is linear.
+        current_tasks.sort_by_key(|task| task.date);
         Self {
             current_tasks,
             removed_tasks: Vec::new(),
             repetition_handler,
+            holidays,
+            blackouts,
+            occurrence_hook,
+            changed: false,
+            task_ids,
+            clock_adjustment: SharedClockAdjustment::default(),
+            occurrence_ledger: None,
+        }
+    }
+    // Current date, through the clock adjustment of the scheduler when the mode is started
+    fn now(&self) -> DateTime<FixedOffset> {
+        SchedulerHelper::adjusted_now(&self.clock_adjustment)
+    }
+    fn report(
+        &self,
+        outcome: OccurrenceOutcome,
+        task: &ScheduledTask<TaskType>,
+        date: DateTime<FixedOffset>,
+    ) {
+        if let Some((mode, occurrence_hook)) = self.occurrence_hook {
+            occurrence_hook(
+                outcome,
+                &TaskContext {
+                    mode,
+                    task: &task.task,
+                    date,
+                    cancel_token: None,
+                    execution: None,
+                    is_backfill: false,
+                },
+            );
+        }
+    }
+    // True when the ledger holds the occurrence of the task, which is then reported as already completed
+    fn already_completed(&self, task: &ScheduledTask<TaskType>) -> bool {
+        let Some((occurrence_ledger, capacity)) = &self.occurrence_ledger else {
+            return false;
+        };
+        let date = task.held_since().unwrap_or(task.date);
+        let completed = task
+            .id
+            .is_some_and(|id| occurrence_ledger.contains(id, date))
+            && !matches!(task.repetition, RepetitionType::SelfScheduled(_));
+        if completed {
+            // Kept as a recent one, the task being moved past it
+            occurrence_ledger.record(task.id.unwrap(), date, *capacity);
+            self.report(OccurrenceOutcome::AlreadyCompleted, task, date);
+        }
+        completed
+    }
+    // Records the occurrence of the task as started in the ledger, before its callback is called. False when the ledger
+    // is disabled.
+    fn start(&self, task: &ScheduledTask<TaskType>) -> bool {
+        if let (Some((occurrence_ledger, _)), Some(id)) = (&self.occurrence_ledger, task.id) {
+            occurrence_ledger.start(id, task.held_since().unwrap_or(task.date));
+            return true;
+        }
+        false
+    }
+    // Records the occurrence of the task in the ledger, once its callback has returned
+    fn complete(&self, task: &ScheduledTask<TaskType>) {
+        if let (Some((occurrence_ledger, capacity)), Some(id)) = (&self.occurrence_ledger, task.id)
+        {
+            occurrence_ledger.record(id, task.held_since().unwrap_or(task.date), *capacity);
         }
     }
     fn get_current_task(&self) -> Option<&ScheduledTask<TaskType>> {
-        // The index is always due to the way
-        self.current_tasks.get(0)
+        // The first task is the earliest one since the tasks are sorted by Self::new and kept sorted afterwards
+        debug_assert!(
+            self.current_tasks.is_sorted_by_key(|task| task.date),
+            "The tasks must be sorted by date"
+        );
+        self.current_tasks.first()
+    }
+    fn remove_task(&mut self, index: usize, removal_reason: RemovalReason) {
+        let task = self.current_tasks.remove(index);
+        self.store_removed_task(task, removal_reason);
     }
-    fn remove_task(&mut self, index: usize) {
-        self.removed_tasks.push(self.current_tasks.remove(index));
+    fn store_removed_task(
+        &mut self,
+        mut task: ScheduledTask<TaskType>,
+        removal_reason: RemovalReason,
+    ) {
+        task.removal_reason = Some(removal_reason);
+        self.removed_tasks.push(task);
+    }
+    // Gives the observer the tasks removed from the index on, keeping the ones it doesn't take
+    fn observe_removals(
+        &mut self,
+        observer: &mut impl RunObserver<TaskType>,
+        observed_removals: usize,
+        waiting: Option<std::time::Duration>,
+    ) {
+        let mut removals = self.removed_tasks.split_off(observed_removals);
+        observer.removed(&mut removals, waiting);
+        self.removed_tasks.append(&mut removals);
     }
 
-    fn update_outdated_tasks(&mut self) {
-        // Registering outdated tasks
-        let now: DateTime<FixedOffset> = Local::now().into();
-        let last = self
+    // Removes the tasks whose count is 0, which never fire
+    fn remove_exhausted_tasks(&mut self) {
+        let (exhausted, current): (Vec<_>, Vec<_>) = self
             .current_tasks
-            .iter()
-            .position(|task| if now > task.date { false } else { true })
-            .unwrap_or(self.current_tasks.len());
-        for i in 0..last {
-            let task = &mut self.current_tasks[i];
-            match &mut task.repetition {
-                RepetitionType::Once => {
-                    self.remove_task(i);
+            .drain(..)
+            .partition(|task| task.repetition.is_exhausted());
+        *self.current_tasks = current;
+        self.changed |= !exhausted.is_empty();
+        for task in exhausted {
+            self.store_removed_task(task, RemovalReason::Finished);
+        }
+    }
+
+    fn apply_command(&mut self, command: SchedulerCommand<TaskType>) {
+        // The tasks are left as they are
+        match command {
+            SchedulerCommand::SetClockAdjustment(clock_adjustment) => {
+                *self.clock_adjustment.lock().unwrap() = Some(clock_adjustment);
+                return;
+            }
+            SchedulerCommand::SetCustomRepetition(swap) => {
+                swap.apply();
+                return;
+            }
+            _ => {}
+        }
+        self.changed = true;
+        match command {
+            SchedulerCommand::AddTask(task) | SchedulerCommand::AddUrgent(task)
+                if task.repetition.is_exhausted() =>
+            {
+                self.store_removed_task(task, RemovalReason::Finished);
+            }
+            SchedulerCommand::AddTask(mut task) => {
+                self.task_ids.assign([&mut task]);
+                let index = self
+                    .current_tasks
+                    .partition_point(|scheduled_task| scheduled_task.date <= task.date);
+                self.current_tasks.insert(index, task);
+            }
+            SchedulerCommand::AddUrgent(mut task) => {
+                self.task_ids.assign([&mut task]);
+                let index = self
+                    .current_tasks
+                    .partition_point(|scheduled_task| scheduled_task.date < task.date);
+                self.current_tasks.insert(index, task);
+            }
+            SchedulerCommand::Cancel(id) => {
+                let (cancelled, kept): (Vec<_>, Vec<_>) = self
+                    .current_tasks
+                    .drain(..)
+                    .partition(|scheduled_task| scheduled_task.id == Some(id));
+                *self.current_tasks = kept;
+                for cancelled_task in cancelled {
+                    self.report(
+                        OccurrenceOutcome::Cancelled,
+                        &cancelled_task,
+                        cancelled_task.date,
+                    );
+                    self.store_removed_task(cancelled_task, RemovalReason::Cancelled);
                 }
-                RepetitionType::Weekly(_) => {
-                    // Important to keep: weekday, time
-                    RepetitionHelpers::update_weekly(&now, &mut task.date);
+            }
+            SchedulerCommand::Batch(commands) => {
+                for command in commands {
+                    self.apply_command(command);
                 }
-                RepetitionType::Monthly(_) => {
-                    // Important to keep: month's day, time
-                    RepetitionHelpers::update_monthly(&now, &mut task.date);
+            }
+            SchedulerCommand::SetRepetition {
+                id,
+                repetition,
+                rebase,
+                carry_count,
+            } => {
+                let now = self.now();
+                for scheduled_task in self
+                    .current_tasks
+                    .iter_mut()
+                    .filter(|scheduled_task| scheduled_task.id == Some(id))
+                {
+                    let mut repetition = repetition.clone();
+                    if let (true, Some(termination), Some(previous)) = (
+                        carry_count,
+                        repetition.termination_mut(),
+                        scheduled_task.repetition.termination(),
+                    ) {
+                        termination.count = previous.count.clone();
+                    }
+                    if rebase == RebasePolicy::FromNow {
+                        if let Some(next_date) =
+                            repetition.next_occurrence(&now, &now, &self.repetition_handler)
+                        {
+                            scheduled_task.date = next_date;
+                        }
+                    }
+                    scheduled_task.repetition = repetition;
                 }
-                RepetitionType::Yearly(_) => RepetitionHelpers::update_yearly(&now, &mut task.date),
-                RepetitionType::ConstGap { gap, count: _ } => {
-                    RepetitionHelpers::update_const_gap(&now, &mut task.date, *gap);
+                self.current_tasks.sort_by_key(|task| task.date);
+                self.remove_exhausted_tasks();
+            }
+            SchedulerCommand::Handoff { tasks, reply } => {
+                let outcome = SchedulerHelper::hand_off(self.current_tasks, tasks);
+                self.task_ids.assign(self.current_tasks.iter_mut());
+                // Nobody waits for the outcome anymore when the sender of the handoff has given up
+                let _ = reply.send(outcome);
+            }
+            SchedulerCommand::SetClockAdjustment(_) | SchedulerCommand::SetCustomRepetition(_) => {}
+        }
+    }
+
+    // Moves the current task, which has just been triggered, to its next occurrence after now (or removes it) and keeps the tasks sorted.
+    // The other tasks are left untouched: the ones that are due too are triggered next.
+    // The next run returned by the callback is only used by SelfScheduled tasks.
+    // Returns the id of the task and its next date or the reason why it has been removed, along with its repetition
+    // (count updated).
+    fn reschedule_current_task(
+        &mut self,
+        now: &DateTime<FixedOffset>,
+        next_run: Option<&NextRun>,
+    ) -> Option<Rescheduling> {
+        let task = self.current_tasks.first_mut()?;
+        if let Some(held_since) = task.held_since() {
+            task.date = held_since;
+            task.set_held_since(None);
+        }
+        let finished = task.repetition.is_finished_on_update();
+        let repetition = task.repetition.clone();
+        let id = task.id;
+        match Self::next_date(
+            task,
+            now,
+            finished,
+            next_run,
+            &self.repetition_handler,
+            self.holidays.as_ref(),
+            self.blackouts.as_ref(),
+        ) {
+            Ok((next_date, moves)) => {
+                task.date = next_date;
+                for (outcome, date) in moves {
+                    self.report(outcome, &self.current_tasks[0], date);
                 }
-                RepetitionType::Custom => {
-                    if let Some(new_date) = self.repetition_handler.update_date(&now, &task.date) {
-                        task.date = new_date;
-                    } else {
-                        self.remove_task(i);
-                    }
+                self.current_tasks.sort_by_key(|task| task.date);
+                Some((id, Ok(next_date), repetition))
+            }
+            Err(removal_reason) => {
+                self.remove_task(0, removal_reason);
+                Some((id, Err(removal_reason), repetition))
+            }
+        }
+    }
+
+    // Holds the current task, whose gate is closed: its occurrence is checked again after the interval, or skipped when
+    // there's none. The date it's checked again at is returned.
+    fn hold_current_task(
+        &mut self,
+        now: &DateTime<FixedOffset>,
+        recheck_interval: Option<Duration>,
+    ) -> Option<DateTime<FixedOffset>> {
+        let date = self.current_tasks.first()?.date;
+        self.changed = true;
+        let recheck_at = recheck_interval.and_then(|interval| now.checked_add_signed(interval));
+        self.report(
+            OccurrenceOutcome::GateClosed { recheck_at },
+            &self.current_tasks[0],
+            date,
+        );
+        let task = &mut self.current_tasks[0];
+        if let Some(recheck_at) = recheck_at {
+            task.set_held_since(Some(task.held_since().unwrap_or(date)));
+            task.date = recheck_at;
+            self.current_tasks.sort_by_key(|task| task.date);
+            return Some(recheck_at);
+        }
+        if let Some(held_since) = task.held_since() {
+            task.date = held_since;
+            task.set_held_since(None);
+        }
+        // The count is left as is, like for the occurrences skipped by the catch-up pass
+        match Self::next_date(
+            task,
+            now,
+            false,
+            None,
+            &self.repetition_handler,
+            self.holidays.as_ref(),
+            self.blackouts.as_ref(),
+        ) {
+            Ok((next_date, moves)) => {
+                task.date = next_date;
+                for (outcome, date) in moves {
+                    self.report(outcome, &self.current_tasks[0], date);
                 }
+                self.current_tasks.sort_by_key(|task| task.date);
             }
+            Err(removal_reason) => self.remove_task(0, removal_reason),
         }
+        None
     }
 
-    fn update_outdated_tasks_and_repetition_count(&mut self) {
-        // Registering outdated tasks
-        let now: DateTime<FixedOffset> = Local::now().into();
-        let last = self
-            .current_tasks
+    #[cfg(feature = "windows-timer")]
+    fn has_gap_shorter_than(&self, threshold: std::time::Duration) -> bool {
+        self.current_tasks.iter().any(|task| match task.repetition {
+            RepetitionType::ConstGap { gap, .. } => gap.to_std().is_ok_and(|gap| gap < threshold),
+            _ => false,
+        })
+    }
+
+    // Occurrences of the current task, which has just been triggered, passed while its callback was running:
+    // the first one and their number. They're dropped by the rescheduling of the task.
+    fn lagging_occurrences(
+        &self,
+        now: &DateTime<FixedOffset>,
+    ) -> Option<(DateTime<FixedOffset>, u64)> {
+        let task = self.current_tasks.first()?;
+        let first_missed = task
+            .repetition
+            .next_occurrence(&task.date, &task.date, &self.repetition_handler)
+            .filter(|first_missed| {
+                task.date < *first_missed
+                    && first_missed <= now
+                    && !task.repetition.is_expired_at(first_missed)
+            })?;
+        Some((
+            first_missed,
+            Self::count_missed_occurrences(task, &first_missed, now, &self.repetition_handler),
+        ))
+    }
+
+    // Catch-up pass done before the first sleep: the occurrences missed by the tasks dated before now are fired
+    // according to the config, then the tasks are moved to their next occurrence.
+    // Once tasks follow the behavior too: fired late before being removed, or removed unfired with Skip (RemovalReason::MissedSkip).
+    // SelfScheduled tasks are left as is, so that they are triggered right away by start.
+    fn catch_up_outdated_tasks(
+        &mut self,
+        now: &DateTime<FixedOffset>,
+        config: &SchedulerConfig,
+        mut f: impl FnMut(&ScheduledTask<TaskType>),
+    ) -> Result<CatchUpReport, String> {
+        let mut pending = self.begin_catch_up(now);
+        let caught_up = self.catch_up_batch(&mut pending, usize::MAX, config, |task| {
+            task.trigger(&mut f)
+        });
+        // The tasks after a task whose repetition doesn't move forward are left as they were
+        self.abandon_catch_up(&mut pending);
+        pending.report.remaining = self.remaining_occurrences();
+        caught_up.map(|_| pending.report)
+    }
+
+    // Occurrences left to the current tasks whose count is finished
+    fn remaining_occurrences(&self) -> u64 {
+        self.current_tasks
             .iter()
-            .position(|task| if now > task.date { false } else { true })
-            .unwrap_or(self.current_tasks.len());
-        for i in 0..last {
-            let task = &mut self.current_tasks[i];
-            match &mut task.repetition {
-                RepetitionType::Once => {
-                    self.remove_task(i);
+            .filter_map(|task| task.repetition.remaining_count())
+            .sum()
+    }
+
+    // Takes the outdated tasks out of the current tasks, to be caught up by Self::catch_up_batch. The tasks whose count is
+    // 0 are removed beforehand.
+    fn begin_catch_up(&mut self, now: &DateTime<FixedOffset>) -> PendingCatchUp<TaskType> {
+        self.remove_exhausted_tasks();
+        let (outdated, current): (Vec<_>, Vec<_>) =
+            self.current_tasks.drain(..).partition(|task| {
+                task.date < *now && !matches!(task.repetition, RepetitionType::SelfScheduled(_))
+            });
+        *self.current_tasks = current;
+        self.changed |= !outdated.is_empty();
+        PendingCatchUp {
+            now: *now,
+            total: outdated.len(),
+            outdated: outdated.into(),
+            report: CatchUpReport::default(),
+        }
+    }
+
+    // Catches up to batch_size outdated tasks, which are put back among the current tasks at their next occurrence.
+    // Stops with an error at the first task whose next occurrence isn't after now, which would be triggered again and
+    // again: the task is put back at its date, the tasks not caught up yet are left in pending.
+    fn catch_up_batch(
+        &mut self,
+        pending: &mut PendingCatchUp<TaskType>,
+        batch_size: usize,
+        config: &SchedulerConfig,
+        mut f: impl FnMut(&ScheduledTask<TaskType>),
+    ) -> Result<(), String> {
+        let now = pending.now;
+        self.changed |= !pending.outdated.is_empty();
+        let report = &mut pending.report;
+        for mut task in std::iter::from_fn(|| pending.outdated.pop_front()).take(batch_size) {
+            let (replays, dropped) = match config.missed_tick_behavior {
+                // The missed occurrences are only counted for the hook
+                MissedTickBehavior::Skip if self.occurrence_hook.is_some() => (
+                    0,
+                    Self::count_missed_occurrences(
+                        &task,
+                        &task.date,
+                        &now,
+                        &self.repetition_handler,
+                    ),
+                ),
+                MissedTickBehavior::Skip => (0, 0),
+                MissedTickBehavior::FireOnce => (1, 0),
+                MissedTickBehavior::FireAll => {
+                    let missed = Self::count_missed_occurrences(
+                        &task,
+                        &task.date,
+                        &now,
+                        &self.repetition_handler,
+                    );
+                    let replays = config
+                        .max_catch_up
+                        .map_or(missed, |max_catch_up| missed.min(max_catch_up.into()));
+                    report.skipped += missed - replays;
+                    (replays, missed - replays)
                 }
-                RepetitionType::Weekly(count) => {
-                    // Check new count
-                    if count.is_finished_on_update() {
-                        self.remove_task(i);
-                        break;
-                    }
-                    RepetitionHelpers::update_weekly(&now, &mut task.date);
+            };
+            // The occurrences replayed are the first ones, or the last ones with CatchUpOrder::Newest
+            let first_replayed = match config.catch_up_order {
+                CatchUpOrder::Newest if replays > 0 && dropped > 0 => {
+                    Self::nth_occurrence(&task, dropped, &self.repetition_handler)
                 }
-                RepetitionType::Monthly(count) => {
-                    // Check new count
-                    if count.is_finished_on_update() {
-                        self.remove_task(i);
-                        break;
+                _ => Some(task.date),
+            };
+            if dropped > 0 {
+                // Reported at the first occurrence skipped, after the ones replayed with CatchUpOrder::Oldest
+                let first_dropped = match config.catch_up_order {
+                    CatchUpOrder::Oldest if replays > 0 => {
+                        Self::nth_occurrence(&task, replays, &self.repetition_handler)
                     }
-                    RepetitionHelpers::update_monthly(&now, &mut task.date);
-                }
-                RepetitionType::Yearly(count) => {
-                    // Check new count
-                    if count.is_finished_on_update() {
-                        self.remove_task(i);
-                        break;
+                    _ => None,
+                };
+                self.report(
+                    OccurrenceOutcome::Skipped { missed: dropped },
+                    &task,
+                    first_dropped.unwrap_or(task.date),
+                );
+            }
+            if replays == 0 && task.repetition == RepetitionType::Once {
+                report.removed_unfired += 1;
+                self.store_removed_task(task, RemovalReason::MissedSkip);
+                continue;
+            }
+            let mut finished = false;
+            let mut replayed = first_replayed;
+            for _ in 0..replays {
+                // Each replay is given the date of its occurrence
+                let Some(date) = replayed else { break };
+                task.date = date;
+                // An occurrence already completed is counted as if it was replayed again, without firing it
+                if !self.already_completed(&task) {
+                    self.start(&task);
+                    // A panicking callback leaves the task among the current ones, at its date
+                    if let Err(panic) = panic::catch_unwind(AssertUnwindSafe(|| f(&task))) {
+                        self.apply_command(SchedulerCommand::AddTask(task));
+                        panic::resume_unwind(panic);
                     }
-                    RepetitionHelpers::update_yearly(&now, &mut task.date)
+                    self.complete(&task);
+                    report.replayed += 1;
+                    self.report(OccurrenceOutcome::CaughtUp, &task, task.date);
                 }
-                RepetitionType::ConstGap { gap, count } => {
-                    // Check new count
-                    if count.is_finished_on_update() {
-                        self.remove_task(i);
-                        break;
-                    }
-                    RepetitionHelpers::update_const_gap(&now, &mut task.date, *gap);
+                // Counts are only decremented for the occurrences actually fired
+                if task.repetition.is_finished_on_update() {
+                    finished = true;
+                    break;
                 }
-                RepetitionType::Custom => {
-                    if let Some(new_date) = self.repetition_handler.update_date(&now, &task.date) {
-                        task.date = new_date;
-                    } else {
-                        self.remove_task(i);
+                replayed = Self::nth_occurrence(&task, 1, &self.repetition_handler)
+                    .filter(|next_date| next_date < &now);
+            }
+            match Self::next_date(
+                &task,
+                &now,
+                finished,
+                None,
+                &self.repetition_handler,
+                self.holidays.as_ref(),
+                self.blackouts.as_ref(),
+            ) {
+                Ok((next_date, _)) if next_date <= now => {
+                    let error = format!(
+                        "The repetition {:?} of the task dated {} doesn't move it past {}",
+                        task.repetition, task.date, now
+                    );
+                    self.apply_command(SchedulerCommand::AddTask(task));
+                    return Err(error);
+                }
+                Ok((next_date, moves)) => {
+                    task.date = next_date;
+                    for (outcome, date) in moves {
+                        self.report(outcome, &task, date);
                     }
+                    self.apply_command(SchedulerCommand::AddTask(task));
                 }
+                Err(removal_reason) => self.store_removed_task(task, removal_reason),
             }
         }
-        self.current_tasks.sort();
+        Ok(())
     }
-}
 
-struct SchedulerHelper;
-impl SchedulerHelper {
-    // This static method permits to be sure that removed_tasks contains all the modes that are presents in scheduled_tasks
-    fn format_removed_tasks<TaskType, RepetitionType>(
-        scheduled_tasks: &HashMap<String, Vec<ScheduledTask<TaskType>>>,
-        removed_tasks: &mut HashMap<String, Vec<ScheduledTask<TaskType>>>,
-    ) {
-        for key in scheduled_tasks.keys() {
-            removed_tasks.entry(key.to_owned()).or_insert(Vec::new());
+    // Puts the tasks that haven't been caught up back among the current tasks, untouched
+    fn abandon_catch_up(&mut self, pending: &mut PendingCatchUp<TaskType>) {
+        for task in pending.outdated.drain(..) {
+            self.apply_command(SchedulerCommand::AddTask(task));
         }
     }
-}
-// This is the main
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug, Clone)]
-pub struct BlockingScheduler<TaskType, CustomRepetitionType = NoCustomRepetition> {
-    pub scheduled_tasks: HashMap<String, Vec<ScheduledTask<TaskType>>>,
-    pub removed_tasks: HashMap<String, Vec<ScheduledTask<TaskType>>>,
 
-    custom_repetition: CustomRepetitionType,
-}
+    // Next occurrence of the task after now, or the reason why it has to be removed.
+    // The occurrence is given along with its moves off a holiday or out of a blackout window, if it was moved.
+    #[allow(clippy::too_many_arguments)]
+    fn next_date(
+        task: &ScheduledTask<TaskType>,
+        now: &DateTime<FixedOffset>,
+        finished: bool,
+        next_run: Option<&NextRun>,
+        repetition_handler: &RepetitionHandlerType,
+        holidays: Option<&Holidays>,
+        blackouts: Option<&Blackouts>,
+    ) -> Result<(DateTime<FixedOffset>, Moves), RemovalReason> {
+        if finished {
+            return Err(RemovalReason::Finished);
+        }
+        let next_date = match (&task.repetition, next_run) {
+            (RepetitionType::SelfScheduled(_), Some(NextRun::At(date))) => Some(*date),
+            (RepetitionType::SelfScheduled(_), Some(NextRun::AfterGap(gap))) => {
+                now.checked_add_signed(*gap)
+            }
+            _ => task
+                .repetition
+                .next_occurrence(now, &task.date, repetition_handler),
+        }
+        .ok_or(RemovalReason::Finished)?;
+        let next_occurrence = |date: &DateTime<FixedOffset>| {
+            task.repetition
+                .next_occurrence(date, date, repetition_handler)
+        };
+        let mut moves = Moves::new();
+        let next_date = match holidays {
+            Some(holidays) if task.skip_holidays => {
+                let moved_date = holidays.avoid(next_date, next_occurrence);
+                if moved_date != next_date {
+                    moves.push((
+                        OccurrenceOutcome::HolidayMoved { to: moved_date },
+                        next_date,
+                    ));
+                }
+                moved_date
+            }
+            _ => next_date,
+        };
+        let next_date = match blackouts {
+            Some(blackouts) => {
+                let moved_date = blackouts
+                    .avoid(next_date, next_occurrence)
+                    .ok_or(RemovalReason::Finished)?;
+                if moved_date != next_date {
+                    moves.push((
+                        OccurrenceOutcome::BlackoutMoved { to: moved_date },
+                        next_date,
+                    ));
+                }
+                moved_date
+            }
+            None => next_date,
+        };
+        if task.repetition.is_expired_at(&next_date) {
+            Err(RemovalReason::Expired)
+        } else {
+            Ok((next_date, moves))
+        }
+    }
 
-impl<TaskType> BlockingScheduler<TaskType, NoCustomRepetition>
-where
-    TaskType: Eq + Default,
-{
-    pub fn new(
-        scheduled_tasks: HashMap<String, Vec<ScheduledTask<TaskType>>>,
-        mut removed_tasks: HashMap<String, Vec<ScheduledTask<TaskType>>>,
-    ) -> Self {
-        SchedulerHelper::format_removed_tasks::<TaskType, NoCustomRepetition>(
-            &scheduled_tasks,
-            &mut removed_tasks,
-        );
-        Self {
-            scheduled_tasks,
-            removed_tasks,
-            custom_repetition: NoCustomRepetition,
+    // Moves the first task out of the blackout windows, then the next first one, until the first task is outside
+    fn avoid_blackouts(&mut self) {
+        while let (Some(task), Some(blackouts)) = (self.current_tasks.first(), &self.blackouts) {
+            let moved_date = blackouts.avoid(task.date, |date| {
+                task.repetition
+                    .next_occurrence(date, date, &self.repetition_handler)
+            });
+            match moved_date {
+                Some(moved_date) if moved_date == task.date => break,
+                Some(moved_date) => {
+                    let date = task.date;
+                    self.current_tasks[0].date = moved_date;
+                    self.report(
+                        OccurrenceOutcome::BlackoutMoved { to: moved_date },
+                        &self.current_tasks[0],
+                        date,
+                    );
+                    self.current_tasks.sort_by_key(|task| task.date);
+                }
+                None => self.remove_task(0, RemovalReason::Finished),
+            }
+            self.changed = true;
         }
     }
-}
 
-impl<TaskType, CustomRepetitionType> BlockingScheduler<TaskType, CustomRepetitionType>
-where
-    TaskType: Eq + Default,
-    CustomRepetitionType: CustomRepetition + Clone,
-{
-    fn new_with_custom_repetition(
-        scheduled_tasks: HashMap<String, Vec<ScheduledTask<TaskType>>>,
-        mut removed_tasks: HashMap<String, Vec<ScheduledTask<TaskType>>>,
-        custom_repetition: CustomRepetitionType,
-    ) -> Self {
-        SchedulerHelper::format_removed_tasks::<TaskType, CustomRepetitionType>(
-            &scheduled_tasks,
-            &mut removed_tasks,
-        );
-        Self {
-            scheduled_tasks,
-            removed_tasks,
-            custom_repetition,
+    // Number of occurrences of the task from the date (included) to now (excluded), the date being an occurrence. The ones
+    // after the count of the task aren't occurrences. They're computed for the repetitions with a constant gap, the other
+    // ones being walked through, up to MAX_ADVANCES occurrences.
+    fn count_missed_occurrences(
+        task: &ScheduledTask<TaskType>,
+        from: &DateTime<FixedOffset>,
+        now: &DateTime<FixedOffset>,
+        repetition_handler: &RepetitionHandlerType,
+    ) -> u64 {
+        let remaining = task.repetition.remaining_count().unwrap_or(u64::MAX);
+        if let Some(gap) = task.repetition.const_gap() {
+            // The occurrences before now, and at or before the until of the termination
+            let end = match task
+                .repetition
+                .termination()
+                .and_then(|termination| termination.until)
+            {
+                Some(until) => (*now).min(
+                    until
+                        .checked_add_signed(Duration::milliseconds(1))
+                        .unwrap_or(until),
+                ),
+                None => *now,
+            };
+            let span = (end - *from).num_milliseconds();
+            let missed = span.saturating_sub(1).max(0) / gap.num_milliseconds().max(1) + 1;
+            return (missed as u64).min(remaining.max(1));
         }
+        let mut missed = 0;
+        let mut occurrence = Some(*from);
+        while let Some(date) = occurrence.filter(|_| missed < remaining.max(1)) {
+            missed += 1;
+            occurrence = task
+                .repetition
+                .next_occurrence(&date, &date, repetition_handler)
+                // A repetition that doesn't move forward would never end
+                .filter(|next_date| {
+                    date < *next_date
+                        && next_date < now
+                        && !task.repetition.is_expired_at(next_date)
+                        && missed < MAX_ADVANCES as u64
+                })
+        }
+        missed
     }
 
-    pub fn start(&mut self, mode: &str, f: fn(&TaskType)) -> Result<(), String> {
-        let mut reading_handler = SchedulerReadingHandler::new(
-            self.scheduled_tasks
-                .get_mut(mode)
-                .ok_or(format!("Couldn't find the requested mode : {}", mode))?,
-            self.custom_repetition.clone(),
-        );
-        reading_handler.update_outdated_tasks();
-        let mut completed = false;
-        while !completed {
-            match reading_handler.get_current_task() {
-                Some(task) => {
-                    let now: DateTime<FixedOffset> = Local::now().into();
-                    let diff = (task.date - now).to_std().or(Err(format!(
-                        "OutOfRangeError occured on this date {}",
-                        &task.date
-                    )))?;
-                    match task.sleep_type {
-                        SleepType::Native => {
-                            std::thread::sleep(diff);
-                        }
-                        #[cfg(feature = "spin_sleep")]
-                        SleepType::SpinSleep(spin_sleeper) => {
-                            spin_sleeper.sleep(diff);
-                        }
-                    }
-                    f(&task.task);
-                    reading_handler.update_outdated_tasks_and_repetition_count();
-                }
-                None => {
-                    completed = true;
+    // The nth occurrence of the task after its date, computed for the repetitions with a constant gap
+    fn nth_occurrence(
+        task: &ScheduledTask<TaskType>,
+        n: u64,
+        repetition_handler: &RepetitionHandlerType,
+    ) -> Option<DateTime<FixedOffset>> {
+        if let Some(gap) = task.repetition.const_gap() {
+            let offset_ms = gap
+                .num_milliseconds()
+                .max(1)
+                .checked_mul(i64::try_from(n).ok()?)?;
+            return task
+                .date
+                .checked_add_signed(Duration::try_milliseconds(offset_ms)?);
+        }
+        let mut date = task.date;
+        for _ in 0..n {
+            date = task
+                .repetition
+                .next_occurrence(&date, &date, repetition_handler)
+                .filter(|next_date| date < *next_date)?;
+        }
+        Some(date)
+    }
+}
+
+// What happens to a task once triggered at now: its next date or the reason why it's removed.
+// Same computation as the rescheduling done by start, without modifying the task.
+#[cfg(feature = "journal")]
+pub(crate) fn next_run<TaskType: Eq>(
+    task: &ScheduledTask<TaskType>,
+    now: &DateTime<FixedOffset>,
+    next_run: Option<&NextRun>,
+    custom_repetition: &impl CustomRepetition,
+    holidays: Option<&Holidays>,
+    blackouts: Option<&Blackouts>,
+) -> Result<DateTime<FixedOffset>, RemovalReason> {
+    let finished = task.repetition.clone().is_finished_on_update();
+    SchedulerReadingHandler::<TaskType, _>::next_date(
+        task,
+        now,
+        finished,
+        next_run,
+        custom_repetition,
+        holidays,
+        blackouts,
+    )
+    .map(|(next_date, _)| next_date)
+}
+
+// Moves of an occurrence off a holiday or out of a blackout window: the outcome reported and the date before the move
+type Moves = Vec<(OccurrenceOutcome, DateTime<FixedOffset>)>;
+
+// What happened to a triggered task, identified by its id: its next date or the reason of its removal, and its
+// repetition (count updated)
+pub(crate) type Rescheduling = (
+    Option<TaskId>,
+    Result<DateTime<FixedOffset>, RemovalReason>,
+    RepetitionType,
+);
+
+// Modes being started by a scheduler and its clones, released when the start returns, see ConcurrentStart
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RunningModes(Arc<(Mutex<HashSet<String>>, Condvar)>);
+
+impl RunningModes {
+    // Marks the mode as started until the guard is dropped, whatever the way the start ends (panics included)
+    fn enter(
+        &self,
+        mode: &str,
+        concurrent_start: ConcurrentStart,
+    ) -> Result<RunningGuard, SchedulerError> {
+        let (modes, released) = &*self.0;
+        let mut modes = modes.lock().unwrap();
+        while modes.contains(mode) {
+            match concurrent_start {
+                ConcurrentStart::Fail => {
+                    return Err(SchedulerError::AlreadyRunning {
+                        mode: mode.to_owned(),
+                    })
                 }
+                ConcurrentStart::Wait => modes = released.wait(modes).unwrap(),
             }
         }
-        unsafe {
-            // This is safe since we applied Self::format_removed_tasks when this struct was constructed
-            self.removed_tasks
-                .get_mut(mode)
-                .unwrap_unchecked()
-                .append(&mut reading_handler.removed_tasks);
-        }
-        Ok(())
+        modes.insert(mode.to_owned());
+        Ok(RunningGuard {
+            running_modes: self.clone(),
+            mode: mode.to_owned(),
+        })
     }
 }
 
-pub struct ParallelScheduler<'ps, TaskType, CustomRepetition = NoCustomRepetition> {
-    scheduler: BlockingScheduler<TaskType, CustomRepetition>,
-    pub thread_handlers: Vec<JoinHandle<Result<(), String>>>,
-    pub scope_thread_handlers: Vec<ScopedJoinHandle<'ps, Result<(), String>>>,
+struct RunningGuard {
+    running_modes: RunningModes,
+    mode: String,
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        let (modes, released) = &*self.running_modes.0;
+        modes.lock().unwrap().remove(&self.mode);
+        released.notify_all();
+    }
+}
+
+// Date written first when a scheduler is serialized, see BlockingScheduler::next_fire. Set by the serialization itself,
+// it's only meant to be read from the serialized form.
+#[cfg(feature = "serde")]
+#[derive(Debug, Default)]
+pub(crate) struct NextFireHint(Mutex<Option<DateTime<FixedOffset>>>);
+
+#[cfg(feature = "serde")]
+impl Clone for NextFireHint {
+    fn clone(&self) -> Self {
+        Self(Mutex::new(*self.0.lock().unwrap()))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for NextFireHint {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.lock().unwrap().serialize(serializer)
+    }
+}
+
+// Told by BlockingScheduler::run what happens while a mode is started
+pub(crate) trait RunObserver<TaskType> {
+    fn caught_up(&mut self, _report: &CatchUpReport) {}
+    // Called when the tasks of the mode have changed otherwise than by the rescheduling of a triggered task
+    // (catch-up, commands, tasks pulled from a source), with the tasks removed since the mode has been started
+    fn replaced(
+        &mut self,
+        _tasks: &[ScheduledTask<TaskType>],
+        _removed_tasks: &[ScheduledTask<TaskType>],
+    ) {
+    }
+    // Called once the triggered task has been rescheduled (see Self::fired)
+    fn rescheduled(&mut self, _rescheduling: &Rescheduling) {}
+    // Called with the tasks removed since the previous call before each wait for the next task, waiting being the time
+    // about to be waited, then once the mode has stopped with None. The observer may take the tasks, the ones left
+    // being kept with the removed tasks of the mode.
+    fn removed(
+        &mut self,
+        _removed_tasks: &mut Vec<ScheduledTask<TaskType>>,
+        _waiting: Option<std::time::Duration>,
+    ) {
+    }
+    // Called when the clock adjustment read by the mode has changed, see ClockAdjustment
+    fn clock_adjusted(&mut self, _clock_adjustment: Option<&ClockAdjustment>) {}
+    // Called before the callback when the ledger is enabled, once the occurrence has been recorded as started (see the
+    // ledger module). The callback waits for it to return.
+    fn starting(&mut self, _task: &ScheduledTask<TaskType>) {}
+    // Called once the callback has returned and the occurrence has been recorded as completed in the ledger
+    fn completed(&mut self, _task: &ScheduledTask<TaskType>) {}
+    // Called once the callback has returned, before the task is rescheduled (see next_run)
+    fn fired(
+        &mut self,
+        _task: &ScheduledTask<TaskType>,
+        _now: &DateTime<FixedOffset>,
+        _next_run: Option<&NextRun>,
+        _custom_repetition: &impl CustomRepetition,
+        _holidays: Option<&Holidays>,
+        _blackouts: Option<&Blackouts>,
+    ) {
+    }
+}
+
+impl<TaskType> RunObserver<TaskType> for () {}
+
+struct SchedulerHelper;
+impl SchedulerHelper {
+    fn now() -> DateTime<FixedOffset> {
+        SystemClock.now()
+    }
+    // Current date on the reference clock of the adjustment, see ClockAdjustment
+    fn adjusted_now(clock_adjustment: &SharedClockAdjustment) -> DateTime<FixedOffset> {
+        Self::now() + Self::clock_offset(clock_adjustment)
+    }
+    fn clock_offset(clock_adjustment: &SharedClockAdjustment) -> Duration {
+        clock_adjustment
+            .lock()
+            .unwrap()
+            .map_or(Duration::zero(), |clock_adjustment| clock_adjustment.offset)
+    }
+    // This static method permits to be sure that removed_tasks contains all the modes that are presents in scheduled_tasks
+    fn format_removed_tasks<TaskType>(
+        scheduled_tasks: &BTreeMap<String, Vec<ScheduledTask<TaskType>>>,
+        removed_tasks: &mut BTreeMap<String, Vec<ScheduledTask<TaskType>>>,
+    ) {
+        for key in scheduled_tasks.keys() {
+            removed_tasks.entry(key.to_owned()).or_default();
+        }
+    }
+    // Moves the pending deletions expired at now to the removed tasks
+    fn expire_pending_deletions<TaskType>(
+        pending_deletions: Option<&mut Vec<PendingDeletion<TaskType>>>,
+        removed_tasks: &mut Vec<ScheduledTask<TaskType>>,
+        now: &DateTime<FixedOffset>,
+        occurrence_hook: Option<(&str, OccurrenceHook<TaskType>)>,
+    ) {
+        let Some(pending_deletions) = pending_deletions else {
+            return;
+        };
+        let (expired, pending): (Vec<_>, Vec<_>) = pending_deletions
+            .drain(..)
+            .partition(|pending_deletion| pending_deletion.expires_at <= *now);
+        *pending_deletions = pending;
+        removed_tasks.extend(expired.into_iter().map(|pending_deletion| {
+            let mut task = pending_deletion.task;
+            if let Some((mode, occurrence_hook)) = occurrence_hook {
+                occurrence_hook(
+                    OccurrenceOutcome::Cancelled,
+                    &TaskContext {
+                        mode,
+                        task: &task.task,
+                        date: task.date,
+                        cancel_token: None,
+                        execution: None,
+                        is_backfill: false,
+                    },
+                );
+            }
+            task.removal_reason = Some(RemovalReason::Cancelled);
+            task
+        }));
+    }
+    // Replaces the current tasks by the new ones. A new task matching a current one (each current task being matched
+    // once) takes over its date and its remaining count, so that an occurrence already triggered isn't triggered again
+    // and an occurrence due isn't lost. A new task with an id matches the current task with that id (see
+    // ScheduledTask::id), a new task without one matches the first current task with the same payload.
+    fn hand_off<TaskType: Eq>(
+        current_tasks: &mut Vec<ScheduledTask<TaskType>>,
+        tasks: Vec<ScheduledTask<TaskType>>,
+    ) -> HandoffOutcome<TaskType> {
+        let mut previous_tasks: Vec<Option<ScheduledTask<TaskType>>> =
+            current_tasks.drain(..).map(Some).collect();
+        let mut carried = Vec::with_capacity(tasks.len());
+        for mut task in tasks {
+            let previous = previous_tasks
+                .iter_mut()
+                .find(|previous| {
+                    previous.as_ref().is_some_and(|previous| match task.id {
+                        Some(id) => previous.id == Some(id),
+                        None => previous.task == task.task,
+                    })
+                })
+                .and_then(Option::take);
+            if let Some(previous) = &previous {
+                task.date = previous.date;
+                task.id = previous.id;
+                if let (Some(termination), Some(previous_termination)) = (
+                    task.repetition.termination_mut(),
+                    previous.repetition.termination(),
+                ) {
+                    termination.count = previous_termination.count.clone();
+                }
+            }
+            carried.push(previous.is_some());
+            current_tasks.push(task);
+        }
+        current_tasks.sort_by_key(|task| task.date);
+        HandoffOutcome {
+            carried,
+            dropped: previous_tasks.into_iter().flatten().collect(),
+        }
+    }
+}
+/// Task cancelled by BlockingScheduler::cancel_with_ttl, restorable until expires_at
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct PendingDeletion<TaskType> {
+    pub task: ScheduledTask<TaskType>,
+    pub expires_at: DateTime<FixedOffset>,
+}
+
+// This is the main
+// The modes are kept sorted by name, so that they're enumerated and serialized in the same order from a run to another.
+// The serde impls are written below so that a deserialized scheduler is normalized, see BlockingScheduler::normalize
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(remote = "Self")
+)]
+#[cfg_attr(
+    feature = "json-schema",
+    derive(JsonSchema),
+    schemars(rename = "BlockingScheduler_for_{TaskType}_and_{CustomRepetitionType}")
+)]
+#[derive(Debug, Clone)]
+pub struct BlockingScheduler<TaskType, CustomRepetitionType = NoCustomRepetition> {
+    // First field written, so that a supervisor can read it without loading the scheduler, see persistence::peek_next_fire
+    #[cfg(feature = "serde")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "next_fire", default, skip_deserializing)
+    )]
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(rename = "next_fire", with = "Option<DateTime<FixedOffset>>")
+    )]
+    next_fire_hint: NextFireHint,
+    // Metadata used to tell schedulers apart, modified_at is bumped by the mutating methods
+    #[cfg_attr(feature = "serde", serde(default))]
+    name: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    description: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default = "SchedulerHelper::now"))]
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(skip_serializing_if = "schema::varying_default")
+    )]
+    created_at: DateTime<FixedOffset>,
+    #[cfg_attr(feature = "serde", serde(default = "SchedulerHelper::now"))]
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(skip_serializing_if = "schema::varying_default")
+    )]
+    modified_at: DateTime<FixedOffset>,
+
+    pub scheduled_tasks: BTreeMap<String, Vec<ScheduledTask<TaskType>>>,
+    pub removed_tasks: BTreeMap<String, Vec<ScheduledTask<TaskType>>>,
+    // Tasks cancelled by Self::cancel_with_ttl, until they're restored or they expire
+    #[cfg_attr(feature = "serde", serde(default))]
+    pending_deletions: BTreeMap<String, Vec<PendingDeletion<TaskType>>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub config: SchedulerConfig,
+    // Gives the ids of the tasks, saved so that the ids given after a load are new ones
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(skip_serializing_if = "schema::varying_default")
+    )]
+    task_ids: TaskIds,
+    // Occurrences completed lately, see the ledger module. Shared with the clones like the ids.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "OccurrenceLedger::is_unused")
+    )]
+    #[cfg_attr(feature = "json-schema", schemars(with = "LedgerForm"))]
+    occurrence_ledger: OccurrenceLedger,
+
+    // Shared with the clones, see Self::set_custom_repetition
+    custom_repetition: SharedCustomRepetition<CustomRepetitionType>,
+    // Report of the last catch-up pass of each mode
+    #[cfg_attr(feature = "serde", serde(skip))]
+    catch_up_reports: BTreeMap<String, CatchUpReport>,
+    // Lag of the modes since they've been started, see Self::lag_report
+    #[cfg_attr(feature = "serde", serde(skip))]
+    lag_reports: BTreeMap<String, LagReport>,
+    // Latency of the modes since they've been started, see Self::latency_report
+    #[cfg_attr(feature = "serde", serde(skip))]
+    latency_reports: BTreeMap<String, LatencyReport>,
+    // See Self::gate_report
+    #[cfg_attr(feature = "serde", serde(skip))]
+    gate_reports: BTreeMap<String, GateReport>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    command_senders: CommandSenders<TaskType>,
+    // Shared by every clone of the scheduler, so that a ParallelScheduler can stop all of its workers at once
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) cancellation_token: CancellationToken,
+    // Called at the given interval while start is waiting for the next task
+    #[cfg_attr(feature = "serde", serde(skip))]
+    heartbeat: Option<(std::time::Duration, SharedHook<dyn FnMut() + Send>)>,
+    // See SleepType::Auto
+    #[cfg_attr(feature = "serde", serde(skip))]
+    wake_up_error: WakeUpError,
+    // Called after each batch of the catch-up pass of start, see Self::set_catch_up_progress
+    #[cfg_attr(feature = "serde", serde(skip))]
+    catch_up_progress: Option<SharedHook<dyn FnMut(usize, usize) + Send>>,
+    // See Self::set_occurrence_hook
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) occurrence_hook: Option<OccurrenceHook<TaskType>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    holidays: Option<Holidays>,
+    // Sources feeding some modes, see Self::set_task_source. Shared with the clones of the scheduler.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    task_sources: BTreeMap<String, Arc<Mutex<ModeSource<TaskType>>>>,
+    // Gates of the modes, see Self::set_gate
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) gates: BTreeMap<String, SharedGate<TaskType>>,
+    // Modes being started by this scheduler and its clones, see ConcurrentStart
+    #[cfg_attr(feature = "serde", serde(skip))]
+    running_modes: RunningModes,
+    // Replaces the sleep types, see Self::set_sleeper. Shared with the clones of the scheduler.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    sleeper: Option<SharedSleeper>,
+    // See Self::set_clock_adjustment. Shared with the clones of the scheduler.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    clock_adjustment: SharedClockAdjustment,
+}
+
+#[cfg(feature = "serde")]
+impl<TaskType, CustomRepetitionType> Serialize for BlockingScheduler<TaskType, CustomRepetitionType>
+where
+    TaskType: Serialize,
+    CustomRepetitionType: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        *self.next_fire_hint.0.lock().unwrap() = self.next_fire();
+        Self::serialize(self, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, TaskType, CustomRepetitionType> Deserialize<'de>
+    for BlockingScheduler<TaskType, CustomRepetitionType>
+where
+    TaskType: Deserialize<'de> + Eq + Default,
+    CustomRepetitionType: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut scheduler = Self::deserialize(deserializer)?;
+        scheduler.normalize();
+        Ok(scheduler)
+    }
+}
+
+impl<TaskType, CustomRepetitionType> BlockingScheduler<TaskType, CustomRepetitionType>
+where
+    TaskType: Eq,
+{
+    // Makes the scheduler uphold what start expects: every scheduled mode has a removed bucket and its tasks are sorted by date.
+    // The removed tasks of the modes that aren't scheduled are handled according to SchedulerConfig::orphan_removed_tasks.
+    // Done by the constructors and by the deserialization, to call again after editing the public fields by hand.
+    pub fn normalize(&mut self) {
+        SchedulerHelper::format_removed_tasks(&self.scheduled_tasks, &mut self.removed_tasks);
+        if self.config.orphan_removed_tasks == OrphanRemovedTasks::Prune {
+            let scheduled_tasks = &self.scheduled_tasks;
+            self.removed_tasks
+                .retain(|mode, _| scheduled_tasks.contains_key(mode));
+        }
+        for tasks in self.scheduled_tasks.values_mut() {
+            // Stable, so the tasks planned at the same date keep their order
+            tasks.sort_by_key(|task| task.date);
+        }
+        // The ids saved with the tasks may be ahead of the ones saved with the scheduler
+        for id in self.tasks().filter_map(ScheduledTask::id) {
+            self.task_ids.skip(id);
+        }
+        self.task_ids.assign(
+            self.scheduled_tasks
+                .values_mut()
+                .chain(self.removed_tasks.values_mut())
+                .flatten()
+                .chain(
+                    self.pending_deletions
+                        .values_mut()
+                        .flatten()
+                        .map(|pending| &mut pending.task),
+                ),
+        );
+    }
+
+    // Moves the tasks of the other scheduler into this one, in the modes of the same name. The tasks whose id is already
+    // held by this scheduler (both have been loaded from the same snapshot for instance) are given a new id: the ids
+    // replaced are returned with their replacement. The settings and hooks of the other scheduler are dropped.
+    pub fn merge(&mut self, other: Self) -> Vec<(TaskId, TaskId)> {
+        for id in other.tasks().filter_map(ScheduledTask::id) {
+            self.task_ids.skip(id);
+        }
+        let mut ids: HashSet<TaskId> = self.tasks().filter_map(ScheduledTask::id).collect();
+        let mut remapped = Vec::new();
+        let task_ids = &self.task_ids;
+        let mut remap = |task: &mut ScheduledTask<TaskType>| {
+            if let Some(id) = task.id.filter(|id| !ids.insert(*id)) {
+                let new_id = task_ids.next();
+                ids.insert(new_id);
+                task.id = Some(new_id);
+                remapped.push((id, new_id));
+            }
+        };
+        for (mode, mut tasks) in other.scheduled_tasks {
+            tasks.iter_mut().for_each(&mut remap);
+            self.scheduled_tasks
+                .entry(mode)
+                .or_default()
+                .append(&mut tasks);
+        }
+        for (mode, mut tasks) in other.removed_tasks {
+            tasks.iter_mut().for_each(&mut remap);
+            self.removed_tasks
+                .entry(mode)
+                .or_default()
+                .append(&mut tasks);
+        }
+        for (mode, mut pending_deletions) in other.pending_deletions {
+            for pending_deletion in pending_deletions.iter_mut() {
+                remap(&mut pending_deletion.task);
+            }
+            self.pending_deletions
+                .entry(mode)
+                .or_default()
+                .append(&mut pending_deletions);
+        }
+        self.normalize();
+        self.modified_at = SchedulerHelper::now();
+        remapped
+    }
+
+    // Takes the removed tasks of every mode out of the scheduler, the modes without any being left out. The tasks are
+    // moved, never cloned: a removed task is handed out once, so that its payload is dropped (or cleaned up) by the
+    // caller alone.
+    pub fn drain_removed(&mut self) -> BTreeMap<String, Vec<ScheduledTask<TaskType>>> {
+        let mut drained = BTreeMap::new();
+        // The buckets are kept, start expects one for each scheduled mode
+        for (mode, tasks) in self.removed_tasks.iter_mut() {
+            if !tasks.is_empty() {
+                drained.insert(mode.clone(), std::mem::take(tasks));
+            }
+        }
+        if !drained.is_empty() {
+            self.modified_at = SchedulerHelper::now();
+        }
+        drained
+    }
+
+    // Every task held: scheduled, removed and pending deletion
+    fn tasks(&self) -> impl Iterator<Item = &ScheduledTask<TaskType>> {
+        self.scheduled_tasks
+            .values()
+            .chain(self.removed_tasks.values())
+            .flatten()
+            .chain(
+                self.pending_deletions
+                    .values()
+                    .flatten()
+                    .map(|pending| &pending.task),
+            )
+    }
+}
+
+impl<TaskType, CustomRepetitionType> BlockingScheduler<TaskType, CustomRepetitionType> {
+    // Takes back the mode of a scheduler given by Self::detach_mode, along with its reports
+    pub(crate) fn attach_mode(&mut self, worker: Self) {
+        self.scheduled_tasks.extend(worker.scheduled_tasks);
+        self.removed_tasks.extend(worker.removed_tasks);
+        self.pending_deletions.extend(worker.pending_deletions);
+        self.catch_up_reports.extend(worker.catch_up_reports);
+        self.lag_reports.extend(worker.lag_reports);
+        self.latency_reports.extend(worker.latency_reports);
+        self.gate_reports.extend(worker.gate_reports);
+        self.modified_at = self.modified_at.max(worker.modified_at);
+    }
+
+    // Saved part of the scheduler with the payloads given by f, see persistence::to_json_persisted
+    #[cfg(feature = "json")]
+    pub(crate) fn map_payloads<Other>(
+        &self,
+        mut f: impl FnMut(&TaskType) -> Other,
+    ) -> BlockingScheduler<Other, CustomRepetitionType> {
+        let mut map = |tasks: &BTreeMap<String, Vec<ScheduledTask<TaskType>>>| {
+            tasks
+                .iter()
+                .map(|(mode, tasks)| {
+                    let tasks = tasks.iter().map(|task| task.with_payload(f(&task.task)));
+                    (mode.clone(), tasks.collect())
+                })
+                .collect()
+        };
+        let (scheduled_tasks, removed_tasks) =
+            (map(&self.scheduled_tasks), map(&self.removed_tasks));
+        let pending_deletions = self
+            .pending_deletions
+            .iter()
+            .map(|(mode, deletions)| {
+                let deletions = deletions.iter().map(|deletion| PendingDeletion {
+                    task: deletion.task.with_payload(f(&deletion.task.task)),
+                    expires_at: deletion.expires_at,
+                });
+                (mode.clone(), deletions.collect())
+            })
+            .collect();
+        self.with_tasks(scheduled_tasks, removed_tasks, pending_deletions)
+    }
+    // Same scheduler with the payloads given by f, the tasks for which f returns None being dropped. The flag given
+    // to f is set for the removed tasks and the pending deletions. See persistence::parse_json_persisted.
+    #[cfg(feature = "json")]
+    pub(crate) fn filter_map_payloads<Other>(
+        mut self,
+        mut f: impl FnMut(&str, bool, ScheduledTask<TaskType>) -> Option<ScheduledTask<Other>>,
+    ) -> BlockingScheduler<Other, CustomRepetitionType> {
+        let mut filter_map = |tasks: BTreeMap<String, Vec<ScheduledTask<TaskType>>>, removed| {
+            tasks
+                .into_iter()
+                .map(|(mode, tasks)| {
+                    let tasks = tasks.into_iter().filter_map(|task| f(&mode, removed, task));
+                    let tasks = tasks.collect();
+                    (mode, tasks)
+                })
+                .collect()
+        };
+        let scheduled_tasks = filter_map(std::mem::take(&mut self.scheduled_tasks), false);
+        let removed_tasks = filter_map(std::mem::take(&mut self.removed_tasks), true);
+        let pending_deletions = std::mem::take(&mut self.pending_deletions)
+            .into_iter()
+            .map(|(mode, deletions)| {
+                let deletions = deletions.into_iter().filter_map(|deletion| {
+                    Some(PendingDeletion {
+                        task: f(&mode, true, deletion.task)?,
+                        expires_at: deletion.expires_at,
+                    })
+                });
+                let deletions = deletions.collect();
+                (mode, deletions)
+            })
+            .collect();
+        self.with_tasks(scheduled_tasks, removed_tasks, pending_deletions)
+    }
+    // Scheduler holding the tasks, the rest of its saved part being the one of this scheduler
+    #[cfg(feature = "json")]
+    fn with_tasks<Other>(
+        &self,
+        scheduled_tasks: BTreeMap<String, Vec<ScheduledTask<Other>>>,
+        removed_tasks: BTreeMap<String, Vec<ScheduledTask<Other>>>,
+        pending_deletions: BTreeMap<String, Vec<PendingDeletion<Other>>>,
+    ) -> BlockingScheduler<Other, CustomRepetitionType> {
+        BlockingScheduler {
+            next_fire_hint: NextFireHint::default(),
+            name: self.name.clone(),
+            description: self.description.clone(),
+            created_at: self.created_at,
+            modified_at: self.modified_at,
+            scheduled_tasks,
+            removed_tasks,
+            pending_deletions,
+            config: self.config.clone(),
+            task_ids: self.task_ids.clone(),
+            occurrence_ledger: self.occurrence_ledger.clone(),
+            custom_repetition: self.custom_repetition.clone(),
+            catch_up_reports: BTreeMap::new(),
+            lag_reports: BTreeMap::new(),
+            latency_reports: BTreeMap::new(),
+            gate_reports: BTreeMap::new(),
+            command_senders: CommandSenders::default(),
+            cancellation_token: CancellationToken::new(),
+            heartbeat: None,
+            catch_up_progress: None,
+            occurrence_hook: None,
+            wake_up_error: WakeUpError::default(),
+            holidays: None,
+            task_sources: BTreeMap::new(),
+            gates: BTreeMap::new(),
+            running_modes: RunningModes::default(),
+            sleeper: None,
+            clock_adjustment: SharedClockAdjustment::default(),
+        }
+    }
+
+    // Earliest date of the scheduled tasks of every mode, written first by the serialization (see
+    // persistence::peek_next_fire). Read from the first task of each mode: the blackout windows, holidays and sources
+    // aren't taken into account, nor what the catch-up pass does with a late task (see Self::global_next). The tasks with
+    // a count of 0, removed unfired, are left out.
+    pub fn next_fire(&self) -> Option<DateTime<FixedOffset>> {
+        self.scheduled_tasks
+            .values()
+            .filter_map(|tasks| tasks.iter().find(|task| !task.repetition.is_exhausted()))
+            .map(|task| task.date)
+            .min()
+    }
+    /// True when a task of a mode is dated at or before now, from the first task of each mode without allocating.
+    /// A supervisor polling several schedulers can start the one that has something due, see Self::next_fire.
+    /// ```
+    /// use chrono::{DateTime, Duration};
+    /// use scheduler::prelude::*;
+    /// # scheduler::system_clock!(|| chrono::Local::now().into());
+    ///
+    /// let now = DateTime::parse_from_rfc3339("2024-01-01T09:00:00+01:00").unwrap();
+    /// let task = |date, task| ScheduledTask::new(date, task, RepetitionType::Once, SleepType::Native);
+    /// let mut scheduler = BlockingScheduler::new(
+    ///     [
+    ///         ("reports".to_string(), vec![task(now - Duration::hours(1), 1), task(now, 2), task(now + Duration::hours(1), 3)]),
+    ///         ("backups".to_string(), vec![task(now + Duration::minutes(5), 4)]),
+    ///     ],
+    ///     [],
+    /// );
+    /// scheduler.config.missed_tick_behavior = MissedTickBehavior::FireOnce;
+    /// assert!(scheduler.has_due(now));
+    /// assert_eq!(scheduler.due_count(now), 2);
+    /// assert_eq!(scheduler.next_fire(), Some(now - Duration::hours(1)));
+    /// // The late task is fired right away when the mode starts
+    /// let (mode, _, first) = scheduler.global_next_after(now).unwrap();
+    /// assert_eq!((mode.as_str(), first), ("reports", now));
+    ///
+    /// let mut fired = Vec::new();
+    /// assert_eq!(scheduler.tick("reports", now, |task| fired.push(*task)).unwrap(), 2);
+    /// assert_eq!(fired, [1, 2]);
+    /// assert!(!scheduler.has_due(now));
+    /// assert_eq!(scheduler.due_count(now), 0);
+    /// let (mode, _, first) = scheduler.global_next_after(now).unwrap();
+    /// assert_eq!((mode.as_str(), first), ("backups", now + Duration::minutes(5)));
+    /// assert_eq!(scheduler.next_fire(), Some(first));
+    /// assert!(scheduler.has_due(first));
+    /// assert_eq!(scheduler.due_count(now + Duration::hours(1)), 2);
+    ///
+    /// // A count of 0 is never triggered
+    /// let exhausted = ScheduledTask::new(now, 5, RepetitionType::Weekly(RepetitionCount::Finished(0).into()), SleepType::Native);
+    /// let scheduler = BlockingScheduler::new([("done".to_string(), vec![exhausted])], []);
+    /// assert!(!scheduler.has_due(now));
+    /// assert_eq!(scheduler.due_count(now), 0);
+    /// assert_eq!(scheduler.next_fire(), None);
+    /// ```
+    pub fn has_due(&self, now: DateTime<FixedOffset>) -> bool {
+        self.scheduled_tasks.values().any(|tasks| {
+            tasks
+                .iter()
+                .take_while(|task| task.date <= now)
+                .any(|task| !task.repetition.is_exhausted())
+        })
+    }
+    // Number of tasks dated at or before now, the ones Self::tick would trigger, found by a binary search in each mode.
+    // The tasks with a count of 0 aren't triggered, they're left out.
+    pub fn due_count(&self, now: DateTime<FixedOffset>) -> usize {
+        self.scheduled_tasks
+            .values()
+            .map(|tasks| {
+                tasks[..tasks.partition_point(|task| task.date <= now)]
+                    .iter()
+                    .filter(|task| !task.repetition.is_exhausted())
+                    .count()
+            })
+            .sum()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<TaskType, CustomRepetitionType> From<BlockingScheduler<TaskType, CustomRepetitionType>>
+    for CompactScheduler<TaskType, CustomRepetitionType>
+where
+    CustomRepetitionType: Clone,
+{
+    fn from(scheduler: BlockingScheduler<TaskType, CustomRepetitionType>) -> Self {
+        let compact = |tasks: BTreeMap<String, Vec<ScheduledTask<TaskType>>>| {
+            tasks
+                .into_iter()
+                .map(|(mode, tasks)| (mode, CompactTasks::from(tasks)))
+                .collect()
+        };
+        Self {
+            next_fire: scheduler.next_fire(),
+            name: scheduler.name,
+            description: scheduler.description,
+            created_at: scheduler.created_at,
+            modified_at: scheduler.modified_at,
+            scheduled_tasks: compact(scheduler.scheduled_tasks),
+            removed_tasks: compact(scheduler.removed_tasks),
+            pending_deletions: scheduler.pending_deletions,
+            config: scheduler.config,
+            task_ids: scheduler.task_ids,
+            occurrence_ledger: scheduler.occurrence_ledger,
+            custom_repetition: scheduler.custom_repetition.get(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<TaskType, CustomRepetitionType> TryFrom<CompactScheduler<TaskType, CustomRepetitionType>>
+    for BlockingScheduler<TaskType, CustomRepetitionType>
+where
+    TaskType: Eq,
+{
+    type Error = String;
+
+    fn try_from(
+        compact: CompactScheduler<TaskType, CustomRepetitionType>,
+    ) -> Result<Self, Self::Error> {
+        let expand = |tasks: BTreeMap<String, CompactTasks<TaskType>>| {
+            tasks
+                .into_iter()
+                .map(|(mode, tasks)| Ok((mode, tasks.try_into()?)))
+                .collect::<Result<BTreeMap<_, _>, String>>()
+        };
+        let mut scheduler = Self {
+            #[cfg(feature = "serde")]
+            next_fire_hint: NextFireHint::default(),
+            name: compact.name,
+            description: compact.description,
+            created_at: compact.created_at,
+            modified_at: compact.modified_at,
+            scheduled_tasks: expand(compact.scheduled_tasks)?,
+            removed_tasks: expand(compact.removed_tasks)?,
+            pending_deletions: compact.pending_deletions,
+            config: compact.config,
+            task_ids: compact.task_ids,
+            occurrence_ledger: compact.occurrence_ledger,
+            custom_repetition: SharedCustomRepetition::new(compact.custom_repetition),
+            catch_up_reports: BTreeMap::new(),
+            lag_reports: BTreeMap::new(),
+            latency_reports: BTreeMap::new(),
+            gate_reports: BTreeMap::new(),
+            command_senders: CommandSenders::default(),
+            cancellation_token: CancellationToken::new(),
+            heartbeat: None,
+            catch_up_progress: None,
+            occurrence_hook: None,
+            wake_up_error: WakeUpError::default(),
+            holidays: None,
+            task_sources: BTreeMap::new(),
+            gates: BTreeMap::new(),
+            running_modes: RunningModes::default(),
+            sleeper: None,
+            clock_adjustment: SharedClockAdjustment::default(),
+        };
+        scheduler.normalize();
+        Ok(scheduler)
+    }
+}
+
+impl<TaskType> BlockingScheduler<TaskType, NoCustomRepetition>
+where
+    TaskType: Eq + Default,
+{
+    pub fn new(
+        scheduled_tasks: impl IntoIterator<Item = (String, Vec<ScheduledTask<TaskType>>)>,
+        removed_tasks: impl IntoIterator<Item = (String, Vec<ScheduledTask<TaskType>>)>,
+    ) -> Self {
+        Self::new_with_custom_repetition(scheduled_tasks, removed_tasks, NoCustomRepetition)
+    }
+
+    /// Builds the modes by routing every task to the mode returned by the router for its payload.
+    /// ```
+    /// use chrono::{Duration, Utc};
+    /// use scheduler::prelude::*;
+    /// # scheduler::system_clock!(|| chrono::Local::now().into());
+    ///
+    /// #[derive(PartialEq, Eq, Default, Debug)]
+    /// enum Job {
+    ///     #[default]
+    ///     Report,
+    ///     Alert,
+    /// }
+    ///
+    /// let now = Utc::now();
+    /// let tasks = [(2, Job::Report), (1, Job::Alert), (1, Job::Report)].map(|(hours, job)| {
+    ///     ScheduledTask::new(now + Duration::hours(hours), job, RepetitionType::Once, SleepType::Native)
+    /// });
+    /// let scheduler = BlockingScheduler::from_routed(tasks, |job| format!("{:?}", job).to_lowercase());
+    /// assert_eq!(scheduler.scheduled_tasks["report"].len(), 2);
+    /// assert_eq!(scheduler.scheduled_tasks["alert"].len(), 1);
+    /// assert!(scheduler.scheduled_tasks["report"][0].date < scheduler.scheduled_tasks["report"][1].date);
+    /// ```
+    pub fn from_routed(
+        tasks: impl IntoIterator<Item = ScheduledTask<TaskType>>,
+        router: impl Fn(&TaskType) -> String,
+    ) -> Self {
+        let mut scheduled_tasks: BTreeMap<String, Vec<ScheduledTask<TaskType>>> = BTreeMap::new();
+        for task in tasks {
+            scheduled_tasks
+                .entry(router(&task.task))
+                .or_default()
+                .push(task);
+        }
+        Self::new(scheduled_tasks, BTreeMap::new())
+    }
+}
+
+impl<TaskType, CustomRepetitionType> BlockingScheduler<TaskType, CustomRepetitionType>
+where
+    TaskType: Eq + Default,
+    CustomRepetitionType: CustomRepetition + Clone,
+{
+    pub fn new_with_custom_repetition(
+        scheduled_tasks: impl IntoIterator<Item = (String, Vec<ScheduledTask<TaskType>>)>,
+        removed_tasks: impl IntoIterator<Item = (String, Vec<ScheduledTask<TaskType>>)>,
+        custom_repetition: CustomRepetitionType,
+    ) -> Self {
+        let now = SchedulerHelper::now();
+        let mut scheduler = Self {
+            #[cfg(feature = "serde")]
+            next_fire_hint: NextFireHint::default(),
+            name: None,
+            description: None,
+            created_at: now,
+            modified_at: now,
+            scheduled_tasks: scheduled_tasks.into_iter().collect(),
+            removed_tasks: removed_tasks.into_iter().collect(),
+            pending_deletions: BTreeMap::new(),
+            config: SchedulerConfig::default(),
+            task_ids: TaskIds::default(),
+            occurrence_ledger: OccurrenceLedger::default(),
+            custom_repetition: SharedCustomRepetition::new(custom_repetition),
+            catch_up_reports: BTreeMap::new(),
+            lag_reports: BTreeMap::new(),
+            latency_reports: BTreeMap::new(),
+            gate_reports: BTreeMap::new(),
+            command_senders: CommandSenders::default(),
+            cancellation_token: CancellationToken::new(),
+            heartbeat: None,
+            catch_up_progress: None,
+            occurrence_hook: None,
+            wake_up_error: WakeUpError::default(),
+            holidays: None,
+            task_sources: BTreeMap::new(),
+            gates: BTreeMap::new(),
+            running_modes: RunningModes::default(),
+            sleeper: None,
+            clock_adjustment: SharedClockAdjustment::default(),
+        };
+        scheduler.normalize();
+        scheduler
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+    pub fn set_name(&mut self, name: Option<String>) {
+        self.name = name;
+        self.modified_at = SchedulerHelper::now();
+    }
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+    pub fn set_description(&mut self, description: Option<String>) {
+        self.description = description;
+        self.modified_at = SchedulerHelper::now();
+    }
+    pub fn created_at(&self) -> DateTime<FixedOffset> {
+        self.created_at
+    }
+    pub fn modified_at(&self) -> DateTime<FixedOffset> {
+        self.modified_at
+    }
+
+    // Scheduler of a worker holding the mode, its tasks being moved out of this one, and sharing what the clones share
+    // (ids, custom repetition, commands, cancellation, sources). Given back by Self::attach_mode once the worker stops,
+    // so that a mode runs on its own thread without its payloads being cloned.
+    pub(crate) fn detach_mode(&mut self, mode: &str) -> Result<Self, String> {
+        if !self.has_mode(mode) {
+            return Err(self.unknown_mode(mode).to_string());
+        }
+        fn take<Value>(map: &mut BTreeMap<String, Value>, mode: &str) -> BTreeMap<String, Value> {
+            map.remove_entry(mode).into_iter().collect()
+        }
+        fn share<Value: Clone>(
+            map: &BTreeMap<String, Value>,
+            mode: &str,
+        ) -> BTreeMap<String, Value> {
+            map.get_key_value(mode)
+                .map(|(mode, value)| (mode.clone(), value.clone()))
+                .into_iter()
+                .collect()
+        }
+        Ok(Self {
+            #[cfg(feature = "serde")]
+            next_fire_hint: NextFireHint::default(),
+            name: self.name.clone(),
+            description: self.description.clone(),
+            created_at: self.created_at,
+            modified_at: self.modified_at,
+            scheduled_tasks: take(&mut self.scheduled_tasks, mode),
+            removed_tasks: take(&mut self.removed_tasks, mode),
+            pending_deletions: take(&mut self.pending_deletions, mode),
+            config: self.config.clone(),
+            task_ids: self.task_ids.clone(),
+            occurrence_ledger: self.occurrence_ledger.clone(),
+            custom_repetition: self.custom_repetition.clone(),
+            catch_up_reports: BTreeMap::new(),
+            lag_reports: BTreeMap::new(),
+            latency_reports: BTreeMap::new(),
+            gate_reports: BTreeMap::new(),
+            command_senders: self.command_senders.clone(),
+            cancellation_token: self.cancellation_token.clone(),
+            heartbeat: self.heartbeat.clone(),
+            wake_up_error: WakeUpError::default(),
+            catch_up_progress: self.catch_up_progress.clone(),
+            occurrence_hook: self.occurrence_hook,
+            holidays: self.holidays.clone(),
+            task_sources: share(&self.task_sources, mode),
+            gates: share(&self.gates, mode),
+            running_modes: self.running_modes.clone(),
+            sleeper: self.sleeper.clone(),
+            clock_adjustment: self.clock_adjustment.clone(),
+        })
+    }
+
+    // The controller reaches the modes of this scheduler and of its clones while they are started
+    pub fn controller(&self) -> SchedulerController<TaskType> {
+        SchedulerController::new(self.command_senders.clone())
+    }
+
+    pub fn has_mode(&self, mode: &str) -> bool {
+        self.scheduled_tasks.contains_key(mode)
+    }
+    // Error given when the mode doesn't exist, suggesting the modes with a close name
+    pub fn unknown_mode(&self, mode: &str) -> SchedulerError {
+        SchedulerError::unknown_mode(mode, self.scheduled_tasks.keys())
+    }
+
+    // Date of the next task of the mode
+    pub fn next_run_time(&self, mode: &str) -> Option<DateTime<FixedOffset>> {
+        let tasks = self.scheduled_tasks.get(mode)?;
+        // The first task may be moved past the others by the blackout windows of the mode
+        let next_task = match self.config.for_mode(mode).blackouts() {
+            Some(blackouts) => tasks
+                .iter()
+                .filter_map(|task| {
+                    blackouts.avoid(task.date, |date| {
+                        task.repetition
+                            .next_occurrence(date, date, &self.custom_repetition)
+                    })
+                })
+                .min(),
+            None => tasks.first().map(|task| task.date),
+        };
+        // The task kept by the source of the mode may come before a task which repeats far away
+        let next_source_task = self
+            .task_sources
+            .get(mode)
+            .and_then(|source| source.lock().unwrap().next_date());
+        next_task.into_iter().chain(next_source_task).min()
+    }
+    pub fn next_run_time_utc(&self, mode: &str) -> Option<DateTime<Utc>> {
+        self.next_run_time(mode)
+            .map(|date| date.with_timezone(&Utc))
+    }
+
+    // Earliest task across all the modes started now, see Self::global_next_after
+    pub fn global_next(&self) -> Option<(String, &ScheduledTask<TaskType>, DateTime<FixedOffset>)> {
+        self.global_next_after(SchedulerHelper::adjusted_now(&self.clock_adjustment))
+    }
+    // Earliest task across all the modes started at the date, with its mode and the date it would first be triggered at.
+    // A task dated before is triggered right away when its missed occurrences are fired by the catch-up pass,
+    // otherwise at its first occurrence after the date (see MissedTickBehavior). Modes tied are ordered by name.
+    // The tasks kept by the sources of the modes aren't taken into account, see Self::next_run_time.
+    pub fn global_next_after(
+        &self,
+        after: DateTime<FixedOffset>,
+    ) -> Option<(String, &ScheduledTask<TaskType>, DateTime<FixedOffset>)> {
+        self.scheduled_tasks
+            .iter()
+            .flat_map(|(mode, tasks)| {
+                tasks.iter().filter_map(move |task| {
+                    Some((self.first_trigger(mode, task, &after)?, mode, task))
+                })
+            })
+            .min_by(|(date, mode, _), (other_date, other_mode, _)| {
+                date.cmp(other_date).then_with(|| mode.cmp(other_mode))
+            })
+            .map(|(date, mode, task)| (mode.clone(), task, date))
+    }
+    // Date at which the task would first be triggered by the mode started at now, None if it would be removed unfired
+    fn first_trigger(
+        &self,
+        mode: &str,
+        task: &ScheduledTask<TaskType>,
+        now: &DateTime<FixedOffset>,
+    ) -> Option<DateTime<FixedOffset>> {
+        if task.repetition.is_exhausted() {
+            return None;
+        }
+        let config = self.config.for_mode(mode);
+        let blackouts = config.blackouts();
+        if task.date >= *now || matches!(task.repetition, RepetitionType::SelfScheduled(_)) {
+            let date = task.date.max(*now);
+            return match &blackouts {
+                Some(blackouts) => blackouts.avoid(date, |date| {
+                    task.repetition
+                        .next_occurrence(date, date, &self.custom_repetition)
+                }),
+                None => Some(date),
+            };
+        }
+        let fired_late = match config.missed_tick_behavior {
+            MissedTickBehavior::Skip => false,
+            MissedTickBehavior::FireOnce => true,
+            MissedTickBehavior::FireAll => config.max_catch_up != Some(0),
+        };
+        if fired_late {
+            return Some(*now);
+        }
+        SchedulerReadingHandler::<TaskType, _>::next_date(
+            task,
+            now,
+            false,
+            None,
+            &self.custom_repetition,
+            self.holidays.as_ref(),
+            blackouts.as_ref(),
+        )
+        .ok()
+        .map(|(next_date, _)| next_date)
+    }
+
+    // Overrides the settings of the config for the mode, None removing its overrides. See SchedulerConfig::for_mode
+    pub fn set_mode_config(&mut self, mode: &str, mode_config: Option<ModeConfig>) {
+        match mode_config {
+            Some(mode_config) => self.config.modes.insert(mode.to_owned(), mode_config),
+            None => self.config.modes.remove(mode),
+        };
+    }
+    pub fn mode_config(&self, mode: &str) -> Option<&ModeConfig> {
+        self.config.modes.get(mode)
+    }
+
+    // Average lateness of the recent wake-ups of start, None until a task has been waited for. See SleepType::Auto
+    pub fn wake_up_error(&self) -> Option<std::time::Duration> {
+        self.wake_up_error.average()
+    }
+
+    /// Report of the last catch-up pass of the mode. The counts of the tasks being only decremented by the occurrences
+    /// fired, a schedule saved and loaded again in between fires each occurrence once.
+    /// ```
+    /// # #[cfg(feature = "json")]
+    /// # {
+    /// use chrono::{DateTime, Duration};
+    /// use scheduler::prelude::*;
+    /// use std::collections::HashMap;
+    /// # scheduler::system_clock!(|| chrono::Local::now().into());
+    ///
+    /// let start = DateTime::parse_from_rfc3339("2024-01-01T09:00:00+01:00").unwrap();
+    /// let week = |week| start + Duration::weeks(week);
+    /// let repetition = RepetitionType::Weekly(RepetitionCount::Finished(10).into());
+    /// let task = ScheduledTask::new(start, "report", repetition, SleepType::Native);
+    /// let mut scheduler = BlockingScheduler::new(HashMap::from([("jobs".to_string(), vec![task])]), HashMap::new());
+    /// let mut fired = 0;
+    /// for week in (0..3).map(week) {
+    ///     scheduler.tick("jobs", week, |_| fired += 1).unwrap();
+    /// }
+    ///
+    /// // Restarted from what has been saved
+    /// let saved = serde_json::to_string(&scheduler).unwrap();
+    /// let mut scheduler: BlockingScheduler<&str> = serde_json::from_str(&saved).unwrap();
+    /// let report = scheduler.catch_up("jobs", week(3), |_| fired += 1).unwrap();
+    /// assert_eq!(report.remaining, 7);
+    /// for week in (3..15).map(week) {
+    ///     scheduler.tick("jobs", week, |_| fired += 1).unwrap();
+    /// }
+    /// assert_eq!(fired, 10);
+    /// assert_eq!(scheduler.catch_up_report("jobs"), Some(&report));
+    /// assert!(scheduler.scheduled_tasks["jobs"].is_empty());
+    /// # }
+    /// ```
+    pub fn catch_up_report(&self, mode: &str) -> Option<&CatchUpReport> {
+        self.catch_up_reports.get(mode)
+    }
+    // Occurrences completed lately, only recorded when SchedulerConfig::occurrence_ledger is set, see the ledger module
+    pub fn occurrence_ledger(&self) -> &OccurrenceLedger {
+        &self.occurrence_ledger
+    }
+    // Lag of the mode since its last start, None when none of its callbacks has lasted past the next occurrence of its task
+    pub fn lag_report(&self, mode: &str) -> Option<&LagReport> {
+        self.lag_reports.get(mode)
+    }
+    /// Wake-to-fire latency of the mode since its last start, None unless SchedulerConfig::realtime is set.
+    /// ```
+    /// # #[cfg(feature = "spin_sleep")]
+    /// # {
+    /// use chrono::{Duration, Local};
+    /// use scheduler::prelude::*;
+    /// use std::collections::HashMap;
+    /// # scheduler::system_clock!(|| chrono::Local::now().into());
+    ///
+    /// let every_10_ms = RepetitionType::ConstGap {
+    ///     gap: Duration::milliseconds(10),
+    ///     termination: Termination::default(),
+    /// };
+    /// let task = ScheduledTask::new(Local::now() + Duration::milliseconds(10), (), every_10_ms, SleepType::Spin);
+    /// let mut scheduler = BlockingScheduler::new(HashMap::from([("audio".to_string(), vec![task])]), HashMap::new());
+    /// scheduler.config.realtime = true;
+    /// let cancellation_token = scheduler.cancellation_token();
+    /// let mut fired = 0;
+    /// scheduler
+    ///     .start("audio", |_| {
+    ///         fired += 1;
+    ///         if fired == 20 {
+    ///             cancellation_token.cancel();
+    ///         }
+    ///     })
+    ///     .unwrap();
+    /// let latency = scheduler.latency_report("audio").unwrap();
+    /// assert_eq!(latency.firings(), 20);
+    /// assert!(latency.percentile(50.0).unwrap() <= latency.percentile(99.0).unwrap());
+    /// assert!(latency.percentile(100.0).unwrap() <= latency.max());
+    /// # }
+    /// ```
+    pub fn latency_report(&self, mode: &str) -> Option<&LatencyReport> {
+        self.latency_reports.get(mode)
+    }
+    // Occurrences of the mode held by a closed gate since its last start (or since it has been ticked), see the gate module
+    pub fn gate_report(&self, mode: &str) -> Option<&GateReport> {
+        self.gate_reports.get(mode)
+    }
+
+    // Inserts the task in the given mode (created if needed), keeping the mode sorted by date
+    pub fn add_task(&mut self, mode: &str, mut task: ScheduledTask<TaskType>) {
+        self.task_ids.assign([&mut task]);
+        let tasks = self.scheduled_tasks.entry(mode.to_owned()).or_default();
+        let index = tasks.partition_point(|scheduled_task| scheduled_task.date <= task.date);
+        tasks.insert(index, task);
+        self.removed_tasks.entry(mode.to_owned()).or_default();
+        self.modified_at = SchedulerHelper::now();
+    }
+
+    /// Attaches the handler to the scheduled task with the id (the handlers aren't saved), or detaches it with None.
+    /// The handler is called instead of the callback of the mode, see ScheduledTask::with_override_handler.
+    /// ```
+    /// use chrono::{Duration, Local};
+    /// use scheduler::prelude::*;
+    /// use std::collections::HashMap;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// # scheduler::system_clock!(|| chrono::Local::now().into());
+    ///
+    /// let now = Local::now();
+    /// let hourly = |task| {
+    ///     let every_hour = RepetitionType::ConstGap { gap: Duration::hours(1), termination: Termination::default() };
+    ///     ScheduledTask::new(now, task, every_hour, SleepType::Native)
+    /// };
+    /// let overridden = Arc::new(AtomicUsize::new(0));
+    /// let counter = overridden.clone();
+    /// let audited = hourly("audit").with_override_handler(move |_| {
+    ///     counter.fetch_add(1, Ordering::SeqCst);
+    /// });
+    /// let mut scheduler = BlockingScheduler::new(
+    ///     HashMap::from([("jobs".to_string(), vec![hourly("backup"), audited])]),
+    ///     HashMap::new(),
+    /// );
+    /// let mut called = Vec::new();
+    /// scheduler.tick("jobs", now, |task| called.push(*task)).unwrap();
+    /// scheduler.tick("jobs", now + Duration::hours(1), |task| called.push(*task)).unwrap();
+    /// assert_eq!(called, ["backup", "backup"]);
+    /// assert_eq!(overridden.load(Ordering::SeqCst), 2);
+    ///
+    /// let audit = scheduler.scheduled_tasks["jobs"].iter().find(|task| task.task == "audit").unwrap().id().unwrap();
+    /// scheduler.set_override_handler(audit, None).unwrap();
+    /// scheduler.tick("jobs", now + Duration::hours(2), |task| called.push(*task)).unwrap();
+    /// assert_eq!(called.len(), 4);
+    /// assert_eq!(overridden.load(Ordering::SeqCst), 2);
+    /// ```
+    pub fn set_override_handler(
+        &mut self,
+        id: TaskId,
+        handler: Option<OverrideHandler<TaskType>>,
+    ) -> Result<(), String> {
+        let task = self
+            .scheduled_tasks
+            .values_mut()
+            .flatten()
+            .find(|task| task.id == Some(id))
+            .ok_or(format!("Couldn't find the requested task : {}", id))?;
+        task.extras
+            .get_or_insert_with(Box::default)
+            .override_handler = handler;
+        Ok(())
+    }
+
+    // Every task of the mode only fires while the gate is open (along with its own gate), see the gate module.
+    // Removed with None. The gates aren't saved.
+    pub fn set_gate(&mut self, mode: &str, gate: Option<SharedGate<TaskType>>) {
+        match gate {
+            Some(gate) => self.gates.insert(mode.to_owned(), gate),
+            None => self.gates.remove(mode),
+        };
+    }
+
+    // Feeds the mode (created if needed) with the tasks of the source as they come within the look-ahead of the current
+    // date, see TaskSource. The tasks pulled are added to the mode: they're rescheduled and removed like the others.
+    // The tasks of the source dated in the past are triggered right away, they aren't caught up.
+    pub fn set_task_source(
+        &mut self,
+        mode: &str,
+        source: impl TaskSource<TaskType> + Send + 'static,
+        look_ahead: Duration,
+    ) {
+        self.scheduled_tasks.entry(mode.to_owned()).or_default();
+        self.removed_tasks.entry(mode.to_owned()).or_default();
+        self.task_sources.insert(
+            mode.to_owned(),
+            Arc::new(Mutex::new(ModeSource::new(Box::new(source), look_ahead))),
+        );
+    }
+    // The tasks already pulled from the source stay in the mode
+    pub fn clear_task_source(&mut self, mode: &str) {
+        self.task_sources.remove(mode);
+    }
+
+    // Checks that no Once task is dated before now. When SchedulerConfig::auto_advance is set, the repeating tasks
+    // dated before now are moved to their first occurrence after now, so that the stored schedule means what start will do.
+    pub fn validate(
+        &mut self,
+        now: impl Into<DateTime<FixedOffset>>,
+    ) -> Result<(), ValidationError> {
+        let now = now.into();
+        for (mode, tasks) in self.scheduled_tasks.iter() {
+            if let Some(task) = tasks
+                .iter()
+                .find(|task| task.date < now && matches!(task.repetition, RepetitionType::Once))
+            {
+                return Err(ValidationError::DateInPast {
+                    mode: mode.clone(),
+                    date: task.date,
+                });
+            }
+        }
+        if self.config.auto_advance {
+            let mut advanced = false;
+            for tasks in self.scheduled_tasks.values_mut() {
+                for task in tasks.iter_mut().filter(|task| task.date < now) {
+                    // A custom repetition without any further occurrence is left to the catch-up pass
+                    if let Some(date) =
+                        task.repetition
+                            .next_occurrence(&now, &task.date, &self.custom_repetition)
+                    {
+                        task.date = date;
+                        advanced = true;
+                    }
+                }
+                tasks.sort_by_key(|task| task.date);
+            }
+            if advanced {
+                self.modified_at = SchedulerHelper::now();
+            }
+        }
+        Ok(())
+    }
+
+    /// Tasks dated in another offset than the one of SchedulerConfig::zone at their date. Not an error, a task may be
+    /// meant for another offset, but the usual cause of a task firing at the wrong hour: its date built in the wrong
+    /// offset. Self::assume_local_time builds the dates in the zone.
+    /// ```
+    /// use chrono::{FixedOffset, NaiveDate};
+    /// use scheduler::prelude::*;
+    /// use scheduler::wall::WallZone;
+    /// use std::collections::HashMap;
+    /// # scheduler::system_clock!(|| chrono::Local::now().into());
+    ///
+    /// let nine = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap().and_hms_opt(9, 0, 0).unwrap();
+    /// let paris = WallZone::Fixed(FixedOffset::east_opt(3600).unwrap());
+    /// let new_york = WallZone::Fixed(FixedOffset::west_opt(5 * 3600).unwrap());
+    /// let mut scheduler = BlockingScheduler::<&str>::new(HashMap::from([("jobs".to_string(), Vec::new())]), HashMap::new());
+    /// for zone in [paris, new_york] {
+    ///     scheduler.config.zone = zone;
+    ///     let date = scheduler.assume_local_time(nine).unwrap();
+    ///     assert_eq!((date.naive_local(), *date.offset()), (nine, zone.offset_at(&date)));
+    /// }
+    ///
+    /// // Dated at 09:00 in Paris, read at 03:00 in New York
+    /// scheduler.config.zone = paris;
+    /// let date = scheduler.assume_local_time(nine).unwrap();
+    /// scheduler.add_task("jobs", ScheduledTask::new(date, "report", RepetitionType::Once, SleepType::Native));
+    /// assert!(scheduler.offset_warnings().is_empty());
+    /// scheduler.config.zone = new_york;
+    /// let warnings = scheduler.offset_warnings();
+    /// assert_eq!(
+    ///     warnings,
+    ///     [ValidationError::OffsetMismatch { mode: "jobs".to_string(), date, expected: new_york.offset_at(&date) }]
+    /// );
+    /// let summary = scheduler.scheduled_tasks["jobs"][0].offset_summary();
+    /// assert_eq!(summary.utc, nine - chrono::Duration::hours(1));
+    /// ```
+    pub fn offset_warnings(&self) -> Vec<ValidationError> {
+        let mut warnings = Vec::new();
+        for (mode, tasks) in self.scheduled_tasks.iter() {
+            for task in tasks {
+                let expected = self.config.zone.offset_at(&task.date);
+                if *task.date.offset() != expected {
+                    warnings.push(ValidationError::OffsetMismatch {
+                        mode: mode.clone(),
+                        date: task.date,
+                        expected,
+                    });
+                }
+            }
+        }
+        warnings
+    }
+    // Date at the local time in SchedulerConfig::zone, for ScheduledTask::new. Fails when the time is skipped by a change
+    // of the local offset.
+    pub fn assume_local_time(&self, local: NaiveDateTime) -> Result<DateTime<FixedOffset>, String> {
+        self.config.zone.from_local_datetime(&local).ok_or(format!(
+            "The local time {} doesn't exist in the zone {}",
+            local, self.config.zone
+        ))
+    }
+
+    // Called by start with the number of outdated tasks caught up so far and their total, after each batch of the
+    // catch-up pass (see SchedulerConfig::catch_up_batch_size)
+    pub fn set_catch_up_progress(
+        &mut self,
+        catch_up_progress: Option<Box<dyn FnMut(usize, usize) + Send>>,
+    ) {
+        self.catch_up_progress = catch_up_progress
+            .map(|catch_up_progress| SharedHook(Arc::new(Mutex::new(catch_up_progress))));
+    }
+    // Tells the hook what becomes of each occurrence of the tasks, see OccurrenceOutcome
+    pub fn set_occurrence_hook(&mut self, occurrence_hook: Option<OccurrenceHook<TaskType>>) {
+        self.occurrence_hook = occurrence_hook;
+    }
+    // The heartbeat is called every interval while start waits for the next task (a liveness probe for instance).
+    // It never delays a task: the wait is split around the heartbeats and the last part keeps the task's sleep type.
+    pub fn set_heartbeat(&mut self, heartbeat: Option<(Duration, Box<dyn FnMut() + Send>)>) {
+        self.heartbeat = heartbeat.map(|(interval, heartbeat)| {
+            (
+                interval
+                    .to_std()
+                    .unwrap_or_default()
+                    .max(MIN_HEARTBEAT_INTERVAL),
+                SharedHook(Arc::new(Mutex::new(heartbeat))),
+            )
+        });
+    }
+
+    pub fn custom_repetition(&self) -> CustomRepetitionType {
+        self.custom_repetition.get()
+    }
+    // Replaces the custom repetition of this scheduler and of its clones, running modes included:
+    // the next Custom task moved to its next occurrence uses the new one, a computation already started keeps the
+    // previous one. Must not be called from CustomRepetition::update_date, which holds the custom repetition.
+    pub fn set_custom_repetition(&self, custom_repetition: CustomRepetitionType) {
+        self.custom_repetition.set(custom_repetition);
+    }
+    // The custom repetition to send to a started mode of this scheduler (or of a clone) through
+    // SchedulerController::set_custom_repetition, which replaces it as set_custom_repetition does once the mode gets to
+    // the command
+    pub fn custom_repetition_swap(
+        &self,
+        custom_repetition: CustomRepetitionType,
+    ) -> CustomRepetitionSwap
+    where
+        CustomRepetitionType: Send + Sync + 'static,
+    {
+        self.custom_repetition.swap(custom_repetition)
+    }
+
+    // Occurrences of the tasks created with skip_holidays landing on a day the provider marks as a holiday are moved
+    // according to the policy. The provider is shared with the clones of the scheduler.
+    pub fn set_holiday_provider(
+        &mut self,
+        provider: impl HolidayProvider + Send + Sync + 'static,
+        policy: HolidayPolicy,
+    ) {
+        self.holidays = Some(Holidays {
+            provider: Arc::new(provider),
+            policy,
+        });
+    }
+    pub fn clear_holiday_provider(&mut self) {
+        self.holidays = None;
+    }
+
+    // Every wait of start goes through the sleeper instead of the sleep types of the tasks, see the sleeper module.
+    // The sleeper is shared with the clones of the scheduler.
+    pub fn set_sleeper(&mut self, sleeper: impl Sleeper + 'static) {
+        self.sleeper = Some(SharedSleeper(Arc::new(sleeper)));
+    }
+    pub fn clear_sleeper(&mut self) {
+        self.sleeper = None;
+    }
+    // The started modes read the current date through the adjustment, None reading the system clock as is. Shared with
+    // the clones of the scheduler, see SchedulerCommand::SetClockAdjustment to adjust a started mode.
+    pub fn set_clock_adjustment(&mut self, clock_adjustment: Option<ClockAdjustment>) {
+        *self.clock_adjustment.lock().unwrap() = clock_adjustment;
+    }
+    pub fn clock_adjustment(&self) -> Option<ClockAdjustment> {
+        *self.clock_adjustment.lock().unwrap()
+    }
+
+    /// Names of the scheduled modes, sorted. The modes are always enumerated in this order: by the methods handling
+    /// several modes, by the workers started for a prefix, and in the serialized scheduler.
+    /// ```
+    /// use chrono::Local;
+    /// use scheduler::prelude::*;
+    /// use std::collections::HashMap;
+    /// # scheduler::system_clock!(|| chrono::Local::now().into());
+    ///
+    /// let task = ScheduledTask::new(Local::now(), (), RepetitionType::Once, SleepType::Native);
+    /// let scheduler = BlockingScheduler::new(
+    ///     ["weekly", "daily", "monthly"].map(|mode| (mode.to_string(), vec![task.clone()])),
+    ///     HashMap::new(),
+    /// );
+    /// assert!(scheduler.modes().eq(["daily", "monthly", "weekly"]));
+    /// assert_eq!(scheduler.modes_matching("m"), ["monthly"]);
+    /// ```
+    pub fn modes(&self) -> impl Iterator<Item = &str> {
+        self.scheduled_tasks.keys().map(String::as_str)
+    }
+    // Modes whose name starts with the prefix, sorted. Names are compared as plain strings: "reports/" matches
+    // "reports/daily" and a mode named "reports/" itself, but not "reports", and an empty prefix matches every mode.
+    pub fn modes_matching(&self, prefix: &str) -> Vec<&str> {
+        self.modes()
+            .filter(|mode| mode.starts_with(prefix))
+            .collect()
+    }
+    // Number of tasks scheduled in the modes matching the prefix, see Self::modes_matching
+    pub fn pending_count_prefix(&self, prefix: &str) -> usize {
+        self.scheduled_tasks
+            .iter()
+            .filter(|(mode, _)| mode.starts_with(prefix))
+            .map(|(_, tasks)| tasks.len())
+            .sum()
+    }
+    // Moves every task of the modes matching the prefix to their removed tasks (RemovalReason::Cancelled).
+    // The modes are kept, empty. Returns the number of cancelled tasks.
+    pub fn cancel_prefix(&mut self, prefix: &str) -> usize {
+        let mut cancelled = 0;
+        for (mode, tasks) in self.scheduled_tasks.iter_mut() {
+            if !mode.starts_with(prefix) {
+                continue;
+            }
+            cancelled += tasks.len();
+            let removed_tasks = self.removed_tasks.entry(mode.clone()).or_default();
+            removed_tasks.extend(tasks.drain(..).map(|mut task| {
+                if let Some(occurrence_hook) = self.occurrence_hook {
+                    occurrence_hook(
+                        OccurrenceOutcome::Cancelled,
+                        &TaskContext {
+                            mode,
+                            task: &task.task,
+                            date: task.date,
+                            cancel_token: None,
+                            execution: None,
+                            is_backfill: false,
+                        },
+                    );
+                }
+                task.removal_reason = Some(RemovalReason::Cancelled);
+                task
+            }));
+        }
+        if cancelled > 0 {
+            self.modified_at = SchedulerHelper::now();
+        }
+        cancelled
+    }
+
+    // Cancels the task of the id in the mode, which can be restored by Self::undo_cancel until the ttl has elapsed.
+    // Afterwards it's moved to the removed tasks (RemovalReason::Cancelled) by the next wake-up of start or tick of the mode.
+    // Returns the number of cancelled tasks, 0 when the mode has no task of the id.
+    pub fn cancel_with_ttl(
+        &mut self,
+        mode: &str,
+        id: TaskId,
+        ttl: Duration,
+    ) -> Result<usize, SchedulerError> {
+        let tasks = SchedulerError::mode_mut(&mut self.scheduled_tasks, mode)?;
+        let expires_at = SchedulerHelper::adjusted_now(&self.clock_adjustment)
+            .checked_add_signed(ttl)
+            .unwrap_or(DateTime::<Utc>::MAX_UTC.into());
+        let (cancelled, kept): (Vec<_>, Vec<_>) = tasks
+            .drain(..)
+            .partition(|scheduled_task| scheduled_task.id == Some(id));
+        *tasks = kept;
+        let count = cancelled.len();
+        self.pending_deletions
+            .entry(mode.to_owned())
+            .or_default()
+            .extend(
+                cancelled
+                    .into_iter()
+                    .map(|task| PendingDeletion { task, expires_at }),
+            );
+        if count > 0 {
+            self.modified_at = SchedulerHelper::now();
+        }
+        Ok(count)
+    }
+    // Restores the task of the id cancelled by Self::cancel_with_ttl, with the date and count it had.
+    // Returns the number of restored tasks, an expired one can't be restored.
+    pub fn undo_cancel(&mut self, mode: &str, id: TaskId) -> usize {
+        let Some(pending_deletions) = self.pending_deletions.get_mut(mode) else {
+            return 0;
+        };
+        let now = SchedulerHelper::adjusted_now(&self.clock_adjustment);
+        let (restored, pending): (Vec<_>, Vec<_>) =
+            pending_deletions.drain(..).partition(|pending_deletion| {
+                pending_deletion.task.id == Some(id) && now < pending_deletion.expires_at
+            });
+        *pending_deletions = pending;
+        let count = restored.len();
+        let tasks = self.scheduled_tasks.entry(mode.to_owned()).or_default();
+        for pending_deletion in restored {
+            let index = tasks.partition_point(|scheduled_task| {
+                scheduled_task.date <= pending_deletion.task.date
+            });
+            tasks.insert(index, pending_deletion.task);
+        }
+        if count > 0 {
+            self.modified_at = SchedulerHelper::now();
+        }
+        count
+    }
+    pub fn pending_deletions(&self, mode: &str) -> &[PendingDeletion<TaskType>] {
+        self.pending_deletions.get(mode).map_or(&[], Vec::as_slice)
+    }
+
+    // Adds the task to the mode returned by the router for its payload, see Self::from_routed and Self::add_task
+    pub fn route_in(
+        &mut self,
+        task: ScheduledTask<TaskType>,
+        router: impl Fn(&TaskType) -> String,
+    ) {
+        let mode = router(&task.task);
+        self.add_task(&mode, task);
+    }
+
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation_token.clone()
+    }
+
+    // Asks every running start of this scheduler (and of its clones) to return once the current callback is over
+    pub fn stop(&self) {
+        self.cancellation_token.cancel();
+    }
+
+    pub fn start(&mut self, mode: &str, mut f: impl FnMut(&TaskType)) -> Result<(), String> {
+        self.run(
+            mode,
+            |task| {
+                f(&task.task);
+                None
+            },
+            &mut (),
+        )
+    }
+
+    // Same as start, the callback returning when the SelfScheduled tasks happen next (ignored for the other tasks).
+    // Counts and until dates are still honored. The SelfScheduled tasks dated in the past are triggered right away
+    // instead of being caught up. start and tick trigger them once, like Once tasks.
+    pub fn start_self_scheduled(
+        &mut self,
+        mode: &str,
+        mut f: impl FnMut(&TaskType) -> NextRun,
+    ) -> Result<(), String> {
+        self.run(mode, |task| Some(f(&task.task)), &mut ())
+    }
+
+    // Body of start, telling the observer what happens (see the journal feature)
+    pub(crate) fn run(
+        &mut self,
+        mode: &str,
+        mut f: impl FnMut(&ScheduledTask<TaskType>) -> Option<NextRun>,
+        observer: &mut impl RunObserver<TaskType>,
+    ) -> Result<(), String> {
+        let mut reading_handler = SchedulerReadingHandler::new(
+            SchedulerError::mode_mut(&mut self.scheduled_tasks, mode)?,
+            self.custom_repetition.clone(),
+            self.holidays.clone(),
+            self.config.for_mode(mode).blackouts(),
+            self.occurrence_hook
+                .map(|occurrence_hook| (mode, occurrence_hook)),
+            self.task_ids.clone(),
+        );
+        reading_handler.clock_adjustment = self.clock_adjustment.clone();
+        reading_handler.occurrence_ledger = self
+            .occurrence_ledger
+            .enabled(self.config.occurrence_ledger);
+        let clock_adjustment = self.clock_adjustment.clone();
+        let adjusted_now = || SchedulerHelper::adjusted_now(&clock_adjustment);
+        let _running = self
+            .running_modes
+            .enter(mode, self.config.concurrent_start)?;
+        let config = self.config.for_mode(mode);
+        // Kept until the end of the run, whatever the way it ends
+        #[cfg(feature = "windows-timer")]
+        let _timer_resolution = TimerResolution::raise(
+            config.high_resolution_timer
+                && reading_handler.has_gap_shorter_than(TimerResolution::THRESHOLD),
+        );
+        let rng_seed = config.effective_rng_seed();
+        let mut rng = SchedulerConfig::rng(rng_seed, mode);
+        let initial_delay = config.startup_delay(&mut rng);
+        let sleeper = self.sleeper.clone();
+        // The waits keep the sleep type given unless the scheduler has a sleeper, see Self::set_sleeper. The targets are
+        // read on the adjusted clock, the sleep being done on the system clock.
+        let sleep_until = |sleep_type: SleepType, target, token: &CancelToken| {
+            let target = target - SchedulerHelper::clock_offset(&clock_adjustment);
+            match &sleeper {
+                Some(sleeper) => sleeper.0.sleep_until(target, &SystemClock, token),
+                None => sleep_type.sleep_until(target, &SystemClock, token),
+            }
+        };
+        let wake = Wake::new(&self.cancellation_token);
+        let startup = adjusted_now() + initial_delay;
+        if sleep_until(SleepType::Native, startup, &wake.token()) == SleepOutcome::Cancelled {
+            wake.release();
+            return Ok(());
+        }
+        self.lag_reports.remove(mode);
+        self.latency_reports.remove(mode);
+        self.gate_reports.remove(mode);
+        let source = self.task_sources.get(mode).cloned();
+        let mut catch_up = Some(reading_handler.begin_catch_up(&adjusted_now()));
+        let catch_up_batch_size = config.catch_up_batch_size.unwrap_or(usize::MAX).max(1);
+        #[cfg(feature = "spin_sleep")]
+        let spin_sleeper = config.spin.sleeper();
+        let (command_sender, commands) = mpsc::channel();
+        self.command_senders.lock().unwrap().insert(
+            mode.to_owned(),
+            CommandSender::new(command_sender, wake.clone()),
+        );
+        let mut next_heartbeat = self
+            .heartbeat
+            .as_ref()
+            .and_then(|(interval, _)| Instant::now().checked_add(*interval));
+        // Removed tasks already given to the observer
+        let mut observed_removals = 0;
+        let mut completed = self.cancellation_token.is_cancelled();
+        // Error of the catch-up pass, returned once the mode has been stopped
+        let mut stalled = None;
+        let mut observed_adjustment = *clock_adjustment.lock().unwrap();
+        // A panicking callback stops the mode as a stop would, the tasks being left in the scheduler, then the panic goes on
+        let run = panic::catch_unwind(AssertUnwindSafe(|| {
+            while !completed {
+                // Taken first, so that a command sent after the ones received wakes the next sleep up
+                let sleep_token = wake.token();
+                // Commands received while the previous task was triggered
+                while let Ok(command) = commands.try_recv() {
+                    reading_handler.apply_command(command);
+                }
+                // Set by a command or by a clone of the scheduler
+                let current_adjustment = *clock_adjustment.lock().unwrap();
+                if current_adjustment != observed_adjustment {
+                    observer.clock_adjusted(current_adjustment.as_ref());
+                    observed_adjustment = current_adjustment;
+                }
+                let removed_count = reading_handler.removed_tasks.len();
+                SchedulerHelper::expire_pending_deletions(
+                    self.pending_deletions.get_mut(mode),
+                    &mut reading_handler.removed_tasks,
+                    &adjusted_now(),
+                    reading_handler.occurrence_hook,
+                );
+                reading_handler.changed |= removed_count != reading_handler.removed_tasks.len();
+                let next_pull = source.as_ref().and_then(|source| {
+                    let mut source = source.lock().unwrap();
+                    let mode_is_empty = reading_handler.get_current_task().is_none();
+                    source.pull(&adjusted_now(), mode_is_empty, |task| {
+                        reading_handler.apply_command(SchedulerCommand::AddTask(task))
+                    });
+                    source.next_pull()
+                });
+                // Left in place while it's caught up, so that a panicking callback doesn't lose the tasks pending
+                if let Some(pending) = &mut catch_up {
+                    if let Err(error) = reading_handler.catch_up_batch(
+                        pending,
+                        catch_up_batch_size,
+                        &config,
+                        |task| {
+                            task.trigger(&mut f);
+                        },
+                    ) {
+                        reading_handler.abandon_catch_up(pending);
+                        stalled = Some(format!("Couldn't catch up the mode {} : {}", mode, error));
+                        break;
+                    }
+                    if let Some(catch_up_progress) = &self.catch_up_progress {
+                        (*catch_up_progress.0.lock().unwrap())(
+                            pending.total - pending.outdated.len(),
+                            pending.total,
+                        );
+                    }
+                    if pending.outdated.is_empty() {
+                        let catch_up_report = CatchUpReport {
+                            initial_delay,
+                            rng_seed,
+                            remaining: reading_handler.remaining_occurrences(),
+                            ..std::mem::take(&mut pending.report)
+                        };
+                        catch_up = None;
+                        observer.caught_up(&catch_up_report);
+                        self.catch_up_reports
+                            .insert(mode.to_owned(), catch_up_report);
+                    } else if self.cancellation_token.is_cancelled() {
+                        reading_handler.abandon_catch_up(pending);
+                        break;
+                    } else {
+                        let now = adjusted_now();
+                        let due = reading_handler
+                            .get_current_task()
+                            .is_some_and(|task| task.date <= now);
+                        // The next batch is caught up right away, unless a task already caught up is due
+                        if !due {
+                            continue;
+                        }
+                    }
+                }
+                reading_handler.avoid_blackouts();
+                if std::mem::take(&mut reading_handler.changed) {
+                    observer.replaced(
+                        reading_handler.current_tasks,
+                        &reading_handler.removed_tasks,
+                    );
+                }
+                // Told before the task is borrowed, the observer taking the removed tasks out of the mode
+                if let Some(task) = reading_handler.get_current_task() {
+                    let waiting = task
+                        .date
+                        .signed_duration_since(adjusted_now())
+                        .to_std()
+                        .unwrap_or_default();
+                    reading_handler.observe_removals(observer, observed_removals, Some(waiting));
+                    observed_removals = reading_handler.removed_tasks.len();
+                }
+                match reading_handler.get_current_task() {
+                    Some(task) => {
+                        let now = adjusted_now();
+                        // A task already due (dated like the previous one for instance) is triggered without sleeping
+                        let diff = (task.date - now).to_std().unwrap_or_default();
+                        let lateness = (now - task.date).to_std().unwrap_or_default();
+                        if let Some(next_pull) =
+                            next_pull.filter(|next_pull| *next_pull < task.date)
+                        {
+                            // The source is pulled again once its next task is within the look-ahead
+                            if sleep_until(SleepType::Native, next_pull, &sleep_token)
+                                == SleepOutcome::Cancelled
+                                && self.cancellation_token.is_cancelled()
+                            {
+                                break;
+                            }
+                            continue;
+                        }
+                        if let (Some((interval, heartbeat)), Some(heartbeat_date)) =
+                            (&self.heartbeat, next_heartbeat)
+                        {
+                            let now = Instant::now();
+                            if heartbeat_date <= now {
+                                (*heartbeat.0.lock().unwrap())();
+                                next_heartbeat = now.checked_add(*interval);
+                                continue;
+                            }
+                            let until_heartbeat = heartbeat_date - now;
+                            if until_heartbeat < diff {
+                                // The task is evaluated again after the heartbeat
+                                let heartbeat_target = Duration::from_std(until_heartbeat)
+                                    .map_or(task.date, |until_heartbeat| {
+                                        adjusted_now() + until_heartbeat
+                                    });
+                                if sleep_until(SleepType::Native, heartbeat_target, &sleep_token)
+                                    == SleepOutcome::Cancelled
+                                    && self.cancellation_token.is_cancelled()
+                                {
+                                    break;
+                                }
+                                continue;
+                            }
+                        }
+                        let sleep_type = task.sleep_type.adapt(self.wake_up_error);
+                        #[cfg(feature = "spin_sleep")]
+                        let sleep_type = sleep_type.resolve(spin_sleeper);
+                        let sleep_start = Instant::now();
+                        match sleep_until(sleep_type, task.date, &sleep_token) {
+                            SleepOutcome::ReachedTarget => {}
+                            SleepOutcome::Cancelled if self.cancellation_token.is_cancelled() => {
+                                break
+                            }
+                            // A command has been sent or the clock has jumped: the current task is evaluated again, it may
+                            // not be the first one anymore
+                            SleepOutcome::Cancelled | SleepOutcome::ClockJumped => continue,
+                        }
+                        if !task.is_gate_open(self.gates.get(mode)) {
+                            let held = reading_handler
+                                .hold_current_task(&adjusted_now(), config.gate_recheck_interval);
+                            self.gate_reports
+                                .entry(mode.to_owned())
+                                .or_default()
+                                .record(held.is_some());
+                            continue;
+                        }
+                        if reading_handler.already_completed(task) {
+                            if let Some(rescheduling) =
+                                reading_handler.reschedule_current_task(&adjusted_now(), None)
+                            {
+                                observer.rescheduled(&rescheduling);
+                            }
+                            continue;
+                        }
+                        // Only the gates and the ledger, which have to be seen as they are at the date of the
+                        // occurrence, are read between the wake-up and the callback: nothing is sorted, the lateness is
+                        // recorded once it has returned
+                        let woken_at = Instant::now();
+                        let started = reading_handler.start(task);
+                        if started {
+                            observer.starting(task);
+                        }
+                        let next_run = task.trigger(&mut f);
+                        reading_handler.complete(task);
+                        if started {
+                            observer.completed(task);
+                        }
+                        let latency = (woken_at - sleep_start).saturating_sub(diff) + lateness;
+                        // The wake-ups of the tasks already due don't tell anything about the accuracy of the sleep
+                        if !diff.is_zero() {
+                            self.wake_up_error.record(latency);
+                        }
+                        if config.realtime {
+                            self.latency_reports
+                                .entry(mode.to_owned())
+                                .or_default()
+                                .record(latency);
+                        }
+                        reading_handler.report(OccurrenceOutcome::Fired, task, task.date);
+                        completed = self.cancellation_token.is_cancelled();
+                        let now = adjusted_now();
+                        match reading_handler.lagging_occurrences(&now) {
+                            Some((first_missed, missed)) => {
+                                let lag_report =
+                                    self.lag_reports.entry(mode.to_owned()).or_default();
+                                lag_report.lag = now - first_missed;
+                                lag_report.consecutive += 1;
+                                lag_report.missed += missed;
+                                reading_handler.report(
+                                    OccurrenceOutcome::Skipped { missed },
+                                    task,
+                                    first_missed,
+                                );
+                            }
+                            None => {
+                                if let Some(lag_report) = self.lag_reports.get_mut(mode) {
+                                    lag_report.lag = Duration::zero();
+                                    lag_report.consecutive = 0;
+                                }
+                            }
+                        }
+                        observer.fired(
+                            task,
+                            &now,
+                            next_run.as_ref(),
+                            &self.custom_repetition,
+                            self.holidays.as_ref(),
+                            reading_handler.blackouts.as_ref(),
+                        );
+                        if let Some(rescheduling) =
+                            reading_handler.reschedule_current_task(&now, next_run.as_ref())
+                        {
+                            observer.rescheduled(&rescheduling);
+                        }
+                    }
+                    None => {
+                        completed = true;
+                    }
+                }
+            }
+        }));
+        if let Some(mut pending) = catch_up {
+            reading_handler.abandon_catch_up(&mut pending);
+        }
+        if reading_handler.changed {
+            observer.replaced(
+                reading_handler.current_tasks,
+                &reading_handler.removed_tasks,
+            );
+        }
+        self.command_senders.lock().unwrap().remove(mode);
+        wake.release();
+        reading_handler.observe_removals(observer, observed_removals, None);
+        let mut removed_tasks = reading_handler.removed_tasks;
+        self.store_removed_tasks(mode, &mut removed_tasks);
+        if let Err(panic) = run {
+            panic::resume_unwind(panic);
+        }
+        stalled.map_or(Ok(()), Err)
+    }
+
+    // Same as start, reporting through on_task_overrun the callbacks lasting longer than SchedulerConfig::expected_max_runtime,
+    // then again every SchedulerConfig::overrun_interval until they return. The callbacks aren't interrupted.
+    // The reports are made from a thread watching the callback, with the time elapsed since it has been called.
+    pub fn start_watched(
+        &mut self,
+        mode: &str,
+        mut f: impl FnMut(&TaskType),
+        on_task_overrun: impl Fn(&TaskType, &str, std::time::Duration) + Sync,
+    ) -> Result<(), String>
+    where
+        TaskType: Sync,
+    {
+        let Some(expected_max_runtime) = self
+            .config
+            .expected_max_runtime
+            .and_then(|expected_max_runtime| expected_max_runtime.to_std().ok())
+        else {
+            return self.start(mode, f);
+        };
+        let overrun_interval = self
+            .config
+            .overrun_interval
+            .and_then(|overrun_interval| overrun_interval.to_std().ok())
+            .unwrap_or(expected_max_runtime)
+            .max(MIN_HEARTBEAT_INTERVAL);
+        let on_task_overrun = &on_task_overrun;
+        self.start(mode, |task| {
+            let started = Instant::now();
+            let returned = (Mutex::new(false), Condvar::new());
+            thread::scope(|scope| {
+                scope.spawn(|| {
+                    let (lock, condvar) = &returned;
+                    let mut wait = expected_max_runtime;
+                    loop {
+                        let (has_returned, _) = condvar
+                            .wait_timeout_while(lock.lock().unwrap(), wait, |has_returned| {
+                                !*has_returned
+                            })
+                            .unwrap();
+                        if *has_returned {
+                            break;
+                        }
+                        drop(has_returned);
+                        on_task_overrun(task, mode, started.elapsed());
+                        wait = overrun_interval;
+                    }
+                });
+                f(task);
+                *returned.0.lock().unwrap() = true;
+                returned.1.notify_one();
+            });
+        })
+    }
+
+    // Same as start, the callback being given the context of the occurrence along with a CancelToken (see
+    // TaskContext::cancel_token) so that a long callback can give up early. The token is cancelled once the scheduler
+    // is stopping, and once the callback has lasted SchedulerConfig::expected_max_runtime when set.
+    pub fn start_with_context(
+        &mut self,
+        mode: &str,
+        mut f: impl FnMut(&TaskContext<'_, TaskType>),
+    ) -> Result<(), String> {
+        let expected_max_runtime = self
+            .config
+            .expected_max_runtime
+            .and_then(|expected_max_runtime| expected_max_runtime.to_std().ok());
+        let cancellation_token = self.cancellation_token.clone();
+        let occurrence_ledger = self.occurrence_ledger.clone();
+        self.run(
+            mode,
+            |task| {
+                let cancel_token = CancelToken::new();
+                cancellation_token.link(&cancel_token);
+                let context = TaskContext {
+                    mode,
+                    task: &task.task,
+                    date: task.date,
+                    cancel_token: Some(&cancel_token),
+                    execution: task.id.and_then(|id| {
+                        occurrence_ledger.execution(id, task.held_since().unwrap_or(task.date))
+                    }),
+                    is_backfill: false,
+                };
+                match expected_max_runtime {
+                    Some(expected_max_runtime) => {
+                        let returned = (Mutex::new(false), Condvar::new());
+                        thread::scope(|scope| {
+                            scope.spawn(|| {
+                                let (lock, condvar) = &returned;
+                                let (has_returned, _) = condvar
+                                    .wait_timeout_while(
+                                        lock.lock().unwrap(),
+                                        expected_max_runtime,
+                                        |has_returned| !*has_returned,
+                                    )
+                                    .unwrap();
+                                if !*has_returned {
+                                    cancel_token.cancel();
+                                }
+                            });
+                            f(&context);
+                            *returned.0.lock().unwrap() = true;
+                            returned.1.notify_one();
+                        });
+                    }
+                    None => f(&context),
+                }
+                cancellation_token.unlink(&cancel_token);
+                None
+            },
+            &mut (),
+        )
+    }
+
+    // Same as start, writing to the journal one JSON line per event of the mode, see the journal module
+    #[cfg(feature = "journal")]
+    pub fn start_with_journal(
+        &mut self,
+        mode: &str,
+        mut f: impl FnMut(&TaskType),
+        journal: impl std::io::Write,
+    ) -> Result<(), String>
+    where
+        TaskType: Serialize,
+    {
+        let mut journal_writer = JournalWriter::new(mode, journal);
+        self.run(
+            mode,
+            |task| {
+                f(&task.task);
+                None
+            },
+            &mut journal_writer,
+        )?;
+        journal_writer.finish()
+    }
+
+    // Starts a mode whose tasks hold keys, the payloads given to the callback being resolved when the tasks are triggered.
+    // The tasks whose key can't be resolved are removed beforehand (RemovalReason::Unresolvable), their keys are returned.
+    pub fn start_resolved<ResolvedType>(
+        &mut self,
+        mode: &str,
+        resolver: &impl TaskResolver<TaskType, ResolvedType>,
+        mut f: impl FnMut(&ResolvedType),
+    ) -> Result<Vec<TaskType>, String>
+    where
+        TaskType: Clone,
+    {
+        let tasks = SchedulerError::mode_mut(&mut self.scheduled_tasks, mode)?;
+        let (resolvable_tasks, mut unresolvable_tasks): (Vec<_>, Vec<_>) = tasks
+            .drain(..)
+            .partition(|task| resolver.resolve(&task.task).is_some());
+        *tasks = resolvable_tasks;
+        let unresolvable_keys = unresolvable_tasks
+            .iter_mut()
+            .map(|task| {
+                task.removal_reason = Some(RemovalReason::Unresolvable);
+                task.task.clone()
+            })
+            .collect();
+        self.store_removed_tasks(mode, &mut unresolvable_tasks);
+        self.start(mode, |key| {
+            if let Some(task) = resolver.resolve(key) {
+                f(&task)
+            }
+        })?;
+        Ok(unresolvable_keys)
+    }
+
+    // Runs the catch-up pass of start as if the current time was now, without any initial delay
+    pub fn catch_up(
+        &mut self,
+        mode: &str,
+        now: impl Into<DateTime<FixedOffset>>,
+        mut f: impl FnMut(&TaskType),
+    ) -> Result<CatchUpReport, String> {
+        self.catch_up_scheduled(mode, now.into(), |task| f(&task.task))
+    }
+    // Same as catch_up, the scheduled tasks being given to f
+    pub(crate) fn catch_up_scheduled(
+        &mut self,
+        mode: &str,
+        now: DateTime<FixedOffset>,
+        f: impl FnMut(&ScheduledTask<TaskType>),
+    ) -> Result<CatchUpReport, String> {
+        let mut reading_handler = SchedulerReadingHandler::new(
+            SchedulerError::mode_mut(&mut self.scheduled_tasks, mode)?,
+            self.custom_repetition.clone(),
+            self.holidays.clone(),
+            self.config.for_mode(mode).blackouts(),
+            self.occurrence_hook
+                .map(|occurrence_hook| (mode, occurrence_hook)),
+            self.task_ids.clone(),
+        );
+        reading_handler.occurrence_ledger = self
+            .occurrence_ledger
+            .enabled(self.config.occurrence_ledger);
+        let config = self.config.for_mode(mode);
+        let caught_up = reading_handler.catch_up_outdated_tasks(&now, &config, f);
+        let mut removed_tasks = reading_handler.removed_tasks;
+        self.store_removed_tasks(mode, &mut removed_tasks);
+        let catch_up_report = caught_up
+            .map_err(|error| format!("Couldn't catch up the mode {} : {}", mode, error))?;
+        self.catch_up_reports
+            .insert(mode.to_owned(), catch_up_report.clone());
+        Ok(catch_up_report)
+    }
+
+    // Catch-up pass of catch_up done on a copy of the tasks of the mode, without the occurrence hook, see the plan module
+    pub(crate) fn simulate_catch_up(
+        &self,
+        mode: &str,
+        now: &DateTime<FixedOffset>,
+    ) -> Result<CatchUpSimulation<TaskType>, String>
+    where
+        TaskType: Clone,
+    {
+        let mut tasks = self
+            .scheduled_tasks
+            .get(mode)
+            .ok_or_else(|| self.unknown_mode(mode).to_string())?
+            .clone();
+        let mut reading_handler = SchedulerReadingHandler::new(
+            &mut tasks,
+            self.custom_repetition.clone(),
+            self.holidays.clone(),
+            self.config.for_mode(mode).blackouts(),
+            None,
+            self.task_ids.clone(),
+        );
+        let mut fired = Vec::new();
+        let mut pending = reading_handler.begin_catch_up(now);
+        let caught_up = reading_handler.catch_up_batch(
+            &mut pending,
+            usize::MAX,
+            &self.config.for_mode(mode),
+            |task| fired.push(task.clone()),
+        );
+        caught_up.map_err(|error| format!("Couldn't catch up the mode {} : {}", mode, error))?;
+        let removed_tasks = reading_handler.removed_tasks;
+        Ok(CatchUpSimulation {
+            fired,
+            tasks,
+            removed_tasks,
+        })
+    }
+
+    // Triggers, without sleeping, every task of the mode due at now (ie dated before or at now) and reschedules them after now.
+    // The returned value is the number of triggered tasks. Nothing is caught up: a task late by several occurrences is triggered once.
+    pub fn tick(
+        &mut self,
+        mode: &str,
+        now: impl Into<DateTime<FixedOffset>>,
+        mut f: impl FnMut(&TaskType),
+    ) -> Result<usize, String> {
+        self.tick_scheduled(mode, now.into(), |task| f(&task.task))
+    }
+    // Same as tick, the scheduled tasks being given to f
+    pub(crate) fn tick_scheduled(
+        &mut self,
+        mode: &str,
+        now: DateTime<FixedOffset>,
+        mut f: impl FnMut(&ScheduledTask<TaskType>),
+    ) -> Result<usize, String> {
+        let mut reading_handler = SchedulerReadingHandler::new(
+            SchedulerError::mode_mut(&mut self.scheduled_tasks, mode)?,
+            self.custom_repetition.clone(),
+            self.holidays.clone(),
+            self.config.for_mode(mode).blackouts(),
+            self.occurrence_hook
+                .map(|occurrence_hook| (mode, occurrence_hook)),
+            self.task_ids.clone(),
+        );
+        reading_handler.occurrence_ledger = self
+            .occurrence_ledger
+            .enabled(self.config.occurrence_ledger);
+        SchedulerHelper::expire_pending_deletions(
+            self.pending_deletions.get_mut(mode),
+            &mut reading_handler.removed_tasks,
+            &now,
+            reading_handler.occurrence_hook,
+        );
+        let source = self.task_sources.get(mode);
+        let pull = |reading_handler: &mut SchedulerReadingHandler<_, _>| {
+            if let Some(source) = source {
+                let mode_is_empty = reading_handler.get_current_task().is_none();
+                source.lock().unwrap().pull(&now, mode_is_empty, |task| {
+                    reading_handler.apply_command(SchedulerCommand::AddTask(task))
+                });
+            }
+        };
+        pull(&mut reading_handler);
+        reading_handler.remove_exhausted_tasks();
+        let mut triggered = 0;
+        reading_handler.avoid_blackouts();
+        while let Some(task) = reading_handler.get_current_task() {
+            if now < task.date {
+                break;
+            }
+            if !task.is_gate_open(self.gates.get(mode)) {
+                let held =
+                    reading_handler.hold_current_task(&now, self.config.gate_recheck_interval);
+                self.gate_reports
+                    .entry(mode.to_owned())
+                    .or_default()
+                    .record(held.is_some());
+                reading_handler.avoid_blackouts();
+                continue;
+            }
+            if !reading_handler.already_completed(task) {
+                reading_handler.start(task);
+                task.trigger(&mut f);
+                reading_handler.complete(task);
+                reading_handler.report(OccurrenceOutcome::Fired, task, task.date);
+                triggered += 1;
+            }
+            reading_handler.reschedule_current_task(&now, None);
+            reading_handler.avoid_blackouts();
+        }
+        // The mode may have been emptied, its next task is taken from the source
+        pull(&mut reading_handler);
+        let mut removed_tasks = reading_handler.removed_tasks;
+        self.store_removed_tasks(mode, &mut removed_tasks);
+        Ok(triggered)
+    }
+
+    fn store_removed_tasks(
+        &mut self,
+        mode: &str,
+        removed_tasks: &mut Vec<ScheduledTask<TaskType>>,
+    ) {
+        // Self::normalize gives every mode its removed tasks, but the modes can be added by hand to scheduled_tasks
+        self.removed_tasks
+            .entry(mode.to_owned())
+            .or_default()
+            .append(removed_tasks);
+    }
+}
+
+// Outcome of ParallelScheduler::join_all
+#[derive(Debug)]
+pub struct JoinSummary {
+    pub results: Vec<Result<(), SchedulerError>>,
+    // True when the workers have been stopped by SIGINT/SIGTERM
+    pub signal_initiated: bool,
+    // With FailurePolicy::StopAll, the mode whose worker failed first and the modes stopped because of it
+    pub failed_mode: Option<String>,
+    pub cancelled_modes: Vec<String>,
+    // Outcome of the last worker of each mode joined, see ModeRunOutcome
+    pub outcomes: HashMap<String, ModeRunOutcome>,
+}
+
+/// What a worker of a ParallelScheduler has done before stopping
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct RunSummary {
+    // Callbacks called
+    pub fired: u64,
+    // Tasks removed from the mode
+    pub removed: usize,
+    pub elapsed: std::time::Duration,
+}
+
+/// Outcome of the worker of a mode, see JoinSummary::outcomes. The tasks left in the mode are moved back to the
+/// ParallelScheduler (see ParallelScheduler::scheduler), so that starting the mode again goes on from them: remaining
+/// gives their ids. A panicking callback ends its worker with an error, the mode being moved back all the same: the task
+/// whose callback panicked is left at the occurrence it was triggered for.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct ModeRunOutcome {
+    pub summary: RunSummary,
+    pub error: Option<SchedulerError>,
+    pub remaining: Vec<TaskId>,
+}
+
+impl ModeRunOutcome {
+    // Ids of the tasks left in the mode by the worker
+    pub(crate) fn remaining<TaskType, CustomRepetitionType>(
+        worker: &BlockingScheduler<TaskType, CustomRepetitionType>,
+        mode: &str,
+    ) -> Vec<TaskId> {
+        worker
+            .scheduled_tasks
+            .get(mode)
+            .into_iter()
+            .flatten()
+            .filter_map(ScheduledTask::id)
+            .collect()
+    }
+    // Error of a worker whose callback has panicked, with the message of the panic when it has one
+    pub(crate) fn panicked(mode: &str, panic: Box<dyn Any + Send>) -> String {
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        format!("The callback of the mode {} panicked : {}", mode, message)
+    }
+    // Result of a worker, its error being the reason of a SchedulerError::Failed
+    pub(crate) fn failed(mode: &str, result: Result<(), String>) -> Result<(), SchedulerError> {
+        result.map_err(|reason| SchedulerError::Failed {
+            mode: mode.to_owned(),
+            reason,
+        })
+    }
+}
+
+/// Tasks of a mode after a ParallelScheduler::handoff
+/// - carried : tasks handed over that took over the progress of a task of the mode
+/// - added : tasks handed over that didn't match any task of the mode
+/// - dropped : tasks of the mode that no task handed over matched
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct ModeHandoff<TaskType> {
+    pub carried: Vec<ScheduledTask<TaskType>>,
+    pub added: Vec<ScheduledTask<TaskType>>,
+    pub dropped: Vec<ScheduledTask<TaskType>>,
+}
+
+// Outcome of ParallelScheduler::handoff, for each mode handed over
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct HandoffReport<TaskType> {
+    pub modes: BTreeMap<String, ModeHandoff<TaskType>>,
+}
+
+/// Represents what a worker failure does to the other workers of a ParallelScheduler
+/// - Isolate : the other workers keep running
+/// - StopAll : the other workers are stopped, as if ParallelScheduler::stop had been called
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum FailurePolicy {
+    #[default]
+    Isolate,
+    StopAll,
+}
+
+/// When the workers of a ParallelScheduler hand the tasks they remove over to it, see ParallelScheduler::removed_tasks.
+/// A worker keeps them until it has removed `every_removals` of them, until the first one has waited `every`, or until
+/// it stops, whichever comes first.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct RemovalFlush {
+    pub every_removals: usize,
+    pub every: std::time::Duration,
+}
+
+impl Default for RemovalFlush {
+    fn default() -> Self {
+        Self {
+            every_removals: 64,
+            every: std::time::Duration::from_secs(1),
+        }
+    }
+}
+
+// Tasks removed by the workers of a ParallelScheduler, shared with them
+#[derive(Debug)]
+struct FlushedRemovals<TaskType> {
+    tasks: BTreeMap<String, Vec<ScheduledTask<TaskType>>>,
+    // Number of times a worker has handed tasks over
+    #[cfg(feature = "test-util")]
+    flushes: u64,
+}
+
+impl<TaskType> Default for FlushedRemovals<TaskType> {
+    fn default() -> Self {
+        Self {
+            tasks: BTreeMap::new(),
+            #[cfg(feature = "test-util")]
+            flushes: 0,
+        }
+    }
+}
+
+// Lock of the FlushedRemovals counting how many times it's been taken with the test-util feature, see
+// ParallelScheduler::removal_locks
+#[derive(Debug)]
+struct SharedRemovals<TaskType> {
+    removals: Mutex<FlushedRemovals<TaskType>>,
+    #[cfg(feature = "test-util")]
+    locks: AtomicU64,
+}
+
+impl<TaskType> Default for SharedRemovals<TaskType> {
+    fn default() -> Self {
+        Self {
+            removals: Mutex::default(),
+            #[cfg(feature = "test-util")]
+            locks: AtomicU64::new(0),
+        }
+    }
+}
+
+impl<TaskType> SharedRemovals<TaskType> {
+    fn lock(&self) -> MutexGuard<'_, FlushedRemovals<TaskType>> {
+        #[cfg(feature = "test-util")]
+        self.locks.fetch_add(1, AtomicOrdering::Relaxed);
+        self.removals.lock().unwrap()
+    }
+}
+
+// Observer of a worker keeping the tasks it removes until they're handed over, see RemovalFlush. It also keeps the
+// dates of the tasks of the mode up to date in the running dates.
+struct RemovalBuffer<TaskType> {
+    mode: String,
+    tasks: Vec<ScheduledTask<TaskType>>,
+    // Tasks removed since the worker has started, see RunSummary::removed
+    removed: usize,
+    // When the first task kept has been removed
+    oldest: Option<Instant>,
+    flush: RemovalFlush,
+    flushed: Arc<SharedRemovals<TaskType>>,
+    running_dates: RunningDates,
+}
+
+// Task of a mode held by a worker, as seen by ParallelScheduler::next_fire, has_due and due_count
+#[derive(Clone, Copy, Debug)]
+struct RunningDate {
+    id: Option<TaskId>,
+    date: DateTime<FixedOffset>,
+    // Count of 0, never triggered
+    exhausted: bool,
+}
+
+impl RunningDate {
+    fn of_tasks<TaskType>(tasks: &[ScheduledTask<TaskType>]) -> Vec<Self> {
+        tasks
+            .iter()
+            .map(|task| Self {
+                id: task.id,
+                date: task.date,
+                exhausted: task.repetition.is_exhausted(),
+            })
+            .collect()
+    }
+}
+
+// Dates of the tasks of the modes held by the workers, sorted in each mode, until they're given back
+type RunningDates = Arc<Mutex<BTreeMap<String, Vec<RunningDate>>>>;
+
+impl<TaskType> RemovalBuffer<TaskType> {
+    fn flush(&mut self) {
+        if self.tasks.is_empty() {
+            return;
+        }
+        let mut flushed = self.flushed.lock();
+        #[cfg(feature = "test-util")]
+        {
+            flushed.flushes += 1;
+        }
+        flushed
+            .tasks
+            .entry(self.mode.clone())
+            .or_default()
+            .append(&mut self.tasks);
+        self.oldest = None;
+    }
+}
+
+// The removed tasks are moved to the buffer, so that each payload ends up in a single place
+impl<TaskType> RunObserver<TaskType> for RemovalBuffer<TaskType> {
+    fn replaced(
+        &mut self,
+        tasks: &[ScheduledTask<TaskType>],
+        _removed_tasks: &[ScheduledTask<TaskType>],
+    ) {
+        self.running_dates
+            .lock()
+            .unwrap()
+            .insert(self.mode.clone(), RunningDate::of_tasks(tasks));
+    }
+    fn rescheduled(&mut self, (id, next_date, repetition): &Rescheduling) {
+        let mut running_dates = self.running_dates.lock().unwrap();
+        let Some(dates) = running_dates.get_mut(&self.mode) else {
+            return;
+        };
+        // The triggered task, the first one when it has no id
+        let Some(index) = dates
+            .iter()
+            .position(|date| date.id == *id)
+            .or((!dates.is_empty()).then_some(0))
+        else {
+            return;
+        };
+        match next_date {
+            Ok(next_date) => {
+                dates[index].date = *next_date;
+                dates[index].exhausted = repetition.is_exhausted();
+                dates.sort_by_key(|date| date.date);
+            }
+            Err(_) => {
+                dates.remove(index);
+            }
+        }
+    }
+    fn removed(
+        &mut self,
+        removed_tasks: &mut Vec<ScheduledTask<TaskType>>,
+        waiting: Option<std::time::Duration>,
+    ) {
+        if !removed_tasks.is_empty() {
+            self.removed += removed_tasks.len();
+            self.tasks.append(removed_tasks);
+            self.oldest.get_or_insert_with(Instant::now);
+        }
+        // Handed over before a wait that would keep them longer than the interval
+        let due = match (waiting, self.oldest) {
+            (Some(waiting), Some(oldest)) => {
+                self.tasks.len() >= self.flush.every_removals
+                    || oldest.elapsed() + waiting >= self.flush.every
+            }
+            _ => true,
+        };
+        if due {
+            self.flush();
+        }
+    }
+}
+
+// Outcome of the workers under FailurePolicy::StopAll, shared with them
+#[derive(Debug, Default)]
+pub(crate) struct FailureState {
+    pub(crate) failed_mode: Option<String>,
+    pub(crate) cancelled_modes: Vec<String>,
+}
+
+pub struct ParallelScheduler<'ps, TaskType, CustomRepetition = NoCustomRepetition> {
+    pub(crate) scheduler: BlockingScheduler<TaskType, CustomRepetition>,
+    // Workers started with Self::start, along with their mode. The finished ones are reaped by every start.
+    thread_handlers: Vec<(String, JoinHandle<Result<(), String>>)>,
+    // Results of the workers reaped by a start, until Self::reap_finished or Self::join_all hands them out
+    reaped: Vec<(String, Result<(), SchedulerError>)>,
+    pub scope_thread_handlers: Vec<ScopedJoinHandle<'ps, Result<(), String>>>,
+    #[cfg(feature = "signals")]
+    signal_received: Arc<AtomicBool>,
+    // Run by the worker of a mode before starting it, see Self::set_thread_setup
+    pub(crate) thread_setups: ThreadSetups,
+    // Applied to the workers started afterwards, see Self::set_failure_policy
+    pub(crate) failure_policy: FailurePolicy,
+    pub(crate) failure_state: Arc<Mutex<FailureState>>,
+    // Token of the workers started with Self::start until Self::join_all, child of the one of the scheduler: a failure
+    // under FailurePolicy::StopAll stops these workers without leaving the scheduler stopped
+    run_token: Option<CancellationToken>,
+    // Applied to the workers started afterwards, see Self::set_removal_flush
+    removal_flush: RemovalFlush,
+    flushed_removals: Arc<SharedRemovals<TaskType>>,
+    // Modes of the workers that have stopped, attached back by Self::reap_finished and Self::join_all
+    stopped_modes: Arc<Mutex<Vec<StoppedMode<TaskType, CustomRepetition>>>>,
+    // Outcomes of the workers joined or reaped, until Self::join_all hands them out
+    outcomes: HashMap<String, ModeRunOutcome>,
+    // Dates of the tasks of the modes held by the workers, see Self::next_fire
+    running_dates: RunningDates,
+}
+
+// Scheduler of a worker that has stopped, with what it has done
+type StoppedMode<TaskType, CustomRepetitionType> = (
+    String,
+    BlockingScheduler<TaskType, CustomRepetitionType>,
+    RunSummary,
+);
+
+// Setup of a worker thread (its priority for instance), an error doesn't prevent the worker from running
+pub type ThreadSetup = Arc<dyn Fn() -> Result<(), String> + Send + Sync>;
+
+// Thread setups of the modes, along with the error of the last run of each, shared with the workers
+#[derive(Clone, Default)]
+pub(crate) struct ThreadSetups {
+    setups: HashMap<String, ThreadSetup>,
+    errors: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl ThreadSetups {
+    // Runs the setup of the mode, if any, on the worker: its error is recorded, a success clears the previous one
+    pub(crate) fn worker(&self, mode: &str) -> impl FnOnce() + Send + 'static {
+        let thread_setup = self.setups.get(mode).cloned();
+        let errors = self.errors.clone();
+        let mode = mode.to_owned();
+        move || match thread_setup.map(|thread_setup| thread_setup()) {
+            Some(Err(error)) => {
+                errors.lock().unwrap().insert(mode, error);
+            }
+            Some(Ok(())) => {
+                errors.lock().unwrap().remove(&mode);
+            }
+            None => {}
+        }
+    }
 }
 impl<'ps, TaskType> ParallelScheduler<'ps, TaskType, NoCustomRepetition>
 where
     TaskType: Eq + Default,
 {
     pub fn new(
-        scheduled_tasks: HashMap<String, Vec<ScheduledTask<TaskType>>>,
-        removed_tasks: HashMap<String, Vec<ScheduledTask<TaskType>>>,
+        scheduled_tasks: impl IntoIterator<Item = (String, Vec<ScheduledTask<TaskType>>)>,
+        removed_tasks: impl IntoIterator<Item = (String, Vec<ScheduledTask<TaskType>>)>,
     ) -> Self {
+        Self::from(BlockingScheduler::new(scheduled_tasks, removed_tasks))
+    }
+}
+
+impl<'ps, TaskType, CustomRepetitionType> From<BlockingScheduler<TaskType, CustomRepetitionType>>
+    for ParallelScheduler<'ps, TaskType, CustomRepetitionType>
+{
+    fn from(scheduler: BlockingScheduler<TaskType, CustomRepetitionType>) -> Self {
         Self {
-            scheduler: BlockingScheduler::new(scheduled_tasks, removed_tasks),
+            scheduler,
             scope_thread_handlers: vec![],
             thread_handlers: vec![],
+            reaped: vec![],
+            #[cfg(feature = "signals")]
+            signal_received: Arc::new(AtomicBool::new(false)),
+            thread_setups: ThreadSetups::default(),
+            failure_policy: FailurePolicy::default(),
+            failure_state: Arc::default(),
+            run_token: None,
+            removal_flush: RemovalFlush::default(),
+            flushed_removals: Arc::default(),
+            stopped_modes: Arc::default(),
+            outcomes: HashMap::new(),
+            running_dates: Arc::default(),
+        }
+    }
+}
+
+impl<'ps, TaskType, CustomRepetitionType> ParallelScheduler<'ps, TaskType, CustomRepetitionType> {
+    // Stops every worker once its current callback is over
+    pub fn stop(&self) {
+        self.scheduler.cancellation_token.cancel();
+    }
+    // Scheduler the workers start from, holding the modes given back by Self::join_all and Self::scope. The modes held by
+    // the workers are missing from it (from its serialization too) until then, see Self::next_fire.
+    pub fn scheduler(&self) -> &BlockingScheduler<TaskType, CustomRepetitionType> {
+        &self.scheduler
+    }
+    /// Same as BlockingScheduler::next_fire, the modes held by the workers started with Self::start included: each
+    /// worker keeps the dates of its tasks up to date as it goes.
+    /// ```
+    /// use chrono::{Duration, Local};
+    /// use scheduler::prelude::*;
+    /// # scheduler::system_clock!(|| chrono::Local::now().into());
+    ///
+    /// let now = Local::now();
+    /// let (in_1_hour, in_2_hours) = (now + Duration::hours(1), now + Duration::hours(2));
+    /// let task = |date, task| ScheduledTask::new(date, task, RepetitionType::Once, SleepType::Native);
+    /// let mut parallel = ParallelScheduler::new(
+    ///     [("running".to_string(), vec![task(in_1_hour, 1), task(in_2_hours, 2)]), ("idle".to_string(), vec![task(in_2_hours, 3)])],
+    ///     [],
+    /// );
+    /// parallel.start("running".to_string(), |_| {}).unwrap();
+    /// assert_eq!(parallel.scheduler().next_fire(), Some(in_2_hours.into()));
+    /// assert_eq!(parallel.next_fire(), Some(in_1_hour.into()));
+    /// assert_eq!(parallel.due_count(in_2_hours.into()), 3);
+    /// assert!(!parallel.has_due(now.into()));
+    /// parallel.stop();
+    /// parallel.join_all();
+    /// assert_eq!(parallel.next_fire(), Some(in_1_hour.into()));
+    /// ```
+    pub fn next_fire(&self) -> Option<DateTime<FixedOffset>> {
+        let running_dates = self.running_dates.lock().unwrap();
+        let running = running_dates
+            .values()
+            .filter_map(|dates| dates.iter().find(|date| !date.exhausted))
+            .map(|date| date.date);
+        running.chain(self.scheduler.next_fire()).min()
+    }
+    // Same as BlockingScheduler::has_due, the modes held by the workers included
+    pub fn has_due(&self, now: DateTime<FixedOffset>) -> bool {
+        self.running_due(now) > 0 || self.scheduler.has_due(now)
+    }
+    // Same as BlockingScheduler::due_count, the modes held by the workers included
+    pub fn due_count(&self, now: DateTime<FixedOffset>) -> usize {
+        self.running_due(now) + self.scheduler.due_count(now)
+    }
+    fn running_due(&self, now: DateTime<FixedOffset>) -> usize {
+        self.running_dates
+            .lock()
+            .unwrap()
+            .values()
+            .map(|dates| {
+                dates[..dates.partition_point(|date| date.date <= now)]
+                    .iter()
+                    .filter(|date| !date.exhausted)
+                    .count()
+            })
+            .sum()
+    }
+    pub fn is_stopped(&self) -> bool {
+        self.scheduler.cancellation_token.is_cancelled()
+    }
+    // The setup is run by the workers of the mode started afterwards (by Self::start, Self::start_scoped_thread or in
+    // Self::scope), before their scheduler starts.
+    // It's meant for thread-wide settings such as the priority, which this crate doesn't set by itself since
+    // the way to do it depends on the platform. A failing setup is recorded and the worker runs anyway.
+    pub fn set_thread_setup(&mut self, mode: &str, thread_setup: Option<ThreadSetup>) {
+        match thread_setup {
+            Some(thread_setup) => self
+                .thread_setups
+                .setups
+                .insert(mode.to_owned(), thread_setup),
+            None => self.thread_setups.setups.remove(mode),
+        };
+    }
+    // Applied to the workers started afterwards
+    pub fn set_failure_policy(&mut self, failure_policy: FailurePolicy) {
+        self.failure_policy = failure_policy;
+    }
+    // Applied to the workers started afterwards
+    pub fn set_removal_flush(&mut self, removal_flush: RemovalFlush) {
+        self.removal_flush = removal_flush;
+    }
+    // Error returned by the last thread setup of the mode, None once a setup has succeeded
+    pub fn thread_setup_error(&self, mode: &str) -> Option<String> {
+        self.thread_setups.errors.lock().unwrap().get(mode).cloned()
+    }
+
+    // See BlockingScheduler::set_custom_repetition, the workers already started are affected
+    pub fn set_custom_repetition(&self, custom_repetition: CustomRepetitionType) {
+        self.scheduler.custom_repetition.set(custom_repetition);
+    }
+
+    // Wires SIGINT and SIGTERM to the cancellation of all the workers.
+    // Only one handler can be installed per process.
+    #[cfg(feature = "signals")]
+    pub fn install_signal_shutdown(&mut self) -> Result<(), ctrlc::Error> {
+        let cancellation_token = self.scheduler.cancellation_token.clone();
+        let signal_received = self.signal_received.clone();
+        ctrlc::set_handler(move || {
+            signal_received.store(true, AtomicOrdering::SeqCst);
+            cancellation_token.cancel();
+        })
+    }
+
+    // Workers started with Self::start that haven't been joined or reaped yet
+    pub fn thread_handlers(&self) -> impl Iterator<Item = (&str, &JoinHandle<Result<(), String>>)> {
+        self.thread_handlers
+            .iter()
+            .map(|(mode, handler)| (mode.as_str(), handler))
+    }
+
+    // Joins the workers that are already over, without waiting for the others.
+    // The results of the workers reaped by the previous starts come first.
+    pub fn reap_finished(&mut self) -> Vec<(String, Result<(), SchedulerError>)> {
+        self.reap();
+        std::mem::take(&mut self.reaped)
+    }
+
+    /// Waits for every thread started with Self::start, including the ones already reaped. The tasks left by each
+    /// worker are back in the scheduler, see JoinSummary::outcomes.
+    /// ```
+    /// use chrono::{DateTime, Duration, FixedOffset, Local};
+    /// use scheduler::prelude::*;
+    /// # scheduler::system_clock!(|| chrono::Local::now().into());
+    ///
+    /// // Stuck on the same date until fixed, then over
+    /// #[derive(Clone)]
+    /// struct Retry(bool);
+    /// impl CustomRepetition for Retry {
+    ///     fn update_date(&self, _: &DateTime<FixedOffset>, date: &DateTime<FixedOffset>) -> Option<DateTime<FixedOffset>> {
+    ///         (!self.0).then_some(*date)
+    ///     }
+    /// }
+    ///
+    /// let now = Local::now();
+    /// let (soon, later) = (now + Duration::milliseconds(30), now + Duration::milliseconds(300));
+    /// let task = |date, task, repetition| ScheduledTask::new(date, task, repetition, SleepType::Native);
+    /// let mut parallel = ParallelScheduler::new_with_custom_repetition(
+    ///     [
+    ///         ("healthy".to_string(), vec![task(soon, "a", RepetitionType::Once), task(soon, "b", RepetitionType::Once)]),
+    ///         ("broken".to_string(), vec![task(now - Duration::hours(1), "retry", RepetitionType::Custom), task(later, "c", RepetitionType::Once)]),
+    ///     ],
+    ///     [],
+    ///     Retry(false),
+    /// );
+    /// parallel.start("healthy".to_string(), |_| {}).unwrap();
+    /// parallel.start("broken".to_string(), |_| {}).unwrap();
+    /// let summary = parallel.join_all();
+    /// assert_eq!(summary.outcomes["healthy"].summary.fired, 2);
+    /// assert_eq!(summary.outcomes["healthy"].error, None);
+    /// let broken = &summary.outcomes["broken"];
+    /// assert!(matches!(&broken.error, Some(SchedulerError::Failed { mode, .. }) if mode == "broken"));
+    /// // The tasks of the failed mode are back, its next start goes on from them
+    /// let left = &parallel.scheduler().scheduled_tasks["broken"];
+    /// assert_eq!(broken.remaining.len(), 2);
+    /// assert!(left.iter().all(|task| broken.remaining.contains(&task.id().unwrap())));
+    /// parallel.set_custom_repetition(Retry(true));
+    /// parallel.start("broken".to_string(), |_| {}).unwrap();
+    /// let summary = parallel.join_all();
+    /// assert_eq!(summary.outcomes["broken"].error, None);
+    /// assert_eq!(summary.outcomes["broken"].summary.fired, 1);
+    /// assert!(summary.outcomes["broken"].remaining.is_empty());
+    /// ```
+    pub fn join_all(&mut self) -> JoinSummary {
+        let joined: Vec<_> = self
+            .thread_handlers
+            .drain(..)
+            .map(|(mode, handler)| {
+                let result = ModeRunOutcome::failed(&mode, Self::join_worker(handler));
+                (mode, result)
+            })
+            .collect();
+        for (mode, result) in &joined {
+            self.record_error(mode, result);
+        }
+        let results = self
+            .reaped
+            .drain(..)
+            .chain(joined)
+            .map(|(_, result)| result)
+            .collect();
+        self.attach_stopped_modes();
+        let failure_state = std::mem::take(&mut *self.failure_state.lock().unwrap());
+        if let Some(run_token) = self.run_token.take() {
+            self.scheduler.cancellation_token.release_child(&run_token);
+        }
+        // Every worker has handed its removed tasks over before stopping
+        let flushed = std::mem::take(&mut self.flushed_removals.lock().tasks);
+        for (mode, mut tasks) in flushed {
+            self.scheduler
+                .removed_tasks
+                .entry(mode)
+                .or_default()
+                .append(&mut tasks);
+        }
+        JoinSummary {
+            results,
+            failed_mode: failure_state.failed_mode,
+            cancelled_modes: failure_state.cancelled_modes,
+            outcomes: std::mem::take(&mut self.outcomes),
+            #[cfg(feature = "signals")]
+            signal_initiated: self.signal_received.load(AtomicOrdering::SeqCst),
+            #[cfg(not(feature = "signals"))]
+            signal_initiated: false,
+        }
+    }
+
+    /// Removed tasks of every mode, the ones removed by the workers included. A worker hands its removed tasks over from
+    /// time to time (see RemovalFlush): while it runs, the tasks it has removed in the last RemovalFlush::every may be
+    /// missing. Once Self::join_all has returned, every removed task is there.
+    /// ```
+    /// use chrono::{Duration, Local};
+    /// use scheduler::prelude::*;
+    /// # scheduler::system_clock!(|| chrono::Local::now().into());
+    ///
+    /// let soon = Local::now() + Duration::milliseconds(20);
+    /// let tasks = (0..3).map(|task| ScheduledTask::new(soon, task, RepetitionType::Once, SleepType::Native));
+    /// let mut parallel = ParallelScheduler::new([("jobs".to_string(), tasks.collect())], []);
+    /// parallel.start("jobs".to_string(), |_| {}).unwrap();
+    /// parallel.join_all();
+    /// let removed_tasks = parallel.removed_tasks();
+    /// assert_eq!(removed_tasks["jobs"].len(), 3);
+    /// assert!(removed_tasks["jobs"].iter().all(|task| task.removal_reason == Some(RemovalReason::Finished)));
+    /// ```
+    pub fn removed_tasks(&self) -> BTreeMap<String, Vec<ScheduledTask<TaskType>>>
+    where
+        TaskType: Clone,
+    {
+        let mut removed_tasks = self.scheduler.removed_tasks.clone();
+        for (mode, tasks) in &self.flushed_removals.lock().tasks {
+            removed_tasks
+                .entry(mode.clone())
+                .or_default()
+                .extend_from_slice(tasks);
+        }
+        removed_tasks
+    }
+    /// Takes the removed tasks of every mode out, the ones handed over by the workers so far included (see
+    /// Self::removed_tasks), without cloning them. Each removed task is given once: drained after Self::join_all, every
+    /// payload of a removed task is dropped by the caller alone, so that the resources it holds can be released there.
+    /// ```
+    /// use chrono::{Duration, Local};
+    /// use scheduler::prelude::*;
+    /// use std::sync::Mutex;
+    /// # scheduler::system_clock!(|| chrono::Local::now().into());
+    ///
+    /// static RELEASED: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+    ///
+    /// // Releases its reservation when dropped, 0 holding none
+    /// #[derive(PartialEq, Eq, Default, Debug)]
+    /// struct Reservation(u32);
+    /// impl Drop for Reservation {
+    ///     fn drop(&mut self) {
+    ///         if self.0 != 0 {
+    ///             RELEASED.lock().unwrap().push(self.0);
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let soon = Local::now() + Duration::milliseconds(20);
+    /// let mode = |first: u32| {
+    ///     let tasks = (first..first + 50).map(|id| ScheduledTask::new(soon, Reservation(id), RepetitionType::Once, SleepType::Native));
+    ///     tasks.collect()
+    /// };
+    /// let mut parallel = ParallelScheduler::new([("a".to_string(), mode(1)), ("b".to_string(), mode(51))], []);
+    /// parallel.start("a".to_string(), |_| {}).unwrap();
+    /// parallel.start("b".to_string(), |_| {}).unwrap();
+    /// // The tasks of a running mode are held by its worker
+    /// assert!(parallel.start("a".to_string(), |_| {}).is_err());
+    /// assert!(parallel.join_all().results.iter().all(Result::is_ok));
+    /// assert!(RELEASED.lock().unwrap().is_empty());
+    ///
+    /// let removed = parallel.drain_removed();
+    /// assert_eq!(removed["a"].len() + removed["b"].len(), 100);
+    /// assert!(parallel.drain_removed().is_empty());
+    /// drop(removed);
+    /// drop(parallel);
+    /// let mut released = RELEASED.lock().unwrap().clone();
+    /// released.sort();
+    /// assert_eq!(released, (1..=100).collect::<Vec<_>>());
+    /// ```
+    pub fn drain_removed(&mut self) -> BTreeMap<String, Vec<ScheduledTask<TaskType>>>
+    where
+        TaskType: Eq,
+    {
+        let mut drained = self.scheduler.drain_removed();
+        let flushed = std::mem::take(&mut self.flushed_removals.lock().tasks);
+        for (mode, mut tasks) in flushed {
+            drained.entry(mode).or_default().append(&mut tasks);
         }
+        drained
+    }
+    // Number of times the workers have handed their removed tasks over, see RemovalFlush. With the test-util feature,
+    // for the tests of the batching.
+    #[cfg(feature = "test-util")]
+    pub fn removal_flushes(&self) -> u64 {
+        self.flushed_removals.lock().flushes
+    }
+    // Number of times the removed tasks handed over have been locked, by the workers and by the methods of this scheduler
+    // reading them (Self::removed_tasks, Self::removal_flushes...). Reading this count doesn't lock them. With the test-util
+    // feature, as Self::removal_flushes.
+    #[cfg(feature = "test-util")]
+    pub fn removal_locks(&self) -> u64 {
+        self.flushed_removals.locks.load(AtomicOrdering::Relaxed)
+    }
+
+    fn reap(&mut self) {
+        let (finished, running) = std::mem::take(&mut self.thread_handlers)
+            .into_iter()
+            .partition(|(_, handler)| handler.is_finished());
+        self.thread_handlers = running;
+        for (mode, handler) in finished {
+            let result = ModeRunOutcome::failed(&mode, Self::join_worker(handler));
+            self.record_error(&mode, &result);
+            self.reaped.push((mode, result));
+        }
+        self.attach_stopped_modes();
+    }
+    fn attach_stopped_modes(&mut self) {
+        let stopped_modes = std::mem::take(&mut *self.stopped_modes.lock().unwrap());
+        for (mode, worker, summary) in stopped_modes {
+            self.running_dates.lock().unwrap().remove(&mode);
+            let outcome = self.outcomes.entry(mode.clone()).or_default();
+            outcome.summary = summary;
+            outcome.remaining = ModeRunOutcome::remaining(&worker, &mode);
+            self.scheduler.attach_mode(worker);
+        }
+    }
+    fn record_error(&mut self, mode: &str, result: &Result<(), SchedulerError>) {
+        self.outcomes.entry(mode.to_owned()).or_default().error = result.clone().err();
+    }
+    fn join_worker(handler: JoinHandle<Result<(), String>>) -> Result<(), String> {
+        handler
+            .join()
+            .unwrap_or(Err("The scheduler thread panicked".to_string()))
     }
 }
 
@@ -327,32 +4251,158 @@ where
     CustomRepetitionType: CustomRepetition + Clone + Send + Sync,
 {
     pub fn new_with_custom_repetition(
-        scheduled_tasks: HashMap<String, Vec<ScheduledTask<TaskType>>>,
-        removed_tasks: HashMap<String, Vec<ScheduledTask<TaskType>>>,
+        scheduled_tasks: impl IntoIterator<Item = (String, Vec<ScheduledTask<TaskType>>)>,
+        removed_tasks: impl IntoIterator<Item = (String, Vec<ScheduledTask<TaskType>>)>,
         custom_repetition: CustomRepetitionType,
     ) -> Self {
-        Self {
-            scheduler: BlockingScheduler::new_with_custom_repetition(
-                scheduled_tasks,
-                removed_tasks,
-                custom_repetition,
-            ),
-            scope_thread_handlers: vec![],
-            thread_handlers: vec![],
+        Self::from(BlockingScheduler::new_with_custom_repetition(
+            scheduled_tasks,
+            removed_tasks,
+            custom_repetition,
+        ))
+    }
+
+    // Replaces the tasks of the modes given, running or not, without restarting the workers.
+    // A running worker swaps its tasks between two callbacks, so this waits for the callbacks in progress.
+    // The tasks equal to a task of the mode take over its progress (see SchedulerHelper::hand_off): a task due during
+    // the swap is triggered once, either before the swap or right after it. The modes not given are left untouched.
+    pub fn handoff(
+        &mut self,
+        new_tasks: impl IntoIterator<Item = (String, Vec<ScheduledTask<TaskType>>)>,
+    ) -> HandoffReport<TaskType> {
+        let mut report = HandoffReport::default();
+        for (mode, tasks) in new_tasks {
+            let (reply, outcome) = mpsc::channel();
+            let sender = self
+                .scheduler
+                .command_senders
+                .lock()
+                .unwrap()
+                .get(&mode)
+                .cloned();
+            let handed_off = sender
+                .and_then(|sender| {
+                    sender
+                        .send(SchedulerCommand::Handoff {
+                            tasks: tasks.clone(),
+                            reply,
+                        })
+                        .ok()
+                })
+                // The worker may have stopped before applying the handoff
+                .and_then(|_| outcome.recv().ok());
+            // Used by the workers started afterwards
+            let idle_outcome = SchedulerHelper::hand_off(
+                self.scheduler
+                    .scheduled_tasks
+                    .entry(mode.clone())
+                    .or_default(),
+                tasks.clone(),
+            );
+            let outcome = handed_off.unwrap_or(idle_outcome);
+            let mut mode_handoff = ModeHandoff {
+                dropped: outcome.dropped,
+                ..ModeHandoff::default()
+            };
+            for (task, carried) in tasks.into_iter().zip(outcome.carried) {
+                if carried {
+                    mode_handoff.carried.push(task);
+                } else {
+                    mode_handoff.added.push(task);
+                }
+            }
+            report.modes.insert(mode, mode_handoff);
         }
+        self.scheduler.normalize();
+        report
     }
+}
 
+// The payloads don't need to be Clone: each worker is given the tasks of its mode
+impl<'ps, TaskType, CustomRepetitionType> ParallelScheduler<'ps, TaskType, CustomRepetitionType>
+where
+    TaskType: Eq + Default + Send,
+    CustomRepetitionType: CustomRepetition + Clone + Send + Sync,
+{
+    // Starts the mode on a thread of its own, until it has no task left or the scheduler is stopped. The tasks of the
+    // mode are moved to the worker, never cloned, and moved back once it has stopped (see Self::join_all): the mode
+    // can't be started again meanwhile (SchedulerError::AlreadyRunning). The tasks the worker removes are handed over,
+    // see Self::removed_tasks.
     pub fn start(&mut self, mode: String, f: fn(&TaskType)) -> std::io::Result<()>
     where
         TaskType: 'static,
         CustomRepetitionType: 'static,
     {
-        let mut scheduler = self.scheduler.clone();
-        self.thread_handlers.push(
-            thread::Builder::new()
-                .name("ThreadScheduler".to_string())
-                .spawn(move || scheduler.start(&mode, f))?,
+        self.reap();
+        // The workers left after the reap are running
+        if self
+            .thread_handlers
+            .iter()
+            .any(|(started, _)| *started == mode)
+        {
+            return Err(std::io::Error::other(SchedulerError::AlreadyRunning {
+                mode,
+            }));
+        }
+        let mut scheduler = self
+            .scheduler
+            .detach_mode(&mode)
+            .map_err(std::io::Error::other)?;
+        let cancellation_token = &self.scheduler.cancellation_token;
+        scheduler.cancellation_token = self
+            .run_token
+            .get_or_insert_with(|| cancellation_token.child())
+            .clone();
+        let thread_setup = self.thread_setups.worker(&mode);
+        let worker_mode = mode.clone();
+        let failure_policy = self.failure_policy;
+        let failure_state = self.failure_state.clone();
+        let stopped_modes = self.stopped_modes.clone();
+        let mut removal_buffer = self.removal_buffer(&mode);
+        let tasks = scheduler.scheduled_tasks.get(&mode).map(Vec::as_slice);
+        self.running_dates.lock().unwrap().insert(
+            mode.clone(),
+            RunningDate::of_tasks(tasks.unwrap_or_default()),
         );
+        let handler = thread::Builder::new()
+            .name("ThreadScheduler".to_string())
+            .spawn(move || {
+                thread_setup();
+                let (result, summary) =
+                    Self::run_worker(&mut scheduler, &worker_mode, f, &mut removal_buffer);
+                if failure_policy == FailurePolicy::StopAll {
+                    let mut failure_state = failure_state.lock().unwrap();
+                    if result.is_err() && failure_state.failed_mode.is_none() {
+                        failure_state.failed_mode = Some(worker_mode.clone());
+                        scheduler.stop();
+                    } else if result.is_ok() && failure_state.failed_mode.is_some() {
+                        failure_state.cancelled_modes.push(worker_mode.clone());
+                    }
+                }
+                stopped_modes
+                    .lock()
+                    .unwrap()
+                    .push((worker_mode, scheduler, summary));
+                result
+            })?;
+        self.thread_handlers.push((mode, handler));
+        Ok(())
+    }
+    // Starts a worker for each mode matching the prefix, see BlockingScheduler::modes_matching
+    pub fn start_prefix(&mut self, prefix: &str, f: fn(&TaskType)) -> std::io::Result<()>
+    where
+        TaskType: 'static,
+        CustomRepetitionType: 'static,
+    {
+        let modes: Vec<String> = self
+            .scheduler
+            .modes_matching(prefix)
+            .into_iter()
+            .map(str::to_owned)
+            .collect();
+        for mode in modes {
+            self.start(mode, f)?;
+        }
         Ok(())
     }
     pub fn start_scoped_thread(&mut self, mode: String, f: fn(&TaskType)) -> std::io::Result<()>
@@ -360,10 +4410,60 @@ where
         TaskType: 'ps,
         CustomRepetitionType: 'ps,
     {
-        let mut scheduler = self.scheduler.clone();
+        let mut scheduler = self
+            .scheduler
+            .detach_mode(&mode)
+            .map_err(std::io::Error::other)?;
+        let thread_setup = self.thread_setups.worker(&mode);
+        let mut removal_buffer = self.removal_buffer(&mode);
         thread::scope(|scope| {
-            scope.spawn(move || scheduler.start(mode.as_str(), f));
+            scope.spawn(|| {
+                thread_setup();
+                Self::run_worker(&mut scheduler, &mode, f, &mut removal_buffer).0
+            });
         });
+        self.scheduler.attach_mode(scheduler);
         Ok(())
     }
+
+    // Starts the mode on the scheduler of a worker, the tasks it removes being handed over through the buffer
+    fn run_worker(
+        scheduler: &mut BlockingScheduler<TaskType, CustomRepetitionType>,
+        mode: &str,
+        f: fn(&TaskType),
+        removal_buffer: &mut RemovalBuffer<TaskType>,
+    ) -> (Result<(), String>, RunSummary) {
+        let started = Instant::now();
+        let mut fired = 0;
+        let run = panic::catch_unwind(AssertUnwindSafe(|| {
+            scheduler.run(
+                mode,
+                |task| {
+                    fired += 1;
+                    f(&task.task);
+                    None
+                },
+                removal_buffer,
+            )
+        }));
+        let result = run.unwrap_or_else(|panic| Err(ModeRunOutcome::panicked(mode, panic)));
+        removal_buffer.flush();
+        let summary = RunSummary {
+            fired,
+            removed: removal_buffer.removed,
+            elapsed: started.elapsed(),
+        };
+        (result, summary)
+    }
+    fn removal_buffer(&self, mode: &str) -> RemovalBuffer<TaskType> {
+        RemovalBuffer {
+            mode: mode.to_owned(),
+            tasks: Vec::new(),
+            removed: 0,
+            oldest: None,
+            flush: self.removal_flush,
+            flushed: self.flushed_removals.clone(),
+            running_dates: self.running_dates.clone(),
+        }
+    }
 }
@@ -0,0 +1,69 @@
+//! JSON Schema of a scheduler saved with serde, for the tools reading or writing the saved schedules (linters,
+//! editors...). BlockingScheduler::json_schema describes the form serde_json writes for a scheduler, the compact form
+//! (see the compact module) being left out. The fields that have a default can be omitted, as when loading.
+//!
+//! ```
+//! use chrono::{DateTime, Duration, NaiveTime};
+//! use schemars::JsonSchema;
+//! use scheduler::prelude::*;
+//! use serde::{Deserialize, Serialize};
+//! use std::collections::HashMap;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! #[derive(Serialize, Deserialize, JsonSchema, PartialEq, Eq, Default, Clone, Debug)]
+//! struct Job {
+//!     name: String,
+//!     retries: u8,
+//! }
+//!
+//! let at = |date| DateTime::parse_from_rfc3339(date).unwrap();
+//! let job = |name: &str| Job { name: name.to_string(), retries: 3 };
+//! let until = Termination { count: RepetitionCount::Infinite, until: Some(at("2024-06-01T00:00:00+02:00")) };
+//! let tasks = vec![
+//!     ScheduledTask::new(at("2024-01-01T09:00:00+01:00"), job("report"), RepetitionType::Weekly(until), SleepType::Native),
+//!     ScheduledTask::new(
+//!         at("2024-01-01T10:00:00+01:00"),
+//!         job("sync"),
+//!         RepetitionType::ConstGap { gap: Duration::hours(1), termination: RepetitionCount::Finished(5).into() },
+//!         SleepType::Auto { tolerance: std::time::Duration::from_millis(2) },
+//!     ),
+//!     ScheduledTask::new(at("2024-01-02T10:00:00+01:00"), job("cleanup"), RepetitionType::Once, SleepType::Native),
+//! ];
+//! let mut scheduler = BlockingScheduler::new(HashMap::from([("jobs".to_string(), tasks)]), HashMap::new());
+//! scheduler.set_name(Some("nightly".to_string()));
+//! scheduler.config.splay = Some(Duration::minutes(5));
+//! scheduler.config.blackout_windows = vec![(NaiveTime::from_hms_opt(1, 0, 0).unwrap(), NaiveTime::from_hms_opt(2, 0, 0).unwrap())];
+//! scheduler.config.modes.insert("jobs".to_string(), ModeConfig { max_catch_up: Some(2), ..ModeConfig::default() });
+//! scheduler.tick("jobs", at("2024-01-01T09:00:00+01:00"), |_| {}).unwrap();
+//! let cleanup = scheduler.scheduled_tasks["jobs"].iter().find(|task| task.task == job("cleanup")).unwrap().id().unwrap();
+//! scheduler.cancel_with_ttl("jobs", cleanup, Duration::days(1)).unwrap();
+//!
+//! let schema = serde_json::to_value(BlockingScheduler::<Job>::json_schema()).unwrap();
+//! let schema = jsonschema::JSONSchema::compile(&schema).unwrap();
+//! let saved = serde_json::to_value(&scheduler).unwrap();
+//! assert!(schema.is_valid(&saved));
+//!
+//! // A scheduler written by hand is checked before being loaded
+//! let mut edited = saved.clone();
+//! edited["scheduled_tasks"]["jobs"][0]["repetition"] = serde_json::json!({"Weekly": {"count": "Forever"}});
+//! assert!(!schema.is_valid(&edited));
+//! assert!(serde_json::from_value::<BlockingScheduler<Job>>(edited).is_err());
+//! ```
+use super::schedulers::BlockingScheduler;
+use schemars::{schema::RootSchema, schema_for, JsonSchema};
+
+impl<TaskType, CustomRepetitionType> BlockingScheduler<TaskType, CustomRepetitionType>
+where
+    TaskType: JsonSchema,
+    CustomRepetitionType: JsonSchema,
+{
+    // JSON Schema of the scheduler as serde writes it, see the schema module
+    pub fn json_schema() -> RootSchema {
+        schema_for!(Self)
+    }
+}
+
+// Keeps the default of a field out of the schema, for the defaults that change from a call to another (dates, epochs)
+pub(crate) fn varying_default<T>(_: &T) -> bool {
+    true
+}
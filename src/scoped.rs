@@ -0,0 +1,313 @@
+//! Parallel modes whose payloads borrow from the caller, neither 'static nor Clone (references into an arena for
+//! instance). ParallelScheduler::scope runs the closure with a ScopedWorkers starting each mode on a thread of its own,
+//! the mode being moved out of the scheduler for the time of the run. The workers are joined before scope returns,
+//! their modes (removed tasks and reports included) being given back to the scheduler, so that nothing borrowed
+//! outlives the scope.
+//!
+//! ```
+//! use chrono::{Duration, Local};
+//! use scheduler::prelude::*;
+//! use std::sync::Mutex;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! // Neither Clone nor 'static
+//! #[derive(PartialEq, Eq, Default, Debug)]
+//! struct Line<'a> {
+//!     text: &'a str,
+//! }
+//!
+//! let arena = String::from("first line\nsecond line");
+//! let soon = Local::now() + Duration::milliseconds(20);
+//! let task = |text| ScheduledTask::new(soon, Line { text }, RepetitionType::Once, SleepType::Native);
+//! let mut parallel = ParallelScheduler::new(
+//!     [
+//!         ("all".to_string(), arena.lines().map(task).collect()),
+//!         ("last".to_string(), arena.lines().skip(1).map(task).collect()),
+//!     ],
+//!     [],
+//! );
+//! let read = Mutex::new(Vec::new());
+//! let ((), summary) = parallel.scope(|workers| {
+//!     workers.start("all", |line| read.lock().unwrap().push(line.text)).unwrap();
+//!     workers.start("last", |line| read.lock().unwrap().push(line.text)).unwrap();
+//! });
+//! assert!(summary.results.iter().all(Result::is_ok));
+//! let mut read = read.into_inner().unwrap();
+//! read.sort();
+//! assert_eq!(read, ["first line", "second line", "second line"]);
+//! // The modes are back, with their removed tasks
+//! assert_eq!(parallel.scheduler().removed_tasks["all"].len(), 2);
+//! assert!(parallel.scheduler().scheduled_tasks["last"].is_empty());
+//! ```
+//!
+//! A worker can't take what it borrows out of the scope:
+//!
+//! ```compile_fail
+//! use chrono::Local;
+//! use scheduler::prelude::*;
+//!
+//! let mut parallel = ParallelScheduler::new(
+//!     [("once".to_string(), vec![ScheduledTask::new(Local::now(), "task", RepetitionType::Once, SleepType::Native)])],
+//!     [],
+//! );
+//! let mut escaped = None;
+//! parallel.scope(|workers| {
+//!     workers.start("once", |_| {}).unwrap();
+//!     escaped = Some(workers);
+//! });
+//! ```
+use super::error::SchedulerError;
+use super::repetitions::CustomRepetition;
+use super::schedulers::{
+    BlockingScheduler, FailurePolicy, FailureState, JoinSummary, ModeRunOutcome, ParallelScheduler,
+    RunSummary, ThreadSetups,
+};
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, Scope, ScopedJoinHandle};
+use std::time::Instant;
+
+// What a worker gives back once its mode has stopped
+type WorkerOutcome<TaskType, CustomRepetitionType> = (
+    BlockingScheduler<TaskType, CustomRepetitionType>,
+    Result<(), String>,
+    RunSummary,
+);
+
+/// Starts the modes of a ParallelScheduler inside ParallelScheduler::scope, see the scoped module
+pub struct ScopedWorkers<'scope, 'env, 'sw, TaskType, CustomRepetitionType> {
+    scope: &'scope Scope<'scope, 'env>,
+    scheduler: &'sw mut BlockingScheduler<TaskType, CustomRepetitionType>,
+    failure_policy: FailurePolicy,
+    failure_state: Arc<Mutex<FailureState>>,
+    thread_setups: ThreadSetups,
+    workers: Vec<(
+        String,
+        ScopedJoinHandle<'scope, WorkerOutcome<TaskType, CustomRepetitionType>>,
+    )>,
+}
+
+impl<'scope, 'env, 'sw, TaskType, CustomRepetitionType>
+    ScopedWorkers<'scope, 'env, 'sw, TaskType, CustomRepetitionType>
+where
+    TaskType: Eq + Default + Send + 'scope,
+    CustomRepetitionType: CustomRepetition + Clone + Send + Sync + 'scope,
+{
+    // Starts the mode on a thread of its own, until it has no task left or the scheduler is stopped, after the thread
+    // setup of the mode (see ParallelScheduler::set_thread_setup). Fails when the mode doesn't exist or is already started
+    // in the scope.
+    pub fn start(
+        &mut self,
+        mode: &str,
+        mut f: impl FnMut(&TaskType) + Send + 'scope,
+    ) -> Result<(), String> {
+        if self.workers.iter().any(|(started, _)| started == mode) {
+            return Err(SchedulerError::AlreadyRunning {
+                mode: mode.to_owned(),
+            }
+            .to_string());
+        }
+        let mut worker = self.scheduler.detach_mode(mode)?;
+        let worker_mode = mode.to_owned();
+        let failure_policy = self.failure_policy;
+        let failure_state = self.failure_state.clone();
+        let thread_setup = self.thread_setups.worker(mode);
+        let handler = thread::Builder::new()
+            .name("ThreadScheduler".to_string())
+            .spawn_scoped(self.scope, move || {
+                thread_setup();
+                let started = Instant::now();
+                let removed = |worker: &BlockingScheduler<_, _>| {
+                    worker.removed_tasks.get(&worker_mode).map_or(0, Vec::len)
+                };
+                let removed_before = removed(&worker);
+                let mut fired = 0;
+                let run = panic::catch_unwind(AssertUnwindSafe(|| {
+                    worker.run(
+                        &worker_mode,
+                        |task| {
+                            fired += 1;
+                            f(&task.task);
+                            None
+                        },
+                        &mut (),
+                    )
+                }));
+                let result =
+                    run.unwrap_or_else(|panic| Err(ModeRunOutcome::panicked(&worker_mode, panic)));
+                let summary = RunSummary {
+                    fired,
+                    removed: removed(&worker) - removed_before,
+                    elapsed: started.elapsed(),
+                };
+                // Same as the workers of ParallelScheduler::start
+                if failure_policy == FailurePolicy::StopAll {
+                    let mut failure_state = failure_state.lock().unwrap();
+                    if result.is_err() && failure_state.failed_mode.is_none() {
+                        failure_state.failed_mode = Some(worker_mode.clone());
+                        worker.stop();
+                    } else if result.is_ok() && failure_state.failed_mode.is_some() {
+                        failure_state.cancelled_modes.push(worker_mode.clone());
+                    }
+                }
+                (worker, result, summary)
+            })
+            .map_err(|error| format!("Couldn't start the mode {} : {}", mode, error))?;
+        self.workers.push((mode.to_owned(), handler));
+        Ok(())
+    }
+
+    // Stops every worker of the scope once its current callback is over
+    pub fn stop(&self) {
+        self.scheduler.stop();
+    }
+}
+
+impl<'ps, TaskType, CustomRepetitionType> ParallelScheduler<'ps, TaskType, CustomRepetitionType>
+where
+    TaskType: Eq + Default + Send,
+    CustomRepetitionType: CustomRepetition + Clone + Send + Sync,
+{
+    // Runs f with the workers of a scope, then waits for them and takes their modes back, see the scoped module.
+    // The workers follow the FailurePolicy of the scheduler. A panicking callback ends its worker with an error, its mode
+    // being taken back as the others.
+    pub fn scope<'env, R>(
+        &mut self,
+        f: impl for<'scope, 'sw> FnOnce(
+            &mut ScopedWorkers<'scope, 'env, 'sw, TaskType, CustomRepetitionType>,
+        ) -> R,
+    ) -> (R, JoinSummary) {
+        // A failure under FailurePolicy::StopAll stops the workers of this scope alone, the scheduler isn't left stopped
+        let run_token = self.scheduler.cancellation_token.child();
+        let cancellation_token =
+            std::mem::replace(&mut self.scheduler.cancellation_token, run_token.clone());
+        let scoped = thread::scope(|scope| {
+            let mut workers = ScopedWorkers {
+                scope,
+                scheduler: &mut self.scheduler,
+                failure_policy: self.failure_policy,
+                failure_state: self.failure_state.clone(),
+                thread_setups: self.thread_setups.clone(),
+                workers: Vec::new(),
+            };
+            let returned = f(&mut workers);
+            let mut results = Vec::new();
+            let mut outcomes = HashMap::new();
+            for (mode, worker) in workers.workers {
+                let mut outcome = ModeRunOutcome::default();
+                let result = match worker.join() {
+                    Ok((worker, result, summary)) => {
+                        outcome.summary = summary;
+                        outcome.remaining = ModeRunOutcome::remaining(&worker, &mode);
+                        workers.scheduler.attach_mode(worker);
+                        result
+                    }
+                    Err(_) => Err(format!(
+                        "The scheduler thread of the mode {} panicked",
+                        mode
+                    )),
+                };
+                let result = ModeRunOutcome::failed(&mode, result);
+                outcome.error = result.clone().err();
+                results.push(result);
+                outcomes.insert(mode, outcome);
+            }
+            let failure_state = std::mem::take(&mut *workers.failure_state.lock().unwrap());
+            (
+                returned,
+                JoinSummary {
+                    results,
+                    signal_initiated: false,
+                    failed_mode: failure_state.failed_mode,
+                    cancelled_modes: failure_state.cancelled_modes,
+                    outcomes,
+                },
+            )
+        });
+        self.scheduler.cancellation_token = cancellation_token;
+        self.scheduler.cancellation_token.release_child(&run_token);
+        scoped
+    }
+
+    /// Runs every mode in a scope until they have all drained, f being called by each for its tasks. The first mode to
+    /// fail stops the others (FailurePolicy::StopAll, whatever the policy of the scheduler), its name and error being
+    /// returned once every worker has stopped. The workers are stopped through a token of their own: the scheduler isn't
+    /// left stopped by a failure, and can be started again. Stopping the scheduler stops the workers.
+    /// ```
+    /// use chrono::{DateTime, Duration, FixedOffset, Local};
+    /// use scheduler::prelude::*;
+    /// use std::sync::Mutex;
+    /// # scheduler::system_clock!(|| chrono::Local::now().into());
+    ///
+    /// // Never moves past its date: the catch-up pass of a mode dated in the past fails
+    /// #[derive(Clone)]
+    /// struct Stuck;
+    /// impl CustomRepetition for Stuck {
+    ///     fn update_date(&self, _: &DateTime<FixedOffset>, date: &DateTime<FixedOffset>) -> Option<DateTime<FixedOffset>> {
+    ///         Some(*date)
+    ///     }
+    /// }
+    ///
+    /// let task = |date, task, repetition| ScheduledTask::new(date, task, repetition, SleepType::Native);
+    /// let mut parallel = ParallelScheduler::new_with_custom_repetition(
+    ///     [
+    ///         ("poisoned".to_string(), vec![task(Local::now() - Duration::minutes(1), "stuck", RepetitionType::Custom)]),
+    ///         ("later".to_string(), vec![task(Local::now() + Duration::days(1), "tomorrow", RepetitionType::Once)]),
+    ///     ],
+    ///     [],
+    ///     Stuck,
+    /// );
+    /// let fired = Mutex::new(Vec::new());
+    /// let (mode, error) = parallel.run_scoped_try_all(|task| fired.lock().unwrap().push(*task)).unwrap_err();
+    /// assert_eq!(mode, "poisoned");
+    /// assert!(matches!(error, SchedulerError::Failed { mode, .. } if mode == "poisoned"));
+    /// assert!(fired.lock().unwrap().is_empty());
+    /// // The far-future task is still there
+    /// assert_eq!(parallel.scheduler().scheduled_tasks["later"].len(), 1);
+    /// // The failure only stopped the call
+    /// assert!(!parallel.is_stopped());
+    ///
+    /// let soon = Local::now() + Duration::milliseconds(20);
+    /// let mut parallel = ParallelScheduler::new(
+    ///     ["first", "second"].map(|mode| (mode.to_string(), vec![ScheduledTask::new(soon, mode, RepetitionType::Once, SleepType::Native)])),
+    ///     [],
+    /// );
+    /// let fired = Mutex::new(Vec::new());
+    /// parallel.run_scoped_try_all(|task| fired.lock().unwrap().push(*task)).unwrap();
+    /// assert_eq!(fired.into_inner().unwrap().len(), 2);
+    /// ```
+    pub fn run_scoped_try_all(
+        &mut self,
+        f: impl Fn(&TaskType) + Sync,
+    ) -> Result<(), (String, SchedulerError)> {
+        let failure_policy = std::mem::replace(&mut self.failure_policy, FailurePolicy::StopAll);
+        let modes: Vec<String> = self.scheduler.scheduled_tasks.keys().cloned().collect();
+        let f = &f;
+        let (started, summary) = self.scope(|workers| {
+            for mode in &modes {
+                if let Err(reason) = workers.start(mode, f) {
+                    workers.stop();
+                    return Err((
+                        mode.clone(),
+                        SchedulerError::Failed {
+                            mode: mode.clone(),
+                            reason,
+                        },
+                    ));
+                }
+            }
+            Ok(())
+        });
+        self.failure_policy = failure_policy;
+        started?;
+        let error = |mode: &String| summary.outcomes.get(mode)?.error.clone();
+        // The failed mode is unknown when its worker has panicked
+        let failed = summary
+            .failed_mode
+            .iter()
+            .chain(summary.outcomes.keys())
+            .find_map(|mode| Some((mode.clone(), error(mode)?)));
+        failed.map_or(Ok(()), Err)
+    }
+}
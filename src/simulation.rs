@@ -0,0 +1,189 @@
+//! Occurrences the modes of a scheduler would fire up to a date, computed without running anything, so that a schedule
+//! can be checked or plotted before it's started. BlockingScheduler::simulate follows the repetitions of the tasks from
+//! their current date, as ScheduledTask::occurrences does: the holidays, blackout windows and gates aren't applied.
+//! A Simulation is written as a timeline, in JSON (Simulation::to_timeline_json) or in CSV (Simulation::to_csv), one
+//! entry per occurrence labelled by the Display of its payload.
+//!
+//! ```
+//! use chrono::{DateTime, Duration};
+//! use scheduler::prelude::*;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let at = |date| DateTime::parse_from_rfc3339(date).unwrap();
+//! let hourly = RepetitionType::ConstGap { gap: Duration::hours(1), termination: Termination::default() };
+//! let backup = ScheduledTask::new(at("2024-01-01T09:00:00+01:00"), "backup", hourly, SleepType::Native);
+//! let digest = ScheduledTask::new(at("2024-01-01T09:30:00+01:00"), "digest", RepetitionType::Once, SleepType::Native);
+//! let scheduler = BlockingScheduler::new(
+//!     [("backups".to_string(), vec![backup]), ("mails".to_string(), vec![digest])],
+//!     [],
+//! );
+//! let simulation = scheduler.simulate(at("2024-01-01T10:00:00+01:00"));
+//! let occurrences: Vec<_> = simulation
+//!     .occurrences
+//!     .iter()
+//!     .map(|occurrence| (occurrence.task, occurrence.start, occurrence.occurrence_index))
+//!     .collect();
+//! assert_eq!(
+//!     occurrences,
+//!     [
+//!         ("backup", at("2024-01-01T09:00:00+01:00"), 0),
+//!         ("digest", at("2024-01-01T09:30:00+01:00"), 0),
+//!         ("backup", at("2024-01-01T10:00:00+01:00"), 1),
+//!     ]
+//! );
+//! // Nothing has been fired
+//! assert_eq!(scheduler.scheduled_tasks["backups"][0].date, at("2024-01-01T09:00:00+01:00"));
+//! ```
+use super::id::TaskId;
+use super::repetitions::{CustomRepetition, RepetitionType};
+use super::schedulers::BlockingScheduler;
+#[cfg(any(feature = "json", feature = "csv"))]
+use chrono::Duration;
+use chrono::{DateTime, FixedOffset};
+#[cfg(feature = "json")]
+use serde::Serialize;
+#[cfg(any(feature = "json", feature = "csv"))]
+use std::fmt::Display;
+
+/// Occurrence of a task planned by BlockingScheduler::simulate
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct SimulatedOccurrence<TaskType> {
+    pub mode: String,
+    pub id: Option<TaskId>,
+    pub task: TaskType,
+    pub start: DateTime<FixedOffset>,
+    pub repetition: RepetitionType,
+    // Rank of the occurrence among the ones of its task in the simulation, from 0
+    pub occurrence_index: u64,
+}
+
+/// Occurrences of the modes of a scheduler up to a date, see the simulation module
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Simulation<TaskType> {
+    // In date order, the occurrences of a same date following the order of the modes then the one of their tasks
+    pub occurrences: Vec<SimulatedOccurrence<TaskType>>,
+    pub until: DateTime<FixedOffset>,
+}
+
+// Entry of the timeline written by Simulation::to_timeline_json and Simulation::to_csv
+#[cfg(any(feature = "json", feature = "csv"))]
+#[cfg_attr(feature = "json", derive(Serialize))]
+struct TimelineEntry<'te> {
+    mode: &'te str,
+    label: String,
+    // RFC 3339
+    start: String,
+    repetition: String,
+    occurrence_index: u64,
+}
+
+#[cfg(any(feature = "json", feature = "csv"))]
+impl<TaskType: Display> Simulation<TaskType> {
+    fn timeline(&self) -> impl Iterator<Item = TimelineEntry<'_>> {
+        self.occurrences.iter().map(|occurrence| TimelineEntry {
+            mode: &occurrence.mode,
+            label: occurrence.task.to_string(),
+            start: occurrence.start.to_rfc3339(),
+            repetition: repetition_label(&occurrence.repetition),
+            occurrence_index: occurrence.occurrence_index,
+        })
+    }
+
+    // Array of the occurrences as { mode, label, start, repetition, occurrence_index } objects, one per line
+    #[cfg(feature = "json")]
+    pub fn to_timeline_json(&self) -> String {
+        let entries = self.timeline().collect::<Vec<_>>();
+        // Strings and integers only, they always serialize
+        serde_json::to_string_pretty(&entries).unwrap_or_default()
+    }
+
+    // Same entries as Self::to_timeline_json, as CSV rows after a header naming the columns
+    #[cfg(feature = "csv")]
+    pub fn to_csv(&self) -> String {
+        let mut writer = ::csv::Writer::from_writer(Vec::new());
+        let header = ["mode", "label", "start", "repetition", "occurrence_index"];
+        // Written to memory, the rows can't fail
+        let _ = writer.write_record(header);
+        for entry in self.timeline() {
+            let _ = writer.write_record([
+                entry.mode,
+                &entry.label,
+                &entry.start,
+                &entry.repetition,
+                &entry.occurrence_index.to_string(),
+            ]);
+        }
+        writer
+            .into_inner()
+            .ok()
+            .and_then(|written| String::from_utf8(written).ok())
+            .unwrap_or_default()
+    }
+}
+
+// Human form of the repetition (see the FromStr impl of RepetitionType) without its termination, "iso-weekly" for an
+// IsoWeekly one which has none
+#[cfg(any(feature = "json", feature = "csv"))]
+fn repetition_label(repetition: &RepetitionType) -> String {
+    match repetition {
+        RepetitionType::Once => "once".to_string(),
+        RepetitionType::Weekly(_) => "weekly".to_string(),
+        RepetitionType::Monthly { .. } => "monthly".to_string(),
+        RepetitionType::Yearly(_) => "yearly".to_string(),
+        RepetitionType::ConstGap { gap, .. } => format!("every {}", gap_label(*gap)),
+        RepetitionType::Custom => "custom".to_string(),
+        RepetitionType::SelfScheduled(_) => "self-scheduled".to_string(),
+        RepetitionType::IsoWeekly { .. } => "iso-weekly".to_string(),
+    }
+}
+
+// In the largest unit dividing the gap
+#[cfg(any(feature = "json", feature = "csv"))]
+fn gap_label(gap: Duration) -> String {
+    let milliseconds = gap.num_milliseconds();
+    [
+        ("w", 604_800_000),
+        ("d", 86_400_000),
+        ("h", 3_600_000),
+        ("m", 60_000),
+        ("s", 1_000),
+    ]
+    .into_iter()
+    .find(|(_, unit)| milliseconds % unit == 0)
+    .map_or(format!("{}ms", milliseconds), |(name, unit)| {
+        format!("{}{}", milliseconds / unit, name)
+    })
+}
+
+impl<TaskType, CustomRepetitionType> BlockingScheduler<TaskType, CustomRepetitionType>
+where
+    TaskType: Eq + Default + Clone,
+    CustomRepetitionType: CustomRepetition + Clone,
+{
+    // Occurrences of the tasks of every mode dated until the given date included, see the simulation module
+    pub fn simulate(&self, until: impl Into<DateTime<FixedOffset>>) -> Simulation<TaskType> {
+        let until = until.into();
+        let custom_repetition = self.custom_repetition();
+        let mut occurrences = Vec::new();
+        for (mode, tasks) in &self.scheduled_tasks {
+            for task in tasks {
+                let dates = task
+                    .occurrences(&custom_repetition)
+                    .take_while(|date| *date <= until);
+                for (occurrence_index, start) in (0..).zip(dates) {
+                    occurrences.push(SimulatedOccurrence {
+                        mode: mode.clone(),
+                        id: task.id(),
+                        task: task.task.clone(),
+                        start,
+                        repetition: task.repetition.clone(),
+                        occurrence_index,
+                    });
+                }
+            }
+        }
+        // Stable, the ties keeping the order they've been pushed in
+        occurrences.sort_by_key(|occurrence| occurrence.start);
+        Simulation { occurrences, until }
+    }
+}
@@ -0,0 +1,346 @@
+//! How a started mode waits. Every wait of BlockingScheduler::start (the startup delay, the wait for the next task, for
+//! the next heartbeat or the next pull of a source) goes through a Sleeper, asked to sleep until a date read from a
+//! Clock. The built-in one is the SleepType of each task, the other waits being native, and BlockingScheduler::set_sleeper
+//! replaces it for every wait of the scheduler (to record the waits, to wait on another timer...).
+//!
+//! A sleeper returns early when the CancelToken it's given is cancelled: the scheduler has been stopped, or a command
+//! has been sent to the mode, which evaluates its tasks again. It returns ClockJumped when the clock has moved away from
+//! the time elapsed, so that the mode aims at its next task again.
+//!
+//! ```
+//! use chrono::{DateTime, Duration, FixedOffset, Local};
+//! use scheduler::prelude::*;
+//! use scheduler::sleeper::{Clock, SleepOutcome, Sleeper};
+//! use std::collections::HashMap;
+//! use std::sync::{Arc, Mutex};
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! // Sleeps natively, recording the targets
+//! #[derive(Clone, Default)]
+//! struct Recorder(Arc<Mutex<Vec<DateTime<FixedOffset>>>>);
+//!
+//! impl Sleeper for Recorder {
+//!     fn sleep_until(&self, target: DateTime<FixedOffset>, clock: &dyn Clock, cancel: &CancelToken) -> SleepOutcome {
+//!         self.0.lock().unwrap().push(target);
+//!         SleepType::Native.sleep_until(target, clock, cancel)
+//!     }
+//! }
+//!
+//! let soon = Local::now() + Duration::milliseconds(20);
+//! let tasks = vec![
+//!     ScheduledTask::new(soon, "first", RepetitionType::Once, SleepType::Native),
+//!     ScheduledTask::new(soon + Duration::milliseconds(10), "second", RepetitionType::Once, SleepType::Native),
+//! ];
+//! let mut scheduler = BlockingScheduler::new(HashMap::from([("jobs".to_string(), tasks)]), HashMap::new());
+//! let recorder = Recorder::default();
+//! scheduler.set_sleeper(recorder.clone());
+//! let mut fired = Vec::new();
+//! scheduler.start("jobs", |task| fired.push(*task)).unwrap();
+//! assert_eq!(fired, ["first", "second"]);
+//! // The startup delay (none here), then the tasks
+//! assert_eq!(recorder.0.lock().unwrap()[1..], [soon, soon + Duration::milliseconds(10)]);
+//! ```
+use super::cancellation::{CancelToken, CancellationToken};
+use super::sleeptype::{SleepType, MAX_SLEEP_SLICE};
+#[cfg(all(feature = "clock", not(feature = "no-ambient-time")))]
+use chrono::Local;
+use chrono::{DateTime, FixedOffset};
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Gap between the clock and the time elapsed above which the clock is considered to have jumped
+const CLOCK_JUMP_TOLERANCE: Duration = Duration::from_secs(1);
+
+/// Gives the current date to a Sleeper
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<FixedOffset>;
+}
+
+/// The system clock, through chrono::Local. Without the clock feature (chrono built without its clock) or with the
+/// no-ambient-time feature, the clock given to system_clock! by the binary, which doesn't link without it: nothing in
+/// the crate reads the time of the system then, a run only depends on the clock given.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[cfg(all(feature = "clock", not(feature = "no-ambient-time")))]
+    fn now(&self) -> DateTime<FixedOffset> {
+        Local::now().into()
+    }
+    #[cfg(any(not(feature = "clock"), feature = "no-ambient-time"))]
+    fn now(&self) -> DateTime<FixedOffset> {
+        extern "Rust" {
+            // Defined by system_clock!
+            fn __scheduler_system_clock_now() -> DateTime<FixedOffset>;
+        }
+        // SAFETY: system_clock! defines it with this signature, the link fails when it's missing or given twice
+        unsafe { __scheduler_system_clock_now() }
+    }
+}
+
+/// Gives the current date to the crate built without the clock feature or with the no-ambient-time feature, from a
+/// function (or a closure capturing nothing) returning a DateTime<FixedOffset>. Called once in the binary, at the root
+/// of a module or in a function: a binary without it doesn't link, so that the crate can't run without a clock. It
+/// expands to nothing when the crate reads the time of the system, the binary building the same with every feature.
+/// ```
+/// use chrono::{DateTime, FixedOffset};
+/// use scheduler::prelude::*;
+/// use scheduler::sleeper::ManualClock;
+/// use std::sync::LazyLock;
+///
+/// static CLOCK: LazyLock<ManualClock> =
+///     LazyLock::new(|| ManualClock::new(DateTime::parse_from_rfc3339("2024-01-01T08:00:00+01:00").unwrap()));
+/// // Without ambient time, the scheduler reads the date of CLOCK
+/// scheduler::system_clock!(|| CLOCK.now());
+///
+/// let date = DateTime::parse_from_rfc3339("2024-01-01T09:00:00+01:00").unwrap();
+/// let task = ScheduledTask::new(date, 0, RepetitionType::Once, SleepType::Native);
+/// let mut scheduler = BlockingScheduler::new([("m".to_string(), vec![task])], []);
+/// scheduler.tick("m", date, |_| {}).unwrap();
+/// assert!(scheduler.scheduled_tasks["m"].is_empty());
+/// ```
+#[macro_export]
+macro_rules! system_clock {
+    ($now:expr) => {
+        $crate::__system_clock!($now);
+    };
+}
+
+#[doc(hidden)]
+#[cfg(any(not(feature = "clock"), feature = "no-ambient-time"))]
+#[macro_export]
+macro_rules! __system_clock {
+    ($now:expr) => {
+        #[no_mangle]
+        fn __scheduler_system_clock_now() -> ::chrono::DateTime<::chrono::FixedOffset> {
+            let now: fn() -> ::chrono::DateTime<::chrono::FixedOffset> = $now;
+            now()
+        }
+    };
+}
+
+// The time of the system is read instead, the function is only checked
+#[doc(hidden)]
+#[cfg(all(feature = "clock", not(feature = "no-ambient-time")))]
+#[macro_export]
+macro_rules! __system_clock {
+    ($now:expr) => {
+        const _: fn() -> ::chrono::DateTime<::chrono::FixedOffset> = $now;
+    };
+}
+
+/// Clock that only moves when told to, to be read by system_clock! for a run fully determined by the dates it's set
+/// at. Clones share the same time.
+#[derive(Clone, Debug)]
+pub struct ManualClock(Arc<Mutex<DateTime<FixedOffset>>>);
+
+impl ManualClock {
+    pub fn new(now: impl Into<DateTime<FixedOffset>>) -> Self {
+        Self(Arc::new(Mutex::new(now.into())))
+    }
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        *self.0.lock().unwrap()
+    }
+    pub fn set(&self, now: impl Into<DateTime<FixedOffset>>) {
+        *self.0.lock().unwrap() = now.into();
+    }
+    pub fn advance(&self, duration: chrono::Duration) {
+        let mut now = self.0.lock().unwrap();
+        *now += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<FixedOffset> {
+        ManualClock::now(self)
+    }
+}
+
+/// Offset of a reference clock (the one of a server issuing the dates of the tasks for instance) from the system clock:
+/// reference time = system time + offset. Set with SchedulerCommand::SetClockAdjustment or
+/// BlockingScheduler::set_clock_adjustment, a started mode then reads the current date on the reference clock.
+/// A reference clock behind the system one delays the tasks by as much, a clock ahead brings them forward.
+/// ```
+/// use chrono::{DateTime, Duration, FixedOffset, Local};
+/// use scheduler::prelude::*;
+/// use scheduler::sleeper::{Clock, ClockAdjustment, SleepOutcome, Sleeper};
+/// use std::collections::HashMap;
+/// use std::sync::{Arc, Mutex};
+/// # scheduler::system_clock!(|| chrono::Local::now().into());
+///
+/// // Records the targets without waiting, except for the wait on the task when `block` is set, which lasts until a
+/// // command wakes it up
+/// #[derive(Clone, Default)]
+/// struct Recorder {
+///     targets: Arc<Mutex<Vec<DateTime<FixedOffset>>>>,
+///     block: bool,
+/// }
+///
+/// impl Sleeper for Recorder {
+///     fn sleep_until(&self, target: DateTime<FixedOffset>, _: &dyn Clock, cancel: &CancelToken) -> SleepOutcome {
+///         let waits = {
+///             let mut targets = self.targets.lock().unwrap();
+///             targets.push(target);
+///             targets.len()
+///         };
+///         if self.block && waits == 2 {
+///             cancel.wait_timeout(std::time::Duration::from_secs(10));
+///         }
+///         if cancel.is_cancelled() { SleepOutcome::Cancelled } else { SleepOutcome::ReachedTarget }
+///     }
+/// }
+///
+/// let in_1_hour = Local::now() + Duration::hours(1);
+/// let scheduler = || {
+///     let task = ScheduledTask::new(in_1_hour, "sync", RepetitionType::Once, SleepType::Native);
+///     BlockingScheduler::new(HashMap::from([("jobs".to_string(), vec![task])]), HashMap::new())
+/// };
+/// let adjustment = |seconds| ClockAdjustment { offset: Duration::seconds(seconds), updated_at: Local::now().into() };
+///
+/// // The reference clock is 5 seconds behind: the task is triggered 5 seconds later on the system clock
+/// let mut delayed = scheduler();
+/// delayed.set_clock_adjustment(Some(adjustment(-5)));
+/// let recorder = Recorder::default();
+/// delayed.set_sleeper(recorder.clone());
+/// delayed.start("jobs", |_| {}).unwrap();
+/// assert_eq!(recorder.targets.lock().unwrap()[1..], [in_1_hour + Duration::seconds(5)]);
+///
+/// // Adjusted while the mode waits for the task: the wait is aimed again
+/// let mut adjusted = scheduler();
+/// let recorder = Recorder { block: true, ..Recorder::default() };
+/// adjusted.set_sleeper(recorder.clone());
+/// let controller = adjusted.controller();
+/// let targets = recorder.targets.clone();
+/// let adjuster = std::thread::spawn(move || {
+///     while targets.lock().unwrap().len() < 2 {
+///         std::thread::sleep(std::time::Duration::from_millis(1));
+///     }
+///     controller.set_clock_adjustment("jobs", adjustment(5)).unwrap();
+/// });
+/// adjusted.start("jobs", |_| {}).unwrap();
+/// adjuster.join().unwrap();
+/// assert_eq!(recorder.targets.lock().unwrap()[1..], [in_1_hour, in_1_hour - Duration::seconds(5)]);
+/// assert_eq!(adjusted.clock_adjustment().unwrap().offset, Duration::seconds(5));
+/// ```
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct ClockAdjustment {
+    pub offset: chrono::Duration,
+    // When the offset has been measured
+    pub updated_at: DateTime<FixedOffset>,
+}
+
+// Adjustment of a scheduler, shared with its clones
+pub(crate) type SharedClockAdjustment = Arc<Mutex<Option<ClockAdjustment>>>;
+
+/// Represents how a sleep ended
+/// - ReachedTarget : the clock has reached the target
+/// - Cancelled : the token has been cancelled before
+/// - ClockJumped : the clock has moved away from the time elapsed (changed by hand, synchronized...)
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum SleepOutcome {
+    ReachedTarget,
+    Cancelled,
+    ClockJumped,
+}
+
+/// Waits until a date, see the sleeper module
+pub trait Sleeper: Send + Sync {
+    fn sleep_until(
+        &self,
+        target: DateTime<FixedOffset>,
+        clock: &dyn Clock,
+        cancel: &CancelToken,
+    ) -> SleepOutcome;
+}
+
+// Sleeps slice by slice until the target, re-reading the clock between slices. Only the last slice uses the sleep type,
+// the previous ones are native and wake up as soon as the token is cancelled: only the spinning end of a SpinSleep
+// can't be interrupted. SleepType::Auto sleeps natively, see BlockingScheduler::wake_up_error.
+impl Sleeper for SleepType {
+    fn sleep_until(
+        &self,
+        target: DateTime<FixedOffset>,
+        clock: &dyn Clock,
+        cancel: &CancelToken,
+    ) -> SleepOutcome {
+        let (started, started_at) = (Instant::now(), clock.now());
+        loop {
+            if cancel.is_cancelled() {
+                return SleepOutcome::Cancelled;
+            }
+            let now = clock.now();
+            let drift = (now - started_at)
+                .to_std()
+                .map(|elapsed| elapsed.abs_diff(started.elapsed()));
+            if drift.map_or(true, |drift| drift > CLOCK_JUMP_TOLERANCE) {
+                return SleepOutcome::ClockJumped;
+            }
+            // The clock is read again after the last slice, the target being reached once the clock says so
+            let remaining = (target - now).to_std().unwrap_or_default();
+            if remaining.is_zero() {
+                return SleepOutcome::ReachedTarget;
+            }
+            if remaining > MAX_SLEEP_SLICE {
+                cancel.wait_timeout(MAX_SLEEP_SLICE);
+                continue;
+            }
+            match self {
+                Self::Native | Self::Auto { .. } => {
+                    cancel.wait_timeout(remaining);
+                }
+                #[cfg(feature = "spin_sleep")]
+                Self::SpinSleep(spin_sleeper) => spin_sleeper.sleep(remaining),
+                #[cfg(feature = "spin_sleep")]
+                Self::Spin => spin_sleep::SpinSleeper::default().sleep(remaining),
+            }
+        }
+    }
+}
+
+// Sleeper replacing the sleep types of a scheduler, shared with its clones
+#[derive(Clone)]
+pub(crate) struct SharedSleeper(pub(crate) Arc<dyn Sleeper>);
+
+impl Debug for SharedSleeper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SharedSleeper")
+    }
+}
+
+// Token a started loop sleeps with, cancelled when the loop is stopped or when a command is sent to it. Renewed once it
+// has woken the loop up, the commands being received in between.
+#[derive(Clone, Debug)]
+pub(crate) struct Wake {
+    token: Arc<Mutex<CancelToken>>,
+    cancellation_token: CancellationToken,
+}
+
+impl Wake {
+    pub(crate) fn new(cancellation_token: &CancellationToken) -> Self {
+        let token = CancelToken::new();
+        cancellation_token.link(&token);
+        Self {
+            token: Arc::new(Mutex::new(token)),
+            cancellation_token: cancellation_token.clone(),
+        }
+    }
+    // Wakes the loop up from its current sleep, or makes its next one return right away
+    pub(crate) fn wake(&self) {
+        self.token.lock().unwrap().cancel();
+    }
+    // Token to sleep with until the next command, to be taken before receiving the commands already sent
+    pub(crate) fn token(&self) -> CancelToken {
+        let mut token = self.token.lock().unwrap();
+        if token.is_cancelled() && !self.cancellation_token.is_cancelled() {
+            self.cancellation_token.unlink(&token);
+            *token = CancelToken::new();
+            self.cancellation_token.link(&token);
+        }
+        token.clone()
+    }
+    // Unlinks the token from the cancellation token, once the loop has stopped
+    pub(crate) fn release(&self) {
+        self.cancellation_token.unlink(&self.token.lock().unwrap());
+    }
+}
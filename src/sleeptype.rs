@@ -1,3 +1,7 @@
+#[cfg(feature = "json-schema")]
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
+#[cfg(feature = "serde")]
+use serde::{de::VariantAccess, ser::SerializeStructVariant};
 #[cfg(feature = "serde")]
 use serde::{
     de::{EnumAccess, Visitor},
@@ -5,21 +9,199 @@ use serde::{
 };
 #[cfg(feature = "spin_sleep")]
 use spin_sleep::SpinSleeper;
-#[cfg(all(feature = "spin_sleep", feature = "serde"))]
-use {
-    serde::{de::VariantAccess, ser::SerializeStructVariant},
-    spin_sleep::SpinStrategy,
-};
+#[cfg(feature = "spin_sleep")]
+use spin_sleep::SpinStrategy;
+use std::time::Duration;
+
+// Longest single sleep done while waiting for a task: far-future dates are never handed to the platform's sleep in one piece
+pub(crate) const MAX_SLEEP_SLICE: Duration = Duration::from_secs(1);
 // You need to know that the ...
-#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Default)]
 pub enum SleepType {
     #[default]
     // Used when you need accuracy to the second. In this case, the scheduler uses std::thread::sleep() which has no cost to your program or computer.
     Native,
-    // Accurate to the millisecond => Use spin sleep which require more ressoruces to work
+    // Accurate to the millisecond => Use spin sleep which require more ressoruces to work.
+    // The sleeper is embedded in the task, prefer SleepType::Spin which uses the scheduler's SpinConfig
     #[cfg(feature = "spin_sleep")]
     SpinSleep(SpinSleeper),
+    // Same as SpinSleep, configured by the SpinConfig of the scheduler
+    #[cfg(feature = "spin_sleep")]
+    Spin,
+    // Native while the recent wake-ups of the scheduler have been late by less than the tolerance, Spin otherwise.
+    // Always native without the spin_sleep feature, see BlockingScheduler::wake_up_error
+    Auto {
+        tolerance: Duration,
+    },
+}
+
+// Average lateness of the recent wake-ups of a scheduler, used by SleepType::Auto
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct WakeUpError(Option<Duration>);
+
+impl WakeUpError {
+    // Weight of the previous wake-ups in the average, out of 8
+    const HISTORY_WEIGHT: u32 = 7;
+
+    pub(crate) fn record(&mut self, lateness: Duration) {
+        self.0 = Some(match self.0 {
+            Some(average) => {
+                (average * Self::HISTORY_WEIGHT + lateness) / (Self::HISTORY_WEIGHT + 1)
+            }
+            None => lateness,
+        });
+    }
+    pub(crate) fn average(&self) -> Option<Duration> {
+        self.0
+    }
+}
+
+// Raises the resolution of the platform's timer to 1 ms while alive, so that the native sleeps are accurate enough for
+// short gaps. Only Windows needs it (its default resolution is about 15.6 ms), it does nothing elsewhere.
+#[cfg(feature = "windows-timer")]
+pub(crate) struct TimerResolution {
+    #[cfg(windows)]
+    raised: bool,
+}
+
+#[cfg(feature = "windows-timer")]
+impl TimerResolution {
+    // Gaps below which a mode needs the raised resolution
+    pub(crate) const THRESHOLD: Duration = Duration::from_millis(100);
+    #[cfg(windows)]
+    const PERIOD_MS: u32 = 1;
+
+    #[cfg_attr(not(windows), allow(unused_variables))]
+    pub(crate) fn raise(needed: bool) -> Self {
+        Self {
+            #[cfg(windows)]
+            raised: needed
+                // This is safe since the period is only given back by drop, when it has been raised
+                && unsafe { windows_sys::Win32::Media::timeBeginPeriod(Self::PERIOD_MS) }
+                    == windows_sys::Win32::Media::TIMERR_NOERROR,
+        }
+    }
+}
+
+#[cfg(all(windows, feature = "windows-timer"))]
+impl Drop for TimerResolution {
+    fn drop(&mut self) {
+        if self.raised {
+            // This is safe since the period has been raised by Self::raise
+            unsafe {
+                windows_sys::Win32::Media::timeEndPeriod(Self::PERIOD_MS);
+            }
+        }
+    }
+}
+
+/// Spin sleeping configuration shared by every SleepType::Spin task of a scheduler
+#[cfg(feature = "spin_sleep")]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct SpinConfig {
+    pub native_accuracy_ns: u32,
+    #[cfg_attr(feature = "serde", serde(with = "spin_strategy_name"))]
+    #[cfg_attr(feature = "json-schema", schemars(with = "SpinStrategyName"))]
+    pub strategy: SpinStrategy,
+}
+
+#[cfg(feature = "spin_sleep")]
+impl Default for SpinConfig {
+    fn default() -> Self {
+        let spin_sleeper = SpinSleeper::default();
+        Self {
+            native_accuracy_ns: spin_sleeper.native_accuracy_ns(),
+            strategy: spin_sleeper.spin_strategy(),
+        }
+    }
+}
+
+#[cfg(feature = "spin_sleep")]
+impl SpinConfig {
+    pub fn sleeper(&self) -> SpinSleeper {
+        SpinSleeper::new(self.native_accuracy_ns).with_spin_strategy(self.strategy)
+    }
+}
+impl SleepType {
+    // SleepType::Auto is turned into the sleep type matching the recent wake-up error
+    #[cfg_attr(not(feature = "spin_sleep"), allow(unused_variables))]
+    pub(crate) fn adapt(self, wake_up_error: WakeUpError) -> Self {
+        match self {
+            #[cfg(feature = "spin_sleep")]
+            Self::Auto { tolerance }
+                if wake_up_error
+                    .average()
+                    .is_some_and(|error| error > tolerance) =>
+            {
+                Self::Spin
+            }
+            Self::Auto { .. } => Self::Native,
+            sleep_type => sleep_type,
+        }
+    }
+
+    // SleepType::Spin is turned into a SpinSleep using the scheduler's sleeper
+    #[cfg(feature = "spin_sleep")]
+    pub(crate) fn resolve(self, spin_sleeper: SpinSleeper) -> Self {
+        match self {
+            Self::Spin => Self::SpinSleep(spin_sleeper),
+            sleep_type => sleep_type,
+        }
+    }
 }
+// Names used by the serialized forms instead of the variants of SpinStrategy
+#[cfg(all(feature = "spin_sleep", feature = "serde"))]
+mod spin_strategy_name {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use spin_sleep::SpinStrategy;
+
+    pub(super) fn name(spin_strategy: SpinStrategy) -> &'static str {
+        if spin_strategy == SpinStrategy::YieldThread {
+            "yield"
+        } else {
+            "spin_loop_hint"
+        }
+    }
+    pub(super) fn from_name<E: de::Error>(name: &str) -> Result<SpinStrategy, E> {
+        match name {
+            "yield" => Ok(SpinStrategy::YieldThread),
+            "spin_loop_hint" => Ok(SpinStrategy::SpinLoopHint),
+            _ => Err(E::unknown_variant(name, &["yield", "spin_loop_hint"])),
+        }
+    }
+
+    pub fn serialize<S: Serializer>(
+        spin_strategy: &SpinStrategy,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(name(*spin_strategy))
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<SpinStrategy, D::Error> {
+        from_name(&String::deserialize(deserializer)?)
+    }
+
+    // Spin strategy of the embedded SleepType::SpinSleep, which used to be serialized as 0 (yield) or 1 (spin loop hint)
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    pub(super) enum LegacySpinStrategy {
+        Legacy(u8),
+        Named(String),
+    }
+    impl LegacySpinStrategy {
+        pub(super) fn spin_strategy<E: de::Error>(self) -> Result<SpinStrategy, E> {
+            match self {
+                Self::Legacy(0) => Ok(SpinStrategy::YieldThread),
+                Self::Legacy(_) => Ok(SpinStrategy::SpinLoopHint),
+                Self::Named(name) => from_name(&name),
+            }
+        }
+    }
+}
+
 #[cfg(feature = "serde")]
 impl Serialize for SleepType {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -31,20 +213,20 @@ impl Serialize for SleepType {
             #[cfg(feature = "spin_sleep")]
             Self::SpinSleep(spin_sleeper) => {
                 let mut sv = serializer.serialize_struct_variant("SleepType", 1, "SpinSleep", 2)?;
-                sv.serialize_field(
-                    "native_accuracy_ns",
-                    &spin_sleeper.clone().native_accuracy_ns(),
-                )?;
+                sv.serialize_field("native_accuracy_ns", &spin_sleeper.native_accuracy_ns())?;
                 sv.serialize_field(
                     "spin_strategy",
-                    if spin_sleeper.spin_strategy() == SpinStrategy::YieldThread {
-                        &0
-                    } else {
-                        &1
-                    },
+                    spin_strategy_name::name(spin_sleeper.spin_strategy()),
                 )?;
                 sv.end()
             }
+            #[cfg(feature = "spin_sleep")]
+            Self::Spin => serializer.serialize_unit_variant("SleepType", 2, "Spin"),
+            Self::Auto { tolerance } => {
+                let mut sv = serializer.serialize_struct_variant("SleepType", 3, "Auto", 1)?;
+                sv.serialize_field("tolerance_ns", &(tolerance.as_nanos() as u64))?;
+                sv.end()
+            }
         }
     }
 }
@@ -61,25 +243,37 @@ impl<'de> Deserialize<'de> for SleepType {
             fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
                 formatter.write_str("Expecting serialized SleepType enum")
             }
-            #[cfg(not(feature = "spin_sleep"))]
-            fn visit_enum<A>(self, _: A) -> Result<Self::Value, A::Error>
-            where
-                A: EnumAccess<'de>,
-            {
-                Ok(SleepType::Native)
-            }
-            #[cfg(feature = "spin_sleep")]
             fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
             where
                 A: EnumAccess<'de>,
             {
                 let variant = data.variant::<String>()?;
-                if variant.0 == "Native" {
-                    Ok(SleepType::Native)
-                } else {
-                    Ok(variant
+                match variant.0.as_str() {
+                    "Native" => {
+                        variant.1.unit_variant()?;
+                        Ok(SleepType::Native)
+                    }
+                    "Auto" => variant.1.struct_variant(&["tolerance_ns"], AutoVisitor),
+                    #[cfg(feature = "spin_sleep")]
+                    "Spin" => {
+                        variant.1.unit_variant()?;
+                        Ok(SleepType::Spin)
+                    }
+                    #[cfg(feature = "spin_sleep")]
+                    _ => Ok(variant
+                        .1
+                        .struct_variant(&["native_accuracy_ns", "spin_strategy"], Self)?),
+                    // The spinning sleep types are read as native ones without the spin_sleep feature
+                    #[cfg(not(feature = "spin_sleep"))]
+                    "Spin" => variant.1.unit_variant().map(|_| SleepType::Native),
+                    #[cfg(not(feature = "spin_sleep"))]
+                    _ => variant
                         .1
-                        .struct_variant(&["native_accuracy_ns", "spin_strategy"], Self)?)
+                        .struct_variant(
+                            &["native_accuracy_ns", "spin_strategy"],
+                            serde::de::IgnoredAny,
+                        )
+                        .map(|_| SleepType::Native),
                 }
             }
 
@@ -88,27 +282,80 @@ impl<'de> Deserialize<'de> for SleepType {
             where
                 A: serde::de::MapAccess<'de>,
             {
+                let native_accuracy_ns = map
+                    .next_entry::<String, u32>()?
+                    .expect("Native accuracy field")
+                    .1;
+                let spin_strategy = map
+                    .next_entry::<String, spin_strategy_name::LegacySpinStrategy>()?
+                    .expect("Spin strategy field")
+                    .1
+                    .spin_strategy()?;
                 Ok(SleepType::SpinSleep(
-                    SpinSleeper::new(
-                        map.next_entry::<String, u32>()?
-                            .expect("Native accuracy field")
-                            .1,
-                    )
-                    .with_spin_strategy(
-                        if map
-                            .next_entry::<String, u8>()?
-                            .expect("Spin strategy field")
-                            .1
-                            == 0
-                        {
-                            SpinStrategy::YieldThread
-                        } else {
-                            SpinStrategy::SpinLoopHint
-                        },
-                    ),
+                    SpinSleeper::new(native_accuracy_ns).with_spin_strategy(spin_strategy),
                 ))
             }
         }
-        deserializer.deserialize_enum("SleepType", &["Native", "SpinSleep"], SleepVisitor)
+        struct AutoVisitor;
+        impl<'de> Visitor<'de> for AutoVisitor {
+            type Value = SleepType;
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("Expecting the tolerance of SleepType::Auto")
+            }
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let tolerance_ns = map
+                    .next_entry::<String, u64>()?
+                    .ok_or_else(|| serde::de::Error::missing_field("tolerance_ns"))?
+                    .1;
+                Ok(SleepType::Auto {
+                    tolerance: Duration::from_nanos(tolerance_ns),
+                })
+            }
+        }
+        deserializer.deserialize_enum(
+            "SleepType",
+            &["Native", "SpinSleep", "Spin", "Auto"],
+            SleepVisitor,
+        )
+    }
+}
+
+// Serialized forms of a SleepType, described by its schema. The spinning sleep types are included even without the
+// spin_sleep feature, since they're read as native ones.
+#[cfg(feature = "json-schema")]
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+enum SleepTypeForm {
+    Native,
+    SpinSleep {
+        native_accuracy_ns: u32,
+        spin_strategy: SpinStrategyName,
+    },
+    Spin,
+    Auto {
+        tolerance_ns: u64,
+    },
+}
+
+// Names of the spin strategies, see spin_strategy_name
+#[cfg(feature = "json-schema")]
+#[derive(JsonSchema)]
+#[schemars(rename = "SpinStrategy", rename_all = "snake_case")]
+#[allow(dead_code)]
+enum SpinStrategyName {
+    Yield,
+    SpinLoopHint,
+}
+
+#[cfg(feature = "json-schema")]
+impl JsonSchema for SleepType {
+    fn schema_name() -> String {
+        "SleepType".to_string()
+    }
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        SleepTypeForm::json_schema(gen)
     }
 }
@@ -0,0 +1,284 @@
+//! Snapshots of a started mode written as JSON by a thread of their own, so that the triggering thread never waits for
+//! the serialization. The triggering thread only sends what changed (a small message per triggered task) to the
+//! snapshot thread, which applies it to its own copy of the scheduler and writes the copy every interval. A firing that
+//! decrements the count of a task is written right away, so that a finished count is never fired past across restarts.
+//! With the ledger enabled, the markers of the occurrences fired (see the ledger module) are written right away too, the
+//! callback waiting for its start marker to be written.
+//! A snapshot is the state of the scheduler as of the last change applied before it was written, the pending deletions
+//! (see BlockingScheduler::cancel_with_ttl) being saved as they were when the mode started.
+//!
+//! ```
+//! use chrono::{Duration, Local};
+//! use scheduler::prelude::*;
+//! use std::collections::HashMap;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let in_ms = |offset| Local::now() + Duration::milliseconds(offset);
+//! let tasks = vec![
+//!     ScheduledTask::new(in_ms(20), "first".to_string(), RepetitionType::Once, SleepType::Native),
+//!     ScheduledTask::new(in_ms(40), "second".to_string(), RepetitionType::Once, SleepType::Native),
+//!     ScheduledTask::new(
+//!         in_ms(60),
+//!         "measure".to_string(),
+//!         RepetitionType::Weekly(RepetitionCount::Finished(2).into()),
+//!         SleepType::Native,
+//!     ),
+//! ];
+//! let mut scheduler =
+//!     BlockingScheduler::new(HashMap::from([("sensors".to_string(), tasks)]), HashMap::new());
+//! // Stops the mode once the weekly task has been triggered
+//! let cancellation_token = scheduler.cancellation_token();
+//! let path = std::env::temp_dir().join("scheduler_snapshot_doctest.json");
+//! scheduler
+//!     .start_with_snapshots(
+//!         "sensors",
+//!         |task| {
+//!             if task == "measure" {
+//!                 cancellation_token.cancel()
+//!             }
+//!         },
+//!         &path,
+//!         std::time::Duration::from_millis(20),
+//!     )
+//!     .unwrap();
+//! let saved: BlockingScheduler<String> =
+//!     serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+//! assert_eq!(saved.scheduled_tasks, scheduler.scheduled_tasks);
+//! assert_eq!(saved.removed_tasks, scheduler.removed_tasks);
+//! assert_eq!(saved.removed_tasks["sensors"].len(), 2);
+//! assert_eq!(saved.scheduled_tasks["sensors"].len(), 1);
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+use super::repetitions::CustomRepetition;
+use super::schedulers::{BlockingScheduler, Rescheduling, RunObserver, ScheduledTask};
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Change of the started mode, sent to the snapshot thread
+enum SnapshotChange<TaskType> {
+    // The first task has been triggered and rescheduled (or removed)
+    Rescheduled(Rescheduling),
+    // The tasks have changed otherwise, the removed tasks being the ones removed since the mode has been started
+    Replaced {
+        tasks: Vec<ScheduledTask<TaskType>>,
+        removed_tasks: Vec<ScheduledTask<TaskType>>,
+    },
+    // An occurrence has been recorded as started or completed by the ledger, shared with the copy. The triggering
+    // thread waits for the start markers to be written, told through the sender.
+    Marked(Option<Sender<()>>),
+}
+
+// Observer of BlockingScheduler::run sending the changes to the snapshot thread
+struct SnapshotRecorder<TaskType> {
+    changes: Sender<SnapshotChange<TaskType>>,
+}
+
+impl<TaskType: Clone> RunObserver<TaskType> for SnapshotRecorder<TaskType> {
+    fn replaced(
+        &mut self,
+        tasks: &[ScheduledTask<TaskType>],
+        removed_tasks: &[ScheduledTask<TaskType>],
+    ) {
+        // The snapshot thread only stops early on a write error, which is returned once the mode has stopped
+        let _ = self.changes.send(SnapshotChange::Replaced {
+            tasks: tasks.to_vec(),
+            removed_tasks: removed_tasks.to_vec(),
+        });
+    }
+    fn rescheduled(&mut self, rescheduling: &Rescheduling) {
+        let _ = self
+            .changes
+            .send(SnapshotChange::Rescheduled(rescheduling.clone()));
+    }
+    fn starting(&mut self, _task: &ScheduledTask<TaskType>) {
+        let (written, marker) = mpsc::channel();
+        if self
+            .changes
+            .send(SnapshotChange::Marked(Some(written)))
+            .is_ok()
+        {
+            // Err when the marker couldn't be written, the error being returned once the mode has stopped
+            let _ = marker.recv();
+        }
+    }
+    fn completed(&mut self, _task: &ScheduledTask<TaskType>) {
+        let _ = self.changes.send(SnapshotChange::Marked(None));
+    }
+}
+
+// Copy of the scheduler kept up to date by the snapshot thread
+struct SnapshotCopy<TaskType, CustomRepetitionType> {
+    scheduler: BlockingScheduler<TaskType, CustomRepetitionType>,
+    mode: String,
+    // Number of removed tasks of the mode when it has been started
+    removed_at_start: usize,
+    path: PathBuf,
+}
+
+impl<TaskType, CustomRepetitionType> SnapshotCopy<TaskType, CustomRepetitionType>
+where
+    TaskType: Serialize + Eq,
+    CustomRepetitionType: Serialize,
+{
+    // Applies the change to the copy, telling whether it has to be written right away: it has decremented the count of a
+    // task, or marked an occurrence in the ledger
+    fn apply(&mut self, change: SnapshotChange<TaskType>) -> bool {
+        let (Some(tasks), Some(removed)) = (
+            self.scheduler.scheduled_tasks.get_mut(&self.mode),
+            self.scheduler.removed_tasks.get_mut(&self.mode),
+        ) else {
+            return false;
+        };
+        match change {
+            SnapshotChange::Rescheduled((id, next_date, repetition)) => {
+                // The triggered task, found by its identity (the first one when it has none)
+                let Some(index) = tasks
+                    .iter()
+                    .position(|task| task.id() == id)
+                    .or((!tasks.is_empty()).then_some(0))
+                else {
+                    return false;
+                };
+                let task = &mut tasks[index];
+                let counted = task.repetition.remaining_count() != repetition.remaining_count();
+                task.repetition = repetition;
+                match next_date {
+                    Ok(date) => {
+                        task.date = date;
+                        tasks.sort_by_key(|task| task.date);
+                    }
+                    Err(removal_reason) => {
+                        let mut task = tasks.remove(index);
+                        task.removal_reason = Some(removal_reason);
+                        removed.push(task);
+                    }
+                }
+                counted
+            }
+            SnapshotChange::Replaced {
+                tasks: current_tasks,
+                removed_tasks,
+            } => {
+                *tasks = current_tasks;
+                removed.truncate(self.removed_at_start);
+                removed.extend(removed_tasks);
+                false
+            }
+            SnapshotChange::Marked(_) => true,
+        }
+    }
+
+    // Writes the copy next to the path then moves it there, so that a crash never leaves a partial snapshot
+    fn write(&self) -> Result<(), String> {
+        let mut temporary_path = self.path.clone().into_os_string();
+        temporary_path.push(".tmp");
+        let written = File::create(&temporary_path)
+            .map_err(|error| error.to_string())
+            .and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                serde_json::to_writer(&mut writer, &self.scheduler)
+                    .map_err(|error| error.to_string())?;
+                writer.flush().map_err(|error| error.to_string())
+            })
+            .and_then(|_| {
+                fs::rename(&temporary_path, &self.path).map_err(|error| error.to_string())
+            });
+        written.map_err(|error| {
+            format!(
+                "Couldn't write the snapshot {} : {}",
+                self.path.display(),
+                error
+            )
+        })
+    }
+
+    // Applies the changes until the mode stops, writing the copy every interval when it has changed. A firing that has
+    // decremented a count is written right away, so that a restart never fires the occurrence again, and so are the
+    // markers of the ledger.
+    fn run(
+        mut self,
+        changes: Receiver<SnapshotChange<TaskType>>,
+        interval: Duration,
+    ) -> Result<(), String> {
+        self.write()?;
+        let mut changed = false;
+        let mut next_write = Instant::now() + interval;
+        loop {
+            match changes.recv_timeout(next_write.saturating_duration_since(Instant::now())) {
+                Ok(change) => {
+                    changed = true;
+                    let written = match &change {
+                        SnapshotChange::Marked(written) => written.clone(),
+                        _ => None,
+                    };
+                    if !self.apply(change) {
+                        continue;
+                    }
+                    self.write()?;
+                    changed = false;
+                    if let Some(written) = written {
+                        let _ = written.send(());
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    return if changed { self.write() } else { Ok(()) };
+                }
+            }
+            if changed {
+                self.write()?;
+                changed = false;
+            }
+            next_write = Instant::now() + interval;
+        }
+    }
+}
+
+impl<TaskType, CustomRepetitionType> BlockingScheduler<TaskType, CustomRepetitionType>
+where
+    TaskType: Serialize + Eq + Default + Clone + Send,
+    CustomRepetitionType: CustomRepetition + Serialize + Clone + Send + Sync,
+{
+    // Same as start, the scheduler being saved as JSON at the path every interval while the mode runs, and once it has
+    // stopped. See the snapshot module. The first error writing a snapshot is returned once the mode has stopped.
+    pub fn start_with_snapshots(
+        &mut self,
+        mode: &str,
+        mut f: impl FnMut(&TaskType),
+        path: impl AsRef<Path>,
+        interval: Duration,
+    ) -> Result<(), String> {
+        if !self.has_mode(mode) {
+            return Err(self.unknown_mode(mode).to_string());
+        }
+        let copy = SnapshotCopy {
+            scheduler: self.clone(),
+            mode: mode.to_owned(),
+            removed_at_start: self.removed_tasks.get(mode).map_or(0, Vec::len),
+            path: path.as_ref().to_owned(),
+        };
+        let (sender, changes) = mpsc::channel();
+        thread::scope(|scope| {
+            let snapshots = scope.spawn(move || copy.run(changes, interval));
+            let mut recorder = SnapshotRecorder { changes: sender };
+            let run = self.run(
+                mode,
+                |task| {
+                    f(&task.task);
+                    None
+                },
+                &mut recorder,
+            );
+            drop(recorder);
+            let written = snapshots
+                .join()
+                .unwrap_or(Err("The snapshot thread has panicked".to_string()));
+            run.and(written)
+        })
+    }
+}
@@ -0,0 +1,149 @@
+//! Modes fed lazily by a TaskSource instead of holding all of their tasks: only the tasks due within a look-ahead
+//! window are pulled into the mode, the next ones staying in the source until the window reaches them.
+//!
+//! ```
+//! use chrono::{DateTime, Duration, FixedOffset};
+//! use scheduler::prelude::*;
+//! use std::collections::HashMap;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! // A task every 10 minutes, forever
+//! struct EveryTenMinutes;
+//!
+//! impl TaskSource<u32> for EveryTenMinutes {
+//!     fn next_after(&mut self, after: DateTime<FixedOffset>) -> Option<ScheduledTask<u32>> {
+//!         let start = DateTime::parse_from_rfc3339("2024-01-01T00:00:00+00:00").unwrap();
+//!         let date = start.max(after + Duration::minutes(10));
+//!         Some(ScheduledTask::new(date, 10, RepetitionType::Once, SleepType::Native))
+//!     }
+//! }
+//!
+//! let mut scheduler = BlockingScheduler::<u32>::new(HashMap::new(), HashMap::new());
+//! scheduler.set_task_source("sensors", EveryTenMinutes, Duration::hours(1));
+//! let mut now = DateTime::parse_from_rfc3339("2024-01-01T00:00:00+00:00").unwrap();
+//! let mut fired = 0;
+//! for _ in 0..24 {
+//!     fired += scheduler.tick("sensors", now, |_| {}).unwrap();
+//!     // The tasks resident are the ones dated within the hour
+//!     assert!(scheduler.scheduled_tasks["sensors"].len() <= 6);
+//!     now += Duration::minutes(30);
+//! }
+//! assert_eq!(fired, 70);
+//! ```
+use super::schedulers::ScheduledTask;
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use std::collections::VecDeque;
+use std::fmt::Debug;
+
+/// Gives the tasks of a mode one by one, see BlockingScheduler::set_task_source.
+/// `after` is the date of the previous task given (the earliest date for the first call): a source computing its
+/// tasks returns the first one dated strictly after it, one keeping its tasks may return several tasks at the same date.
+/// The tasks must be given in date order, None ending the source.
+pub trait TaskSource<TaskType> {
+    fn next_after(&mut self, after: DateTime<FixedOffset>) -> Option<ScheduledTask<TaskType>>;
+}
+
+/// Tasks already materialized, given in date order
+#[derive(Clone, Debug, Default)]
+pub struct VecSource<TaskType> {
+    tasks: VecDeque<ScheduledTask<TaskType>>,
+}
+
+impl<TaskType: Eq> VecSource<TaskType> {
+    pub fn new(mut tasks: Vec<ScheduledTask<TaskType>>) -> Self {
+        tasks.sort_by_key(|task| task.date);
+        Self {
+            tasks: tasks.into(),
+        }
+    }
+}
+
+impl<TaskType: Eq> FromIterator<ScheduledTask<TaskType>> for VecSource<TaskType> {
+    fn from_iter<Tasks: IntoIterator<Item = ScheduledTask<TaskType>>>(tasks: Tasks) -> Self {
+        Self::new(tasks.into_iter().collect())
+    }
+}
+
+impl<TaskType> TaskSource<TaskType> for VecSource<TaskType> {
+    fn next_after(&mut self, _after: DateTime<FixedOffset>) -> Option<ScheduledTask<TaskType>> {
+        self.tasks.pop_front()
+    }
+}
+
+// Source of a mode along with what has been pulled from it so far
+pub(crate) struct ModeSource<TaskType> {
+    source: Box<dyn TaskSource<TaskType> + Send>,
+    look_ahead: Duration,
+    // Date of the last task given by the source
+    cursor: DateTime<FixedOffset>,
+    // Task given by the source beyond the look-ahead, kept until the look-ahead reaches it
+    next: Option<ScheduledTask<TaskType>>,
+    exhausted: bool,
+}
+
+impl<TaskType> Debug for ModeSource<TaskType> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModeSource")
+            .field("look_ahead", &self.look_ahead)
+            .field("cursor", &self.cursor)
+            .field("exhausted", &self.exhausted)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<TaskType> ModeSource<TaskType> {
+    pub(crate) fn new(source: Box<dyn TaskSource<TaskType> + Send>, look_ahead: Duration) -> Self {
+        Self {
+            source,
+            look_ahead,
+            cursor: DateTime::<Utc>::MIN_UTC.into(),
+            next: None,
+            exhausted: false,
+        }
+    }
+
+    // Gives to add the tasks of the source dated up to now plus the look-ahead.
+    // When the mode is empty, the next task is given whatever its date so that the mode always knows its next run.
+    pub(crate) fn pull(
+        &mut self,
+        now: &DateTime<FixedOffset>,
+        mode_is_empty: bool,
+        mut add: impl FnMut(ScheduledTask<TaskType>),
+    ) {
+        let horizon = now
+            .checked_add_signed(self.look_ahead)
+            .unwrap_or(DateTime::<Utc>::MAX_UTC.into());
+        let mut mode_is_empty = mode_is_empty;
+        loop {
+            if self.next.is_none() && !self.exhausted {
+                self.next = self.source.next_after(self.cursor);
+                match &self.next {
+                    Some(task) => self.cursor = task.date,
+                    None => self.exhausted = true,
+                }
+            }
+            match self.next.take() {
+                Some(task) if task.date <= horizon || mode_is_empty => {
+                    mode_is_empty = false;
+                    add(task);
+                }
+                next => {
+                    self.next = next;
+                    return;
+                }
+            }
+        }
+    }
+
+    // Date of the next task kept by the source
+    pub(crate) fn next_date(&self) -> Option<DateTime<FixedOffset>> {
+        self.next.as_ref().map(|task| task.date)
+    }
+    // Date at which the look-ahead reaches the next task kept by the source
+    pub(crate) fn next_pull(&self) -> Option<DateTime<FixedOffset>> {
+        self.next_date().map(|date| {
+            date.checked_sub_signed(self.look_ahead)
+                .unwrap_or(DateTime::<Utc>::MIN_UTC.into())
+        })
+    }
+}
@@ -0,0 +1,294 @@
+//! The tasks of a mode as an async Stream, yielding an item each time a task is due.
+//! The time is measured with the tokio clock, so a paused runtime drives the schedule without waiting.
+//! The items are the FiredTasks of the records module, the ones the journal writes.
+//!
+//! Each item owns its payload, cloned from the task when it fires. For large payloads the consumer only reads, the tasks
+//! can hold them behind an Arc (see ScheduledTask::into_shared): the scheduler is then a `BlockingScheduler<Arc<T>>`
+//! and a firing only clones the Arc. In exchange the consumer can't change the payload without cloning it
+//! (Arc::make_mut), the payload lives as long as its task or the last item holding it, and saving the scheduler needs
+//! the `rc` feature of serde.
+//!
+//! ```
+//! use chrono::{Duration, Local};
+//! use scheduler::prelude::*;
+//! use std::collections::HashMap;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! #[tokio::main(flavor = "current_thread", start_paused = true)]
+//! async fn main() {
+//!     let start = Local::now() + Duration::hours(1);
+//!     let task = ScheduledTask::new(
+//!         start,
+//!         "backup",
+//!         RepetitionType::ConstGap {
+//!             gap: Duration::hours(1),
+//!             termination: RepetitionCount::Finished(3).into(),
+//!         },
+//!         SleepType::Native,
+//!     );
+//!     let scheduler = BlockingScheduler::new(
+//!         HashMap::from([("hourly".to_string(), vec![task])]),
+//!         HashMap::new(),
+//!     );
+//!     let mut firings = scheduler.into_stream("hourly").unwrap();
+//!     let mut scheduled = Vec::new();
+//!     while let Some(fired) = firings.next().await {
+//!         scheduled.push(fired.record.scheduled_for);
+//!     }
+//!     assert_eq!(scheduled.len(), 3);
+//!     assert_eq!(scheduled[0], start);
+//!     assert_eq!((scheduled[2] - scheduled[0]).num_minutes(), 120);
+//! }
+//! ```
+use super::config::BackpressurePolicy;
+use super::error::SchedulerError;
+use super::id::TaskId;
+use super::occurrence::{OccurrenceOutcome, TaskContext};
+use super::records::{ExecutionRecord, FiredTask};
+use super::repetitions::CustomRepetition;
+use super::schedulers::BlockingScheduler;
+use super::sleeper::{Clock, SystemClock};
+use chrono::{DateTime, Duration, FixedOffset};
+use futures_core::Stream;
+use std::collections::{BTreeMap, VecDeque};
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::{self, Receiver, UnboundedReceiver};
+use tokio::time::{Instant, Sleep};
+
+/// Stream of the tasks of a mode, see BlockingScheduler::into_stream.
+/// The wait for the next task only starts when the stream is polled, a consumer polling late gets the task late
+/// (once, even if several occurrences have been missed meanwhile). Dropping the stream stops the schedule.
+pub struct FiringStream<TaskType, CustomRepetitionType> {
+    scheduler: BlockingScheduler<TaskType, CustomRepetitionType>,
+    mode: String,
+    // Wall clock date matching an instant of the tokio clock, the dates are converted through it
+    anchor: (Instant, DateTime<FixedOffset>),
+    sleep: Option<Pin<Box<Sleep>>>,
+    // Tasks due at the same time, yielded one by one
+    due: VecDeque<FiredTask<TaskType>>,
+    // Firings yielded so far by task, giving the occurrence index of the next one
+    fired: BTreeMap<Option<TaskId>, u64>,
+}
+
+impl<TaskType, CustomRepetitionType> FiringStream<TaskType, CustomRepetitionType>
+where
+    TaskType: Eq + Default + Clone,
+    CustomRepetitionType: CustomRepetition + Clone,
+{
+    pub async fn next(&mut self) -> Option<FiredTask<TaskType>> {
+        poll_fn(|cx| self.poll_firing(cx)).await
+    }
+
+    pub fn scheduler(&self) -> &BlockingScheduler<TaskType, CustomRepetitionType> {
+        &self.scheduler
+    }
+    pub fn into_scheduler(self) -> BlockingScheduler<TaskType, CustomRepetitionType> {
+        self.scheduler
+    }
+
+    fn now(&self) -> DateTime<FixedOffset> {
+        let (instant, date) = self.anchor;
+        Duration::from_std(Instant::now() - instant).map_or(date, |elapsed| date + elapsed)
+    }
+
+    fn poll_firing(&mut self, cx: &mut Context<'_>) -> Poll<Option<FiredTask<TaskType>>> {
+        loop {
+            if let Some(fired) = self.due.pop_front() {
+                return Poll::Ready(Some(fired));
+            }
+            if self.scheduler.cancellation_token().is_cancelled() {
+                return Poll::Ready(None);
+            }
+            let Some(next_date) = self.scheduler.next_run_time(&self.mode) else {
+                return Poll::Ready(None);
+            };
+            let anchor = self.anchor;
+            let sleep = self.sleep.get_or_insert_with(|| {
+                let (instant, date) = anchor;
+                Box::pin(tokio::time::sleep_until(
+                    instant + (next_date - date).to_std().unwrap_or_default(),
+                ))
+            });
+            if sleep.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            self.sleep = None;
+            let now = self.now();
+            let (due, fired, mode) = (&mut self.due, &mut self.fired, &self.mode);
+            // The mode has been checked by BlockingScheduler::into_stream
+            let _ = self.scheduler.tick_scheduled(mode, now, |task| {
+                let occurrence_index = fired.entry(task.id()).or_default();
+                let record = ExecutionRecord::builder(mode, task.date, now)
+                    .task_id(task.id())
+                    .occurrence_index(*occurrence_index)
+                    .build();
+                *occurrence_index += 1;
+                due.push_back(FiredTask::new(task.task.clone(), record))
+            });
+        }
+    }
+}
+
+impl<TaskType, CustomRepetitionType> Stream for FiringStream<TaskType, CustomRepetitionType>
+where
+    TaskType: Eq + Default + Clone + Unpin,
+    CustomRepetitionType: CustomRepetition + Clone + Unpin,
+{
+    type Item = FiredTask<TaskType>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_firing(cx)
+    }
+}
+
+/// Stream of the tasks of a mode driven by a tokio task, see BlockingScheduler::into_eager_stream.
+/// The tasks are yielded at their date whether the stream is polled or not, the ones not consumed yet are buffered up to
+/// SchedulerConfig::stream_capacity.
+pub struct EagerFiringStream<TaskType> {
+    firings: Firings<TaskType>,
+    dropped: Arc<AtomicU64>,
+}
+
+// Channel of an EagerFiringStream. The bounded receiver is reached by the task driving the schedule, which takes the
+// oldest firing out of it with BackpressurePolicy::DropOldest: the task only holds a Weak, so that dropping the stream
+// closes the channel.
+enum Firings<TaskType> {
+    Unbounded(UnboundedReceiver<FiredTask<TaskType>>),
+    Bounded(Arc<Mutex<Receiver<FiredTask<TaskType>>>>),
+}
+
+impl<TaskType> EagerFiringStream<TaskType> {
+    pub async fn next(&mut self) -> Option<FiredTask<TaskType>> {
+        poll_fn(|cx| self.poll_firing(cx)).await
+    }
+
+    // Firings dropped so far because the consumer was behind, see BackpressurePolicy
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(AtomicOrdering::Relaxed)
+    }
+
+    fn poll_firing(&mut self, cx: &mut Context<'_>) -> Poll<Option<FiredTask<TaskType>>> {
+        match &mut self.firings {
+            Firings::Unbounded(firings) => firings.poll_recv(cx),
+            Firings::Bounded(firings) => firings.lock().unwrap().poll_recv(cx),
+        }
+    }
+}
+
+impl<TaskType> Stream for EagerFiringStream<TaskType> {
+    type Item = FiredTask<TaskType>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_firing(cx)
+    }
+}
+
+impl<TaskType, CustomRepetitionType> BlockingScheduler<TaskType, CustomRepetitionType>
+where
+    TaskType: Eq + Default + Clone,
+    CustomRepetitionType: CustomRepetition + Clone,
+{
+    // Yields the tasks of the mode as they're due, until the mode has no task left or the scheduler is stopped.
+    // The tasks dated in the past are yielded right away, once.
+    pub fn into_stream(
+        mut self,
+        mode: &str,
+    ) -> Result<FiringStream<TaskType, CustomRepetitionType>, String> {
+        SchedulerError::mode_mut(&mut self.scheduled_tasks, mode)?;
+        Ok(FiringStream {
+            scheduler: self,
+            mode: mode.to_owned(),
+            anchor: (Instant::now(), SystemClock.now()),
+            sleep: None,
+            due: VecDeque::new(),
+            fired: BTreeMap::new(),
+        })
+    }
+
+    // Same as Self::into_stream, the schedule being driven by a task spawned on the current tokio runtime.
+    // The firings not consumed yet are buffered up to SchedulerConfig::stream_capacity, then handled as told by
+    // SchedulerConfig::backpressure_policy. The task ends once the stream is dropped.
+    pub fn into_eager_stream(self, mode: &str) -> Result<EagerFiringStream<TaskType>, String>
+    where
+        TaskType: Send + 'static,
+        CustomRepetitionType: Send + Sync + 'static,
+    {
+        let (capacity, policy) = (self.config.stream_capacity, self.config.backpressure_policy);
+        let occurrence_hook = self.occurrence_hook;
+        let mut firings = self.into_stream(mode)?;
+        let dropped = Arc::new(AtomicU64::new(0));
+        let Some(capacity) = capacity else {
+            let (sender, receiver) = mpsc::unbounded_channel();
+            tokio::spawn(async move {
+                while let Some(fired) = firings.next().await {
+                    if sender.send(fired).is_err() {
+                        break;
+                    }
+                }
+            });
+            return Ok(EagerFiringStream {
+                firings: Firings::Unbounded(receiver),
+                dropped,
+            });
+        };
+        // A channel can't be empty, a capacity of 0 buffers a single firing
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        let receiver = Arc::new(Mutex::new(receiver));
+        let (consumer, counted) = (Arc::downgrade(&receiver), dropped.clone());
+        let mode = mode.to_owned();
+        tokio::spawn(async move {
+            while let Some(fired) = firings.next().await {
+                let fired = match policy {
+                    BackpressurePolicy::Block => match sender.send(fired).await {
+                        Ok(()) => continue,
+                        Err(_) => break,
+                    },
+                    BackpressurePolicy::DropNewest => match sender.try_send(fired) {
+                        Ok(()) => continue,
+                        Err(TrySendError::Full(fired)) => fired,
+                        Err(TrySendError::Closed(_)) => break,
+                    },
+                    BackpressurePolicy::DropOldest => match sender.try_send(fired) {
+                        Ok(()) => continue,
+                        Err(TrySendError::Full(fired)) => {
+                            let Some(consumer) = consumer.upgrade() else {
+                                break;
+                            };
+                            let oldest = consumer.lock().unwrap().try_recv();
+                            // The consumer may have taken the oldest firing meanwhile, leaving the room all the same
+                            let _ = sender.try_send(fired);
+                            match oldest {
+                                Ok(oldest) => oldest,
+                                Err(_) => continue,
+                            }
+                        }
+                        Err(TrySendError::Closed(_)) => break,
+                    },
+                };
+                counted.fetch_add(1, AtomicOrdering::Relaxed);
+                if let Some(occurrence_hook) = occurrence_hook {
+                    occurrence_hook(
+                        OccurrenceOutcome::StreamDropped,
+                        &TaskContext {
+                            mode: &mode,
+                            task: &fired.task,
+                            date: fired.record.scheduled_for,
+                            is_backfill: false,
+                            cancel_token: None,
+                            execution: None,
+                        },
+                    );
+                }
+            }
+        });
+        Ok(EagerFiringStream {
+            firings: Firings::Bounded(receiver),
+            dropped,
+        })
+    }
+}
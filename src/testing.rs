@@ -0,0 +1,436 @@
+//! Helpers to test schedules without waiting for real time to pass.
+//!
+//! ```
+//! use chrono::{DateTime, Duration};
+//! use scheduler::prelude::*;
+//! use scheduler::testing::ScheduleHarness;
+//! use std::collections::HashMap;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let start = DateTime::parse_from_rfc3339("2024-01-01T09:00:00+01:00").unwrap();
+//! let task = ScheduledTask::new(
+//!     start + Duration::minutes(30),
+//!     "backup",
+//!     RepetitionType::ConstGap {
+//!         gap: Duration::hours(1),
+//!         termination: RepetitionCount::Finished(3).into(),
+//!     },
+//!     SleepType::Native,
+//! );
+//! let scheduler = BlockingScheduler::new(
+//!     HashMap::from([("daily".to_string(), vec![task])]),
+//!     HashMap::new(),
+//! );
+//! let mut harness = ScheduleHarness::new(scheduler, "daily", start).unwrap();
+//! scheduler::assert_next_fire_at!(harness, start + Duration::minutes(30));
+//!
+//! harness.advance(Duration::hours(5)).unwrap();
+//! assert_eq!(harness.fired().len(), 3);
+//! assert_eq!(harness.fired()[2].0, start + Duration::minutes(150));
+//! assert_eq!(harness.next_fire(), None);
+//! ```
+//!
+//! BlockingScheduler::global_next_after gives the first task the modes would trigger, late tasks included:
+//!
+//! ```
+//! use chrono::{DateTime, Duration};
+//! use scheduler::prelude::*;
+//! use scheduler::testing::ScheduleHarness;
+//! use std::collections::HashMap;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let start = DateTime::parse_from_rfc3339("2024-01-01T09:00:00+01:00").unwrap();
+//! let every_hour = |date, task| {
+//!     ScheduledTask::new(
+//!         date,
+//!         task,
+//!         RepetitionType::ConstGap {
+//!             gap: Duration::hours(1),
+//!             termination: Termination::default(),
+//!         },
+//!         SleepType::Native,
+//!     )
+//! };
+//! let mut scheduler = BlockingScheduler::new(
+//!     HashMap::from([
+//!         ("late".to_string(), vec![every_hour(start - Duration::minutes(90), "late")]),
+//!         ("soon".to_string(), vec![every_hour(start + Duration::minutes(20), "soon")]),
+//!     ]),
+//!     HashMap::new(),
+//! );
+//! // The late task skips its missed occurrences: its next one is at 09:30
+//! let (mode, task, date) = scheduler.global_next_after(start).unwrap();
+//! assert_eq!((mode.as_str(), task.task, date), ("soon", "soon", start + Duration::minutes(20)));
+//! // It's fired right away once missed occurrences are caught up
+//! scheduler.config.missed_tick_behavior = MissedTickBehavior::FireOnce;
+//! let (mode, task, date) = scheduler.global_next_after(start).unwrap();
+//! assert_eq!((mode.as_str(), task.task, date), ("late", "late", start));
+//! let mut first_fires: Vec<_> = ["late", "soon"]
+//!     .into_iter()
+//!     .map(|mode| {
+//!         let mut harness = ScheduleHarness::new(scheduler.clone(), mode, start).unwrap();
+//!         harness.advance(Duration::hours(1)).unwrap();
+//!         (harness.fired()[0].0, mode)
+//!     })
+//!     .collect();
+//! first_fires.sort();
+//! assert_eq!(first_fires[0], (date, mode.as_str()));
+//! ```
+//!
+//! The adherence report tells whether each task fired every occurrence it was expected to, once and on time:
+//!
+//! ```
+//! use chrono::{DateTime, Duration};
+//! use scheduler::prelude::*;
+//! use scheduler::testing::ScheduleHarness;
+//! use std::collections::HashMap;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let start = DateTime::parse_from_rfc3339("2024-01-15T09:00:00+01:00").unwrap();
+//! let infinite = || Termination::default();
+//! let tasks = vec![
+//!     ScheduledTask::new(start, "weekly", RepetitionType::Weekly(infinite()), SleepType::Native),
+//!     ScheduledTask::new(start, "monthly", RepetitionType::Monthly { day: None, termination: infinite() }, SleepType::Native),
+//!     ScheduledTask::new(
+//!         start + Duration::minutes(30),
+//!         "every 6 hours",
+//!         RepetitionType::ConstGap { gap: Duration::hours(6), termination: RepetitionCount::Finished(200).into() },
+//!         SleepType::Native,
+//!     ),
+//! ];
+//! let scheduler = BlockingScheduler::new(HashMap::from([("jobs".to_string(), tasks)]), HashMap::new());
+//! let mut harness = ScheduleHarness::new(scheduler, "jobs", start).unwrap();
+//! harness.advance(Duration::days(60)).unwrap();
+//! scheduler::assert_adherent!(harness, Duration::zero());
+//!
+//! let report = harness.adherence_report();
+//! let expected: Vec<_> = report.tasks.values().map(|task| task.expected.len()).collect();
+//! // Up to the 15th of March, the sixth month of the ConstGap task stopping at its count
+//! assert_eq!(expected, [9, 3, 200]);
+//! assert!(harness.firings().iter().all(|firing| firing.fired_at == firing.scheduled));
+//!
+//! // Started a day late, the missed occurrences are skipped
+//! let late = ScheduledTask::new(start, "daily", RepetitionType::ConstGap { gap: Duration::days(1), termination: infinite() }, SleepType::Native);
+//! let scheduler = BlockingScheduler::new(HashMap::from([("late".to_string(), vec![late])]), HashMap::new());
+//! let mut harness = ScheduleHarness::new(scheduler, "late", start + Duration::hours(30)).unwrap();
+//! harness.advance(Duration::days(2)).unwrap();
+//! let report = harness.adherence_report();
+//! assert!(!report.is_adherent(Duration::hours(1)));
+//! let task = report.tasks.values().next().unwrap();
+//! assert_eq!(task.missed, [0, 1]);
+//! assert_eq!(task.expected.len(), 4);
+//! ```
+//!
+//! A RecordingSleeper lets start run a mode without waiting, recording what it would have waited for:
+//!
+//! ```
+//! use chrono::{Duration, Local};
+//! use scheduler::prelude::*;
+//! use scheduler::testing::RecordingSleeper;
+//! use std::collections::HashMap;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let now = Local::now();
+//! let (in_1_hour, in_2_hours) = (now + Duration::hours(1), now + Duration::hours(2));
+//! let tasks = vec![
+//!     ScheduledTask::new(in_2_hours, "archive", RepetitionType::Once, SleepType::Native),
+//!     ScheduledTask::new(in_1_hour, "export", RepetitionType::Once, SleepType::Auto { tolerance: std::time::Duration::ZERO }),
+//! ];
+//! let mut scheduler = BlockingScheduler::new(HashMap::from([("jobs".to_string(), tasks)]), HashMap::new());
+//! scheduler.config.splay = Some(Duration::minutes(5));
+//! let sleeper = RecordingSleeper::new();
+//! scheduler.set_sleeper(sleeper.clone());
+//! let mut fired = Vec::new();
+//! scheduler.start("jobs", |task| fired.push(*task)).unwrap();
+//! assert_eq!(fired, ["export", "archive"]);
+//! // The startup delay drawn from the splay, then the tasks
+//! let targets = sleeper.targets();
+//! assert_eq!(targets.len(), 3);
+//! assert!(targets[0] <= Local::now() + Duration::minutes(5));
+//! assert_eq!(targets[1..], [in_1_hour, in_2_hours]);
+//! assert_eq!(scheduler.removed_tasks["jobs"].len(), 2);
+//! ```
+use super::cancellation::CancelToken;
+use super::id::TaskId;
+use super::repetitions::{CustomRepetition, NoCustomRepetition};
+use super::schedulers::{BlockingScheduler, ScheduledTask};
+pub use super::sleeper::ManualClock;
+use super::sleeper::{Clock, SleepOutcome, Sleeper};
+use chrono::{DateTime, Duration, FixedOffset};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Sleeper returning right away, recording the dates it has been asked to sleep until, see BlockingScheduler::set_sleeper.
+/// Clones share the same record.
+#[derive(Clone, Debug, Default)]
+pub struct RecordingSleeper(Arc<Mutex<Vec<DateTime<FixedOffset>>>>);
+
+impl RecordingSleeper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn targets(&self) -> Vec<DateTime<FixedOffset>> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl Sleeper for RecordingSleeper {
+    fn sleep_until(
+        &self,
+        target: DateTime<FixedOffset>,
+        _: &dyn Clock,
+        cancel: &CancelToken,
+    ) -> SleepOutcome {
+        self.0.lock().unwrap().push(target);
+        if cancel.is_cancelled() {
+            SleepOutcome::Cancelled
+        } else {
+            SleepOutcome::ReachedTarget
+        }
+    }
+}
+
+/// Runs one mode of a scheduler against a ManualClock, recording every triggered task with the date it was triggered at.
+/// The catch-up pass happens when the harness is built, at the clock's time, then the tasks are triggered through
+/// BlockingScheduler::tick as the clock is advanced.
+pub struct ScheduleHarness<TaskType, CustomRepetitionType = NoCustomRepetition> {
+    scheduler: BlockingScheduler<TaskType, CustomRepetitionType>,
+    mode: String,
+    clock: ManualClock,
+    fired: Vec<(DateTime<FixedOffset>, TaskType)>,
+    // The tasks as they were before their first firing, giving their expected occurrences
+    expected: BTreeMap<TaskId, ScheduledTask<TaskType>>,
+    firings: Vec<Firing>,
+}
+
+/// Triggering of a task by a ScheduleHarness. The occurrence is the index of the expected occurrence it stands for
+/// (see ScheduledTask::occurrences): the one dated like the task, the last one before the firing otherwise. None when
+/// the task fired before its first occurrence.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Firing {
+    pub id: TaskId,
+    pub occurrence: Option<usize>,
+    pub scheduled: DateTime<FixedOffset>,
+    pub fired_at: DateTime<FixedOffset>,
+}
+
+/// Expected occurrences of a task up to the time of a ScheduleHarness, matched against its firings
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct TaskAdherence {
+    pub expected: Vec<DateTime<FixedOffset>>,
+    // Indexes of the expected occurrences that haven't fired
+    pub missed: Vec<usize>,
+    // Indexes of the expected occurrences that have fired more than once, once per extra firing
+    pub duplicated: Vec<usize>,
+    // Firings standing for no expected occurrence
+    pub unexpected: Vec<DateTime<FixedOffset>>,
+    // Longest time between an expected occurrence and its firing
+    pub worst_latency: Duration,
+}
+
+/// Adherence of the tasks of a ScheduleHarness to their schedule, see ScheduleHarness::adherence_report
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct AdherenceReport {
+    pub tasks: BTreeMap<TaskId, TaskAdherence>,
+}
+
+impl AdherenceReport {
+    // True when every expected occurrence has fired once, within the tolerance, and nothing else has fired
+    pub fn is_adherent(&self, tolerance: Duration) -> bool {
+        self.tasks.values().all(|task| {
+            task.missed.is_empty()
+                && task.duplicated.is_empty()
+                && task.unexpected.is_empty()
+                && task.worst_latency <= tolerance
+        })
+    }
+}
+
+impl<TaskType, CustomRepetitionType> ScheduleHarness<TaskType, CustomRepetitionType>
+where
+    TaskType: Eq + Default + Clone,
+    CustomRepetitionType: CustomRepetition + Clone,
+{
+    pub fn new(
+        scheduler: BlockingScheduler<TaskType, CustomRepetitionType>,
+        mode: &str,
+        now: impl Into<DateTime<FixedOffset>>,
+    ) -> Result<Self, String> {
+        let now = now.into();
+        let expected = scheduler
+            .scheduled_tasks
+            .get(mode)
+            .into_iter()
+            .flatten()
+            .filter_map(|task| Some((task.id()?, task.clone())))
+            .collect();
+        let mut harness = Self {
+            scheduler,
+            mode: mode.to_owned(),
+            clock: ManualClock::new(now),
+            fired: Vec::new(),
+            expected,
+            firings: Vec::new(),
+        };
+        let custom_repetition = harness.scheduler.custom_repetition();
+        let (fired, expected, firings) = (
+            &mut harness.fired,
+            &mut harness.expected,
+            &mut harness.firings,
+        );
+        harness.scheduler.catch_up_scheduled(mode, now, |task| {
+            fired.push((now, task.task.clone()));
+            Self::record(expected, firings, &custom_repetition, task, now);
+        })?;
+        Ok(harness)
+    }
+
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        self.clock.now()
+    }
+    pub fn clock(&self) -> &ManualClock {
+        &self.clock
+    }
+
+    pub fn advance(&mut self, duration: Duration) -> Result<(), String> {
+        self.advance_to(self.clock.now() + duration)
+    }
+
+    // Moves the clock from task to task up to the date, triggering every task met on the way
+    pub fn advance_to(&mut self, date: impl Into<DateTime<FixedOffset>>) -> Result<(), String> {
+        let date = date.into();
+        let custom_repetition = self.scheduler.custom_repetition();
+        while let Some(next_fire) = self.next_fire().filter(|next_fire| *next_fire <= date) {
+            self.clock.set(next_fire);
+            let (fired, expected, firings) =
+                (&mut self.fired, &mut self.expected, &mut self.firings);
+            self.scheduler
+                .tick_scheduled(&self.mode, next_fire, |task| {
+                    fired.push((next_fire, task.task.clone()));
+                    Self::record(expected, firings, &custom_repetition, task, next_fire);
+                })?;
+        }
+        if self.clock.now() < date {
+            self.clock.set(date);
+        }
+        Ok(())
+    }
+
+    pub fn fired(&self) -> &[(DateTime<FixedOffset>, TaskType)] {
+        &self.fired
+    }
+    pub fn firings(&self) -> &[Firing] {
+        &self.firings
+    }
+
+    // Matches the occurrences each task was expected to fire up to now against its firings. A task added after the
+    // harness has been built is expected from its first firing on.
+    pub fn adherence_report(&self) -> AdherenceReport {
+        let custom_repetition = self.scheduler.custom_repetition();
+        let now = self.clock.now();
+        let mut report = AdherenceReport::default();
+        for (id, task) in &self.expected {
+            let expected: Vec<_> = task
+                .occurrences(&custom_repetition)
+                .take_while(|date| *date <= now)
+                .collect();
+            let mut fired = vec![0; expected.len()];
+            let mut adherence = TaskAdherence::default();
+            for firing in self.firings.iter().filter(|firing| firing.id == *id) {
+                match firing
+                    .occurrence
+                    .filter(|occurrence| *occurrence < expected.len())
+                {
+                    Some(occurrence) => {
+                        fired[occurrence] += 1;
+                        if fired[occurrence] > 1 {
+                            adherence.duplicated.push(occurrence);
+                        }
+                        adherence.worst_latency = adherence
+                            .worst_latency
+                            .max(firing.fired_at - expected[occurrence]);
+                    }
+                    None => adherence.unexpected.push(firing.fired_at),
+                }
+            }
+            adherence.missed = (0..expected.len())
+                .filter(|occurrence| fired[*occurrence] == 0)
+                .collect();
+            adherence.expected = expected;
+            report.tasks.insert(*id, adherence);
+        }
+        report
+    }
+
+    // Records the firing of the task, see Firing
+    fn record(
+        expected: &mut BTreeMap<TaskId, ScheduledTask<TaskType>>,
+        firings: &mut Vec<Firing>,
+        custom_repetition: &CustomRepetitionType,
+        task: &ScheduledTask<TaskType>,
+        fired_at: DateTime<FixedOffset>,
+    ) {
+        let Some(id) = task.id() else {
+            return;
+        };
+        let expected = expected.entry(id).or_insert_with(|| task.clone());
+        let mut occurrence = None;
+        for (index, date) in expected.occurrences(custom_repetition).enumerate() {
+            if task.date < date && fired_at < date {
+                break;
+            }
+            occurrence = Some(index);
+            if date == task.date {
+                break;
+            }
+        }
+        firings.push(Firing {
+            id,
+            occurrence,
+            scheduled: task.date,
+            fired_at,
+        });
+    }
+
+    pub fn next_fire(&self) -> Option<DateTime<FixedOffset>> {
+        self.scheduler
+            .scheduled_tasks
+            .get(&self.mode)?
+            .iter()
+            .map(|task| task.date)
+            .min()
+    }
+
+    pub fn scheduler(&self) -> &BlockingScheduler<TaskType, CustomRepetitionType> {
+        &self.scheduler
+    }
+    pub fn into_scheduler(self) -> BlockingScheduler<TaskType, CustomRepetitionType> {
+        self.scheduler
+    }
+}
+
+/// Asserts that every task of the harness has fired each of its expected occurrences once, within the tolerance, see
+/// ScheduleHarness::adherence_report
+#[macro_export]
+macro_rules! assert_adherent {
+    ($harness:expr, $tolerance:expr) => {{
+        let report = $harness.adherence_report();
+        assert!(
+            report.is_adherent($tolerance),
+            "the schedule isn't adherent: {:?}",
+            report
+        )
+    }};
+}
+
+/// Asserts the date of the next task the harness will trigger
+#[macro_export]
+macro_rules! assert_next_fire_at {
+    ($harness:expr, $date:expr) => {
+        assert_eq!(
+            $harness.next_fire(),
+            Some($date),
+            "unexpected next fire date"
+        )
+    };
+}
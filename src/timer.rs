@@ -0,0 +1,104 @@
+//! Waits until a date with the same sleep as the schedulers, for the one-shot cases that don't need a schedule.
+//!
+//! ```
+//! use chrono::{Duration, Local};
+//! use scheduler::prelude::*;
+//! use scheduler::timer::Timer;
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let date = Local::now() + Duration::milliseconds(50);
+//! assert!(Timer::at(date).with_sleep(SleepType::Native).wait());
+//! assert!(date <= Local::now());
+//!
+//! let handle = Timer::at(Local::now() + Duration::hours(1)).spawn(|| unreachable!()).unwrap();
+//! handle.cancel();
+//! assert!(!handle.join());
+//! ```
+use super::cancellation::{CancelToken, CancellationToken};
+use super::sleeper::{SleepOutcome, Sleeper, SystemClock};
+use super::sleeptype::SleepType;
+use chrono::{DateTime, FixedOffset};
+use std::thread::{self, JoinHandle};
+
+/// Sleep until a date, cancellable through a CancellationToken
+#[derive(Clone, Debug)]
+pub struct Timer {
+    date: DateTime<FixedOffset>,
+    sleep_type: SleepType,
+    cancellation_token: CancellationToken,
+}
+
+impl Timer {
+    pub fn at(date: impl Into<DateTime<FixedOffset>>) -> Self {
+        Self {
+            date: date.into(),
+            sleep_type: SleepType::Native,
+            cancellation_token: CancellationToken::new(),
+        }
+    }
+    pub fn with_sleep(self, sleep_type: SleepType) -> Self {
+        Self { sleep_type, ..self }
+    }
+    pub fn with_cancellation_token(self, cancellation_token: CancellationToken) -> Self {
+        Self {
+            cancellation_token,
+            ..self
+        }
+    }
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation_token.clone()
+    }
+
+    // Blocks until the date, returns false when cancelled before.
+    // The wait goes on after a clock jump, so that a clock moved backward doesn't end it early.
+    pub fn wait(&self) -> bool {
+        let cancel = CancelToken::new();
+        self.cancellation_token.link(&cancel);
+        let outcome = loop {
+            match self
+                .sleep_type
+                .sleep_until(self.date, &SystemClock, &cancel)
+            {
+                SleepOutcome::ClockJumped => continue,
+                outcome => break outcome,
+            }
+        };
+        self.cancellation_token.unlink(&cancel);
+        outcome == SleepOutcome::ReachedTarget
+    }
+
+    // Runs f on a new thread once the date is reached, unless the timer is cancelled before
+    pub fn spawn(self, f: impl FnOnce() + Send + 'static) -> std::io::Result<TimerHandle> {
+        let cancellation_token = self.cancellation_token.clone();
+        let thread_handler = thread::Builder::new()
+            .name("ThreadTimer".to_string())
+            .spawn(move || {
+                let reached = self.wait();
+                if reached {
+                    f();
+                }
+                reached
+            })?;
+        Ok(TimerHandle {
+            thread_handler,
+            cancellation_token,
+        })
+    }
+}
+
+/// Handle of a spawned Timer
+#[derive(Debug)]
+pub struct TimerHandle {
+    thread_handler: JoinHandle<bool>,
+    cancellation_token: CancellationToken,
+}
+
+impl TimerHandle {
+    pub fn cancel(&self) {
+        self.cancellation_token.cancel();
+    }
+    // Waits for the thread, returns true when f has been run
+    pub fn join(self) -> bool {
+        self.thread_handler.join().unwrap_or(false)
+    }
+}
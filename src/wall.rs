@@ -0,0 +1,497 @@
+//! Wall-clock schedules: the days a job runs on (every day, some days of the week or a day of the month), its times of
+//! the day and the offset they're read in, as a single value checked once. A WallSchedule is written
+//! "<days> <times> [<zone>] [or-last]":
+//! - days : "daily", weekdays separated by commas ("Mon,Wed") or a day of the month ("15th")
+//! - times : times of the day separated by commas ("09:30,18:00:15")
+//! - zone : "Local" (when missing), "UTC" or an offset ("+01:00"). Named time zones ("Europe/Paris") aren't supported,
+//!   the crate only computing with fixed offsets.
+//! - or-last : for a day of the month, the months without the day run on their last day instead of being skipped
+//!
+//! It's also the form of the schedule entries of the TOML and CSV schedules, and Every::weekday_at, Every::day_at
+//! and Every::month_day_at build theirs from one.
+//!
+//! ```
+//! use chrono::{DateTime, Duration, FixedOffset, NaiveTime, Weekday};
+//! use scheduler::wall::{DayRule, MonthOverflow, WallSchedule, WallZone};
+//! # scheduler::system_clock!(|| chrono::Local::now().into());
+//!
+//! let schedule: WallSchedule = "Wed,Mon 18:00,09:30 +01:00".parse().unwrap();
+//! assert_eq!(schedule.to_string(), "Mon,Wed 09:30,18:00 +01:00");
+//! let at = |date| DateTime::parse_from_rfc3339(date).unwrap();
+//! // 2024-01-01 is a Monday
+//! assert_eq!(schedule.first_occurrence_after(at("2024-01-01T10:00:00+01:00")), Some(at("2024-01-01T18:00:00+01:00")));
+//! assert_eq!(schedule.first_occurrence_after(at("2024-01-01T18:00:00+01:00")), Some(at("2024-01-03T09:30:00+01:00")));
+//! assert!("Mon 09:30 Europe/Paris".parse::<WallSchedule>().unwrap_err().contains("Named time zones"));
+//! assert!("Mon 25:00".parse::<WallSchedule>().is_err());
+//! assert!("daily 09:30 or-last".parse::<WallSchedule>().is_err());
+//!
+//! // Every schedule is read back from its written form
+//! let fixed = |seconds| WallZone::Fixed(FixedOffset::east_opt(seconds).unwrap());
+//! let offsets = [WallZone::Local, fixed(0), fixed(-34200)];
+//! let hms = |hour, minute, second| NaiveTime::from_hms_opt(hour, minute, second).unwrap();
+//! let times = [vec![hms(0, 0, 0)], vec![hms(23, 59, 59), hms(7, 5, 0)]];
+//! let mut schedules = Vec::new();
+//! for (zone, times) in offsets.iter().flat_map(|zone| times.iter().map(move |times| (*zone, times))) {
+//!     schedules.push(WallSchedule::new(DayRule::Daily, times.clone(), zone).unwrap());
+//!     for days in 1..128u32 {
+//!         let weekdays = (0..7u8).filter(|day| days & (1 << day) != 0).map(|day| Weekday::try_from(day).unwrap());
+//!         schedules.push(WallSchedule::new(DayRule::Weekdays(weekdays.collect()), times.clone(), zone).unwrap());
+//!     }
+//!     for day in 1..=31 {
+//!         schedules.push(WallSchedule::new(DayRule::DayOfMonth(day), times.clone(), zone).unwrap());
+//!         let clamped = WallSchedule::new(DayRule::DayOfMonth(day), times.clone(), zone).unwrap();
+//!         schedules.push(clamped.with_overflow(MonthOverflow::Clamp).unwrap());
+//!     }
+//! }
+//! for schedule in &schedules {
+//!     assert_eq!(schedule.to_string().parse::<WallSchedule>().as_ref(), Ok(schedule));
+//! }
+//!
+//! // The first occurrence is after now, on an accepted day and time, and there's none in between
+//! let start = at("2023-12-20T08:17:00+02:00");
+//! for schedule in schedules.iter().filter(|schedule| schedule.zone() != WallZone::Local) {
+//!     let WallZone::Fixed(offset) = schedule.zone() else { unreachable!() };
+//!     for step in 0..40 {
+//!         let now = start + Duration::hours(step * 37);
+//!         let first = schedule.first_occurrence_after(now).unwrap();
+//!         assert!(now < first && first.offset() == &offset);
+//!         assert!(schedule.times().contains(&first.time()));
+//!         assert!(schedule.accepts_day(first.date_naive()));
+//!         assert_eq!(schedule.first_occurrence_after(first - Duration::seconds(1)), Some(first));
+//!         assert_eq!(schedule.first_occurrence_after(now + (first - now) / 2), Some(first));
+//!     }
+//! }
+//! ```
+use super::repetitions::{RepetitionCount, RepetitionType};
+#[cfg(any(not(feature = "clock"), feature = "no-ambient-time"))]
+use super::sleeper::{Clock, SystemClock};
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
+    Timelike, Weekday,
+};
+#[cfg(all(feature = "clock", not(feature = "no-ambient-time")))]
+use chrono::{Local, Offset};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::Display;
+use std::str::FromStr;
+
+// Longest stretch without the day of a month (from a 31st of January to the 31st of March)
+const MAX_DAYS_BETWEEN: i64 = 62;
+
+/// Represents the days a WallSchedule runs on
+/// - Daily : every day
+/// - Weekdays : the days of the week, in their order from Monday once in a WallSchedule
+/// - DayOfMonth : the day of each month, from 1 to 31
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub enum DayRule {
+    Daily,
+    Weekdays(Vec<Weekday>),
+    DayOfMonth(u32),
+}
+
+/// Represents the offset the times of a WallSchedule are read in
+/// - Local : the offset of the system, at each date (the one of the clock given to system_clock!
+///   without the clock feature)
+/// - Fixed : the offset
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Default)]
+pub enum WallZone {
+    #[default]
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl WallZone {
+    // Offset of the zone at the date
+    pub fn offset_at(&self, date: &DateTime<FixedOffset>) -> FixedOffset {
+        match self {
+            Self::Local => Self::local_offset(date),
+            Self::Fixed(offset) => *offset,
+        }
+    }
+    // Earliest date at the local time in the zone, None when the time is skipped by a change of the local offset
+    pub fn from_local_datetime(&self, local: &NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        match self {
+            Self::Local => Self::from_local(local),
+            Self::Fixed(offset) => offset.from_local_datetime(local).single(),
+        }
+    }
+
+    // Offset of the system at the date
+    #[cfg(all(feature = "clock", not(feature = "no-ambient-time")))]
+    fn local_offset(date: &DateTime<FixedOffset>) -> FixedOffset {
+        date.with_timezone(&Local).offset().fix()
+    }
+    // Date of the system at now
+    #[cfg(all(feature = "clock", not(feature = "no-ambient-time")))]
+    fn local_date(now: &DateTime<FixedOffset>) -> NaiveDate {
+        now.with_timezone(&Local).date_naive()
+    }
+    // Earliest date at the local time of the system, None when the time is skipped by a change of the offset
+    #[cfg(all(feature = "clock", not(feature = "no-ambient-time")))]
+    fn from_local(local: &NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        Local
+            .from_local_datetime(local)
+            .earliest()
+            .map(DateTime::<FixedOffset>::from)
+    }
+    // Without the clock feature or with no-ambient-time, the offset of the system is the one of the clock given to
+    // system_clock!
+    #[cfg(any(not(feature = "clock"), feature = "no-ambient-time"))]
+    fn local_offset(_: &DateTime<FixedOffset>) -> FixedOffset {
+        *SystemClock.now().offset()
+    }
+    #[cfg(any(not(feature = "clock"), feature = "no-ambient-time"))]
+    fn local_date(now: &DateTime<FixedOffset>) -> NaiveDate {
+        now.with_timezone(SystemClock.now().offset()).date_naive()
+    }
+    #[cfg(any(not(feature = "clock"), feature = "no-ambient-time"))]
+    fn from_local(local: &NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        SystemClock
+            .now()
+            .offset()
+            .from_local_datetime(local)
+            .single()
+    }
+}
+
+/// Represents what a WallSchedule on a day of the month does for the months without the day
+/// - Skip : the month is skipped
+/// - Clamp : the schedule runs on the last day of the month instead
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Default)]
+pub enum MonthOverflow {
+    #[default]
+    Skip,
+    Clamp,
+}
+
+/// Days and times of the day a job runs on, see the wall module
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct WallSchedule {
+    days: DayRule,
+    times: Vec<NaiveTime>,
+    zone: WallZone,
+    overflow: MonthOverflow,
+}
+
+impl WallSchedule {
+    // The weekdays and the times are sorted and their duplicates removed. Fails when there's no weekday or no time, when
+    // a time has a fraction of second and when the day of the month isn't between 1 and 31.
+    pub fn new(days: DayRule, times: Vec<NaiveTime>, zone: WallZone) -> Result<Self, String> {
+        let days = match days {
+            DayRule::Weekdays(mut weekdays) => {
+                weekdays.sort_by_key(Weekday::num_days_from_monday);
+                weekdays.dedup();
+                if weekdays.is_empty() {
+                    return Err("A schedule needs at least one weekday".to_string());
+                }
+                DayRule::Weekdays(weekdays)
+            }
+            DayRule::DayOfMonth(day) if !(1..=31).contains(&day) => {
+                return Err(format!("Invalid day of the month : {}", day))
+            }
+            days => days,
+        };
+        let mut times = times;
+        times.sort();
+        times.dedup();
+        if times.is_empty() {
+            return Err("A schedule needs at least one time of the day".to_string());
+        }
+        if let Some(time) = times.iter().find(|time| time.nanosecond() != 0) {
+            return Err(format!(
+                "The times can't have a fraction of second : {}",
+                time
+            ));
+        }
+        Ok(Self {
+            days,
+            times,
+            zone,
+            overflow: MonthOverflow::Skip,
+        })
+    }
+
+    // Fails when the overflow isn't Skip and the schedule isn't on a day of the month
+    pub fn with_overflow(self, overflow: MonthOverflow) -> Result<Self, String> {
+        if overflow != MonthOverflow::Skip && !matches!(self.days, DayRule::DayOfMonth(_)) {
+            return Err(
+                "Only a schedule on a day of the month can be given an overflow".to_string(),
+            );
+        }
+        Ok(Self { overflow, ..self })
+    }
+
+    pub fn days(&self) -> &DayRule {
+        &self.days
+    }
+    pub fn times(&self) -> &[NaiveTime] {
+        &self.times
+    }
+    pub fn zone(&self) -> WallZone {
+        self.zone
+    }
+    pub fn overflow(&self) -> MonthOverflow {
+        self.overflow
+    }
+
+    // Whether the schedule runs on the day
+    pub fn accepts_day(&self, day: NaiveDate) -> bool {
+        match &self.days {
+            DayRule::Daily => true,
+            DayRule::Weekdays(weekdays) => weekdays.contains(&day.weekday()),
+            DayRule::DayOfMonth(month_day) => {
+                day.day() == *month_day
+                    || (self.overflow == MonthOverflow::Clamp
+                        && day.succ_opt().is_some_and(|next| next.day() == 1)
+                        && day.day() < *month_day)
+            }
+        }
+    }
+
+    // First date of the schedule after now, in the offset of the zone at that date. The times skipped by a change of the
+    // local offset are left out. None when there's none in the supported dates.
+    pub fn first_occurrence_after(
+        &self,
+        now: impl Into<DateTime<FixedOffset>>,
+    ) -> Option<DateTime<FixedOffset>> {
+        let now = now.into();
+        let today = match self.zone {
+            WallZone::Local => WallZone::local_date(&now),
+            WallZone::Fixed(offset) => now.with_timezone(&offset).date_naive(),
+        };
+        (0..=MAX_DAYS_BETWEEN)
+            .filter_map(|days| today.checked_add_signed(Duration::days(days)))
+            .filter(|day| self.accepts_day(*day))
+            .flat_map(|day| self.times.iter().map(move |time| day.and_time(*time)))
+            .filter_map(|date| self.zone.from_local_datetime(&date))
+            .find(|date| now < *date)
+    }
+
+    // First date and repetition of each task the schedule is made of, after now: a task per time repeated every day for
+    // Daily, per weekday and time repeated every week for Weekdays and per time repeated every month for DayOfMonth.
+    // Fails for a day of the month after the 28th unless the schedule clamps it (MonthOverflow::Clamp), the monthly
+    // repetitions moving to the last day of the months without the day where the schedule skips them.
+    pub fn slots(
+        &self,
+        now: impl Into<DateTime<FixedOffset>>,
+    ) -> Result<Vec<(DateTime<FixedOffset>, RepetitionType)>, String> {
+        let now = now.into();
+        let infinite = || RepetitionCount::Infinite.into();
+        let (rules, repetition) = match &self.days {
+            DayRule::Daily => (
+                vec![DayRule::Daily],
+                RepetitionType::ConstGap {
+                    gap: Duration::days(1),
+                    termination: infinite(),
+                },
+            ),
+            DayRule::Weekdays(weekdays) => (
+                weekdays
+                    .iter()
+                    .map(|weekday| DayRule::Weekdays(vec![*weekday]))
+                    .collect(),
+                RepetitionType::Weekly(infinite()),
+            ),
+            DayRule::DayOfMonth(day) if *day > 28 && self.overflow == MonthOverflow::Skip => {
+                return Err(format!(
+                    "The tasks of a schedule on the {} can't be built : the monthly repetitions don't skip the \
+                     months without the day, see MonthOverflow::Clamp",
+                    Ordinal(*day)
+                ))
+            }
+            DayRule::DayOfMonth(day) => (
+                vec![DayRule::DayOfMonth(*day)],
+                RepetitionType::Monthly {
+                    day: Some(*day),
+                    termination: infinite(),
+                },
+            ),
+        };
+        let mut slots = Vec::new();
+        for days in rules {
+            for time in &self.times {
+                let slot = Self {
+                    days: days.clone(),
+                    times: vec![*time],
+                    ..self.clone()
+                };
+                let date = slot.first_occurrence_after(now).ok_or(format!(
+                    "The schedule {} has no occurrence after {}",
+                    slot, now
+                ))?;
+                slots.push((date, repetition.clone()));
+            }
+        }
+        Ok(slots)
+    }
+}
+
+// Day of the month written "1st", "2nd", "3rd", "4th"...
+struct Ordinal(u32);
+
+impl Display for Ordinal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let suffix = match (self.0 % 10, self.0 % 100) {
+            (_, 11..=13) => "th",
+            (1, _) => "st",
+            (2, _) => "nd",
+            (3, _) => "rd",
+            _ => "th",
+        };
+        write!(f, "{}{}", self.0, suffix)
+    }
+}
+
+impl Display for WallSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.days {
+            DayRule::Daily => write!(f, "daily")?,
+            DayRule::Weekdays(weekdays) => {
+                let weekdays: Vec<_> = weekdays.iter().map(Weekday::to_string).collect();
+                write!(f, "{}", weekdays.join(","))?
+            }
+            DayRule::DayOfMonth(day) => write!(f, "{}", Ordinal(*day))?,
+        }
+        let times: Vec<_> = self
+            .times
+            .iter()
+            .map(|time| match time.second() {
+                0 => time.format("%H:%M").to_string(),
+                _ => time.format("%H:%M:%S").to_string(),
+            })
+            .collect();
+        write!(f, " {}", times.join(","))?;
+        if self.zone != WallZone::Local {
+            write!(f, " {}", self.zone)?;
+        }
+        if self.overflow == MonthOverflow::Clamp {
+            write!(f, " or-last")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for WallSchedule {
+    type Err = String;
+
+    fn from_str(schedule: &str) -> Result<Self, Self::Err> {
+        let mut words = schedule.split_whitespace();
+        let days = match words.next().ok_or("Empty schedule".to_string())? {
+            "daily" => DayRule::Daily,
+            day if day.ends_with(char::is_alphabetic)
+                && day.starts_with(|c: char| c.is_ascii_digit()) =>
+            {
+                let number = day.trim_end_matches(char::is_alphabetic);
+                if !["st", "nd", "rd", "th"].contains(&&day[number.len()..]) {
+                    return Err(format!("Invalid day of the month : {}", day));
+                }
+                DayRule::DayOfMonth(
+                    number
+                        .parse()
+                        .map_err(|error| format!("Invalid day of the month {} : {}", day, error))?,
+                )
+            }
+            weekdays => DayRule::Weekdays(
+                weekdays
+                    .split(',')
+                    .map(|weekday| {
+                        weekday
+                            .parse()
+                            .map_err(|_| format!("Invalid weekday : {}", weekday))
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+        };
+        let times = words
+            .next()
+            .ok_or(format!("Missing the times of the day : {}", schedule))?
+            .split(',')
+            .map(|time| {
+                NaiveTime::parse_from_str(time, "%H:%M:%S")
+                    .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M"))
+                    .map_err(|error| format!("Invalid time {} : {}", time, error))
+            })
+            .collect::<Result<_, _>>()?;
+        let mut zone = WallZone::Local;
+        let mut overflow = MonthOverflow::Skip;
+        let mut next = words.next();
+        if let Some(written) = next.filter(|word| *word != "or-last") {
+            zone = written.parse()?;
+            next = words.next();
+        }
+        if next == Some("or-last") {
+            overflow = MonthOverflow::Clamp;
+            next = words.next();
+        }
+        if next.is_some() {
+            return Err(format!("Unexpected words in the schedule : {}", schedule));
+        }
+        Self::new(days, times, zone)?.with_overflow(overflow)
+    }
+}
+
+// Written "Local" or as an offset ("+01:00"), as in a WallSchedule. "UTC" is read as +00:00.
+impl Display for WallZone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Local => write!(f, "Local"),
+            Self::Fixed(offset) => write!(f, "{}", offset),
+        }
+    }
+}
+
+impl FromStr for WallZone {
+    type Err = String;
+
+    fn from_str(zone: &str) -> Result<Self, Self::Err> {
+        match zone {
+            "Local" => Ok(Self::Local),
+            "UTC" => Ok(Self::Fixed(FixedOffset::east_opt(0).unwrap())),
+            offset if offset.starts_with(['+', '-']) => offset
+                .parse()
+                .map(Self::Fixed)
+                .map_err(|error| format!("Invalid offset {} : {}", offset, error)),
+            name if name.contains('/') => Err(format!(
+                "Named time zones aren't supported, use an offset such as +01:00 : {}",
+                name
+            )),
+            _ => Err(format!(
+                "Invalid zone, expected Local, UTC or an offset : {}",
+                zone
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for WallZone {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for WallZone {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for WallSchedule {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for WallSchedule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,72 @@
+// Eager streams whose consumer is behind, one firing buffered
+#![cfg(feature = "tokio")]
+
+use chrono::{Duration, Local};
+use scheduler::prelude::*;
+use std::sync::Mutex;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| Local::now().into());
+
+static DROPPED: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+// Four tasks due one second apart, consumed once they've all been fired
+async fn delivered(policy: BackpressurePolicy) -> (Vec<FiredTask<u32>>, u64) {
+    let start = Local::now() + Duration::seconds(1);
+    let tasks = (0..4)
+        .map(|task| {
+            ScheduledTask::new(
+                start + Duration::seconds(task.into()),
+                task,
+                RepetitionType::Once,
+                SleepType::Native,
+            )
+        })
+        .collect();
+    let mut scheduler = BlockingScheduler::new([("m".to_string(), tasks)], []);
+    scheduler.config.stream_capacity = Some(1);
+    scheduler.config.backpressure_policy = policy;
+    if policy == BackpressurePolicy::DropNewest {
+        scheduler.set_occurrence_hook(Some(|outcome, context| {
+            if outcome == OccurrenceOutcome::StreamDropped {
+                DROPPED.lock().unwrap().push(*context.task)
+            }
+        }));
+    }
+    let mut firings = scheduler.into_eager_stream("m").unwrap();
+    tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+    let mut delivered = Vec::new();
+    while let Some(fired) = firings.next().await {
+        delivered.push(fired);
+    }
+    (delivered, firings.dropped())
+}
+
+fn tasks(delivered: &[FiredTask<u32>]) -> Vec<u32> {
+    delivered.iter().map(|fired| fired.task).collect()
+}
+
+#[tokio::test(start_paused = true)]
+async fn block_delivers_every_firing_late() {
+    let (delivered, dropped) = delivered(BackpressurePolicy::Block).await;
+    assert_eq!(tasks(&delivered), [0, 1, 2, 3]);
+    assert_eq!(dropped, 0);
+    // The schedule has waited for the consumer
+    assert!(delivered[3].record.latency > std::time::Duration::from_secs(5));
+}
+
+#[tokio::test(start_paused = true)]
+async fn drop_newest_keeps_the_first_firing() {
+    let (delivered, dropped) = delivered(BackpressurePolicy::DropNewest).await;
+    assert_eq!(tasks(&delivered), [0]);
+    assert_eq!(dropped, 3);
+    assert_eq!(*DROPPED.lock().unwrap(), [1, 2, 3]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn drop_oldest_keeps_the_last_firing() {
+    let (delivered, dropped) = delivered(BackpressurePolicy::DropOldest).await;
+    assert_eq!(tasks(&delivered), [3]);
+    assert_eq!(dropped, 3);
+    assert!(delivered[0].record.latency < std::time::Duration::from_secs(1));
+}
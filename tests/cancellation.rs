@@ -0,0 +1,41 @@
+use chrono::{Duration, Local};
+use scheduler::prelude::*;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| Local::now().into());
+
+#[test]
+fn looping_callback_gives_up_on_stop() {
+    let soon = Local::now() + Duration::milliseconds(20);
+    let task = ScheduledTask::new(soon, 0, RepetitionType::Once, SleepType::Native);
+    let mut scheduler = BlockingScheduler::new(
+        HashMap::from([("loop".to_string(), vec![task])]),
+        HashMap::new(),
+    );
+    let stopper = scheduler.clone();
+    let looping = Arc::new(AtomicBool::new(false));
+    let callback_looping = looping.clone();
+    let worker = thread::spawn(move || {
+        scheduler
+            .start_with_context("loop", |context| {
+                callback_looping.store(true, Ordering::SeqCst);
+                let token = context.cancel_token().unwrap();
+                while !token.is_cancelled() {
+                    thread::sleep(std::time::Duration::from_millis(1));
+                }
+            })
+            .unwrap();
+    });
+    while !looping.load(Ordering::SeqCst) {
+        thread::sleep(std::time::Duration::from_millis(5));
+    }
+    let stopped = Instant::now();
+    stopper.stop();
+    worker.join().unwrap();
+    assert!(stopped.elapsed() < std::time::Duration::from_millis(200));
+}
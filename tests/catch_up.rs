@@ -0,0 +1,131 @@
+use chrono::{DateTime, Duration, FixedOffset};
+use scheduler::prelude::*;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| chrono::Local::now().into());
+
+static OUTCOMES: Mutex<Vec<(OccurrenceOutcome, DateTime<FixedOffset>)>> = Mutex::new(Vec::new());
+
+fn record(outcome: OccurrenceOutcome, context: &TaskContext<'_, u32>) {
+    OUTCOMES.lock().unwrap().push((outcome, context.date));
+}
+
+fn at(date: &str) -> DateTime<FixedOffset> {
+    DateTime::parse_from_rfc3339(date).unwrap()
+}
+
+fn every(gap: Duration, count: RepetitionCount) -> BlockingScheduler<u32> {
+    let repetition = RepetitionType::ConstGap {
+        gap,
+        termination: count.into(),
+    };
+    let task = ScheduledTask::new(
+        at("2024-01-01T09:00:00+01:00"),
+        0,
+        repetition,
+        SleepType::Native,
+    );
+    let mut scheduler = BlockingScheduler::new(
+        HashMap::from([("m".to_string(), vec![task])]),
+        HashMap::new(),
+    );
+    scheduler.config.missed_tick_behavior = MissedTickBehavior::FireAll;
+    scheduler.config.max_catch_up = Some(5);
+    scheduler
+}
+
+fn minutes(from: &str, range: std::ops::Range<i64>) -> Vec<DateTime<FixedOffset>> {
+    range
+        .map(|minute| at(from) + Duration::minutes(minute))
+        .collect()
+}
+
+#[test]
+fn catch_up_of_an_hour_of_minutes_replays_five() {
+    // After an hour of outage, 60 occurrences every minute from 09:00 to 09:59 have been missed
+    let now = at("2024-01-01T09:59:30+01:00");
+    let mut replayed = Vec::new();
+    for order in [CatchUpOrder::Oldest, CatchUpOrder::Newest] {
+        let mut scheduler = every(Duration::minutes(1), RepetitionCount::Infinite);
+        scheduler.config.catch_up_order = order;
+        scheduler.set_occurrence_hook(Some(record));
+        let mut fired = 0;
+        let report = scheduler.catch_up("m", now, |_| fired += 1).unwrap();
+        assert_eq!((fired, report.replayed, report.skipped), (5, 5, 55));
+        assert_eq!(
+            scheduler.scheduled_tasks["m"][0].date,
+            at("2024-01-01T10:00:00+01:00")
+        );
+        let outcomes = std::mem::take(&mut *OUTCOMES.lock().unwrap());
+        let caught_up = outcomes
+            .iter()
+            .filter(|(outcome, _)| *outcome == OccurrenceOutcome::CaughtUp);
+        replayed.push(caught_up.map(|(_, date)| *date).collect::<Vec<_>>());
+        let skipped = outcomes
+            .iter()
+            .find(|(outcome, _)| matches!(outcome, OccurrenceOutcome::Skipped { .. }));
+        let first_skipped = match order {
+            CatchUpOrder::Oldest => at("2024-01-01T09:05:00+01:00"),
+            _ => at("2024-01-01T09:00:00+01:00"),
+        };
+        assert_eq!(
+            skipped,
+            Some(&(OccurrenceOutcome::Skipped { missed: 55 }, first_skipped))
+        );
+    }
+    assert_eq!(replayed[0], minutes("2024-01-01T09:00:00+01:00", 0..5));
+    assert_eq!(replayed[1], minutes("2024-01-01T09:55:00+01:00", 0..5));
+}
+
+#[test]
+fn catch_up_skips_nothing_after_the_count() {
+    // 3 occurrences left: the 57 other ones of the hour weren't to happen
+    let mut scheduler = every(Duration::minutes(1), RepetitionCount::Finished(3));
+    let report = scheduler
+        .catch_up("m", at("2024-01-01T10:00:30+01:00"), |_| {})
+        .unwrap();
+    assert_eq!((report.replayed, report.skipped), (3, 0));
+    assert!(scheduler.scheduled_tasks["m"].is_empty());
+    assert_eq!(
+        scheduler.removed_tasks["m"][0].removal_reason,
+        Some(RemovalReason::Finished)
+    );
+}
+
+#[test]
+fn catch_up_of_years_of_seconds_is_computed() {
+    // About 315 million occurrences missed, counted without being walked through
+    let mut scheduler = every(Duration::seconds(1), RepetitionCount::Infinite);
+    let now = at("2034-01-01T09:00:00+01:00");
+    let missed = (now - at("2024-01-01T09:00:00+01:00")).num_seconds() as u64;
+    let report = scheduler.catch_up("m", now, |_| {}).unwrap();
+    assert_eq!((report.replayed, report.skipped), (5, missed - 5));
+    assert_eq!(
+        scheduler.scheduled_tasks["m"][0].date,
+        now + Duration::seconds(1)
+    );
+}
+
+// The outdated tasks are caught up by start
+#[test]
+fn catch_up_progress_is_told_to_a_closure() {
+    let an_hour_ago = chrono::Local::now() - Duration::hours(1);
+    let tasks = (0..5)
+        .map(|task| ScheduledTask::new(an_hour_ago, task, RepetitionType::Once, SleepType::Native))
+        .collect();
+    let mut scheduler =
+        BlockingScheduler::new(HashMap::from([("m".to_string(), tasks)]), HashMap::new());
+    scheduler.config.missed_tick_behavior = MissedTickBehavior::FireAll;
+    scheduler.config.catch_up_batch_size = Some(2);
+    let (progress, received) = std::sync::mpsc::channel();
+    scheduler.set_catch_up_progress(Some(Box::new(move |done, total| {
+        progress.send((done, total)).unwrap();
+    })));
+    scheduler.start("m", |_| {}).unwrap();
+    assert_eq!(
+        received.try_iter().collect::<Vec<_>>(),
+        [(2, 5), (4, 5), (5, 5)]
+    );
+}
@@ -0,0 +1,128 @@
+use chrono::{DateTime, Duration, FixedOffset, Local};
+use scheduler::prelude::*;
+use std::collections::HashMap;
+use std::thread;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| Local::now().into());
+
+fn once(date: DateTime<Local>, task: u32) -> ScheduledTask<u32> {
+    ScheduledTask::new(date, task, RepetitionType::Once, SleepType::Native)
+}
+
+#[test]
+fn urgent_task_preempts_the_sleep() {
+    let later = Local::now() + Duration::seconds(5);
+    let mut scheduler = BlockingScheduler::new(
+        HashMap::from([("alerts".to_string(), vec![once(later, 1)])]),
+        HashMap::new(),
+    );
+    let controller = scheduler.controller();
+    let worker = thread::spawn(move || {
+        let mut fired = Vec::new();
+        scheduler
+            .start("alerts", |task| fired.push((*task, Local::now())))
+            .unwrap();
+        fired
+    });
+    // Started by then, sleeping toward the task 5 s away
+    thread::sleep(std::time::Duration::from_millis(200));
+    let sent = Local::now();
+    // Due right now, along with a task due at the same date queued before it
+    controller
+        .batch("alerts", |batch| {
+            batch.add_task(once(sent, 2)).add_urgent(once(sent, 3));
+        })
+        .unwrap();
+    let fired = worker.join().unwrap();
+    let tasks: Vec<_> = fired.iter().map(|(task, _)| *task).collect();
+    assert_eq!(tasks, [3, 2, 1]);
+    // Within the latency of a command, long before the task it was sleeping toward
+    assert!(fired[0].1 - sent < Duration::milliseconds(200));
+    // The previous head fires at its date
+    assert!(fired[2].1 >= later);
+    assert!(fired[2].1 - later < Duration::milliseconds(200));
+}
+
+#[test]
+fn tasks_sharing_a_payload_are_cancelled_and_restored_by_id() {
+    let now = Local::now();
+    let tasks = vec![
+        once(now + Duration::hours(1), 7),
+        once(now + Duration::hours(2), 7),
+    ];
+    let mut scheduler =
+        BlockingScheduler::new(HashMap::from([("jobs".to_string(), tasks)]), HashMap::new());
+    let [first, second] =
+        [0, 1].map(|index| scheduler.scheduled_tasks["jobs"][index].id().unwrap());
+    assert_eq!(
+        scheduler.cancel_with_ttl("jobs", first, Duration::days(1)),
+        Ok(1)
+    );
+    assert_eq!(
+        scheduler.cancel_with_ttl("jobs", second, Duration::days(1)),
+        Ok(1)
+    );
+    assert_eq!(scheduler.pending_deletions("jobs").len(), 2);
+    assert_eq!(scheduler.undo_cancel("jobs", second), 1);
+    let kept: Vec<_> = scheduler.scheduled_tasks["jobs"]
+        .iter()
+        .map(|task| task.id())
+        .collect();
+    assert_eq!(kept, [Some(second)]);
+    assert_eq!(
+        scheduler.pending_deletions("jobs")[0].task.id(),
+        Some(first)
+    );
+}
+
+// Moves a Custom task by a fixed gap
+#[derive(Clone)]
+struct Gap(Duration);
+
+impl CustomRepetition for Gap {
+    fn update_date(
+        &self,
+        _: &DateTime<FixedOffset>,
+        current_date: &DateTime<FixedOffset>,
+    ) -> Option<DateTime<FixedOffset>> {
+        Some(*current_date + self.0)
+    }
+}
+
+#[test]
+fn custom_repetition_swapped_while_started_changes_the_cadence() {
+    let first = Local::now() + Duration::milliseconds(50);
+    let task = ScheduledTask::new(first, 0, RepetitionType::Custom, SleepType::Native);
+    let mut scheduler = BlockingScheduler::new_with_custom_repetition(
+        HashMap::from([("m".to_string(), vec![task])]),
+        HashMap::new(),
+        Gap(Duration::milliseconds(50)),
+    );
+    let controller = scheduler.controller();
+    let swap = scheduler.custom_repetition_swap(Gap(Duration::milliseconds(200)));
+    let stopper = scheduler.clone();
+    let mut swap = Some(swap);
+    let mut dates = Vec::new();
+    scheduler
+        .start_with_context("m", |context| {
+            dates.push(context.date);
+            // Sent between the first two firings, the task being already moved by the previous gap
+            if let Some(swap) = swap.take() {
+                controller.set_custom_repetition("m", swap).unwrap();
+            }
+            if dates.len() == 3 {
+                stopper.stop();
+            }
+        })
+        .unwrap();
+    assert_eq!(
+        dates,
+        [
+            first,
+            first + Duration::milliseconds(50),
+            first + Duration::milliseconds(250)
+        ]
+    );
+    assert_eq!(scheduler.custom_repetition().0, Duration::milliseconds(200));
+}
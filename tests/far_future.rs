@@ -0,0 +1,80 @@
+use chrono::{DateTime, Duration, FixedOffset, Local, TimeZone};
+use scheduler::prelude::*;
+use scheduler::repetitions::RepetitionHelpers;
+use std::collections::HashMap;
+use std::time::Instant;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| Local::now().into());
+
+fn at(date: &str) -> DateTime<FixedOffset> {
+    DateTime::parse_from_rfc3339(date).unwrap()
+}
+
+#[test]
+fn task_in_the_year_9999_is_waited_for_without_overflow() {
+    let year_9999 = Local.with_ymd_and_hms(9999, 12, 31, 23, 59, 59).unwrap();
+    let soon = Local::now() + Duration::milliseconds(20);
+    let tasks = vec![
+        ScheduledTask::new(soon, 1, RepetitionType::Once, SleepType::Native),
+        ScheduledTask::new(year_9999, 2, RepetitionType::Once, SleepType::Native),
+    ];
+    let mut scheduler =
+        BlockingScheduler::new(HashMap::from([("far".to_string(), tasks)]), HashMap::new());
+    let token = scheduler.cancellation_token();
+    let stopper = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        token.cancel();
+    });
+    let started = Instant::now();
+    let mut fired = Vec::new();
+    scheduler.start("far", |task| fired.push(*task)).unwrap();
+    stopper.join().unwrap();
+    // The sleep toward the year 9999 is cut into slices, the stop being seen right away
+    assert!(started.elapsed() < std::time::Duration::from_secs(2));
+    assert_eq!(fired, [1]);
+    assert_eq!(scheduler.scheduled_tasks["far"][0].date, year_9999);
+}
+
+#[test]
+fn gap_next_to_duration_max_saturates() {
+    let date = at("2024-01-01T09:00:00+01:00");
+    for gap in [
+        Duration::MAX,
+        Duration::MAX - Duration::milliseconds(1),
+        Duration::days(365 * 9000),
+    ] {
+        let repetition = RepetitionType::ConstGap {
+            gap,
+            termination: Termination::default(),
+        };
+        let next = repetition
+            .next_occurrence(&date, &date, &NoCustomRepetition)
+            .unwrap();
+        assert!(date < next);
+        let mut updated = date;
+        RepetitionHelpers::update_const_gap(&date, &mut updated, gap);
+        assert_eq!(updated, next);
+    }
+
+    // Once fired, the task is moved to the latest date instead of panicking
+    let repetition = RepetitionType::ConstGap {
+        gap: Duration::MAX,
+        termination: Termination::default(),
+    };
+    let task = ScheduledTask::new(date, 0, repetition, SleepType::Native);
+    let mut scheduler = BlockingScheduler::new(
+        HashMap::from([("gap".to_string(), vec![task])]),
+        HashMap::new(),
+    );
+    assert_eq!(scheduler.tick("gap", date, |_| {}).unwrap(), 1);
+    assert!(scheduler.scheduled_tasks["gap"]
+        .iter()
+        .all(|task| date < task.date));
+    assert_eq!(
+        scheduler
+            .tick("gap", at("9999-12-31T00:00:00+01:00"), |_| {})
+            .unwrap(),
+        0
+    );
+}
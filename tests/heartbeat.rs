@@ -0,0 +1,35 @@
+use chrono::{Duration, Local};
+use scheduler::prelude::*;
+use std::collections::HashMap;
+use std::sync::mpsc;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| Local::now().into());
+
+#[test]
+fn heartbeat_keeps_its_state_between_calls() {
+    let task = ScheduledTask::new(
+        Local::now() + Duration::milliseconds(300),
+        (),
+        RepetitionType::Once,
+        SleepType::Native,
+    );
+    let mut scheduler = BlockingScheduler::new(
+        HashMap::from([("jobs".to_string(), vec![task])]),
+        HashMap::new(),
+    );
+    let (beats, received) = mpsc::channel();
+    let mut count = 0;
+    scheduler.set_heartbeat(Some((
+        Duration::milliseconds(50),
+        Box::new(move || {
+            count += 1;
+            beats.send(count).unwrap();
+        }),
+    )));
+    scheduler.start("jobs", |_| {}).unwrap();
+    let beats: Vec<_> = received.try_iter().collect();
+    // About 6 heartbeats before the task, numbered by the closure itself
+    assert!(beats.len() >= 3, "{beats:?}");
+    assert!(beats.iter().copied().eq(1..=beats.len()));
+}
@@ -0,0 +1,56 @@
+// Wake-to-fire latency of a realtime mode firing 1000 times, ignored by default as it lasts about 10 s and depends on
+// the load of the machine: `cargo test --features spin_sleep --test latency -- --ignored`. The bound of the p99 is
+// SCHEDULER_LATENCY_P99_MS milliseconds, 50 by default.
+#![cfg(feature = "spin_sleep")]
+
+use chrono::{Duration, Local};
+use scheduler::prelude::*;
+use std::collections::HashMap;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| Local::now().into());
+
+#[test]
+#[ignore]
+fn realtime_p99_latency_stays_under_the_bound() {
+    let bound = std::env::var("SCHEDULER_LATENCY_P99_MS")
+        .ok()
+        .and_then(|milliseconds| milliseconds.parse().ok())
+        .map_or(
+            std::time::Duration::from_millis(50),
+            std::time::Duration::from_millis,
+        );
+    let every_10_ms = RepetitionType::ConstGap {
+        gap: Duration::milliseconds(10),
+        termination: Termination::default(),
+    };
+    let task = ScheduledTask::new(
+        Local::now() + Duration::milliseconds(10),
+        (),
+        every_10_ms,
+        SleepType::Spin,
+    );
+    let mut scheduler = BlockingScheduler::new(
+        HashMap::from([("audio".to_string(), vec![task])]),
+        HashMap::new(),
+    );
+    scheduler.config.realtime = true;
+    let cancellation_token = scheduler.cancellation_token();
+    let mut fired = 0;
+    scheduler
+        .start("audio", |_| {
+            fired += 1;
+            if fired == 1000 {
+                cancellation_token.cancel();
+            }
+        })
+        .unwrap();
+    let latency = scheduler.latency_report("audio").unwrap();
+    assert_eq!(latency.firings(), 1000);
+    let p99 = latency.percentile(99.0).unwrap();
+    assert!(
+        p99 < bound,
+        "p99 of {p99:?} (max {:?}) over {bound:?}",
+        latency.max()
+    );
+}
@@ -0,0 +1,170 @@
+use chrono::{DateTime, Duration, FixedOffset};
+use scheduler::prelude::*;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| chrono::Local::now().into());
+
+fn at(date: &str) -> DateTime<FixedOffset> {
+    DateTime::parse_from_rfc3339(date).unwrap()
+}
+
+fn ledgered(task: ScheduledTask<u32>, capacity: Option<usize>) -> BlockingScheduler<u32> {
+    let mut scheduler = BlockingScheduler::new([("m".to_string(), vec![task])], []);
+    scheduler.config.occurrence_ledger = capacity;
+    scheduler
+}
+
+fn hourly(date: DateTime<FixedOffset>) -> ScheduledTask<u32> {
+    let repetition = RepetitionType::ConstGap {
+        gap: Duration::hours(1),
+        termination: Termination::default(),
+    };
+    ScheduledTask::new(date, 0, repetition, SleepType::Native)
+}
+
+#[test]
+fn disabled_ledger_records_nothing() {
+    let first = at("2024-01-01T09:00:00+01:00");
+    let mut scheduler = ledgered(hourly(first), None);
+    scheduler.tick("m", first, |_| {}).unwrap();
+    assert!(scheduler.occurrence_ledger().is_empty());
+    scheduler.scheduled_tasks.get_mut("m").unwrap()[0].date = first;
+    assert_eq!(scheduler.tick("m", first, |_| {}).unwrap(), 1);
+}
+
+#[test]
+fn ledger_forgets_the_oldest_occurrences() {
+    let first = at("2024-01-01T09:00:00+01:00");
+    let mut scheduler = ledgered(hourly(first), Some(2));
+    for hours in 0..3 {
+        scheduler
+            .tick("m", first + Duration::hours(hours), |_| {})
+            .unwrap();
+    }
+    let id = scheduler.scheduled_tasks["m"][0].id().unwrap();
+    let ledger = scheduler.occurrence_ledger();
+    assert_eq!(ledger.len(), 2);
+    assert!(!ledger.contains(id, first));
+    assert!(ledger.contains(id, first + Duration::hours(1)));
+    assert!(ledger.contains(id, first + Duration::hours(2)));
+}
+
+// Restores from the snapshots of a scheduler
+#[cfg(feature = "json")]
+mod snapshot {
+    use super::*;
+    use std::sync::Mutex;
+
+    static OUTCOMES: Mutex<Vec<(OccurrenceOutcome, DateTime<FixedOffset>)>> =
+        Mutex::new(Vec::new());
+
+    fn record(outcome: OccurrenceOutcome, context: &TaskContext<'_, u32>) {
+        OUTCOMES.lock().unwrap().push((outcome, context.date));
+    }
+
+    // Copy of the scheduler sharing its ledger, as the one written by start_with_snapshots: once the original has
+    // fired, it holds the completed occurrence with the task still at its date
+    fn stale_snapshot(
+        scheduler: &mut BlockingScheduler<u32>,
+        fire_at: DateTime<FixedOffset>,
+    ) -> String {
+        let snapshot = scheduler.clone();
+        assert_eq!(scheduler.tick("m", fire_at, |_| {}).unwrap(), 1);
+        let saved = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(snapshot.scheduled_tasks["m"][0].date, fire_at);
+        saved
+    }
+
+    #[test]
+    fn restored_snapshot_doesnt_fire_a_completed_occurrence_again() {
+        let first = at("2024-01-01T09:00:00+01:00");
+        let mut scheduler = ledgered(hourly(first), Some(16));
+        let saved = stale_snapshot(&mut scheduler, first);
+
+        let mut restored: BlockingScheduler<u32> = serde_json::from_str(&saved).unwrap();
+        let id = restored.scheduled_tasks["m"][0].id().unwrap();
+        assert!(restored.occurrence_ledger().contains(id, first));
+        restored.config.missed_tick_behavior = MissedTickBehavior::FireAll;
+        restored.set_occurrence_hook(Some(record));
+        let mut fired = 0;
+        let report = restored
+            .catch_up("m", at("2024-01-01T11:30:00+01:00"), |_| fired += 1)
+            .unwrap();
+        // 09:00 was completed before the snapshot, 10:00 and 11:00 are replayed
+        assert_eq!((fired, report.replayed), (2, 2));
+        assert_eq!(
+            *OUTCOMES.lock().unwrap(),
+            [
+                (OccurrenceOutcome::AlreadyCompleted, first),
+                (OccurrenceOutcome::CaughtUp, at("2024-01-01T10:00:00+01:00")),
+                (OccurrenceOutcome::CaughtUp, at("2024-01-01T11:00:00+01:00")),
+            ]
+        );
+        assert_eq!(
+            restored.scheduled_tasks["m"][0].date,
+            at("2024-01-01T12:00:00+01:00")
+        );
+        assert_eq!(restored.occurrence_ledger().len(), 3);
+    }
+
+    #[test]
+    fn restored_snapshot_skips_a_completed_occurrence_when_started() {
+        let soon = (chrono::Local::now() + Duration::milliseconds(50)).fixed_offset();
+        let task = ScheduledTask::new(soon, 0, RepetitionType::Once, SleepType::Native);
+        let mut scheduler = ledgered(task, Some(16));
+        let saved = stale_snapshot(&mut scheduler, soon);
+
+        let mut restored: BlockingScheduler<u32> = serde_json::from_str(&saved).unwrap();
+        let mut fired = 0;
+        restored.start("m", |_| fired += 1).unwrap();
+        assert_eq!(fired, 0);
+        assert!(restored.scheduled_tasks["m"].is_empty());
+        assert_eq!(
+            restored.removed_tasks["m"][0].removal_reason,
+            Some(RemovalReason::Finished)
+        );
+    }
+
+    #[test]
+    fn occurrence_started_before_a_crash_is_replayed_with_its_token() {
+        let path = std::env::temp_dir().join("scheduler_ledger_crash_test.json");
+        let soon = (chrono::Local::now() + Duration::milliseconds(50)).fixed_offset();
+        let task = ScheduledTask::new(soon, 0, RepetitionType::Once, SleepType::Native);
+        let mut scheduler = ledgered(task, Some(16));
+        let id = scheduler.scheduled_tasks["m"][0].id().unwrap();
+        // The snapshot as the callback finds it, the process crashing before the callback returns
+        let mut crashed = None;
+        scheduler
+            .start_with_snapshots(
+                "m",
+                |_| crashed = Some(std::fs::read_to_string(&path).unwrap()),
+                &path,
+                std::time::Duration::from_secs(3600),
+            )
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(scheduler.occurrence_ledger().contains(id, soon));
+
+        let mut restored: BlockingScheduler<u32> = serde_json::from_str(&crashed.unwrap()).unwrap();
+        assert!(!restored.occurrence_ledger().contains(id, soon));
+        restored.config.missed_tick_behavior = MissedTickBehavior::FireOnce;
+        let later = (chrono::Local::now() + Duration::milliseconds(50)).fixed_offset();
+        restored.add_task(
+            "m",
+            ScheduledTask::new(later, 1, RepetitionType::Once, SleepType::Native),
+        );
+        let mut contexts = Vec::new();
+        restored
+            .start_with_context("m", |context| {
+                contexts.push((
+                    *context.task,
+                    context.execution_token(),
+                    context.is_replay(),
+                ))
+            })
+            .unwrap();
+        // Fired again with the token of its first start, the new occurrence getting the next token
+        assert_eq!(contexts, [(0, Some(0), true), (1, Some(1), false)]);
+        assert!(restored.occurrence_ledger().contains(id, soon));
+    }
+}
@@ -0,0 +1,62 @@
+// Name, description and dates of a BlockingScheduler
+use chrono::{Duration, Local};
+use scheduler::prelude::*;
+use std::collections::HashMap;
+use std::thread;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| Local::now().into());
+
+fn once(in_ms: i64, task: u32) -> ScheduledTask<u32> {
+    ScheduledTask::new(
+        Local::now() + Duration::milliseconds(in_ms),
+        task,
+        RepetitionType::Once,
+        SleepType::Native,
+    )
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn metadata_survives_a_serde_round_trip() {
+    let mut scheduler = BlockingScheduler::new(
+        HashMap::from([("m".to_string(), vec![once(3_600_000, 1)])]),
+        HashMap::new(),
+    );
+    scheduler.set_name(Some("backups".to_string()));
+    scheduler.set_description(Some("Nightly copies of the databases".to_string()));
+    thread::sleep(std::time::Duration::from_millis(5));
+    scheduler.add_task("m", once(7_200_000, 2));
+    assert!(scheduler.modified_at() > scheduler.created_at());
+
+    let saved = serde_json::to_string(&scheduler).unwrap();
+    let restored: BlockingScheduler<u32> = serde_json::from_str(&saved).unwrap();
+    assert_eq!(restored.name(), Some("backups"));
+    assert_eq!(
+        restored.description(),
+        Some("Nightly copies of the databases")
+    );
+    assert_eq!(restored.created_at(), scheduler.created_at());
+    assert_eq!(restored.modified_at(), scheduler.modified_at());
+}
+
+#[test]
+fn modified_at_follows_the_tasks_added_not_the_runs() {
+    let mut scheduler = BlockingScheduler::new(
+        HashMap::from([("m".to_string(), vec![once(10, 1)])]),
+        HashMap::new(),
+    );
+    let created = scheduler.modified_at();
+    assert_eq!(created, scheduler.created_at());
+
+    thread::sleep(std::time::Duration::from_millis(5));
+    scheduler.add_task("m", once(20, 2));
+    let added = scheduler.modified_at();
+    assert!(added > created);
+
+    let mut fired = Vec::new();
+    scheduler.start("m", |task| fired.push(*task)).unwrap();
+    assert_eq!(fired, [1, 2]);
+    assert_eq!(scheduler.modified_at(), added);
+    assert_eq!(scheduler.created_at(), created);
+}
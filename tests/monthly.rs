@@ -0,0 +1,135 @@
+use chrono::{DateTime, FixedOffset, NaiveTime};
+use scheduler::prelude::*;
+use scheduler::wall::{DayRule, MonthOverflow, WallSchedule, WallZone};
+use std::collections::HashMap;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| chrono::Local::now().into());
+
+fn at(date: &str) -> DateTime<FixedOffset> {
+    DateTime::parse_from_rfc3339(date).unwrap()
+}
+
+fn monthly(date: &str) -> ScheduledTask<u32> {
+    let repetition = RepetitionType::Monthly {
+        day: None,
+        termination: Termination::default(),
+    };
+    ScheduledTask::new(at(date), 0, repetition, SleepType::Native)
+}
+
+fn monthly_scheduler(
+    task: ScheduledTask<u32>,
+    behavior: MissedTickBehavior,
+) -> BlockingScheduler<u32> {
+    let mut scheduler = BlockingScheduler::new(
+        HashMap::from([("m".to_string(), vec![task])]),
+        HashMap::new(),
+    );
+    scheduler.config.missed_tick_behavior = behavior;
+    scheduler
+}
+
+fn next_date(scheduler: &BlockingScheduler<u32>) -> DateTime<FixedOffset> {
+    scheduler.scheduled_tasks["m"][0].date
+}
+
+#[test]
+fn monthly_on_the_31st_comes_back_after_february() {
+    for (year, end_of_february) in [(2023, 28), (2024, 29)] {
+        let mut scheduler = monthly_scheduler(
+            monthly(&format!("{}-01-31T09:00:00+01:00", year)),
+            MissedTickBehavior::Skip,
+        );
+        let mut dates = Vec::new();
+        for _ in 0..5 {
+            let date = next_date(&scheduler);
+            assert_eq!(scheduler.tick("m", date, |_| {}).unwrap(), 1);
+            dates.push(next_date(&scheduler));
+        }
+        let expected = [(2, end_of_february), (3, 31), (4, 30), (5, 31), (6, 30)]
+            .map(|(month, day)| at(&format!("{}-{:02}-{}T09:00:00+01:00", year, month, day)));
+        assert_eq!(dates, expected);
+    }
+}
+
+#[test]
+fn monthly_catch_up_lands_on_the_same_day_as_the_ticks() {
+    // Caught up in the middle of June from the 31st of January: the 30th of June, as when ticked every month
+    let mut scheduler = monthly_scheduler(
+        monthly("2023-01-31T09:00:00+01:00"),
+        MissedTickBehavior::FireAll,
+    );
+    let mut fired = 0;
+    scheduler
+        .catch_up("m", at("2023-06-15T12:00:00+01:00"), |_| fired += 1)
+        .unwrap();
+    assert_eq!(fired, 5);
+    assert_eq!(next_date(&scheduler), at("2023-06-30T09:00:00+01:00"));
+    assert_eq!(
+        scheduler.tick("m", next_date(&scheduler), |_| {}).unwrap(),
+        1
+    );
+    assert_eq!(next_date(&scheduler), at("2023-07-31T09:00:00+01:00"));
+
+    // A task saved on the clamped day keeps its day
+    let mut task = monthly("2023-01-31T09:00:00+01:00");
+    task.date = at("2023-02-28T09:00:00+01:00");
+    let mut scheduler = monthly_scheduler(task, MissedTickBehavior::FireOnce);
+    scheduler
+        .catch_up("m", at("2023-04-02T12:00:00+01:00"), |_| {})
+        .unwrap();
+    assert_eq!(next_date(&scheduler), at("2023-04-30T09:00:00+01:00"));
+}
+
+#[test]
+fn monthly_schedule_after_the_28th_is_clamped() {
+    let nine = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+    let schedule = WallSchedule::new(
+        DayRule::DayOfMonth(31),
+        vec![nine],
+        WallZone::Fixed(FixedOffset::east_opt(3600).unwrap()),
+    )
+    .unwrap();
+    assert!(schedule.slots(at("2023-02-10T00:00:00+01:00")).is_err());
+
+    let schedule = schedule.with_overflow(MonthOverflow::Clamp).unwrap();
+    let slots = schedule.slots(at("2023-02-10T00:00:00+01:00")).unwrap();
+    let (date, repetition) = slots[0].clone();
+    assert_eq!(date, at("2023-02-28T09:00:00+01:00"));
+    let mut scheduler = monthly_scheduler(
+        ScheduledTask::new(date, 0, repetition, SleepType::Native),
+        MissedTickBehavior::Skip,
+    );
+    scheduler.tick("m", date, |_| {}).unwrap();
+    assert_eq!(next_date(&scheduler), at("2023-03-31T09:00:00+01:00"));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn monthly_keeps_its_day_through_serde() {
+    let task = monthly("2024-01-31T09:00:00+01:00");
+    let saved = serde_json::to_string(&task.repetition).unwrap();
+    assert_eq!(
+        saved,
+        r#"{"Monthly":{"day":31,"count":"Infinite","until":null}}"#
+    );
+    assert_eq!(
+        serde_json::from_str::<RepetitionType>(&saved).unwrap(),
+        task.repetition
+    );
+    // The forms written before the day existed
+    let unanchored = RepetitionType::Monthly {
+        day: None,
+        termination: Termination::default(),
+    };
+    for saved in [
+        r#"{"Monthly":{"count":"Infinite","until":null}}"#,
+        r#"{"Monthly":"Infinite"}"#,
+    ] {
+        assert_eq!(
+            serde_json::from_str::<RepetitionType>(saved).unwrap(),
+            unanchored
+        );
+    }
+}
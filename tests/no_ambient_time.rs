@@ -0,0 +1,119 @@
+// Runs of the scheduler determined by a ManualClock, nothing being read from the time of the system
+#![cfg(feature = "no-ambient-time")]
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveTime};
+use scheduler::every::Every;
+use scheduler::prelude::*;
+use scheduler::sleeper::{Clock, ManualClock, SleepOutcome, Sleeper};
+use std::sync::{LazyLock, Mutex, MutexGuard, PoisonError};
+
+fn at(date: &str) -> DateTime<FixedOffset> {
+    DateTime::parse_from_rfc3339(date).unwrap()
+}
+
+// Clock of the whole test binary
+static CLOCK: LazyLock<ManualClock> =
+    LazyLock::new(|| ManualClock::new(at("2024-01-01T00:00:00+01:00")));
+scheduler::system_clock!(|| CLOCK.now());
+
+// The clock of the binary set at the date. The tests hold the guard while they run, one at a time.
+fn clock(now: &str) -> (MutexGuard<'static, ()>, ManualClock) {
+    static SERIAL: Mutex<()> = Mutex::new(());
+    let serial = SERIAL.lock().unwrap_or_else(PoisonError::into_inner);
+    CLOCK.set(at(now));
+    (serial, CLOCK.clone())
+}
+
+// Moves the clock to the date it's asked to sleep until, so that a start doesn't wait
+struct Jump(ManualClock);
+
+impl Sleeper for Jump {
+    fn sleep_until(
+        &self,
+        target: DateTime<FixedOffset>,
+        _: &dyn Clock,
+        cancel: &CancelToken,
+    ) -> SleepOutcome {
+        if cancel.is_cancelled() {
+            return SleepOutcome::Cancelled;
+        }
+        if self.0.now() < target {
+            self.0.set(target);
+        }
+        SleepOutcome::ReachedTarget
+    }
+}
+
+fn hourly(date: &str, count: u64) -> ScheduledTask<&'static str> {
+    let repetition = RepetitionType::ConstGap {
+        gap: Duration::hours(1),
+        termination: RepetitionCount::Finished(count).into(),
+    };
+    ScheduledTask::new(at(date), "hourly", repetition, SleepType::Native)
+}
+
+#[test]
+fn start_runs_on_the_manual_clock() {
+    let (_serial, clock) = clock("2024-01-01T08:00:00+01:00");
+    let tasks = vec![
+        hourly("2024-01-01T09:00:00+01:00", 3),
+        ScheduledTask::new(
+            at("2024-01-01T09:30:00+01:00"),
+            "once",
+            RepetitionType::Once,
+            SleepType::Native,
+        ),
+    ];
+    let mut scheduler = BlockingScheduler::new([("m".to_string(), tasks)], []);
+    assert_eq!(scheduler.created_at(), at("2024-01-01T08:00:00+01:00"));
+    scheduler.set_sleeper(Jump(clock.clone()));
+    let mut fired = Vec::new();
+    scheduler
+        .start("m", |task| fired.push((*task, clock.now())))
+        .unwrap();
+    assert_eq!(
+        fired,
+        [
+            ("hourly", at("2024-01-01T09:00:00+01:00")),
+            ("once", at("2024-01-01T09:30:00+01:00")),
+            ("hourly", at("2024-01-01T10:00:00+01:00")),
+            ("hourly", at("2024-01-01T11:00:00+01:00")),
+        ]
+    );
+    assert_eq!(scheduler.removed_tasks["m"].len(), 2);
+}
+
+#[test]
+fn start_catches_up_at_the_manual_clock() {
+    let (_serial, clock) = clock("2024-01-01T12:30:00+01:00");
+    let mut scheduler = BlockingScheduler::new(
+        [(
+            "m".to_string(),
+            vec![hourly("2024-01-01T09:00:00+01:00", 5)],
+        )],
+        [],
+    );
+    scheduler.config.missed_tick_behavior = MissedTickBehavior::FireAll;
+    scheduler.set_sleeper(Jump(clock.clone()));
+    let mut fired = Vec::new();
+    scheduler.start("m", |_| fired.push(clock.now())).unwrap();
+    // 09:00 to 12:00 replayed at 12:30, then 13:00 at its date
+    let mut expected = vec![at("2024-01-01T12:30:00+01:00"); 4];
+    expected.push(at("2024-01-01T13:00:00+01:00"));
+    assert_eq!(fired, expected);
+    assert_eq!(scheduler.catch_up_report("m").unwrap().replayed, 4);
+}
+
+#[test]
+fn local_times_are_read_in_the_offset_of_the_clock() {
+    let (_serial, _clock) = clock("2024-03-01T10:15:00+05:00");
+    let nine = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+    let job = Every::day_at(nine)
+        .unwrap()
+        .with_payload(0_u32)
+        .spawn(|_| {})
+        .unwrap();
+    assert_eq!(job.next_fire(), Some(at("2024-03-02T09:00:00+05:00")));
+    job.stop();
+    job.join().unwrap();
+}
@@ -0,0 +1,30 @@
+use chrono::{Duration, Local};
+use scheduler::prelude::*;
+use std::collections::HashMap;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| Local::now().into());
+
+#[test]
+fn unsorted_mode_fires_in_date_order() {
+    let now = Local::now();
+    let once = |in_ms, task| {
+        ScheduledTask::new(
+            now + Duration::milliseconds(in_ms),
+            task,
+            RepetitionType::Once,
+            SleepType::Native,
+        )
+    };
+    let mut scheduler = BlockingScheduler::new(HashMap::new(), HashMap::new());
+    // Built by hand, the later task first
+    scheduler
+        .scheduled_tasks
+        .insert("reversed".to_string(), vec![once(200, 2), once(100, 1)]);
+    let mut fired = Vec::new();
+    scheduler
+        .start("reversed", |task| fired.push(*task))
+        .unwrap();
+    assert_eq!(fired, [1, 2]);
+    assert!(scheduler.scheduled_tasks["reversed"].is_empty());
+}
@@ -0,0 +1,226 @@
+use chrono::{DateTime, Duration, FixedOffset, Local};
+use scheduler::prelude::*;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| Local::now().into());
+
+fn task(in_ms: i64, task: u32) -> ScheduledTask<u32> {
+    ScheduledTask::new(
+        Local::now() + Duration::milliseconds(in_ms),
+        task,
+        RepetitionType::Once,
+        SleepType::Native,
+    )
+}
+
+// The tasks left by a panicking callback are late once the mode is started again
+fn late_fired(modes: [(String, Vec<ScheduledTask<u32>>); 1]) -> ParallelScheduler<'static, u32> {
+    let mut scheduler = BlockingScheduler::new(modes, []);
+    scheduler.config.missed_tick_behavior = MissedTickBehavior::FireOnce;
+    ParallelScheduler::from(scheduler)
+}
+
+#[test]
+fn panicking_callback_gives_the_mode_back() {
+    static PANICKED: AtomicBool = AtomicBool::new(false);
+    static FIRED: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+    fn panics_once(task: &u32) {
+        if !PANICKED.swap(true, Ordering::SeqCst) {
+            panic!("first call");
+        }
+        FIRED.lock().unwrap().push(*task);
+    }
+
+    let mut parallel = late_fired([("m".to_string(), vec![task(10, 1), task(20, 2)])]);
+    parallel.start("m".to_string(), panics_once).unwrap();
+    let summary = parallel.join_all();
+    let error = summary.outcomes["m"].error.as_ref().unwrap();
+    assert!(error.to_string().contains("first call"), "{}", error);
+    // The task whose callback panicked is left at its occurrence, with the one after it
+    assert_eq!(parallel.scheduler().scheduled_tasks["m"].len(), 2);
+    assert_eq!(summary.outcomes["m"].remaining.len(), 2);
+
+    parallel.start("m".to_string(), panics_once).unwrap();
+    let summary = parallel.join_all();
+    assert!(summary.results.iter().all(Result::is_ok));
+    assert_eq!(*FIRED.lock().unwrap(), [1, 2]);
+    assert!(parallel.scheduler().scheduled_tasks["m"].is_empty());
+}
+
+#[test]
+fn panicking_catch_up_keeps_the_late_tasks() {
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    fn panics_first(_: &u32) {
+        if CALLS.fetch_add(1, Ordering::SeqCst) == 0 {
+            panic!("catching up");
+        }
+    }
+
+    let mut parallel = late_fired([("late".to_string(), vec![task(-60_000, 1), task(-30_000, 2)])]);
+    parallel.start("late".to_string(), panics_first).unwrap();
+    let summary = parallel.join_all();
+    assert!(summary.outcomes["late"].error.is_some());
+    assert!(
+        matches!(&summary.results[..], [Err(SchedulerError::Failed { mode, .. })] if mode == "late")
+    );
+    assert_eq!(parallel.scheduler().scheduled_tasks["late"].len(), 2);
+
+    parallel.start("late".to_string(), panics_first).unwrap();
+    assert!(parallel.join_all().results.iter().all(Result::is_ok));
+    assert_eq!(CALLS.load(Ordering::SeqCst), 3);
+    assert_eq!(parallel.scheduler().removed_tasks["late"].len(), 2);
+}
+
+#[test]
+fn panicking_scoped_worker_gives_the_mode_back() {
+    let mut parallel = late_fired([("m".to_string(), vec![task(10, 1), task(20, 2)])]);
+    let ((), summary) = parallel.scope(|workers| {
+        workers.start("m", |_| panic!("scoped")).unwrap();
+    });
+    assert!(summary.outcomes["m"].error.is_some());
+    assert_eq!(summary.outcomes["m"].remaining.len(), 2);
+    assert_eq!(parallel.scheduler().scheduled_tasks["m"].len(), 2);
+
+    let fired = Mutex::new(Vec::new());
+    parallel.scope(|workers| {
+        workers
+            .start("m", |task| fired.lock().unwrap().push(*task))
+            .unwrap();
+    });
+    assert_eq!(fired.into_inner().unwrap(), [1, 2]);
+}
+
+// Never moves past its date: the catch-up pass of a mode dated in the past fails
+#[derive(Clone)]
+struct Stuck;
+
+impl CustomRepetition for Stuck {
+    fn update_date(
+        &self,
+        _: &DateTime<FixedOffset>,
+        date: &DateTime<FixedOffset>,
+    ) -> Option<DateTime<FixedOffset>> {
+        Some(*date)
+    }
+}
+
+#[test]
+fn failing_mode_stops_the_others() {
+    let mut parallel = ParallelScheduler::new_with_custom_repetition(
+        [
+            (
+                "poisoned".to_string(),
+                vec![ScheduledTask::new(
+                    Local::now() - Duration::minutes(1),
+                    1,
+                    RepetitionType::Custom,
+                    SleepType::Native,
+                )],
+            ),
+            ("later".to_string(), vec![task(3_600_000, 2)]),
+        ],
+        [],
+        Stuck,
+    );
+    parallel.set_failure_policy(FailurePolicy::StopAll);
+    let started = Instant::now();
+    parallel.start("poisoned".to_string(), |_| {}).unwrap();
+    parallel.start("later".to_string(), |_| {}).unwrap();
+    let summary = parallel.join_all();
+    // Long before the task of the other mode
+    assert!(started.elapsed() < std::time::Duration::from_secs(2));
+    assert_eq!(summary.failed_mode.as_deref(), Some("poisoned"));
+    assert_eq!(summary.cancelled_modes, ["later"]);
+    assert!(summary.outcomes["poisoned"].error.is_some());
+    assert!(summary.outcomes["later"].error.is_none());
+    assert_eq!(parallel.scheduler().scheduled_tasks["later"].len(), 1);
+
+    // The failure has only stopped the workers of that run: started again, the mode waits for its task
+    assert!(!parallel.is_stopped());
+    parallel.start("later".to_string(), |_| {}).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    assert!(parallel.reap_finished().is_empty());
+    parallel.stop();
+    let summary = parallel.join_all();
+    assert!(summary.outcomes["later"].error.is_none());
+    assert_eq!(summary.failed_mode, None);
+    assert_eq!(parallel.scheduler().scheduled_tasks["later"].len(), 1);
+}
+
+// Counts the locks with the test-util feature
+#[cfg(feature = "test-util")]
+#[test]
+fn removed_tasks_are_handed_over_by_batches() {
+    let soon = Local::now() + Duration::milliseconds(50);
+    let modes = (0..4).map(|mode| {
+        let tasks = (0..2_500)
+            .map(|task| ScheduledTask::new(soon, task, RepetitionType::Once, SleepType::Native));
+        (format!("mode {}", mode), tasks.collect())
+    });
+    let mut parallel = ParallelScheduler::new(modes, []);
+    for mode in 0..4 {
+        parallel.start(format!("mode {}", mode), |_| {}).unwrap();
+    }
+    assert!(parallel.join_all().results.iter().all(Result::is_ok));
+    // Far fewer than one lock per removal, join_all included
+    let locks = parallel.removal_locks();
+    assert!(locks < 10_000 / 10, "{} locks", locks);
+    let removed_tasks = parallel.removed_tasks();
+    assert_eq!(parallel.removal_locks(), locks + 1);
+    for mode in 0..4 {
+        let mut removed: Vec<u32> = removed_tasks[&format!("mode {}", mode)]
+            .iter()
+            .map(|task| task.task)
+            .collect();
+        removed.sort_unstable();
+        assert_eq!(removed, (0..2_500).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn thread_setups_run_on_the_workers() {
+    let ran_on = Arc::new(Mutex::new(Vec::new()));
+    // Records the thread it runs on
+    let setup = |result: Result<(), String>| -> ThreadSetup {
+        let ran_on = ran_on.clone();
+        Arc::new(move || {
+            let name = thread::current().name().map(str::to_owned);
+            ran_on.lock().unwrap().push(name);
+            result.clone()
+        })
+    };
+    let scheduler = BlockingScheduler::new(
+        [
+            ("failing".to_string(), vec![task(10, 1)]),
+            ("succeeding".to_string(), vec![task(10, 2)]),
+        ],
+        [],
+    );
+    let mut parallel = ParallelScheduler::from(scheduler);
+    parallel.set_thread_setup("failing", Some(setup(Err("No priority".to_string()))));
+    parallel.set_thread_setup("succeeding", Some(setup(Ok(()))));
+    parallel.start("failing".to_string(), |_| {}).unwrap();
+    parallel.start("succeeding".to_string(), |_| {}).unwrap();
+    let summary = parallel.join_all();
+    // The failing setup doesn't prevent its worker from running
+    assert!(summary.results.iter().all(Result::is_ok));
+    assert_eq!(summary.outcomes["failing"].summary.fired, 1);
+    assert_eq!(
+        parallel.thread_setup_error("failing").as_deref(),
+        Some("No priority")
+    );
+    assert_eq!(parallel.thread_setup_error("succeeding"), None);
+
+    // Run by the scoped workers as well, a success clearing the previous error
+    parallel.set_thread_setup("failing", Some(setup(Ok(()))));
+    parallel.scope(|workers| workers.start("failing", |_| {}).unwrap());
+    assert_eq!(parallel.thread_setup_error("failing"), None);
+    assert_eq!(
+        *ran_on.lock().unwrap(),
+        vec![Some("ThreadScheduler".to_string()); 3]
+    );
+}
@@ -0,0 +1,56 @@
+// Lock of the snapshots loaded through a PersistedScheduler
+#![cfg(feature = "json")]
+
+use chrono::DateTime;
+use scheduler::persistence::{PersistError, PersistedScheduler};
+use scheduler::prelude::*;
+use std::path::PathBuf;
+use std::thread;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| chrono::Local::now().into());
+
+fn snapshot(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(name);
+    let at = DateTime::parse_from_rfc3339("2024-01-01T09:00:00+01:00").unwrap();
+    let task = ScheduledTask::new(at, 1, RepetitionType::Once, SleepType::Native);
+    let scheduler = BlockingScheduler::<u32>::new([("jobs".to_string(), vec![task])], []);
+    std::fs::write(&path, serde_json::to_string(&scheduler).unwrap()).unwrap();
+    path
+}
+
+#[test]
+fn second_lock_attempt_is_refused_until_the_first_is_dropped() {
+    let path = snapshot("scheduler_persistence_lock_test.json");
+    let (persisted, warnings) = PersistedScheduler::<u32>::load(&path, None).unwrap();
+    assert!(warnings.is_empty());
+    assert!(!persisted.is_read_only());
+
+    let second = path.clone();
+    let refused = thread::spawn(move || PersistedScheduler::<u32>::load(&second, None).map(|_| ()))
+        .join()
+        .unwrap();
+    assert_eq!(
+        refused,
+        Err(PersistError::AlreadyLocked {
+            pid_hint: Some(std::process::id())
+        })
+    );
+    // Looked at all the same
+    let (inspected, _) = PersistedScheduler::<u32>::load_read_only(&path, None).unwrap();
+    assert_eq!(
+        inspected.scheduler.scheduled_tasks,
+        persisted.scheduler.scheduled_tasks
+    );
+    assert_eq!(inspected.save(), Err(PersistError::ReadOnly));
+
+    persisted.save().unwrap();
+    drop(persisted);
+    let second = path.clone();
+    let acquired = thread::spawn(move || PersistedScheduler::<u32>::load(&second, None).is_ok())
+        .join()
+        .unwrap();
+    assert!(acquired);
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(path.with_extension("json.lock")).unwrap();
+}
@@ -0,0 +1,100 @@
+// Surface of the prelude: every item is imported by name, so that removing or renaming one breaks this build
+
+#[cfg(feature = "spin_sleep")]
+use scheduler::prelude::SpinConfig;
+use scheduler::prelude::{
+    BackpressurePolicy, BlackoutPolicy, BlockingScheduler, CancelToken, CancellationToken,
+    CatchUpOrder, CatchUpReport, CommandBatch, ConcurrentStart, CustomRepetition, Every,
+    ExecutionRecord, FailurePolicy, FiredTask, Gate, GateReport, HandoffReport, HolidayPolicy,
+    HolidayProvider, JoinSummary, LagReport, LatencyReport, MissedTickBehavior, ModeConfig,
+    ModeHandoff, ModeRunOutcome, NextRun, NoCustomRepetition, OccurrenceOutcome, OffsetSummary,
+    OrphanRemovedTasks, OverrideHandler, ParallelScheduler, PendingDeletion, RebasePolicy,
+    RemovalFlush, RemovalReason, RepetitionCount, RepetitionType, RunSummary, RunningJob,
+    ScheduledTask, SchedulerCommand, SchedulerConfig, SchedulerController, SchedulerError,
+    SharedGate, SleepType, StaticSetProvider, TaskContext, TaskId, TaskResolver, TaskSource,
+    Termination, ThreadSetup, ValidationError, VecSource, WallSchedule, WeekParity,
+    WeekendProvider,
+};
+use std::any::type_name;
+use std::collections::HashMap;
+
+// The traits of the prelude, as bounds
+fn implements_the_traits<
+    Repetition: CustomRepetition,
+    TaskGate: Gate<u32>,
+    Holidays: HolidayProvider,
+    Resolver: TaskResolver<String, u32>,
+    Source: TaskSource<u32>,
+>() {
+}
+
+#[test]
+fn prelude_items_are_exported() {
+    // Named as types, the build being the check
+    let _ = [
+        type_name::<BackpressurePolicy>(),
+        type_name::<BlackoutPolicy>(),
+        type_name::<BlockingScheduler<u32>>(),
+        type_name::<CancelToken>(),
+        type_name::<CancellationToken>(),
+        type_name::<CatchUpOrder>(),
+        type_name::<CatchUpReport>(),
+        type_name::<CommandBatch<u32>>(),
+        type_name::<ConcurrentStart>(),
+        type_name::<Every>(),
+        type_name::<ExecutionRecord>(),
+        type_name::<FailurePolicy>(),
+        type_name::<FiredTask<u32>>(),
+        type_name::<GateReport>(),
+        type_name::<HandoffReport<u32>>(),
+        type_name::<HolidayPolicy>(),
+        type_name::<JoinSummary>(),
+        type_name::<LagReport>(),
+        type_name::<LatencyReport>(),
+        type_name::<MissedTickBehavior>(),
+        type_name::<ModeConfig>(),
+        type_name::<ModeHandoff<u32>>(),
+        type_name::<ModeRunOutcome>(),
+        type_name::<NextRun>(),
+        type_name::<NoCustomRepetition>(),
+        type_name::<OccurrenceOutcome>(),
+        type_name::<OffsetSummary>(),
+        type_name::<OrphanRemovedTasks>(),
+        type_name::<OverrideHandler<u32>>(),
+        type_name::<ParallelScheduler<'static, u32>>(),
+        type_name::<PendingDeletion<u32>>(),
+        type_name::<RebasePolicy>(),
+        type_name::<RemovalFlush>(),
+        type_name::<RemovalReason>(),
+        type_name::<RepetitionCount>(),
+        type_name::<RepetitionType>(),
+        type_name::<RunSummary>(),
+        type_name::<RunningJob<u32>>(),
+        type_name::<ScheduledTask<u32>>(),
+        type_name::<SchedulerCommand<u32>>(),
+        type_name::<SchedulerConfig>(),
+        type_name::<SchedulerController<u32>>(),
+        type_name::<SchedulerError>(),
+        type_name::<SharedGate<u32>>(),
+        type_name::<SleepType>(),
+        #[cfg(feature = "spin_sleep")]
+        type_name::<SpinConfig>(),
+        type_name::<StaticSetProvider>(),
+        type_name::<TaskContext<'static, u32>>(),
+        type_name::<TaskId>(),
+        type_name::<Termination>(),
+        type_name::<ThreadSetup>(),
+        type_name::<ValidationError>(),
+        type_name::<VecSource<u32>>(),
+        type_name::<WallSchedule>(),
+        type_name::<WeekParity>(),
+        type_name::<WeekendProvider>(),
+    ];
+    implements_the_traits::<
+        NoCustomRepetition,
+        fn(&u32) -> bool,
+        WeekendProvider,
+        HashMap<String, u32>,
+        VecSource<u32>,
+    >();
+}
@@ -0,0 +1,83 @@
+// The same firings, told by a FiringStream and by the journal
+#![cfg(all(feature = "tokio", feature = "journal"))]
+
+use chrono::{Duration, Local};
+use scheduler::journal::JournalEvent;
+use scheduler::prelude::*;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| Local::now().into());
+
+fn scheduler() -> BlockingScheduler<String> {
+    let start = Local::now() + Duration::milliseconds(100);
+    let twice = RepetitionType::ConstGap {
+        gap: Duration::milliseconds(50),
+        termination: RepetitionCount::Finished(2).into(),
+    };
+    let tasks = vec![
+        ScheduledTask::new(start, "backup".to_string(), twice, SleepType::Native),
+        ScheduledTask::new(
+            start + Duration::milliseconds(20),
+            "report".to_string(),
+            RepetitionType::Once,
+            SleepType::Native,
+        ),
+    ];
+    BlockingScheduler::new([("m".to_string(), tasks)], [])
+}
+
+#[test]
+fn stream_items_and_journal_lines_are_the_same_records() {
+    let scheduler = scheduler();
+    let mut journaled = scheduler.clone();
+    // Run side by side, each of them firing the tasks at their date
+    let journaling = std::thread::spawn(move || {
+        let mut journal = Vec::new();
+        journaled
+            .start_with_journal("m", |_| {}, &mut journal)
+            .unwrap();
+        journal
+    });
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .unwrap();
+    let streamed = runtime.block_on(async {
+        let mut firings = scheduler.into_stream("m").unwrap();
+        let mut streamed = Vec::new();
+        while let Some(fired) = firings.next().await {
+            streamed.push(fired);
+        }
+        streamed
+    });
+
+    let journal = journaling.join().unwrap();
+    let mut written: Vec<FiredTask<String>> = String::from_utf8(journal)
+        .unwrap()
+        .lines()
+        .filter_map(|line| match serde_json::from_str(line).unwrap() {
+            JournalEvent::Fired { fired, .. } => Some(fired),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(written.len(), 3);
+    assert_eq!(streamed.len(), 3);
+    let indexes: Vec<_> = streamed
+        .iter()
+        .map(|fired| (fired.task.as_str(), fired.record.occurrence_index))
+        .collect();
+    assert_eq!(indexes, [("backup", 0), ("report", 0), ("backup", 1)]);
+    assert!(streamed.iter().all(|fired| fired.record.task_id.is_some()));
+    // Both runs fire the tasks at their own instant, the gap of the repeated task being counted from it: the rest of the
+    // records is the same
+    for (written, streamed) in written.iter_mut().zip(&streamed) {
+        written.record.fired_at = streamed.record.fired_at;
+        written.record.latency = streamed.record.latency;
+        if streamed.record.occurrence_index > 0 {
+            written.record.scheduled_for = streamed.record.scheduled_for;
+        }
+    }
+    assert_eq!(written, streamed);
+}
@@ -0,0 +1,124 @@
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, TimeZone, Timelike, Weekday};
+use proptest::prelude::*;
+use scheduler::prelude::*;
+
+const FIFTY_YEARS: i64 = 50 * 366 * 24 * 3600;
+
+// Custom repetition of the suite, every 90 minutes
+struct NinetyMinutes;
+
+impl CustomRepetition for NinetyMinutes {
+    fn update_date(
+        &self,
+        origin: &DateTime<FixedOffset>,
+        current_date: &DateTime<FixedOffset>,
+    ) -> Option<DateTime<FixedOffset>> {
+        let mut date = *current_date + Duration::minutes(90);
+        while date <= *origin {
+            date += Duration::minutes(90);
+        }
+        Some(date)
+    }
+}
+
+// A date within 50 years of 2024, in an offset between -12:00 and +14:00
+fn date() -> impl Strategy<Value = DateTime<FixedOffset>> {
+    (-FIFTY_YEARS..=FIFTY_YEARS, -48..=56_i32).prop_map(|(seconds, quarters)| {
+        let offset = FixedOffset::east_opt(quarters * 15 * 60).unwrap();
+        let start = offset.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        start + Duration::seconds(seconds)
+    })
+}
+
+fn weekday() -> impl Strategy<Value = Weekday> {
+    (0..7_u8).prop_map(|day| Weekday::try_from(day).unwrap())
+}
+
+fn last_day(year: i32, month: u32) -> u32 {
+    (28..=31)
+        .rev()
+        .find(|day| NaiveDate::from_ymd_opt(year, month, *day).is_some())
+        .unwrap()
+}
+
+fn same_time(date: &DateTime<FixedOffset>, next: &DateTime<FixedOffset>) -> bool {
+    (date.hour(), date.minute(), date.second()) == (next.hour(), next.minute(), next.second())
+}
+
+fn next(
+    repetition: &RepetitionType,
+    origin: &DateTime<FixedOffset>,
+    date: &DateTime<FixedOffset>,
+) -> DateTime<FixedOffset> {
+    let next = repetition
+        .next_occurrence(origin, date, &NinetyMinutes)
+        .unwrap();
+    assert!(*origin < next, "{:?} from {} to {}", repetition, date, next);
+    // In the offset of the date
+    next.with_timezone(date.offset())
+}
+
+proptest! {
+    #[test]
+    fn without_next_occurrence(origin in date(), date in date()) {
+        for repetition in [RepetitionType::Once, RepetitionType::SelfScheduled(Termination::default())] {
+            prop_assert_eq!(repetition.next_occurrence(&origin, &date, &NinetyMinutes), None);
+        }
+    }
+
+    #[test]
+    fn weekly_keeps_the_weekday(origin in date(), date in date()) {
+        let next = next(&RepetitionType::Weekly(Termination::default()), &origin, &date);
+        prop_assert_eq!(next.weekday(), date.weekday());
+        prop_assert!(same_time(&date, &next));
+        prop_assert!(next <= origin + Duration::weeks(1));
+    }
+
+    #[test]
+    fn monthly_keeps_the_day_or_the_last_one(origin in date(), date in date(), day in proptest::option::of(1..=31_u32)) {
+        let repetition = RepetitionType::Monthly { day, termination: Termination::default() };
+        let next = next(&repetition, &origin, &date);
+        let day = day.unwrap_or(date.day());
+        prop_assert_eq!(next.day(), day.min(last_day(next.year(), next.month())));
+        prop_assert!(same_time(&date, &next));
+        prop_assert!(next <= origin + Duration::days(62));
+    }
+
+    #[test]
+    fn yearly_keeps_the_month_and_the_day(origin in date(), date in date()) {
+        let next = next(&RepetitionType::Yearly(Termination::default()), &origin, &date);
+        prop_assert_eq!((next.month(), next.day()), (date.month(), date.day()));
+        prop_assert!(same_time(&date, &next));
+        prop_assert!(next <= origin + Duration::days(8 * 366));
+    }
+
+    #[test]
+    fn const_gap_stays_on_the_grid(origin in date(), date in date(), gap_ms in 1..=40 * 24 * 3600 * 1000_i64) {
+        let gap = Duration::milliseconds(gap_ms);
+        let repetition = RepetitionType::ConstGap { gap, termination: Termination::default() };
+        let next = next(&repetition, &origin, &date);
+        prop_assert_eq!((next - date).num_milliseconds() % gap_ms, 0);
+        prop_assert!(next <= origin + gap);
+    }
+
+    #[test]
+    fn iso_weekly_keeps_the_weekday_and_the_parity(
+        origin in date(),
+        date in date(),
+        weekday in weekday(),
+        parity in proptest::option::of(prop_oneof![Just(WeekParity::Even), Just(WeekParity::Odd)]),
+    ) {
+        let repetition = RepetitionType::IsoWeekly { parity, weekday, termination: Termination::default() };
+        let next = next(&repetition, &origin, &date);
+        prop_assert_eq!(next.weekday(), weekday);
+        prop_assert!(parity.is_none_or(|parity| parity.matches(next.iso_week().week())));
+        prop_assert!(same_time(&date, &next));
+        prop_assert!(next <= origin + Duration::weeks(3));
+    }
+
+    #[test]
+    fn custom_is_advanced_past_origin(origin in date(), date in date()) {
+        let next = next(&RepetitionType::Custom, &origin, &date);
+        prop_assert_eq!((next - date).num_minutes() % 90, 0);
+    }
+}
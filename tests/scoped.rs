@@ -0,0 +1,52 @@
+use chrono::{Duration, Local};
+use scheduler::prelude::*;
+use std::sync::Mutex;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| Local::now().into());
+
+#[test]
+fn scoped_workers_borrow_their_payloads() {
+    // Neither Clone nor 'static
+    #[derive(PartialEq, Eq, Default, Debug)]
+    struct Word<'a>(&'a str);
+
+    let arena = String::from("first second third");
+    let soon = Local::now() + Duration::milliseconds(20);
+    let task = |word| ScheduledTask::new(soon, Word(word), RepetitionType::Once, SleepType::Native);
+    let mut parallel = ParallelScheduler::new(
+        [
+            ("all".to_string(), arena.split(' ').map(task).collect()),
+            (
+                "first".to_string(),
+                arena.split(' ').take(1).map(task).collect(),
+            ),
+        ],
+        [],
+    );
+    let read = Mutex::new(Vec::new());
+    let ((), summary) = parallel.scope(|workers| {
+        workers
+            .start("all", |word| read.lock().unwrap().push(word.0))
+            .unwrap();
+        workers
+            .start("first", |word| read.lock().unwrap().push(word.0))
+            .unwrap();
+    });
+    assert!(summary.results.iter().all(Result::is_ok));
+    let mut read = read.into_inner().unwrap();
+    read.sort();
+    assert_eq!(read, ["first", "first", "second", "third"]);
+    // Merged back before the scope has ended
+    assert_eq!(parallel.scheduler().removed_tasks["all"].len(), 3);
+    assert_eq!(
+        parallel.scheduler().removed_tasks["first"][0].task,
+        Word("first")
+    );
+}
+
+#[test]
+fn scoped_borrows_cant_escape() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/scoped_*.rs");
+}
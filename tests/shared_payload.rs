@@ -0,0 +1,50 @@
+// Streams of tasks holding their payload behind an Arc
+#![cfg(feature = "tokio")]
+
+use chrono::{Duration, Local};
+use scheduler::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| Local::now().into());
+
+static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+// Counts its clones
+#[derive(PartialEq, Eq, Default, Debug)]
+struct Payload(Vec<u8>);
+
+impl Clone for Payload {
+    fn clone(&self) -> Self {
+        CLONES.fetch_add(1, Ordering::Relaxed);
+        Self(self.0.clone())
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn shared_payloads_are_never_cloned() {
+    let three_times = RepetitionType::ConstGap {
+        gap: Duration::seconds(1),
+        termination: RepetitionCount::Finished(3).into(),
+    };
+    let task = ScheduledTask::new(
+        Local::now() + Duration::seconds(1),
+        Payload(vec![0; 1 << 20]),
+        three_times,
+        SleepType::Native,
+    )
+    .with_gate(|payload: &Payload| !payload.0.is_empty())
+    .into_shared();
+    let scheduler = BlockingScheduler::new([("m".to_string(), vec![task])], []);
+    let mut firings = scheduler.clone().into_eager_stream("m").unwrap();
+    let mut delivered = Vec::new();
+    while let Some(fired) = firings.next().await {
+        delivered.push(fired.task);
+    }
+    assert_eq!(delivered.len(), 3);
+    assert!(delivered
+        .iter()
+        .all(|payload| Arc::ptr_eq(payload, &scheduler.scheduled_tasks["m"][0].task)));
+    assert_eq!(CLONES.load(Ordering::Relaxed), 0);
+}
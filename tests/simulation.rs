@@ -0,0 +1,87 @@
+// Timelines of a small two-mode schedule, compared with the ones committed in tests/fixtures
+#![cfg(all(feature = "json", feature = "csv"))]
+
+use chrono::{DateTime, Duration, FixedOffset};
+use scheduler::prelude::*;
+use scheduler::simulation::Simulation;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| chrono::Local::now().into());
+
+fn at(date: &str) -> DateTime<FixedOffset> {
+    DateTime::parse_from_rfc3339(date).unwrap()
+}
+
+fn simulation() -> Simulation<&'static str> {
+    let hourly = RepetitionType::ConstGap {
+        gap: Duration::hours(1),
+        termination: RepetitionCount::Finished(3).into(),
+    };
+    let backups = vec![ScheduledTask::new(
+        at("2024-01-01T09:00:00+01:00"),
+        "backup",
+        hourly,
+        SleepType::Native,
+    )];
+    let mails = vec![
+        ScheduledTask::new(
+            at("2024-01-01T09:00:00+01:00"),
+            "digest, weekly",
+            RepetitionType::Weekly(Termination::default()),
+            SleepType::Native,
+        ),
+        // 10:30 in the offset of the other tasks
+        ScheduledTask::new(
+            at("2024-01-01T09:30:00+00:00"),
+            "welcome",
+            RepetitionType::Once,
+            SleepType::Native,
+        ),
+    ];
+    let scheduler = BlockingScheduler::new(
+        [
+            ("mails".to_string(), mails),
+            ("backups".to_string(), backups),
+        ],
+        [],
+    );
+    scheduler.simulate(at("2024-01-08T09:00:00+01:00"))
+}
+
+#[test]
+fn timeline_json_matches_the_fixture() {
+    assert_eq!(
+        simulation().to_timeline_json(),
+        include_str!("fixtures/timeline.json").trim_end()
+    );
+}
+
+#[test]
+fn timeline_csv_matches_the_fixture() {
+    assert_eq!(simulation().to_csv(), include_str!("fixtures/timeline.csv"));
+}
+
+#[test]
+fn ties_follow_the_modes_then_their_tasks() {
+    let simulation = simulation();
+    let order: Vec<_> = simulation
+        .occurrences
+        .iter()
+        .map(|occurrence| (occurrence.mode.as_str(), occurrence.task))
+        .collect();
+    assert_eq!(
+        order,
+        [
+            ("backups", "backup"),
+            ("mails", "digest, weekly"),
+            ("backups", "backup"),
+            ("mails", "welcome"),
+            ("backups", "backup"),
+            ("mails", "digest, weekly"),
+        ]
+    );
+    assert!(simulation
+        .occurrences
+        .windows(2)
+        .all(|pair| pair[0].start <= pair[1].start));
+}
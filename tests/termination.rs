@@ -0,0 +1,66 @@
+use chrono::{DateTime, Duration, FixedOffset};
+use scheduler::prelude::*;
+use std::collections::HashMap;
+
+// Read by the crate built without ambient time (the no-ambient-time feature)
+scheduler::system_clock!(|| chrono::Local::now().into());
+
+fn at(date: &str) -> DateTime<FixedOffset> {
+    DateTime::parse_from_rfc3339(date).unwrap()
+}
+
+// Every day from the 1st of January 2024, ticked at each occurrence until it's removed: the occurrences fired and the
+// removal reason
+fn daily_until_removed(count: u64, until: &str) -> (u32, Option<RemovalReason>) {
+    let repetition = RepetitionType::ConstGap {
+        gap: Duration::days(1),
+        termination: Termination {
+            count: RepetitionCount::Finished(count),
+            until: Some(at(until)),
+        },
+    };
+    let task = ScheduledTask::new(
+        at("2024-01-01T09:00:00+01:00"),
+        0,
+        repetition,
+        SleepType::Native,
+    );
+    let mut scheduler = BlockingScheduler::new(
+        HashMap::from([("daily".to_string(), vec![task])]),
+        HashMap::new(),
+    );
+    let mut fired = 0;
+    while let Some(date) = scheduler.scheduled_tasks["daily"]
+        .first()
+        .map(|task| task.date)
+    {
+        scheduler.tick("daily", date, |_| fired += 1).unwrap();
+    }
+    (fired, scheduler.removed_tasks["daily"][0].removal_reason)
+}
+
+#[test]
+fn count_reached_before_the_date() {
+    assert_eq!(
+        daily_until_removed(3, "2024-03-31T23:59:59+01:00"),
+        (3, Some(RemovalReason::Finished))
+    );
+}
+
+#[test]
+fn date_reached_before_the_count() {
+    // The 1st, 2nd and 3rd of January: the 4th is after the date
+    assert_eq!(
+        daily_until_removed(100, "2024-01-03T12:00:00+01:00"),
+        (3, Some(RemovalReason::Expired))
+    );
+}
+
+#[test]
+fn date_and_count_reached_together() {
+    // The last occurrence of the count is the last one before the date, the count telling first
+    assert_eq!(
+        daily_until_removed(3, "2024-01-03T09:00:00+01:00"),
+        (3, Some(RemovalReason::Finished))
+    );
+}
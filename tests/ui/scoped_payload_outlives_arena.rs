@@ -0,0 +1,14 @@
+use chrono::Local;
+use scheduler::prelude::*;
+
+fn main() {
+    let mut parallel;
+    {
+        let arena = String::from("line");
+        let task = ScheduledTask::new(Local::now(), arena.as_str(), RepetitionType::Once, SleepType::Native);
+        parallel = ParallelScheduler::new([("once".to_string(), vec![task])], []);
+    }
+    parallel.scope(|workers| {
+        workers.start("once", |_| {}).unwrap();
+    });
+}
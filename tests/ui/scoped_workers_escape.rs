@@ -0,0 +1,12 @@
+use chrono::Local;
+use scheduler::prelude::*;
+
+fn main() {
+    let task = ScheduledTask::new(Local::now(), "task", RepetitionType::Once, SleepType::Native);
+    let mut parallel = ParallelScheduler::new([("once".to_string(), vec![task])], []);
+    let mut escaped = None;
+    parallel.scope(|workers| {
+        workers.start("once", |_| {}).unwrap();
+        escaped = Some(workers);
+    });
+}